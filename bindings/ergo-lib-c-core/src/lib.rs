@@ -14,6 +14,7 @@ mod error;
 pub use error::*;
 
 use ergo_lib::ergotree_ir::chain::address::{AddressEncoder, NetworkPrefix};
+use ergo_lib::wallet::secret_key::SecretKey as InnerSecretKey;
 
 pub struct Address(ergo_lib::ergotree_ir::chain::address::Address);
 pub type AddressPtr = *mut Address;
@@ -46,3 +47,52 @@ pub fn address_delete(address: AddressPtr) {
         std::mem::drop(boxed);
     }
 }
+
+pub struct SecretKey(pub InnerSecretKey);
+pub type SecretKeyPtr = *mut SecretKey;
+pub type ConstSecretKeyPtr = *const SecretKey;
+
+pub unsafe fn secret_key_generate_random_dlog(
+    secret_key_out: *mut SecretKeyPtr,
+) -> Result<(), Error> {
+    secret_key_put(InnerSecretKey::random_dlog(), secret_key_out)
+}
+
+pub unsafe fn secret_key_generate_random_dht(
+    secret_key_out: *mut SecretKeyPtr,
+) -> Result<(), Error> {
+    secret_key_put(InnerSecretKey::random_dht(), secret_key_out)
+}
+
+pub unsafe fn secret_key_from_json(
+    json_str: &str,
+    secret_key_out: *mut SecretKeyPtr,
+) -> Result<(), Error> {
+    let secret_key: InnerSecretKey = serde_json::from_str(json_str).map_err(Error::misc)?;
+    secret_key_put(secret_key, secret_key_out)
+}
+
+pub unsafe fn secret_key_to_json(secret_key: ConstSecretKeyPtr) -> Result<String, Error> {
+    let secret_key = secret_key
+        .as_ref()
+        .ok_or(Error::InvalidArgument("secret_key"))?;
+    serde_json::to_string(&secret_key.0).map_err(Error::misc)
+}
+
+pub fn secret_key_delete(secret_key: SecretKeyPtr) {
+    if !secret_key.is_null() {
+        let boxed = unsafe { Box::from_raw(secret_key) };
+        std::mem::drop(boxed);
+    }
+}
+
+unsafe fn secret_key_put(
+    secret_key: InnerSecretKey,
+    secret_key_out: *mut SecretKeyPtr,
+) -> Result<(), Error> {
+    let secret_key_out: &mut SecretKeyPtr = secret_key_out
+        .as_mut()
+        .ok_or(Error::InvalidArgument("secret_key_out"))?;
+    *secret_key_out = Box::into_raw(Box::new(SecretKey(secret_key)));
+    Ok(())
+}
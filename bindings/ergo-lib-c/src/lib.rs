@@ -12,8 +12,11 @@
 
 use ergo_lib::ergotree_ir::chain;
 
-use ergo_lib_c_core::{address_delete, address_from_testnet};
-pub use ergo_lib_c_core::{Address, Error};
+use ergo_lib_c_core::{
+    address_delete, address_from_testnet, secret_key_delete, secret_key_from_json,
+    secret_key_generate_random_dht, secret_key_generate_random_dlog, secret_key_to_json,
+};
+pub use ergo_lib_c_core::{Address, Error, SecretKey};
 use std::{
     ffi::{CStr, CString},
     os::raw::c_char,
@@ -21,6 +24,7 @@ use std::{
 
 pub type ErrorPtr = *mut Error;
 pub type AddressPtr = *mut Address;
+pub type SecretKeyPtr = *mut SecretKey;
 
 pub struct ErgoStateContext(ergo_lib::chain::ergo_state_context::ErgoStateContext);
 pub type ErgoStateContextPtr = *mut ErgoStateContext;
@@ -75,6 +79,49 @@ pub extern "C" fn ergo_wallet_address_delete(address: AddressPtr) {
     address_delete(address)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_secret_key_generate_random_dlog(
+    secret_key_out: *mut SecretKeyPtr,
+) -> ErrorPtr {
+    Error::c_api_from(secret_key_generate_random_dlog(secret_key_out))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_secret_key_generate_random_dht(
+    secret_key_out: *mut SecretKeyPtr,
+) -> ErrorPtr {
+    Error::c_api_from(secret_key_generate_random_dht(secret_key_out))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_secret_key_from_json(
+    json_str: *const c_char,
+    secret_key_out: *mut SecretKeyPtr,
+) -> ErrorPtr {
+    let json = CStr::from_ptr(json_str).to_string_lossy();
+    Error::c_api_from(secret_key_from_json(&json, secret_key_out))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ergo_wallet_secret_key_to_json(
+    secret_key: SecretKeyPtr,
+    json_str_out: *mut *mut c_char,
+) -> ErrorPtr {
+    let res = secret_key_to_json(secret_key).and_then(|json| {
+        let json_str_out = json_str_out
+            .as_mut()
+            .ok_or(Error::InvalidArgument("json_str_out"))?;
+        *json_str_out = CString::new(json).map_err(Error::misc)?.into_raw();
+        Ok(())
+    });
+    Error::c_api_from(res)
+}
+
+#[no_mangle]
+pub extern "C" fn ergo_wallet_secret_key_delete(secret_key: SecretKeyPtr) {
+    secret_key_delete(secret_key)
+}
+
 pub struct UnspentBoxes(Vec<chain::ergo_box::ErgoBoxCandidate>);
 pub type UnspentBoxesPtr = *mut UnspentBoxes;
 
@@ -137,6 +137,15 @@ impl Address {
         .map_err(to_js)
     }
 
+    /// Create a P2SH address from the given script
+    pub fn p2sh_from_ergo_tree(ergo_tree: &ErgoTree) -> Result<Address, JsValue> {
+        ergo_lib::ergotree_ir::chain::address::Address::p2sh_from_ergo_tree(
+            &ergo_tree.clone().into(),
+        )
+        .map(Address)
+        .map_err(to_js)
+    }
+
     /// Create a P2PK address from serialized PK bytes(EcPoint/GroupElement)
     pub fn p2pk_from_pk_bytes(bytes: &[u8]) -> Result<Address, JsValue> {
         ergo_lib::ergotree_ir::chain::address::Address::p2pk_from_pk_bytes(bytes)
@@ -273,3 +282,80 @@ impl NetworkAddress {
         self.0.address().into()
     }
 }
+
+/// Detailed report on the encoded address (network, type, checksum, content checks)
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AddressInspection(ergo_lib::ergotree_ir::chain::address::AddressInspection);
+
+#[wasm_bindgen]
+impl AddressInspection {
+    /// Inspect (base58) address from string without checking the network prefix
+    pub fn from_base58(s: &str) -> AddressInspection {
+        AddressInspection(
+            ergo_lib::ergotree_ir::chain::address::AddressEncoder::inspect_address_str(s),
+        )
+    }
+
+    /// Inspect a serialized address (that includes the network prefix)
+    pub fn from_bytes(data: Vec<u8>) -> AddressInspection {
+        AddressInspection(
+            ergo_lib::ergotree_ir::chain::address::AddressEncoder::inspect_address_bytes(&data),
+        )
+    }
+
+    /// Returns true if the address passed all the checks
+    pub fn is_valid(&self) -> bool {
+        self.0.is_valid()
+    }
+
+    /// Base58 decoding error, if any
+    pub fn base58_error(&self) -> Option<String> {
+        self.0.base58_error.clone()
+    }
+
+    /// Network from the prefix byte
+    pub fn network(&self) -> Option<NetworkPrefix> {
+        self.0.network.map(NetworkPrefix::from)
+    }
+
+    /// Address type from the prefix byte
+    pub fn address_type_prefix(&self) -> Option<AddressTypePrefix> {
+        self.0.address_type.map(AddressTypePrefix::from)
+    }
+
+    /// Checksum matches the prefix byte and content bytes
+    pub fn checksum_valid(&self) -> bool {
+        self.0.checksum_valid
+    }
+
+    /// Size of the content bytes (without prefix byte and checksum)
+    pub fn content_length(&self) -> usize {
+        self.0.content_length
+    }
+
+    /// Content length is valid for the address type
+    pub fn content_length_valid(&self) -> bool {
+        self.0.content_length_valid
+    }
+
+    /// Whether the P2S content is a valid ErgoTree (undefined for other address types)
+    pub fn ergo_tree_valid(&self) -> Option<bool> {
+        self.0.ergo_tree_valid
+    }
+
+    /// Content is a public key or a P2S script that is recognized as P2PK
+    pub fn is_p2pk(&self) -> bool {
+        self.0.is_p2pk
+    }
+
+    /// Decoded address (regardless of the checksum validity)
+    pub fn address(&self) -> Option<Address> {
+        self.0.address.clone().map(Address)
+    }
+
+    /// The same address encoded (base58) for the other network
+    pub fn other_network_address(&self) -> Option<String> {
+        self.0.other_network_address.clone()
+    }
+}
@@ -49,6 +49,16 @@ impl Constant {
             .map_err(|e| JsValue::from_str(&format! {"{:?}", e}))
     }
 
+    /// Human-readable value, e.g. `Coll(1,2)` or `ProveDlog(02..)`
+    pub fn to_rendered_string(&self) -> String {
+        self.0.to_rendered_string()
+    }
+
+    /// Human-readable type, e.g. `Coll[SByte]`
+    pub fn sigma_type(&self) -> String {
+        ergo_lib::ergotree_ir::mir::constant::render_stype(&self.0.tpe)
+    }
+
     /// Create from i32 value
     pub fn from_i32(v: i32) -> Constant {
         Constant(v.into())
@@ -66,15 +66,137 @@ impl SimpleBoxSelector {
         target_balance: &BoxValue,
         target_tokens: &Tokens,
     ) -> Result<BoxSelection, JsValue> {
-        let target_tokens: Vec<ergo_lib::ergotree_ir::chain::token::Token> =
-            target_tokens.clone().into();
-        self.0
-            .select(
-                inputs.clone().into(),
-                target_balance.clone().into(),
-                target_tokens.as_slice(),
-            )
-            .map_err(to_js)
-            .map(BoxSelection)
+        select_with(&self.0, inputs, target_balance, target_tokens)
+    }
+}
+
+fn select_with<S: BoxSelector<ergo_lib::ergotree_ir::chain::ergo_box::ErgoBox>>(
+    selector: &S,
+    inputs: &ErgoBoxes,
+    target_balance: &BoxValue,
+    target_tokens: &Tokens,
+) -> Result<BoxSelection, JsValue> {
+    let target_tokens: Vec<ergo_lib::ergotree_ir::chain::token::Token> =
+        target_tokens.clone().into();
+    selector
+        .select(
+            inputs.clone().into(),
+            target_balance.clone().into(),
+            target_tokens.as_slice(),
+        )
+        .map_err(to_js)
+        .map(BoxSelection)
+}
+
+fn selector_options(max_inputs: Option<u32>) -> wallet::box_selector::BoxSelectorOptions {
+    wallet::box_selector::BoxSelectorOptions {
+        max_inputs: max_inputs.map(|n| n as usize),
+        ..Default::default()
+    }
+}
+
+/// Collects inputs starting from the largest (by value), tends to select the fewest inputs
+#[wasm_bindgen]
+pub struct LargestFirstBoxSelector(wallet::box_selector::LargestFirstBoxSelector);
+
+#[wasm_bindgen]
+impl LargestFirstBoxSelector {
+    /// Create new selector, `max_inputs` - optional limit on the number of selected inputs
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_inputs: Option<u32>) -> Self {
+        LargestFirstBoxSelector(wallet::box_selector::LargestFirstBoxSelector::with_options(
+            selector_options(max_inputs),
+        ))
+    }
+
+    /// Selects inputs to satisfy target balance and tokens (see [`SimpleBoxSelector::select`])
+    pub fn select(
+        &self,
+        inputs: &ErgoBoxes,
+        target_balance: &BoxValue,
+        target_tokens: &Tokens,
+    ) -> Result<BoxSelection, JsValue> {
+        select_with(&self.0, inputs, target_balance, target_tokens)
+    }
+}
+
+/// Collects inputs starting from the smallest (by value), consolidates dust boxes
+#[wasm_bindgen]
+pub struct SmallestFirstBoxSelector(wallet::box_selector::SmallestFirstBoxSelector);
+
+#[wasm_bindgen]
+impl SmallestFirstBoxSelector {
+    /// Create new selector, `max_inputs` - optional limit on the number of selected inputs
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_inputs: Option<u32>) -> Self {
+        SmallestFirstBoxSelector(
+            wallet::box_selector::SmallestFirstBoxSelector::with_options(selector_options(
+                max_inputs,
+            )),
+        )
+    }
+
+    /// Selects inputs to satisfy target balance and tokens (see [`SimpleBoxSelector::select`])
+    pub fn select(
+        &self,
+        inputs: &ErgoBoxes,
+        target_balance: &BoxValue,
+        target_tokens: &Tokens,
+    ) -> Result<BoxSelection, JsValue> {
+        select_with(&self.0, inputs, target_balance, target_tokens)
+    }
+}
+
+/// Looks for inputs exactly matching the target (no change), falls back to largest-first
+#[wasm_bindgen]
+pub struct BranchAndBoundBoxSelector(wallet::box_selector::BranchAndBoundBoxSelector);
+
+#[wasm_bindgen]
+impl BranchAndBoundBoxSelector {
+    /// Create new selector, `max_inputs` - optional limit on the number of selected inputs
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_inputs: Option<u32>) -> Self {
+        BranchAndBoundBoxSelector(
+            wallet::box_selector::BranchAndBoundBoxSelector::with_options(selector_options(
+                max_inputs,
+            )),
+        )
+    }
+
+    /// Selects inputs to satisfy target balance and tokens (see [`SimpleBoxSelector::select`])
+    pub fn select(
+        &self,
+        inputs: &ErgoBoxes,
+        target_balance: &BoxValue,
+        target_tokens: &Tokens,
+    ) -> Result<BoxSelection, JsValue> {
+        select_with(&self.0, inputs, target_balance, target_tokens)
+    }
+}
+
+/// Picks random inputs and improves the selection so that the change is comparable to the target
+#[wasm_bindgen]
+pub struct RandomImproveBoxSelector(wallet::box_selector::RandomImproveBoxSelector);
+
+#[wasm_bindgen]
+impl RandomImproveBoxSelector {
+    /// Create new selector, `max_inputs` - optional limit on the number of selected inputs
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_inputs: Option<u32>) -> Self {
+        RandomImproveBoxSelector(
+            wallet::box_selector::RandomImproveBoxSelector::with_options(selector_options(
+                max_inputs,
+            )),
+        )
+    }
+
+    /// Selects inputs to satisfy target balance and tokens (see [`SimpleBoxSelector::select`])
+    pub fn select(
+        &self,
+        inputs: &ErgoBoxes,
+        target_balance: &BoxValue,
+        target_tokens: &Tokens,
+    ) -> Result<BoxSelection, JsValue> {
+        select_with(&self.0, inputs, target_balance, target_tokens)
     }
 }
@@ -5,7 +5,10 @@ use wasm_bindgen::prelude::*;
 extern crate derive_more;
 use derive_more::{From, Into};
 
+use crate::block_header::BlockHeaders;
+use crate::error_conversion::to_js;
 use crate::header::PreHeader;
+use crate::parameters::Parameters;
 
 /// Blockchain state (last headers, etc.)
 #[wasm_bindgen]
@@ -22,6 +25,24 @@ impl ErgoStateContext {
         ergo_state_context.into()
     }
 
+    /// Create from the last headers (at least 10, without gaps, in ascending height order as
+    /// returned by the node or in descending order) with the pre-header of the next block
+    /// and the current blockchain parameters
+    pub fn from_headers(
+        headers: &BlockHeaders,
+        parameters: &Parameters,
+    ) -> Result<ErgoStateContext, JsValue> {
+        let headers: Vec<ergo_lib::ergotree_ir::chain::header::Header> = headers.clone().into();
+        chain::ergo_state_context::ErgoStateContext::from_headers(headers, (*parameters).into())
+            .map(ErgoStateContext)
+            .map_err(to_js)
+    }
+
+    /// Current blockchain parameters
+    pub fn parameters(&self) -> Parameters {
+        self.0.parameters.into()
+    }
+
     /// empty (dummy) context (for signing P2PK tx only)
     pub fn dummy() -> ErgoStateContext {
         ErgoStateContext(chain::ergo_state_context::ErgoStateContext::dummy())
@@ -3,6 +3,7 @@
 use std::num::ParseIntError;
 
 use base16::DecodeError;
+use ergo_lib::chain::ergo_state_context::ErgoStateContextError;
 use ergo_lib::ergotree_ir::chain::address::AddressEncoderError;
 use ergo_lib::ergotree_ir::chain::address::AddressError;
 use ergo_lib::ergotree_ir::chain::digest32::Digest32Error;
@@ -54,6 +55,7 @@ from_error_to_wrap!(TokenAmountError);
 from_error_to_wrap!(TxBuilderError);
 from_error_to_wrap!(WalletError);
 from_error_to_wrap!(DecodeError);
+from_error_to_wrap!(ErgoStateContextError);
 
 macro_rules! from_error_to_wrap_via_debug {
     ($t:ident) => {
@@ -27,6 +27,7 @@ pub mod ergo_state_ctx;
 pub mod ergo_tree;
 pub mod header;
 pub mod input;
+pub mod parameters;
 pub mod prover_result;
 pub mod secret_key;
 pub mod token;
@@ -0,0 +1,38 @@
+//! Blockchain parameters (adjustable by the miners voting)
+use ergo_lib::chain;
+use ergo_lib::chain::parameters::Parameter;
+use wasm_bindgen::prelude::*;
+
+extern crate derive_more;
+use derive_more::{From, Into};
+
+/// Blockchain parameters used by the transaction builders and validators
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, From, Into)]
+pub struct Parameters(chain::parameters::Parameters);
+
+#[wasm_bindgen]
+impl Parameters {
+    /// Parameters set on launch
+    pub fn default_parameters() -> Parameters {
+        chain::parameters::Parameters::default().into()
+    }
+
+    /// Parameter value by its id (1 - storage fee factor, 2 - min value per byte,
+    /// 3 - max block size, 4 - max block cost, 5 - token access cost, 6 - input cost,
+    /// 7 - data input cost, 8 - output cost, 123 - block version)
+    pub fn get(&self, parameter_id: u8) -> Result<i32, JsValue> {
+        Ok(self.0.get(parameter(parameter_id)?))
+    }
+
+    /// Set parameter value by its id (see [`Parameters::get`] for the ids)
+    pub fn set(&mut self, parameter_id: u8, value: i32) -> Result<(), JsValue> {
+        self.0.set(parameter(parameter_id)?, value);
+        Ok(())
+    }
+}
+
+fn parameter(id: u8) -> Result<Parameter, JsValue> {
+    Parameter::from_id(id)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown parameter id: {}", id)))
+}
@@ -1,11 +1,13 @@
 //! Secret key
 use std::convert::TryInto;
 
+use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DhTupleProverInput;
 use ergo_lib::ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
 use ergo_lib::wallet;
 use wasm_bindgen::prelude::*;
 
 use crate::address::Address;
+use crate::error_conversion::to_js;
 
 extern crate derive_more;
 use derive_more::{From, Into};
@@ -36,9 +38,43 @@ impl SecretKey {
             .ok_or_else(|| JsValue::from_str("failed to parse scalar"))
     }
 
+    /// generate random Diffie-Hellman tuple secret
+    pub fn random_dht() -> SecretKey {
+        SecretKey(wallet::secret_key::SecretKey::random_dht())
+    }
+
+    /// Parse Diffie-Hellman tuple secret key from bytes (SEC-1-encoded scalar followed by the
+    /// points g, h, u, v)
+    pub fn dht_from_bytes(bytes: &[u8]) -> Result<SecretKey, JsValue> {
+        let sized_bytes: &[u8; DhTupleProverInput::SIZE_BYTES] =
+            bytes.try_into().map_err(|_| {
+                JsValue::from_str(&format!(
+                    "expected byte array of size {}, found {}",
+                    DhTupleProverInput::SIZE_BYTES,
+                    bytes.len()
+                ))
+            })?;
+        wallet::secret_key::SecretKey::dht_from_bytes(sized_bytes)
+            .map(SecretKey)
+            .ok_or_else(|| JsValue::from_str("failed to parse Diffie-Hellman tuple secret"))
+    }
+
+    /// Parse from JSON
+    pub fn from_json(json: &str) -> Result<SecretKey, JsValue> {
+        serde_json::from_str(json).map(Self).map_err(to_js)
+    }
+
+    /// JSON representation
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string_pretty(&self.0).map_err(to_js)
+    }
+
     /// Address (encoded public image)
-    pub fn get_address(&self) -> Address {
-        self.0.get_address_from_public_image().into()
+    pub fn get_address(&self) -> Result<Address, JsValue> {
+        self.0
+            .get_address_from_public_image()
+            .map(Address::from)
+            .map_err(to_js)
     }
 
     /// Encode from a serialized key
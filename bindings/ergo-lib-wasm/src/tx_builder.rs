@@ -5,6 +5,7 @@ use wasm_bindgen::prelude::*;
 use crate::box_selector::BoxSelection;
 use crate::data_input::DataInputs;
 use crate::error_conversion::to_js;
+use crate::token::Tokens;
 use crate::{
     address::Address, box_coll::ErgoBoxCandidates, ergo_box::BoxValue,
     transaction::UnsignedTransaction,
@@ -55,6 +56,12 @@ impl TxBuilder {
         self.0.set_data_inputs(data_inputs.into())
     }
 
+    /// Permit burning of the given tokens (exact amounts), building fails if any input tokens
+    /// are burned without permission
+    pub fn set_token_burn_permit(&mut self, tokens: &Tokens) {
+        self.0.set_token_burn_permit(tokens.clone().into())
+    }
+
     /// Build the unsigned transaction
     pub fn build(&self) -> Result<UnsignedTransaction, JsValue> {
         self.0
@@ -98,4 +105,9 @@ impl TxBuilder {
     pub fn min_change_value(&self) -> BoxValue {
         self.0.min_change_value().into()
     }
+
+    /// Get tokens permitted to be burned
+    pub fn token_burn_permit(&self) -> Tokens {
+        self.0.token_burn_permit().into()
+    }
 }
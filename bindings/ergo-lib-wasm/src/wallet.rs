@@ -15,8 +15,19 @@ pub struct Wallet(ergo_lib::wallet::Wallet);
 impl Wallet {
     /// Create wallet instance loading secret key from mnemonic
     #[wasm_bindgen]
-    pub fn from_mnemonic(_mnemonic_phrase: &str, _mnemonic_pass: &str) -> Wallet {
-        todo!()
+    pub fn from_mnemonic(mnemonic_phrase: &str, mnemonic_pass: &str) -> Result<Wallet, JsValue> {
+        ergo_lib::wallet::Wallet::from_mnemonic(mnemonic_phrase, mnemonic_pass)
+            .map(Wallet)
+            .map_err(to_js)
+    }
+
+    /// Create wallet instance decrypting the secret from the encrypted secret storage
+    /// (JSON keystore format of the reference node wallet)
+    #[wasm_bindgen]
+    pub fn from_encrypted_storage(json: &str, password: &str) -> Result<Wallet, JsValue> {
+        ergo_lib::wallet::Wallet::from_encrypted_storage(json.as_bytes(), password)
+            .map(Wallet)
+            .map_err(to_js)
     }
 
     /// Create wallet using provided secret key
@@ -175,6 +175,8 @@ it('TxBuilder burn token test', async () => {
   const data_inputs = new DataInputs();
   const tx_builder = TxBuilder.new(box_selection, tx_outputs, 0, fee, change_address, min_change_value);
   tx_builder.set_data_inputs(data_inputs);
+  // explicitly permit burning the selected tokens
+  tx_builder.set_token_burn_permit(tokens);
   const tx = tx_builder.build();
   assert(tx != null);
 });
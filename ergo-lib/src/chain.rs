@@ -3,7 +3,11 @@
 #[cfg(feature = "json")]
 pub mod json;
 
+pub mod block_transactions;
 pub mod contract;
 pub mod ergo_box;
 pub mod ergo_state_context;
+pub mod extension;
+pub mod merkle;
+pub mod parameters;
 pub mod transaction;
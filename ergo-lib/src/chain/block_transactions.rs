@@ -0,0 +1,185 @@
+//! Transactions Merkle tree of the block (`transaction_root` in the header) and the transaction
+//! inclusion proofs
+
+use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::tx_id::TxId;
+
+use super::merkle::MerkleProof;
+use super::merkle::MerkleTree;
+use super::transaction::Transaction;
+
+/// Block version before the witness ids were added to the transactions Merkle tree
+pub const INITIAL_BLOCK_VERSION: u8 = 1;
+
+/// Witness id of the transaction (commitment to the spending proofs), the last 31 bytes of the
+/// blake2b256 hash of the concatenated input proofs. It's one byte shorter than the
+/// transaction id to tell them apart in the transactions Merkle tree.
+pub fn witness_id(tx: &Transaction) -> Vec<u8> {
+    let proofs: Vec<u8> = tx
+        .inputs
+        .iter()
+        .flat_map(|i| match &i.spending_proof.proof {
+            ProofBytes::Empty => vec![],
+            ProofBytes::Some(bytes) => bytes.clone(),
+        })
+        .collect();
+    blake2b256_hash(&proofs).0[1..].to_vec()
+}
+
+/// Leaves of the block transactions Merkle tree: transaction ids followed by the witness ids
+/// (blocks after [`INITIAL_BLOCK_VERSION`])
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BlockTransactionIds {
+    tx_ids: Vec<TxId>,
+    witness_ids: Vec<Vec<u8>>,
+}
+
+impl BlockTransactionIds {
+    /// Create from the transaction ids and witness ids (empty for [`INITIAL_BLOCK_VERSION`]
+    /// blocks), e.g. received by the light client
+    pub fn new(tx_ids: Vec<TxId>, witness_ids: Vec<Vec<u8>>) -> Self {
+        BlockTransactionIds {
+            tx_ids,
+            witness_ids,
+        }
+    }
+
+    /// Ids of the block transactions for the given block version
+    pub fn from_transactions(transactions: &[Transaction], block_version: u8) -> Self {
+        let witness_ids = if block_version > INITIAL_BLOCK_VERSION {
+            transactions.iter().map(witness_id).collect()
+        } else {
+            vec![]
+        };
+        BlockTransactionIds::new(transactions.iter().map(|tx| tx.id()).collect(), witness_ids)
+    }
+
+    fn merkle_tree(&self) -> MerkleTree {
+        MerkleTree::new(
+            self.tx_ids
+                .iter()
+                .map(|id| id.0 .0.to_vec())
+                .chain(self.witness_ids.iter().cloned())
+                .collect(),
+        )
+    }
+
+    /// Merkle tree root (`transaction_root` in the header)
+    pub fn transactions_root(&self) -> Digest32 {
+        self.merkle_tree().root_hash()
+    }
+
+    /// Check that the transactions root matches the one in the header
+    pub fn matches_header(&self, header: &Header) -> bool {
+        self.transactions_root() == header.transaction_root
+    }
+
+    /// Proof of the transaction inclusion in the block, `None` if the transaction is not in
+    /// the block
+    pub fn proof_for(&self, tx_id: &TxId) -> Option<MerkleProof> {
+        let idx = self.tx_ids.iter().position(|id| id == tx_id)?;
+        self.merkle_tree().proof_by_index(idx)
+    }
+}
+
+/// Check that the proof is for the given transaction id and it leads to the transactions root
+/// of the given header
+pub fn verify_tx_inclusion(proof: &MerkleProof, tx_id: &TxId, header: &Header) -> bool {
+    proof.leaf_data.as_slice() == tx_id.0 .0.as_ref() && proof.valid(&header.transaction_root)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use sigma_test_util::force_any_val;
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_tx_inclusion(txs in vec(any::<Transaction>(), 1..8), block_version in 1..3u8) {
+            let ids = BlockTransactionIds::from_transactions(&txs, block_version);
+            let mut header = force_any_val::<Header>();
+            header.version = block_version;
+            header.transaction_root = ids.transactions_root();
+            prop_assert!(ids.matches_header(&header));
+            for tx in &txs {
+                let proof = ids.proof_for(&tx.id()).unwrap();
+                prop_assert!(verify_tx_inclusion(&proof, &tx.id(), &header));
+                prop_assert!(!verify_tx_inclusion(&proof, &TxId::zero(), &header));
+            }
+            prop_assert!(ids.proof_for(&TxId::zero()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_transactions_root_v2() {
+        // one transaction without proofs (9148408c..., the emission box spending at height
+        // 284761), the root is calculated independently from the tree definition
+        // (blake2b256 of 0x00 ++ leaf, 0x01 ++ left ++ right)
+        let tx_id = TxId(
+            Digest32::try_from(
+                "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9".to_string(),
+            )
+            .unwrap(),
+        );
+        let witness_id =
+            base16::decode("5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+                .unwrap();
+        assert_eq!(blake2b256_hash(&[]).0[1..].to_vec(), witness_id);
+        let ids = BlockTransactionIds::new(vec![tx_id.clone()], vec![witness_id]);
+        assert_eq!(
+            ids.transactions_root(),
+            Digest32::try_from(
+                "04d6c0bfaac071590b5d953bb8540a25ff71ed7581e03056f3a369a55bf0e184".to_string()
+            )
+            .unwrap()
+        );
+        let proof = ids.proof_for(&tx_id).unwrap();
+        assert_eq!(proof.levels.len(), 1);
+        assert_eq!(
+            proof.levels[0].hash,
+            Some(
+                Digest32::try_from(
+                    "165acf47053d386366a509a4a6c0ac25d3c2c46da692dc8a22df4e611894dc95".to_string()
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_witness_ids() {
+        let txs = vec![
+            force_any_val::<Transaction>(),
+            force_any_val::<Transaction>(),
+        ];
+        let v1 = BlockTransactionIds::from_transactions(&txs, 1);
+        let v2 = BlockTransactionIds::from_transactions(&txs, 2);
+        assert_ne!(v1.transactions_root(), v2.transactions_root());
+        let ids = txs.iter().map(|tx| tx.id()).collect::<Vec<_>>();
+        assert_eq!(
+            BlockTransactionIds::new(ids.clone(), vec![]).transactions_root(),
+            v1.transactions_root()
+        );
+        let witness_ids = txs.iter().map(witness_id).collect::<Vec<_>>();
+        assert!(witness_ids.iter().all(|w| w.len() == 31));
+        assert_eq!(
+            BlockTransactionIds::new(ids, witness_ids).transactions_root(),
+            v2.transactions_root()
+        );
+        assert_eq!(
+            BlockTransactionIds::new(vec![], vec![]).transactions_root(),
+            blake2b256_hash(&[])
+        );
+    }
+}
@@ -1,3 +1,4 @@
 //! Ergo box related types
 
 pub mod box_builder;
+pub mod eip4;
@@ -1,7 +1,7 @@
 //! ErgoBoxCandidate builder
 
 use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 
 use ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
@@ -14,6 +14,10 @@ use ergotree_ir::mir::constant::Constant;
 use ergotree_ir::serialization::{SigmaSerializable, SigmaSerializationError};
 use thiserror::Error;
 
+use crate::chain::parameters::Parameters;
+
+use super::eip4::Eip4Token;
+
 /// ErgoBoxCandidate builder errors
 #[derive(Error, PartialEq, Eq, Clone, Debug)]
 pub enum ErgoBoxCandidateBuilderError {
@@ -34,8 +38,8 @@ pub enum ErgoBoxCandidateBuilderError {
     #[error("When minting token no other tokens should be in the box (according to EIP4)")]
     ExclusiveMintedTokenError,
 
-    /// When minting token R4, R5, R6 register are holding issued token info(according to EIP4) and cannot be used
-    #[error("R4, R5, R6 are holding issuing token info and cannot be used(found {0:?} are used)")]
+    /// When minting token R4..R9 registers are holding issued token info(according to EIP4) and cannot be used
+    #[error("R4..R9 are holding issuing token info and cannot be used(found {0:?} are used)")]
     MintedTokenRegisterOverwriteError(NonMandatoryRegisterId),
 
     /// Serialization error
@@ -43,15 +47,6 @@ pub enum ErgoBoxCandidateBuilderError {
     SerializationError(#[from] SigmaSerializationError),
 }
 
-/// Minted token info (id, amount, name, desc)
-#[derive(Debug, Clone)]
-struct MintingToken {
-    token: Token,
-    name: String,
-    desc: String,
-    num_decimals: usize,
-}
-
 /// ErgoBoxCandidate builder
 #[derive(Debug, Clone)]
 pub struct ErgoBoxCandidateBuilder {
@@ -61,7 +56,7 @@ pub struct ErgoBoxCandidateBuilder {
     tokens: Vec<Token>,
     additional_registers: HashMap<NonMandatoryRegisterId, Constant>,
     creation_height: u32,
-    minting_token: Option<MintingToken>,
+    minting_token: Option<Eip4Token>,
 }
 
 impl ErgoBoxCandidateBuilder {
@@ -91,6 +86,12 @@ impl ErgoBoxCandidateBuilder {
         self.min_value_per_byte
     }
 
+    /// Set limits (minimal value per byte of the serialized box size) from the current
+    /// blockchain parameters
+    pub fn set_parameters(&mut self, parameters: &Parameters) {
+        self.min_value_per_byte = parameters.min_value_per_byte.max(0) as u32;
+    }
+
     /// Set new box value
     pub fn set_value(&mut self, new_value: BoxValue) {
         self.value = new_value;
@@ -110,10 +111,17 @@ impl ErgoBoxCandidateBuilder {
     /// Calculate minimal box value for the current box serialized size(in bytes)
     pub fn calc_min_box_value(&self) -> Result<BoxValue, ErgoBoxCandidateBuilderError> {
         let box_size_bytes = self.calc_box_size_bytes()?;
-        Ok(
-            BoxValue::try_from(box_size_bytes as i64 * BoxValue::MIN_VALUE_PER_BOX_BYTE as i64)
-                .unwrap(),
-        )
+        Ok(self.min_box_value(box_size_bytes))
+    }
+
+    /// Minimal box value for the given serialized box size, but not lower than [`BoxValue::MIN`]
+    /// (e.g. when the minimal value per byte is set to 0)
+    fn min_box_value(&self, box_size_bytes: usize) -> BoxValue {
+        let raw = (box_size_bytes as u64)
+            .saturating_mul(self.min_value_per_byte as u64)
+            .clamp(BoxValue::MIN_RAW, BoxValue::MAX_RAW);
+        // within bounds after clamping
+        BoxValue::try_from(raw).unwrap()
     }
 
     /// Set register with a given id (R4-R9) to the given value
@@ -143,12 +151,13 @@ impl ErgoBoxCandidateBuilder {
         token_desc: String,
         num_decimals: usize,
     ) {
-        self.minting_token = Some(MintingToken {
-            token,
-            name: token_name,
-            desc: token_desc,
-            num_decimals,
-        });
+        self.minting_token = Some(Eip4Token::new(token, token_name, token_desc, num_decimals));
+    }
+
+    /// Mint token with the info (including asset type) encoded in R4..R9 as defined in
+    /// <https://github.com/ergoplatform/eips/blob/master/eip-0004.md>
+    pub fn mint_eip4_token(&mut self, token: Eip4Token) {
+        self.minting_token = Some(token);
     }
 
     /// Add given token id and token amount
@@ -165,54 +174,17 @@ impl ErgoBoxCandidateBuilder {
             if !self.tokens.is_empty() {
                 return Err(ErgoBoxCandidateBuilderError::ExclusiveMintedTokenError);
             }
-            if additional_registers
-                .get(&NonMandatoryRegisterId::R4)
-                .is_some()
-            {
-                return Err(
-                    ErgoBoxCandidateBuilderError::MintedTokenRegisterOverwriteError(
-                        NonMandatoryRegisterId::R4,
-                    ),
-                );
-            }
-            if additional_registers
-                .get(&NonMandatoryRegisterId::R5)
-                .is_some()
-            {
-                return Err(
-                    ErgoBoxCandidateBuilderError::MintedTokenRegisterOverwriteError(
-                        NonMandatoryRegisterId::R5,
-                    ),
-                );
-            }
-            if additional_registers
-                .get(&NonMandatoryRegisterId::R6)
-                .is_some()
-            {
-                return Err(
-                    ErgoBoxCandidateBuilderError::MintedTokenRegisterOverwriteError(
-                        NonMandatoryRegisterId::R6,
-                    ),
-                );
+            for (register_id, value) in minting_token.registers() {
+                if additional_registers.contains_key(&register_id) {
+                    return Err(
+                        ErgoBoxCandidateBuilderError::MintedTokenRegisterOverwriteError(
+                            register_id,
+                        ),
+                    );
+                }
+                additional_registers.insert(register_id, value);
             }
             tokens.push(minting_token.token);
-            additional_registers.insert(
-                NonMandatoryRegisterId::R4,
-                minting_token.name.as_bytes().to_vec().into(),
-            );
-            additional_registers.insert(
-                NonMandatoryRegisterId::R5,
-                minting_token.desc.as_bytes().to_vec().into(),
-            );
-            additional_registers.insert(
-                NonMandatoryRegisterId::R6,
-                minting_token
-                    .num_decimals
-                    .to_string()
-                    .as_bytes()
-                    .to_vec()
-                    .into(),
-            );
         }
         let regs = NonMandatoryRegisters::new(additional_registers)?;
         let b = ErgoBoxCandidate {
@@ -223,9 +195,7 @@ impl ErgoBoxCandidateBuilder {
             creation_height: self.creation_height,
         };
         let box_size_bytes = b.sigma_serialize_bytes()?.len();
-        let min_box_value: BoxValue = (box_size_bytes as i64 * self.min_value_per_byte as i64)
-            .try_into()
-            .unwrap();
+        let min_box_value = self.min_box_value(box_size_bytes);
         if self.value >= min_box_value {
             Ok(b)
         } else {
@@ -245,6 +215,8 @@ impl ErgoBoxCandidateBuilder {
 #[cfg(test)]
 mod tests {
 
+    use std::convert::TryInto;
+
     use ergotree_ir::base16_str::Base16Str;
     use ergotree_ir::chain::token::TokenId;
     use sigma_test_util::force_any_val;
@@ -285,6 +257,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_min_box_value_from_parameters() {
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 1);
+        let default_min_value = builder.calc_min_box_value().unwrap();
+        let parameters = Parameters {
+            min_value_per_byte: BoxValue::MIN_VALUE_PER_BOX_BYTE as i32 * 2,
+            ..Parameters::default()
+        };
+        builder.set_parameters(&parameters);
+        assert_eq!(
+            builder.min_box_value_per_byte(),
+            BoxValue::MIN_VALUE_PER_BOX_BYTE * 2
+        );
+        assert_eq!(
+            *builder.calc_min_box_value().unwrap().as_u64(),
+            default_min_value.as_u64() * 2
+        );
+    }
+
+    #[test]
+    fn test_zero_min_value_per_byte_from_parameters() {
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::MIN, force_any_val::<ErgoTree>(), 1);
+        let parameters = Parameters {
+            min_value_per_byte: 0,
+            ..Parameters::default()
+        };
+        builder.set_parameters(&parameters);
+        assert_eq!(builder.min_box_value_per_byte(), 0);
+        assert_eq!(builder.calc_min_box_value().unwrap(), BoxValue::MIN);
+        assert_eq!(builder.build().unwrap().value, BoxValue::MIN);
+    }
+
     #[test]
     fn test_box_size_estimation() {
         let builder =
@@ -423,6 +429,35 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_mint_eip4_nft_register_overwrite() {
+        use crate::chain::ergo_box::eip4::{Eip4AssetType, Eip4Nft, NftType};
+        let token = Eip4Token {
+            asset_type: Some(Eip4AssetType::Nft(Eip4Nft {
+                nft_type: NftType::Picture,
+                content_hash: [0u8; 32],
+                link: None,
+            })),
+            ..Eip4Token::new(
+                Token {
+                    token_id: force_any_val::<TokenId>(),
+                    amount: 1.try_into().unwrap(),
+                },
+                "NFT".to_string(),
+                "picture".to_string(),
+                0,
+            )
+        };
+        let mut box_builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0);
+        box_builder.mint_eip4_token(token);
+        box_builder.set_register_value(R8, force_any_val::<Constant>());
+        assert_eq!(
+            box_builder.build(),
+            Err(ErgoBoxCandidateBuilderError::MintedTokenRegisterOverwriteError(R8))
+        );
+    }
+
     #[test]
     fn test_add_token() {
         let token = Token {
@@ -0,0 +1,442 @@
+//! Token metadata as defined in <https://github.com/ergoplatform/eips/blob/master/eip-0004.md>
+
+use std::convert::TryFrom;
+
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::types::stype::SType;
+use thiserror::Error;
+
+/// SHA-256 content hash size in bytes
+pub const CONTENT_HASH_SIZE: usize = 32;
+
+/// NFT subtype (second byte of R7)
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum NftType {
+    /// Picture artwork (0x01 0x01)
+    Picture,
+    /// Audio artwork (0x01 0x02)
+    Audio,
+    /// Video artwork (0x01 0x03)
+    Video,
+}
+
+impl NftType {
+    fn type_byte(self) -> u8 {
+        match self {
+            NftType::Picture => 0x01,
+            NftType::Audio => 0x02,
+            NftType::Video => 0x03,
+        }
+    }
+}
+
+/// NFT artwork info
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Eip4Nft {
+    /// NFT subtype (R7)
+    pub nft_type: NftType,
+    /// SHA-256 hash of the artwork content (R8)
+    pub content_hash: [u8; CONTENT_HASH_SIZE],
+    /// Link to the artwork (R9, optional)
+    pub link: Option<String>,
+}
+
+/// Asset type (R7)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Eip4AssetType {
+    /// NFT artwork (0x01 followed by the subtype), content hash in R8 and link in R9
+    Nft(Eip4Nft),
+    /// Membership token, threshold signature (0x02 0x01)
+    MembershipThresholdSig,
+    /// Asset type not known to this library (raw R7 bytes)
+    Other(Vec<u8>),
+}
+
+impl Eip4AssetType {
+    const NFT_PREFIX: u8 = 0x01;
+    const MEMBERSHIP_THRESHOLD_SIG: [u8; 2] = [0x02, 0x01];
+
+    fn type_bytes(&self) -> Vec<u8> {
+        match self {
+            Eip4AssetType::Nft(nft) => vec![Eip4AssetType::NFT_PREFIX, nft.nft_type.type_byte()],
+            Eip4AssetType::MembershipThresholdSig => {
+                Eip4AssetType::MEMBERSHIP_THRESHOLD_SIG.to_vec()
+            }
+            Eip4AssetType::Other(bytes) => bytes.clone(),
+        }
+    }
+}
+
+/// Errors on parsing EIP-4 token info from the box registers
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum Eip4TokenError {
+    /// Box does not have any tokens
+    #[error("Box does not have any tokens")]
+    NoTokens,
+    /// Mandatory register is empty
+    #[error("Register {0:?} is empty")]
+    MissingRegister(NonMandatoryRegisterId),
+    /// Register has unexpected type
+    #[error("Register {register:?} is expected to be Coll[Byte], found {tpe:?}")]
+    InvalidRegisterType {
+        /// register id
+        register: NonMandatoryRegisterId,
+        /// actual type of the register value
+        tpe: SType,
+    },
+    /// Register value is not a valid UTF-8 string
+    #[error("Register {0:?} is not a valid UTF-8 string")]
+    InvalidUtf8(NonMandatoryRegisterId),
+    /// Number of decimals (R6) is not a valid number
+    #[error("Invalid number of decimals: {0}")]
+    InvalidDecimals(String),
+    /// Content hash (R8) has unexpected size
+    #[error("Content hash is expected to be 32 bytes (SHA-256), found {0} bytes")]
+    InvalidContentHash(usize),
+}
+
+/// Token info stored in the registers R4..R9 of the box where it is minted (EIP-4)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Eip4Token {
+    /// Token id (box id of the first input box in the minting transaction) and amount
+    pub token: Token,
+    /// Verbose name (R4)
+    pub name: String,
+    /// Description (R5)
+    pub description: String,
+    /// Number of decimals (R6)
+    pub decimals: usize,
+    /// Asset type (R7, with asset specific info in R8 and R9), `None` for a plain token
+    pub asset_type: Option<Eip4AssetType>,
+}
+
+impl Eip4Token {
+    /// Plain token (without asset type)
+    pub fn new(token: Token, name: String, description: String, decimals: usize) -> Self {
+        Eip4Token {
+            token,
+            name,
+            description,
+            decimals,
+            asset_type: None,
+        }
+    }
+
+    /// Register values encoding the token info, ordered by the register id (R4 first)
+    pub fn registers(&self) -> Vec<(NonMandatoryRegisterId, Constant)> {
+        let mut regs = vec![
+            (
+                NonMandatoryRegisterId::R4,
+                self.name.as_bytes().to_vec().into(),
+            ),
+            (
+                NonMandatoryRegisterId::R5,
+                self.description.as_bytes().to_vec().into(),
+            ),
+            (
+                NonMandatoryRegisterId::R6,
+                self.decimals.to_string().as_bytes().to_vec().into(),
+            ),
+        ];
+        if let Some(asset_type) = &self.asset_type {
+            regs.push((NonMandatoryRegisterId::R7, asset_type.type_bytes().into()));
+            if let Eip4AssetType::Nft(nft) = asset_type {
+                regs.push((NonMandatoryRegisterId::R8, nft.content_hash.to_vec().into()));
+                if let Some(link) = &nft.link {
+                    regs.push((NonMandatoryRegisterId::R9, link.as_bytes().to_vec().into()));
+                }
+            }
+        }
+        regs
+    }
+
+    /// Parse the token info from the registers of the box where the given token is minted
+    pub fn from_registers(
+        token: Token,
+        registers: &NonMandatoryRegisters,
+    ) -> Result<Self, Eip4TokenError> {
+        let name = get_string(registers, NonMandatoryRegisterId::R4)?;
+        let description = get_string(registers, NonMandatoryRegisterId::R5)?;
+        let decimals_str = get_string(registers, NonMandatoryRegisterId::R6)?;
+        let decimals = decimals_str
+            .parse()
+            .map_err(|_| Eip4TokenError::InvalidDecimals(decimals_str))?;
+        let asset_type = match get_bytes_opt(registers, NonMandatoryRegisterId::R7)? {
+            None => None,
+            Some(type_bytes) => Some(match type_bytes.as_slice() {
+                [Eip4AssetType::NFT_PREFIX, t @ 0x01..=0x03] => {
+                    let nft_type = match t {
+                        0x01 => NftType::Picture,
+                        0x02 => NftType::Audio,
+                        _ => NftType::Video,
+                    };
+                    let hash = get_bytes(registers, NonMandatoryRegisterId::R8)?;
+                    let content_hash = <[u8; CONTENT_HASH_SIZE]>::try_from(hash.as_slice())
+                        .map_err(|_| Eip4TokenError::InvalidContentHash(hash.len()))?;
+                    let link = get_bytes_opt(registers, NonMandatoryRegisterId::R9)?
+                        .map(|bytes| to_string(NonMandatoryRegisterId::R9, bytes))
+                        .transpose()?;
+                    Eip4AssetType::Nft(Eip4Nft {
+                        nft_type,
+                        content_hash,
+                        link,
+                    })
+                }
+                bytes if bytes == Eip4AssetType::MEMBERSHIP_THRESHOLD_SIG => {
+                    Eip4AssetType::MembershipThresholdSig
+                }
+                bytes => Eip4AssetType::Other(bytes.to_vec()),
+            }),
+        };
+        Ok(Eip4Token {
+            token,
+            name,
+            description,
+            decimals,
+            asset_type,
+        })
+    }
+}
+
+impl TryFrom<&ErgoBox> for Eip4Token {
+    type Error = Eip4TokenError;
+
+    /// Parse the token info of the first token of the box (minted token)
+    fn try_from(b: &ErgoBox) -> Result<Self, Self::Error> {
+        let token = b.tokens.first().ok_or(Eip4TokenError::NoTokens)?;
+        Eip4Token::from_registers(token.clone(), &b.additional_registers)
+    }
+}
+
+impl TryFrom<&ErgoBoxCandidate> for Eip4Token {
+    type Error = Eip4TokenError;
+
+    /// Parse the token info of the first token of the box (minted token)
+    fn try_from(b: &ErgoBoxCandidate) -> Result<Self, Self::Error> {
+        let token = b.tokens.first().ok_or(Eip4TokenError::NoTokens)?;
+        Eip4Token::from_registers(token.clone(), &b.additional_registers)
+    }
+}
+
+fn get_bytes_opt(
+    registers: &NonMandatoryRegisters,
+    register: NonMandatoryRegisterId,
+) -> Result<Option<Vec<u8>>, Eip4TokenError> {
+    registers
+        .get(register)
+        .map(|c| {
+            if c.tpe == SType::SColl(Box::new(SType::SByte)) {
+                c.v.clone().try_extract_into::<Vec<u8>>().ok()
+            } else {
+                None
+            }
+            .ok_or_else(|| Eip4TokenError::InvalidRegisterType {
+                register,
+                tpe: c.tpe.clone(),
+            })
+        })
+        .transpose()
+}
+
+fn get_bytes(
+    registers: &NonMandatoryRegisters,
+    register: NonMandatoryRegisterId,
+) -> Result<Vec<u8>, Eip4TokenError> {
+    get_bytes_opt(registers, register)?.ok_or(Eip4TokenError::MissingRegister(register))
+}
+
+fn get_string(
+    registers: &NonMandatoryRegisters,
+    register: NonMandatoryRegisterId,
+) -> Result<String, Eip4TokenError> {
+    to_string(register, get_bytes(registers, register)?)
+}
+
+fn to_string(register: NonMandatoryRegisterId, bytes: Vec<u8>) -> Result<String, Eip4TokenError> {
+    String::from_utf8(bytes).map_err(|_| Eip4TokenError::InvalidUtf8(register))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::token::TokenId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+
+    fn token() -> Token {
+        Token {
+            token_id: force_any_val::<TokenId>(),
+            amount: 1.try_into().unwrap(),
+        }
+    }
+
+    fn mint(eip4_token: Eip4Token) -> ErgoBoxCandidate {
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0);
+        builder.mint_eip4_token(eip4_token);
+        builder.build().unwrap()
+    }
+
+    fn registers(regs: Vec<(NonMandatoryRegisterId, Constant)>) -> NonMandatoryRegisters {
+        NonMandatoryRegisters::new(regs.into_iter().collect()).unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let asset_types = vec![
+            None,
+            Some(Eip4AssetType::Nft(Eip4Nft {
+                nft_type: NftType::Picture,
+                content_hash: [1u8; CONTENT_HASH_SIZE],
+                link: Some("https://example.com/picture.png".to_string()),
+            })),
+            Some(Eip4AssetType::Nft(Eip4Nft {
+                nft_type: NftType::Audio,
+                content_hash: [2u8; CONTENT_HASH_SIZE],
+                link: None,
+            })),
+            Some(Eip4AssetType::Nft(Eip4Nft {
+                nft_type: NftType::Video,
+                content_hash: [3u8; CONTENT_HASH_SIZE],
+                link: None,
+            })),
+            Some(Eip4AssetType::MembershipThresholdSig),
+            Some(Eip4AssetType::Other(vec![0x03, 0x01])),
+        ];
+        for asset_type in asset_types {
+            let eip4_token = Eip4Token {
+                asset_type,
+                ..Eip4Token::new(token(), "NFT".to_string(), "description".to_string(), 0)
+            };
+            let b = mint(eip4_token.clone());
+            assert_eq!(Eip4Token::try_from(&b), Ok(eip4_token));
+        }
+    }
+
+    #[test]
+    fn registers_order() {
+        let eip4_token = Eip4Token {
+            asset_type: Some(Eip4AssetType::Nft(Eip4Nft {
+                nft_type: NftType::Picture,
+                content_hash: [1u8; CONTENT_HASH_SIZE],
+                link: Some("https://example.com/picture.png".to_string()),
+            })),
+            ..Eip4Token::new(token(), "NFT".to_string(), "description".to_string(), 0)
+        };
+        let ids: Vec<NonMandatoryRegisterId> = eip4_token
+            .registers()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                NonMandatoryRegisterId::R4,
+                NonMandatoryRegisterId::R5,
+                NonMandatoryRegisterId::R6,
+                NonMandatoryRegisterId::R7,
+                NonMandatoryRegisterId::R8,
+                NonMandatoryRegisterId::R9
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_mint_token() {
+        let token = token();
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0);
+        builder.mint_token(token.clone(), "USD".to_string(), "USD token".to_string(), 2);
+        let b = builder.build().unwrap();
+        assert_eq!(
+            Eip4Token::try_from(&b),
+            Ok(Eip4Token::new(
+                token,
+                "USD".to_string(),
+                "USD token".to_string(),
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_invalid_registers() {
+        use NonMandatoryRegisterId::*;
+        let name: Constant = b"name".to_vec().into();
+        let desc: Constant = b"desc".to_vec().into();
+        let decimals: Constant = b"2".to_vec().into();
+        assert_eq!(
+            Eip4Token::from_registers(token(), &registers(vec![(R4, name.clone())])),
+            Err(Eip4TokenError::MissingRegister(R5))
+        );
+        assert_eq!(
+            Eip4Token::from_registers(
+                token(),
+                &registers(vec![
+                    (R4, name.clone()),
+                    (R5, desc.clone()),
+                    (R6, 2i32.into())
+                ])
+            ),
+            Err(Eip4TokenError::InvalidRegisterType {
+                register: R6,
+                tpe: SType::SInt
+            })
+        );
+        assert_eq!(
+            Eip4Token::from_registers(
+                token(),
+                &registers(vec![
+                    (R4, name.clone()),
+                    (R5, desc.clone()),
+                    (R6, b"two".to_vec().into())
+                ])
+            ),
+            Err(Eip4TokenError::InvalidDecimals("two".to_string()))
+        );
+        assert_eq!(
+            Eip4Token::from_registers(
+                token(),
+                &registers(vec![
+                    (R4, vec![0xffu8, 0xfe].into()),
+                    (R5, desc.clone()),
+                    (R6, decimals.clone())
+                ])
+            ),
+            Err(Eip4TokenError::InvalidUtf8(R4))
+        );
+        assert_eq!(
+            Eip4Token::from_registers(
+                token(),
+                &registers(vec![
+                    (R4, name),
+                    (R5, desc),
+                    (R6, decimals),
+                    (R7, vec![0x01u8, 0x01].into()),
+                    (R8, vec![0u8; 20].into())
+                ])
+            ),
+            Err(Eip4TokenError::InvalidContentHash(20))
+        );
+    }
+
+    #[test]
+    fn parse_box_without_tokens() {
+        let b =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0)
+                .build()
+                .unwrap();
+        assert_eq!(Eip4Token::try_from(&b), Err(Eip4TokenError::NoTokens));
+    }
+}
@@ -3,6 +3,9 @@ use std::convert::TryInto;
 
 use ergotree_ir::chain::header::Header;
 use ergotree_ir::chain::preheader::PreHeader;
+use thiserror::Error;
+
+use super::parameters::Parameters;
 
 /// Blockchain state (last headers, etc.)
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -12,9 +15,75 @@ pub struct ErgoStateContext {
     pub pre_header: PreHeader,
     /// Fixed number of last block headers in descending order (first header is the newest one)
     pub headers: [Header; 10],
+    /// Current blockchain parameters
+    pub parameters: Parameters,
+}
+
+/// Errors on building the state context
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ErgoStateContextError {
+    /// Not enough headers
+    #[error("Expected at least {expected} headers, got {actual}")]
+    NotEnoughHeaders {
+        /// Required number of headers
+        expected: usize,
+        /// Number of the provided headers
+        actual: usize,
+    },
+    /// Header is not the parent of the next (by height) header
+    #[error("Header at height {0} is not the parent of the next header")]
+    HeadersNotChained(u32),
+    /// The newest header is at the maximal height, there is no next block
+    #[error("No block can follow the header at height {0}")]
+    HeightOverflow(u32),
 }
 
 impl ErgoStateContext {
+    /// Number of the last headers in the context
+    pub const HEADERS_NUM: usize = 10;
+
+    /// Create new instance
+    pub fn new(pre_header: PreHeader, headers: [Header; 10], parameters: Parameters) -> Self {
+        ErgoStateContext {
+            pre_header,
+            headers,
+            parameters,
+        }
+    }
+
+    /// Create from the last headers (at least [`ErgoStateContext::HEADERS_NUM`], without gaps,
+    /// in ascending height order as returned by the node or in descending order) with the
+    /// pre-header of the next block (see [`PreHeader::for_next_block`])
+    pub fn from_headers(
+        mut headers: Vec<Header>,
+        parameters: Parameters,
+    ) -> Result<Self, ErgoStateContextError> {
+        if headers.len() < Self::HEADERS_NUM {
+            return Err(ErgoStateContextError::NotEnoughHeaders {
+                expected: Self::HEADERS_NUM,
+                actual: headers.len(),
+            });
+        }
+        if headers[0].height < headers[headers.len() - 1].height {
+            headers.reverse();
+        }
+        if let Some(w) = headers
+            .windows(2)
+            .find(|w| w[1].height.checked_add(1) != Some(w[0].height) || w[0].parent_id != w[1].id)
+        {
+            return Err(ErgoStateContextError::HeadersNotChained(w[1].height));
+        }
+        headers.truncate(Self::HEADERS_NUM);
+        let pre_header = PreHeader::for_next_block(&headers[0])
+            .ok_or(ErgoStateContextError::HeightOverflow(headers[0].height))?;
+        #[allow(clippy::unwrap_used)]
+        Ok(ErgoStateContext::new(
+            pre_header,
+            headers.try_into().unwrap(),
+            parameters,
+        ))
+    }
+
     /// Dummy instance intended for tests where actual values are not used
     pub fn dummy() -> ErgoStateContext {
         let headers = vec![Header::dummy(); 10]
@@ -23,6 +92,79 @@ impl ErgoStateContext {
         ErgoStateContext {
             pre_header: PreHeader::dummy(),
             headers,
+            parameters: Parameters::default(),
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ergotree_ir::chain::block_id::BlockId;
+    use ergotree_ir::chain::digest32::Digest32;
+
+    use super::*;
+
+    fn chain(from_height: u32, len: u32) -> Vec<Header> {
+        let block_id = |height: u32| {
+            let mut bytes = [0u8; 32];
+            bytes[..4].copy_from_slice(&height.to_be_bytes());
+            BlockId(Digest32::from(bytes))
+        };
+        (from_height..=from_height + (len - 1))
+            .map(|height| Header {
+                id: block_id(height),
+                parent_id: block_id(height - 1),
+                height,
+                timestamp: height as u64 * 120_000,
+                ..Header::dummy()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn from_headers_ascending() {
+        let headers = chain(100, 12);
+        let ctx = ErgoStateContext::from_headers(headers.clone(), Parameters::default()).unwrap();
+        assert_eq!(ctx.headers[0], headers[11]);
+        assert_eq!(ctx.headers[9], headers[2]);
+        assert_eq!(ctx.pre_header.height, 112);
+        assert_eq!(ctx.pre_header.parent_id, headers[11].id);
+        assert_eq!(ctx.pre_header.timestamp, headers[11].timestamp);
+    }
+
+    #[test]
+    fn from_headers_descending() {
+        let mut headers = chain(100, 10);
+        headers.reverse();
+        let ctx = ErgoStateContext::from_headers(headers.clone(), Parameters::default()).unwrap();
+        assert_eq!(ctx.headers.to_vec(), headers);
+    }
+
+    #[test]
+    fn from_headers_invalid() {
+        assert_eq!(
+            ErgoStateContext::from_headers(chain(100, 9), Parameters::default()),
+            Err(ErgoStateContextError::NotEnoughHeaders {
+                expected: 10,
+                actual: 9
+            })
+        );
+        let mut headers = chain(100, 11);
+        headers.remove(5);
+        assert_eq!(
+            ErgoStateContext::from_headers(headers, Parameters::default()),
+            Err(ErgoStateContextError::HeadersNotChained(104))
+        );
+        let mut headers = chain(100, 10);
+        headers[4].height = u32::MAX;
+        assert_eq!(
+            ErgoStateContext::from_headers(headers, Parameters::default()),
+            Err(ErgoStateContextError::HeadersNotChained(u32::MAX))
+        );
+        assert_eq!(
+            ErgoStateContext::from_headers(chain(u32::MAX - 9, 10), Parameters::default()),
+            Err(ErgoStateContextError::HeightOverflow(u32::MAX))
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Block extension (key-value fields holding the blockchain parameters, NiPoPoW interlinks, etc.)
+
+use std::convert::TryInto;
+
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::Digest;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::header::Header;
+use thiserror::Error;
+
+use super::merkle::MerkleProof;
+use super::merkle::MerkleTree;
+use super::parameters::Parameters;
+use super::parameters::ParametersError;
+
+/// Extension field key
+pub type FieldKey = [u8; 2];
+
+/// Errors of the block extension
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ExtensionError {
+    /// Field value is too long
+    #[error("Value of the field {key:?} is too long ({len} bytes)")]
+    FieldValueTooLong {
+        /// field key
+        key: FieldKey,
+        /// value length
+        len: usize,
+    },
+    /// Duplicate field key
+    #[error("Duplicate field key {0:?}")]
+    DuplicateKey(FieldKey),
+    /// Interlinks vector field is malformed
+    #[error("Interlinks are improperly packed in the field {0:?}")]
+    InvalidInterlinksField(FieldKey),
+}
+
+/// Block extension section
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Extension {
+    /// Id of the block header
+    pub header_id: BlockId,
+    /// Key-value fields (in the order of the Merkle tree leaves)
+    fields: Vec<(FieldKey, Vec<u8>)>,
+}
+
+impl Extension {
+    /// Maximum size of the field value
+    pub const FIELD_VALUE_MAX_SIZE: usize = 64;
+    /// Prefix of the keys of the fields holding the NiPoPoW interlinks vector
+    pub const INTERLINKS_KEY_PREFIX: u8 = 0x01;
+
+    /// Create extension checking field value sizes and keys uniqueness
+    pub fn new(
+        header_id: BlockId,
+        fields: Vec<(FieldKey, Vec<u8>)>,
+    ) -> Result<Extension, ExtensionError> {
+        for (idx, (key, value)) in fields.iter().enumerate() {
+            if value.len() > Extension::FIELD_VALUE_MAX_SIZE {
+                return Err(ExtensionError::FieldValueTooLong {
+                    key: *key,
+                    len: value.len(),
+                });
+            }
+            if fields[..idx].iter().any(|(k, _)| k == key) {
+                return Err(ExtensionError::DuplicateKey(*key));
+            }
+        }
+        Ok(Extension { header_id, fields })
+    }
+
+    /// Key-value fields
+    pub fn fields(&self) -> &[(FieldKey, Vec<u8>)] {
+        &self.fields
+    }
+
+    /// Value of the field with the given key
+    pub fn field(&self, key: &FieldKey) -> Option<&Vec<u8>> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Merkle tree leaf of the field (`key length ++ key ++ value`)
+    pub fn field_leaf_data(key: &FieldKey, value: &[u8]) -> Vec<u8> {
+        let mut leaf = Vec::with_capacity(1 + key.len() + value.len());
+        leaf.push(key.len() as u8);
+        leaf.extend_from_slice(key);
+        leaf.extend_from_slice(value);
+        leaf
+    }
+
+    fn merkle_tree(&self) -> MerkleTree {
+        MerkleTree::new(
+            self.fields
+                .iter()
+                .map(|(k, v)| Extension::field_leaf_data(k, v))
+                .collect(),
+        )
+    }
+
+    /// Merkle tree root of the fields (`extension_root` in the header)
+    pub fn digest(&self) -> Digest32 {
+        self.merkle_tree().root_hash()
+    }
+
+    /// Check that the extension belongs to the given header (same header id and the fields root)
+    pub fn matches_header(&self, header: &Header) -> bool {
+        self.header_id == header.id && self.digest() == header.extension_root
+    }
+
+    /// Proof of the field membership to be checked against `extension_root` of the header.
+    /// Leaf data of the proof is [`Extension::field_leaf_data`].
+    pub fn proof_for(&self, key: &FieldKey) -> Option<MerkleProof> {
+        let idx = self.fields.iter().position(|(k, _)| k == key)?;
+        self.merkle_tree().proof_by_index(idx)
+    }
+
+    /// Blockchain parameters (stored in the extension of the first block of the voting epoch)
+    pub fn parameters(&self) -> Result<Parameters, ParametersError> {
+        Parameters::from_extension_fields(&self.fields)
+    }
+
+    /// NiPoPoW interlinks vector
+    pub fn interlinks(&self) -> Result<Vec<BlockId>, ExtensionError> {
+        unpack_interlinks(&self.fields)
+    }
+}
+
+/// Pack NiPoPoW interlinks vector into the extension fields. Runs of the same block id are packed
+/// into one field: key is `[0x01, index of the first id in the run]`, value is
+/// `run length ++ block id`.
+pub fn pack_interlinks(links: &[BlockId]) -> Vec<(FieldKey, Vec<u8>)> {
+    let mut fields = vec![];
+    let mut idx = 0;
+    while idx < links.len() {
+        let link = &links[idx];
+        let run_length = links[idx..]
+            .iter()
+            .take(u8::MAX as usize)
+            .take_while(|l| *l == link)
+            .count();
+        let mut value = Vec::with_capacity(1 + Digest32::SIZE);
+        value.push(run_length as u8);
+        value.extend_from_slice(link.0 .0.as_ref());
+        fields.push(([Extension::INTERLINKS_KEY_PREFIX, idx as u8], value));
+        idx += run_length;
+    }
+    fields
+}
+
+/// Unpack NiPoPoW interlinks vector from the extension fields (other fields are ignored)
+pub fn unpack_interlinks(fields: &[(FieldKey, Vec<u8>)]) -> Result<Vec<BlockId>, ExtensionError> {
+    let mut links = vec![];
+    for (key, value) in fields
+        .iter()
+        .filter(|(k, _)| k[0] == Extension::INTERLINKS_KEY_PREFIX)
+    {
+        let (run_length, id) = value
+            .split_first()
+            .ok_or(ExtensionError::InvalidInterlinksField(*key))?;
+        let id: [u8; 32] = id
+            .try_into()
+            .map_err(|_| ExtensionError::InvalidInterlinksField(*key))?;
+        links.resize(
+            links.len() + *run_length as usize,
+            BlockId(Digest::from(id)),
+        );
+    }
+    Ok(links)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use sigma_test_util::force_any_val;
+    use std::convert::TryFrom;
+
+    use crate::chain::parameters::Parameter;
+
+    use super::*;
+
+    fn block_id(b: u8) -> BlockId {
+        BlockId(Digest::from([b; 32]))
+    }
+
+    #[test]
+    fn test_interlinks_roundtrip() {
+        let links = vec![
+            block_id(0),
+            block_id(1),
+            block_id(1),
+            block_id(1),
+            block_id(2),
+        ];
+        let fields = pack_interlinks(&links);
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[1].0, [0x01, 1]);
+        assert_eq!(fields[1].1[0], 3);
+        assert_eq!(fields[2].0, [0x01, 4]);
+        assert_eq!(unpack_interlinks(&fields).unwrap(), links);
+        assert!(pack_interlinks(&[]).is_empty());
+        assert_eq!(
+            unpack_interlinks(&[([0x01, 0], vec![1, 2])]),
+            Err(ExtensionError::InvalidInterlinksField([0x01, 0]))
+        );
+    }
+
+    #[test]
+    fn test_extension_fields() {
+        let mut fields = Parameters::default().to_extension_fields();
+        fields.extend(pack_interlinks(&[block_id(7), block_id(8)]));
+        let ext = Extension::new(block_id(9), fields.clone()).unwrap();
+        assert_eq!(ext.parameters().unwrap(), Parameters::default());
+        assert_eq!(ext.interlinks().unwrap(), vec![block_id(7), block_id(8)]);
+        let key = [0x00, Parameter::InputCost.id()];
+        assert_eq!(
+            ext.field(&key),
+            Some(&Parameters::DEFAULT_INPUT_COST.to_be_bytes().to_vec())
+        );
+        assert_eq!(ext.field(&[0x02, 0]), None);
+
+        fields.push(fields[0].clone());
+        assert_eq!(
+            Extension::new(block_id(9), fields),
+            Err(ExtensionError::DuplicateKey([0x00, 1]))
+        );
+        assert_eq!(
+            Extension::new(block_id(9), vec![([0x02, 0], vec![0; 65])]),
+            Err(ExtensionError::FieldValueTooLong {
+                key: [0x02, 0],
+                len: 65
+            })
+        );
+    }
+
+    #[test]
+    fn test_interlinks_digest() {
+        // interlinks of the mainnet genesis block and block 471745 (twice), the digest is
+        // calculated independently from the tree definition (blake2b256 of
+        // 0x00 ++ key length ++ key ++ value, 0x01 ++ left ++ right)
+        let id = |s: &str| BlockId(Digest32::try_from(s.to_string()).unwrap());
+        let genesis = id("b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b");
+        let parent = id("6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34");
+        let fields = vec![
+            (
+                [0x01, 0],
+                base16::decode(
+                    "01b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b",
+                )
+                .unwrap(),
+            ),
+            (
+                [0x01, 1],
+                base16::decode(
+                    "026481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34",
+                )
+                .unwrap(),
+            ),
+        ];
+        let links = vec![genesis, parent.clone(), parent];
+        assert_eq!(pack_interlinks(&links), fields);
+        assert_eq!(unpack_interlinks(&fields).unwrap(), links);
+        let ext = Extension::new(block_id(9), fields).unwrap();
+        let mut header = force_any_val::<Header>();
+        header.id = block_id(9);
+        header.extension_root = Digest32::try_from(
+            "773faf005a21b73c5729da0c76b2ca30f1715aa925e53f3d37462cc813d73812".to_string(),
+        )
+        .unwrap();
+        assert!(ext.matches_header(&header));
+    }
+
+    #[test]
+    fn test_header_and_field_proofs() {
+        let fields = pack_interlinks(&[block_id(1), block_id(2), block_id(3)]);
+        let ext = Extension::new(block_id(9), fields.clone()).unwrap();
+        let mut header = force_any_val::<Header>();
+        assert!(!ext.matches_header(&header));
+        header.id = block_id(9);
+        header.extension_root = ext.digest();
+        assert!(ext.matches_header(&header));
+        for (key, value) in &fields {
+            let proof = ext.proof_for(key).unwrap();
+            assert_eq!(proof.leaf_data, Extension::field_leaf_data(key, value));
+            assert!(proof.valid(&header.extension_root));
+        }
+        assert!(ext.proof_for(&[0x00, 1]).is_none());
+        let empty = Extension::new(block_id(9), vec![]).unwrap();
+        assert_eq!(
+            empty.digest(),
+            ergotree_ir::chain::digest32::blake2b256_hash(&[])
+        );
+    }
+}
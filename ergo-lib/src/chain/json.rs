@@ -1,6 +1,7 @@
 //! JSON serialization
 
 pub(crate) mod context_extension;
+pub mod node;
 pub(crate) mod transaction;
 
 use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
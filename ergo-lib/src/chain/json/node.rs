@@ -0,0 +1,994 @@
+//! Ergo node REST API models (blocks, headers, `/info`, `/utxo/byIdBinary`)
+
+use std::convert::TryFrom;
+
+use ergotree_ir::chain::base16_bytes::Base16DecodedBytes;
+use ergotree_ir::chain::block_id::BlockId;
+use ergotree_ir::chain::digest32::ADDigest;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::votes::Votes;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::chain::block_transactions::BlockTransactionIds;
+use crate::chain::ergo_state_context::ErgoStateContext;
+use crate::chain::ergo_state_context::ErgoStateContextError;
+use crate::chain::extension::Extension;
+use crate::chain::extension::ExtensionError;
+use crate::chain::merkle::LevelNode;
+use crate::chain::merkle::MerkleProof;
+use crate::chain::merkle::NodeSide;
+use crate::chain::parameters::Parameters;
+use crate::chain::transaction::Transaction;
+
+/// Errors on converting node API models
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum NodeJsonError {
+    /// Invalid group element (public key) bytes
+    #[error("Invalid group element: {0}")]
+    InvalidGroupElement(SigmaParsingError),
+    /// Failed to build the state context from headers
+    #[error("State context error: {0}")]
+    StateContextError(#[from] ErgoStateContextError),
+    /// Box parsing failed
+    #[error("Box parsing failed: {0}")]
+    BoxParsingError(SigmaParsingError),
+    /// Box id differs from the one calculated from the box bytes
+    #[error("Box id {0:?} differs from the one calculated from the box bytes")]
+    InvalidBoxId(BoxId),
+    /// Extension field key is not 2 bytes long
+    #[error("Invalid extension field key: {0:?}")]
+    InvalidExtensionKey(Vec<u8>),
+    /// Invalid extension fields
+    #[error("Extension error: {0}")]
+    ExtensionError(#[from] ExtensionError),
+    /// Extension digest differs from the one calculated from the fields
+    #[error("Extension digest {0:?} differs from the one calculated from the fields")]
+    InvalidExtensionDigest(Digest32),
+    /// Invalid Merkle proof level
+    #[error("Invalid Merkle proof: {0}")]
+    InvalidMerkleProof(String),
+    /// Header id differs from the one calculated from the header fields
+    #[error("Header id {0:?} differs from the one calculated from the header fields")]
+    InvalidHeaderId(BlockId),
+    /// Header serialization failed (on the header id calculation)
+    #[error("Header serialization failed: {0}")]
+    HeaderSerializationError(SigmaSerializationError),
+    /// Block section belongs to another block
+    #[error("Block section header id {0:?} differs from the block header id")]
+    InvalidSectionHeaderId(BlockId),
+    /// Transactions root in the header differs from the one calculated from the block transactions
+    #[error(
+        "Transactions root {0:?} in the header differs from the one of the block transactions"
+    )]
+    InvalidTransactionsRoot(Digest32),
+    /// Extension root in the header differs from the extension digest
+    #[error("Extension root {0:?} in the header differs from the extension digest")]
+    InvalidExtensionRoot(Digest32),
+}
+
+/// Autolykos PoW solution of the block header
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct PowSolutionsJson {
+    /// Public key of the miner
+    #[serde(rename = "pk")]
+    pub pk: Base16DecodedBytes,
+    /// One-time public key (Autolykos v1)
+    #[serde(rename = "w")]
+    pub w: Base16DecodedBytes,
+    /// Nonce
+    #[serde(rename = "n")]
+    pub n: Base16DecodedBytes,
+    /// Distance (Autolykos v1), as a number or a string
+    #[serde(rename = "d", with = "pow_distance")]
+    pub d: BigInt,
+}
+
+/// Block header (`/blocks/{headerId}/header`, `/blocks/lastHeaders/{count}`)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct HeaderJson {
+    /// Block id
+    #[serde(rename = "id")]
+    pub id: BlockId,
+    /// Parent block id
+    #[serde(rename = "parentId")]
+    pub parent_id: BlockId,
+    /// Block version
+    #[serde(rename = "version")]
+    pub version: u8,
+    /// Block height
+    #[serde(rename = "height")]
+    pub height: u32,
+    /// Timestamp of a block in ms from UNIX epoch
+    #[serde(rename = "timestamp")]
+    pub timestamp: u64,
+    /// Difficulty in a compressed view
+    #[serde(rename = "nBits")]
+    pub n_bits: u64,
+    /// Hash of AD proofs
+    #[serde(rename = "adProofsRoot")]
+    pub ad_proofs_root: Digest32,
+    /// AVL tree root of the state after the block application
+    #[serde(rename = "stateRoot")]
+    pub state_root: ADDigest,
+    /// Merkle tree root of the block transactions
+    #[serde(rename = "transactionsRoot")]
+    pub transactions_root: Digest32,
+    /// Merkle tree root of the extension
+    #[serde(rename = "extensionHash")]
+    pub extension_hash: Digest32,
+    /// PoW solution
+    #[serde(rename = "powSolutions")]
+    pub pow_solutions: PowSolutionsJson,
+    /// Votes for changing system parameters
+    #[serde(rename = "votes")]
+    pub votes: Votes,
+    /// Block size in bytes
+    #[serde(rename = "size", default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+}
+
+/// Checks that the header id matches the one calculated from the header fields
+impl TryFrom<HeaderJson> for Header {
+    type Error = NodeJsonError;
+
+    fn try_from(h: HeaderJson) -> Result<Self, Self::Error> {
+        let header = header_unchecked(h)?;
+        let id = header
+            .calc_id()
+            .map_err(NodeJsonError::HeaderSerializationError)?;
+        if id == header.id {
+            Ok(header)
+        } else {
+            Err(NodeJsonError::InvalidHeaderId(header.id))
+        }
+    }
+}
+
+/// Header with the id taken as is
+fn header_unchecked(h: HeaderJson) -> Result<Header, NodeJsonError> {
+    let parse_point = |bytes: &[u8]| {
+        EcPoint::sigma_parse_bytes(bytes).map_err(NodeJsonError::InvalidGroupElement)
+    };
+    Ok(Header {
+        version: h.version,
+        id: h.id,
+        parent_id: h.parent_id,
+        ad_proofs_root: h.ad_proofs_root,
+        state_root: h.state_root,
+        transaction_root: h.transactions_root,
+        timestamp: h.timestamp,
+        n_bits: h.n_bits,
+        height: h.height,
+        extension_root: h.extension_hash,
+        miner_pk: parse_point(&h.pow_solutions.pk.0)?.into(),
+        pow_onetime_pk: parse_point(&h.pow_solutions.w.0)?.into(),
+        nonce: h.pow_solutions.n.0,
+        pow_distance: h.pow_solutions.d,
+        votes: h.votes,
+    })
+}
+
+/// Block transactions (`/blocks/{headerId}/transactions`)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct BlockTransactionsJson {
+    /// Block id
+    #[serde(rename = "headerId")]
+    pub header_id: BlockId,
+    /// Transactions
+    #[serde(rename = "transactions")]
+    pub transactions: Vec<Transaction>,
+    /// Size in bytes
+    #[serde(rename = "size", default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+}
+
+/// Block extension (`/blocks/{headerId}/extension`)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ExtensionJson {
+    /// Block id
+    #[serde(rename = "headerId")]
+    pub header_id: BlockId,
+    /// Merkle tree root of the fields
+    #[serde(rename = "digest")]
+    pub digest: Digest32,
+    /// Key-value fields (2-byte keys)
+    #[serde(rename = "fields")]
+    pub fields: Vec<(Base16DecodedBytes, Base16DecodedBytes)>,
+}
+
+/// AD proofs of the state transformation (`/blocks/{headerId}/proofFor/...`, full block)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AdProofsJson {
+    /// Block id
+    #[serde(rename = "headerId")]
+    pub header_id: BlockId,
+    /// Serialized proof
+    #[serde(rename = "proofBytes")]
+    pub proof_bytes: Base16DecodedBytes,
+    /// Hash of the proof (`Header::ad_proofs_root`)
+    #[serde(rename = "digest")]
+    pub digest: Digest32,
+    /// Size in bytes
+    #[serde(rename = "size", default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+}
+
+/// Full block (`/blocks/{headerId}`)
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct FullBlockJson {
+    /// Header
+    #[serde(rename = "header")]
+    pub header: HeaderJson,
+    /// Transactions
+    #[serde(rename = "blockTransactions")]
+    pub block_transactions: BlockTransactionsJson,
+    /// Extension
+    #[serde(rename = "extension")]
+    pub extension: ExtensionJson,
+    /// AD proofs (missing if the node is not storing them)
+    #[serde(rename = "adProofs", default)]
+    pub ad_proofs: Option<AdProofsJson>,
+    /// Size in bytes
+    #[serde(rename = "size", default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+}
+
+/// Full block with the transactions and extension checked against the header
+/// (see `TryFrom<FullBlockJson>`)
+#[derive(PartialEq, Debug, Clone)]
+pub struct FullBlock {
+    /// Header
+    pub header: Header,
+    /// Transactions
+    pub transactions: Vec<Transaction>,
+    /// Extension
+    pub extension: Extension,
+}
+
+/// Merkle proof of the transaction inclusion (`/blocks/{headerId}/proofFor/{txId}`)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct MerkleProofJson {
+    /// Leaf data (transaction id)
+    #[serde(rename = "leafData")]
+    pub leaf_data: Base16DecodedBytes,
+    /// Sibling hashes (empty if missing) with the side (0 - left, 1 - right) of the node
+    /// the proof is going through, from the leaf up to the root
+    #[serde(rename = "levels")]
+    pub levels: Vec<(Base16DecodedBytes, u8)>,
+}
+
+/// Current blockchain parameters (part of `/info`)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ParametersJson {
+    /// Height when the parameters were adopted
+    #[serde(rename = "height")]
+    pub height: u32,
+    /// Storage fee factor (per byte per storage period)
+    #[serde(rename = "storageFeeFactor")]
+    pub storage_fee_factor: i32,
+    /// Minimum value per byte of an output
+    #[serde(rename = "minValuePerByte")]
+    pub min_value_per_byte: i32,
+    /// Maximum block size
+    #[serde(rename = "maxBlockSize")]
+    pub max_block_size: i32,
+    /// Maximum cumulative computational cost of a block
+    #[serde(rename = "maxBlockCost")]
+    pub max_block_cost: i32,
+    /// Block version
+    #[serde(rename = "blockVersion")]
+    pub block_version: i32,
+    /// Token access cost
+    #[serde(rename = "tokenAccessCost")]
+    pub token_access_cost: i32,
+    /// Cost per one transaction input
+    #[serde(rename = "inputCost")]
+    pub input_cost: i32,
+    /// Cost per one data input
+    #[serde(rename = "dataInputCost")]
+    pub data_input_cost: i32,
+    /// Cost per one transaction output
+    #[serde(rename = "outputCost")]
+    pub output_cost: i32,
+}
+
+/// Node status (`/info`)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct NodeInfoJson {
+    /// Node name
+    #[serde(rename = "name")]
+    pub name: String,
+    /// Node version
+    #[serde(rename = "appVersion")]
+    pub app_version: String,
+    /// Network (`mainnet`, `testnet`)
+    #[serde(rename = "network", default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Height of the best full block (`None` if not synced yet)
+    #[serde(rename = "fullHeight")]
+    pub full_height: Option<u32>,
+    /// Height of the best header
+    #[serde(rename = "headersHeight")]
+    pub headers_height: Option<u32>,
+    /// Best full block id
+    #[serde(rename = "bestFullHeaderId")]
+    pub best_full_header_id: Option<BlockId>,
+    /// Best header id
+    #[serde(rename = "bestHeaderId")]
+    pub best_header_id: Option<BlockId>,
+    /// Genesis block id
+    #[serde(rename = "genesisBlockId", default)]
+    pub genesis_block_id: Option<BlockId>,
+    /// State type (`utxo`, `digest`)
+    #[serde(rename = "stateType")]
+    pub state_type: String,
+    /// Whether the node is mining
+    #[serde(rename = "isMining")]
+    pub is_mining: bool,
+    /// Number of the connected peers
+    #[serde(rename = "peersCount")]
+    pub peers_count: u32,
+    /// Number of the mempool transactions
+    #[serde(rename = "unconfirmedCount")]
+    pub unconfirmed_count: u32,
+    /// Current blockchain parameters
+    #[serde(rename = "parameters")]
+    pub parameters: ParametersJson,
+    /// Node time in ms from UNIX epoch
+    #[serde(rename = "currentTime")]
+    pub current_time: u64,
+}
+
+/// Serialized box (`/utxo/byIdBinary/{boxId}`)
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct SerializedBoxJson {
+    /// Box id
+    #[serde(rename = "boxId")]
+    pub box_id: BoxId,
+    /// Serialized box
+    #[serde(rename = "bytes")]
+    pub bytes: Base16DecodedBytes,
+}
+
+impl TryFrom<SerializedBoxJson> for ErgoBox {
+    type Error = NodeJsonError;
+
+    fn try_from(sb: SerializedBoxJson) -> Result<Self, Self::Error> {
+        let b = ErgoBox::sigma_parse_bytes(&sb.bytes.0).map_err(NodeJsonError::BoxParsingError)?;
+        if b.box_id() == sb.box_id {
+            Ok(b)
+        } else {
+            Err(NodeJsonError::InvalidBoxId(sb.box_id))
+        }
+    }
+}
+
+impl TryFrom<ExtensionJson> for Extension {
+    type Error = NodeJsonError;
+
+    fn try_from(ext: ExtensionJson) -> Result<Self, Self::Error> {
+        let fields = ext
+            .fields
+            .into_iter()
+            .map(|(key, value)| match <[u8; 2]>::try_from(key.0.as_slice()) {
+                Ok(key) => Ok((key, value.0)),
+                Err(_) => Err(NodeJsonError::InvalidExtensionKey(key.0)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let extension = Extension::new(ext.header_id, fields)?;
+        if extension.digest() == ext.digest {
+            Ok(extension)
+        } else {
+            Err(NodeJsonError::InvalidExtensionDigest(ext.digest))
+        }
+    }
+}
+
+/// Checks the header id, that the sections belong to the header, and that the transactions root
+/// and the extension root in the header match the block transactions and extension
+impl TryFrom<FullBlockJson> for FullBlock {
+    type Error = NodeJsonError;
+
+    fn try_from(b: FullBlockJson) -> Result<Self, Self::Error> {
+        let header = Header::try_from(b.header)?;
+        for section_header_id in &[&b.block_transactions.header_id, &b.extension.header_id] {
+            if *section_header_id != &header.id {
+                return Err(NodeJsonError::InvalidSectionHeaderId(
+                    (*section_header_id).clone(),
+                ));
+            }
+        }
+        let transactions = b.block_transactions.transactions;
+        if !BlockTransactionIds::from_transactions(&transactions, header.version)
+            .matches_header(&header)
+        {
+            return Err(NodeJsonError::InvalidTransactionsRoot(
+                header.transaction_root,
+            ));
+        }
+        let extension = Extension::try_from(b.extension)?;
+        if !extension.matches_header(&header) {
+            return Err(NodeJsonError::InvalidExtensionRoot(header.extension_root));
+        }
+        Ok(FullBlock {
+            header,
+            transactions,
+            extension,
+        })
+    }
+}
+
+impl TryFrom<MerkleProofJson> for MerkleProof {
+    type Error = NodeJsonError;
+
+    fn try_from(proof: MerkleProofJson) -> Result<Self, Self::Error> {
+        let levels = proof
+            .levels
+            .into_iter()
+            .map(|(hash, side)| {
+                let side = match side {
+                    0 => NodeSide::Left,
+                    1 => NodeSide::Right,
+                    _ => {
+                        return Err(NodeJsonError::InvalidMerkleProof(format!(
+                            "invalid node side {}",
+                            side
+                        )))
+                    }
+                };
+                let hash = if hash.0.is_empty() {
+                    None
+                } else {
+                    Some(Digest32::try_from(hash).map_err(|e| {
+                        NodeJsonError::InvalidMerkleProof(format!("invalid hash: {}", e))
+                    })?)
+                };
+                Ok(LevelNode { hash, side })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MerkleProof {
+            leaf_data: proof.leaf_data.0,
+            levels,
+        })
+    }
+}
+
+impl From<MerkleProof> for MerkleProofJson {
+    fn from(proof: MerkleProof) -> Self {
+        MerkleProofJson {
+            leaf_data: Base16DecodedBytes(proof.leaf_data),
+            levels: proof
+                .levels
+                .into_iter()
+                .map(|l| {
+                    let hash = l.hash.map(Vec::from).unwrap_or_default();
+                    (Base16DecodedBytes(hash), l.side as u8)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<ParametersJson> for Parameters {
+    fn from(p: ParametersJson) -> Self {
+        Parameters {
+            storage_fee_factor: p.storage_fee_factor,
+            min_value_per_byte: p.min_value_per_byte,
+            max_block_size: p.max_block_size,
+            max_block_cost: p.max_block_cost,
+            token_access_cost: p.token_access_cost,
+            input_cost: p.input_cost,
+            data_input_cost: p.data_input_cost,
+            output_cost: p.output_cost,
+            block_version: p.block_version,
+        }
+    }
+}
+
+/// Build from the last headers (`/blocks/lastHeaders/10`) with the launch parameters,
+/// see [`ErgoStateContext::from_headers`]
+impl TryFrom<Vec<HeaderJson>> for ErgoStateContext {
+    type Error = NodeJsonError;
+
+    fn try_from(headers_json: Vec<HeaderJson>) -> Result<Self, Self::Error> {
+        let headers = headers_json
+            .into_iter()
+            .map(Header::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ErgoStateContext::from_headers(
+            headers,
+            Parameters::default(),
+        )?)
+    }
+}
+
+/// PoW distance is a number in the node API, but can be too big for JSON number types
+mod pow_distance {
+    use num_bigint::BigInt;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumOrStr {
+        Num(u64),
+        Str(String),
+    }
+
+    pub fn serialize<S: Serializer>(d: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&d.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        match NumOrStr::deserialize(deserializer)? {
+            NumOrStr::Num(n) => Ok(n.into()),
+            NumOrStr::Str(s) => s
+                .parse::<BigInt>()
+                .map_err(|e| D::Error::custom(format!("invalid PoW distance {}: {}", s, e))),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryInto;
+
+    use crate::chain::block_transactions::verify_tx_inclusion;
+
+    use super::*;
+
+    // mainnet `/blocks/4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b/header`
+    const HEADER_JSON: &str = r#"
+    {
+        "extensionId": "d16f25b14457186df4c5f6355579cc769261ce1aebc8209949ca6feadbac5a3f",
+        "difficulty": "626412390187008",
+        "votes": "040000",
+        "timestamp": 1618929697400,
+        "size": 221,
+        "stateRoot": "8ad868627ea4f7de6e2a2fe3f98fafe57f914e0f2ef3331c006def36c697f92713",
+        "height": 471746,
+        "nBits": 117586360,
+        "version": 2,
+        "id": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+        "adProofsRoot": "d882aaf42e0a95eb95fcce5c3705adf758e591532f733efe790ac3c404730c39",
+        "transactionsRoot": "63eaa9aff76a1de3d71c81e4b2d92e8d97ae572a8e9ab9e66599ed0912dd2f8b",
+        "extensionHash": "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44",
+        "powSolutions": {
+            "pk": "02b3a06d6eaa8671431ba1db4dd427a77f75a5c2acbd71bfb725d38adc2b55f669",
+            "w": "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "n": "5939ecfee6b0d7f4",
+            "d": 0
+        },
+        "adProofsId": "86eaa41f328bee598e33e52c9e515952ad3b7874102f762847f17318a776a7ae",
+        "transactionsId": "ac80245714f25aa2fafe5494ad02a26d46e7955b8f5709f3659f1b9440797b3e",
+        "parentId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34"
+    }"#;
+
+    const TX_JSON: &str = r#"
+    {
+      "id": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+      "inputs": [
+        {
+          "boxId": "9126af0675056b80d1fda7af9bf658464dbfa0b128afca7bf7dae18c27fe8456",
+          "spendingProof": {
+            "proofBytes": "",
+            "extension": {}
+          }
+        }
+      ],
+      "dataInputs": [],
+      "outputs": [
+        {
+          "boxId": "b979c439dc698ce5e823b21c722a6e23721af010e4df8c72de0bfd0c3d9ccf6b",
+          "value": 74187765000000000,
+          "ergoTree": "101004020e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a7017300730110010204020404040004c0fd4f05808c82f5f6030580b8c9e5ae040580f882ad16040204c0944004c0f407040004000580f882ad16d19683030191a38cc7a7019683020193c2b2a57300007473017302830108cdeeac93a38cc7b2a573030001978302019683040193b1a5730493c2a7c2b2a573050093958fa3730673079973089c73097e9a730a9d99a3730b730c0599c1a7c1b2a5730d00938cc7b2a5730e0001a390c1a7730f",
+          "assets": [],
+          "creationHeight": 284761,
+          "additionalRegisters": {},
+          "transactionId": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+          "index": 0
+        },
+        {
+          "boxId": "e56847ed19b3dc6b72828fcfb992fdf7310828cf291221269b7ffc72fd66706e",
+          "value": 67500000000,
+          "ergoTree": "100204a00b08cd021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604ea02d192a39a8cc7a70173007301",
+          "assets": [],
+          "creationHeight": 284761,
+          "additionalRegisters": {},
+          "transactionId": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+          "index": 1
+        }
+      ],
+      "size": 344
+    }"#;
+
+    /// Chain of the headers (with the ids calculated from the fields) on top of the fixture
+    fn headers_chain(len: u32) -> Vec<HeaderJson> {
+        let mut headers: Vec<HeaderJson> = vec![serde_json::from_str(HEADER_JSON).unwrap()];
+        for _ in 1..len {
+            let parent = headers.last().unwrap();
+            let mut h = HeaderJson {
+                parent_id: parent.id.clone(),
+                height: parent.height + 1,
+                timestamp: parent.timestamp + 120_000,
+                ..parent.clone()
+            };
+            h.id = header_unchecked(h.clone()).unwrap().calc_id().unwrap();
+            headers.push(h);
+        }
+        headers
+    }
+
+    #[test]
+    fn parse_header() {
+        let h: HeaderJson = serde_json::from_str(HEADER_JSON).unwrap();
+        let header = Header::try_from(h.clone()).unwrap();
+        assert_eq!(header.id, h.id);
+        assert_eq!(header.calc_id().unwrap(), h.id);
+        assert_eq!(header.height, 471746);
+        assert_eq!(header.votes, Votes([4, 0, 0]));
+        assert_eq!(header.nonce, base16::decode("5939ecfee6b0d7f4").unwrap());
+        assert_eq!(header.pow_distance, BigInt::from(0));
+        assert_eq!(
+            header.miner_pk.sigma_serialize_bytes().unwrap(),
+            h.pow_solutions.pk.0
+        );
+        // header JSON round trip
+        let h_parsed: HeaderJson =
+            serde_json::from_str(&serde_json::to_string(&h).unwrap()).unwrap();
+        assert_eq!(Header::try_from(h_parsed).unwrap(), header);
+    }
+
+    #[test]
+    fn parse_header_invalid_id() {
+        let h: HeaderJson = serde_json::from_str(HEADER_JSON).unwrap();
+        let tampered = HeaderJson {
+            timestamp: h.timestamp + 1,
+            ..h.clone()
+        };
+        assert_eq!(
+            Header::try_from(tampered),
+            Err(NodeJsonError::InvalidHeaderId(h.id))
+        );
+    }
+
+    #[test]
+    fn parse_header_pow_distance_as_str() {
+        let json = HEADER_JSON.replace(
+            r#""d": 0"#,
+            r#""d": "9873605427306513519728947562939624087652345092583647234""#,
+        );
+        let h: HeaderJson = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            h.pow_solutions.d.to_string(),
+            "9873605427306513519728947562939624087652345092583647234"
+        );
+        let h_parsed: HeaderJson =
+            serde_json::from_str(&serde_json::to_string(&h).unwrap()).unwrap();
+        assert_eq!(h_parsed, h);
+    }
+
+    /// Full block on top of the mainnet header fixture with the fixture transaction and two
+    /// extension fields (genesis block interlink and a one-byte field). Its `transactionsRoot`,
+    /// `extensionHash` and `id` are calculated for these sections.
+    fn full_block_json() -> String {
+        let header_json = HEADER_JSON
+            .replace(
+                "63eaa9aff76a1de3d71c81e4b2d92e8d97ae572a8e9ab9e66599ed0912dd2f8b",
+                "04d6c0bfaac071590b5d953bb8540a25ff71ed7581e03056f3a369a55bf0e184",
+            )
+            .replace(
+                "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44",
+                "3bf55aa7d32078ab1c5a5f3a3a78378a3403a4457f7c725f3d8140809dc9105b",
+            )
+            .replace(
+                "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+                "b3ef04d06e3a9ead3ccf46d91a21cfba13e7b77189b7db3bf6e877823fc1e046",
+            );
+        format!(
+            r#"{{
+            "header": {},
+            "blockTransactions": {{
+                "headerId": "b3ef04d06e3a9ead3ccf46d91a21cfba13e7b77189b7db3bf6e877823fc1e046",
+                "transactions": [{}],
+                "blockVersion": 2,
+                "size": 380
+            }},
+            "extension": {{
+                "headerId": "b3ef04d06e3a9ead3ccf46d91a21cfba13e7b77189b7db3bf6e877823fc1e046",
+                "digest": "3bf55aa7d32078ab1c5a5f3a3a78378a3403a4457f7c725f3d8140809dc9105b",
+                "fields": [
+                    ["0100", "01b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b"],
+                    ["0001", "00"]
+                ]
+            }},
+            "adProofs": {{
+                "headerId": "b3ef04d06e3a9ead3ccf46d91a21cfba13e7b77189b7db3bf6e877823fc1e046",
+                "proofBytes": "0000",
+                "digest": "a80bbd4d69b4f017da6dd9250448ef1cde492121fc350727e755c7b7ae2988ad",
+                "size": 2
+            }},
+            "size": 1053
+        }}"#,
+            header_json, TX_JSON
+        )
+    }
+
+    #[test]
+    fn parse_full_block() {
+        let json = full_block_json();
+        let b: FullBlockJson = serde_json::from_str(&json).unwrap();
+        assert_eq!(b.extension.fields[0].0 .0, vec![1u8, 0]);
+        assert_eq!(b.ad_proofs.clone().unwrap().proof_bytes.0, vec![0u8, 0]);
+        let block = FullBlock::try_from(b).unwrap();
+        assert_eq!(block.header.height, 471746);
+        assert_eq!(block.transactions.len(), 1);
+        let tx_ids = BlockTransactionIds::from_transactions(&block.transactions, 2);
+        let proof = tx_ids.proof_for(&block.transactions[0].id()).unwrap();
+        assert!(verify_tx_inclusion(
+            &proof,
+            &block.transactions[0].id(),
+            &block.header
+        ));
+        assert!(block.extension.matches_header(&block.header));
+        assert_eq!(
+            block.extension.interlinks().unwrap(),
+            vec![BlockId(
+                Digest32::try_from(
+                    "b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b".to_string()
+                )
+                .unwrap()
+            )]
+        );
+        assert!(block.extension.parameters().is_err());
+        // without AD proofs
+        let json = json.replace(r#""adProofs""#, r#""adProofsMissing""#);
+        let b: FullBlockJson = serde_json::from_str(&json).unwrap();
+        assert!(b.ad_proofs.is_none());
+    }
+
+    #[test]
+    fn verify_tx_inclusion_node_proof() {
+        // `/blocks/{headerId}/proofFor/{txId}` for the transaction of the full block fixture
+        let json = r#"
+        {
+            "leafData": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+            "levels": [
+                ["165acf47053d386366a509a4a6c0ac25d3c2c46da692dc8a22df4e611894dc95", 0]
+            ]
+        }"#;
+        let block =
+            FullBlock::try_from(serde_json::from_str::<FullBlockJson>(&full_block_json()).unwrap())
+                .unwrap();
+        let tx_id = block.transactions[0].id();
+        let proof =
+            MerkleProof::try_from(serde_json::from_str::<MerkleProofJson>(json).unwrap()).unwrap();
+        assert_eq!(
+            Some(&proof),
+            BlockTransactionIds::from_transactions(&block.transactions, block.header.version)
+                .proof_for(&tx_id)
+                .as_ref()
+        );
+        assert!(verify_tx_inclusion(&proof, &tx_id, &block.header));
+        let header: HeaderJson = serde_json::from_str(HEADER_JSON).unwrap();
+        assert!(!verify_tx_inclusion(
+            &proof,
+            &tx_id,
+            &Header::try_from(header).unwrap()
+        ));
+    }
+
+    #[test]
+    fn parse_full_block_not_matching_header() {
+        let b: FullBlockJson = serde_json::from_str(&full_block_json()).unwrap();
+        let header_root = |b: &FullBlockJson| b.header.transactions_root.clone();
+        let mut no_txs = b.clone();
+        no_txs.block_transactions.transactions = vec![];
+        assert_eq!(
+            FullBlock::try_from(no_txs),
+            Err(NodeJsonError::InvalidTransactionsRoot(header_root(&b)))
+        );
+        // extension of another block (valid digest of its own fields)
+        let mut other_ext = b.clone();
+        other_ext.extension.fields.truncate(1);
+        other_ext.extension.digest = Extension::new(
+            b.extension.header_id.clone(),
+            vec![([1, 0], b.extension.fields[0].1 .0.clone())],
+        )
+        .unwrap()
+        .digest();
+        assert_eq!(
+            FullBlock::try_from(other_ext),
+            Err(NodeJsonError::InvalidExtensionRoot(
+                b.header.extension_hash.clone()
+            ))
+        );
+        let mut tampered_ext = b.clone();
+        tampered_ext.extension.fields[1].1 = Base16DecodedBytes(vec![1]);
+        assert_eq!(
+            FullBlock::try_from(tampered_ext),
+            Err(NodeJsonError::InvalidExtensionDigest(
+                b.extension.digest.clone()
+            ))
+        );
+        let mut other_block = b.clone();
+        other_block.block_transactions.header_id = b.header.parent_id.clone();
+        assert_eq!(
+            FullBlock::try_from(other_block),
+            Err(NodeJsonError::InvalidSectionHeaderId(
+                b.header.parent_id.clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_mempool_transactions() {
+        let json = format!("[{}]", TX_JSON);
+        let txs: Vec<Transaction> = serde_json::from_str(&json).unwrap();
+        let tx_id: String = txs[0].id().into();
+        assert_eq!(
+            tx_id,
+            "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9"
+        );
+    }
+
+    #[test]
+    fn parse_merkle_proof() {
+        let json = r#"
+        {
+            "leafData": "9148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e9",
+            "levels": [
+                ["", 0],
+                ["ce8a3fe2fd0ad3c6ea2b3f48e5a9ce1f47b1dd9e8a14dcfb2d7a0d5bcc5c82e7", 1]
+            ]
+        }"#;
+        let proof_json: MerkleProofJson = serde_json::from_str(json).unwrap();
+        let proof = MerkleProof::try_from(proof_json.clone()).unwrap();
+        assert_eq!(proof.levels.len(), 2);
+        assert_eq!(proof.levels[0].hash, None);
+        assert_eq!(proof.levels[1].side, NodeSide::Right);
+        assert_eq!(MerkleProofJson::from(proof), proof_json);
+        let invalid_side = json.replace(", 1]", ", 2]");
+        assert!(MerkleProof::try_from(
+            serde_json::from_str::<MerkleProofJson>(&invalid_side).unwrap()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_node_info() {
+        // `/info` of a mainnet node synced up to the header fixture (block 471746)
+        let json = r#"
+        {
+            "currentTime": 1618929713212,
+            "network": "mainnet",
+            "name": "ergo-mainnet-4.0.8",
+            "stateType": "utxo",
+            "difficulty": 626412390187008,
+            "bestFullHeaderId": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+            "bestHeaderId": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+            "peersCount": 30,
+            "unconfirmedCount": 5,
+            "appVersion": "4.0.8",
+            "stateRoot": "8ad868627ea4f7de6e2a2fe3f98fafe57f914e0f2ef3331c006def36c697f92713",
+            "genesisBlockId": "b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b",
+            "previousFullHeaderId": "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34",
+            "fullHeight": 471746,
+            "headersHeight": 471746,
+            "stateVersion": "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b",
+            "fullBlocksScore": 1143247866418049695539200,
+            "launchTime": 1618843285043,
+            "lastSeenMessageTime": 1618929711840,
+            "headersScore": 1143247866418049695539200,
+            "parameters": {
+                "outputCost": 100,
+                "tokenAccessCost": 100,
+                "maxBlockCost": 7030268,
+                "height": 471040,
+                "maxBlockSize": 1271009,
+                "dataInputCost": 100,
+                "blockVersion": 2,
+                "inputCost": 2000,
+                "storageFeeFactor": 1250000,
+                "minValuePerByte": 360
+            },
+            "isMining": false
+        }"#;
+        let info: NodeInfoJson = serde_json::from_str(json).unwrap();
+        let header: HeaderJson = serde_json::from_str(HEADER_JSON).unwrap();
+        assert_eq!(info.full_height, Some(header.height));
+        assert_eq!(info.best_full_header_id, Some(header.id));
+        assert_eq!(info.parameters.min_value_per_byte, 360);
+        assert_eq!(info.parameters.max_block_cost, 7030268);
+        // parameters are adopted at the start of the voting epoch (1024 blocks)
+        assert_eq!(info.parameters.height, header.height / 1024 * 1024);
+        let params = Parameters::from(info.parameters);
+        assert_eq!(params.storage_fee_factor, 1250000);
+        assert_eq!(params.max_block_size, 1271009);
+        assert_eq!(params.block_version, 2);
+    }
+
+    #[test]
+    fn parse_node_info_not_synced() {
+        let json = r#"
+        {
+            "currentTime": 1634654987512,
+            "name": "ergo-testnet-4.0.16",
+            "stateType": "digest",
+            "bestFullHeaderId": null,
+            "bestHeaderId": null,
+            "peersCount": 0,
+            "unconfirmedCount": 0,
+            "appVersion": "4.0.16",
+            "fullHeight": null,
+            "headersHeight": null,
+            "parameters": {
+                "outputCost": 100,
+                "tokenAccessCost": 100,
+                "maxBlockCost": 1000000,
+                "height": 0,
+                "maxBlockSize": 524288,
+                "dataInputCost": 100,
+                "blockVersion": 1,
+                "inputCost": 2000,
+                "storageFeeFactor": 1250000,
+                "minValuePerByte": 360
+            },
+            "isMining": false
+        }"#;
+        let info: NodeInfoJson = serde_json::from_str(json).unwrap();
+        assert_eq!(info.full_height, None);
+        assert_eq!(info.best_header_id, None);
+    }
+
+    #[test]
+    fn parse_serialized_box() {
+        let json = r#"
+        {
+            "boxId": "e56847ed19b3dc6b72828fcfb992fdf7310828cf291221269b7ffc72fd66706e",
+            "bytes": "8086c1bafb01100204a00b08cd021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604ea02d192a39a8cc7a70173007301d9b01100009148408c04c2e38a6402a7950d6157730fa7d49e9ab3b9cadec481d7769918e901"
+        }"#;
+        let sb: SerializedBoxJson = serde_json::from_str(json).unwrap();
+        let b = ErgoBox::try_from(sb.clone()).unwrap();
+        assert_eq!(b.value, 67500000000u64.try_into().unwrap());
+        let wrong_id = SerializedBoxJson {
+            box_id: "9126af0675056b80d1fda7af9bf658464dbfa0b128afca7bf7dae18c27fe8456"
+                .to_string()
+                .try_into()
+                .unwrap(),
+            ..sb
+        };
+        assert!(ErgoBox::try_from(wrong_id).is_err());
+    }
+
+    #[test]
+    fn state_context_from_last_headers() {
+        // node returns the last headers in ascending order
+        let headers = headers_chain(10);
+        let ctx = ErgoStateContext::try_from(headers.clone()).unwrap();
+        assert_eq!(ctx.headers[0].height, 471755);
+        assert_eq!(ctx.headers[9].height, 471746);
+        assert_eq!(ctx.pre_header.height, 471756);
+        assert_eq!(ctx.pre_header.parent_id, headers[9].id);
+        assert_eq!(ctx.parameters, Parameters::default());
+        assert_eq!(
+            ErgoStateContext::try_from(headers[1..].to_vec()),
+            Err(NodeJsonError::StateContextError(
+                ErgoStateContextError::NotEnoughHeaders {
+                    expected: 10,
+                    actual: 9
+                }
+            ))
+        );
+    }
+}
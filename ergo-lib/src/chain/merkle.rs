@@ -0,0 +1,321 @@
+//! Merkle tree and membership proofs (as used for the block sections roots in the header)
+
+use std::convert::TryFrom;
+use std::io::Read;
+
+use ergotree_ir::chain::digest32::blake2b256_hash;
+use ergotree_ir::chain::digest32::Digest32;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::serialization::sigma_byte_reader::SigmaByteRead;
+use ergotree_ir::serialization::sigma_byte_writer::SigmaByteWrite;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+use ergotree_ir::serialization::SigmaSerializeResult;
+
+/// Prefix of the leaf data before hashing
+const LEAF_PREFIX: u8 = 0;
+/// Prefix of the children hashes before hashing
+const INTERNAL_NODE_PREFIX: u8 = 1;
+/// Maximum capacity allocated upfront for the parsed leaf data (the length is read from
+/// the untrusted input, the rest is allocated as the data is actually read)
+const LEAF_DATA_PREALLOC: usize = 4096;
+
+fn leaf_hash(data: &[u8]) -> Digest32 {
+    let mut bytes = Vec::with_capacity(data.len() + 1);
+    bytes.push(LEAF_PREFIX);
+    bytes.extend_from_slice(data);
+    blake2b256_hash(&bytes)
+}
+
+/// Hash of the internal node, `right` is `None` for the missing right child
+fn internal_node_hash(left: &Digest32, right: Option<&Digest32>) -> Digest32 {
+    let mut bytes = Vec::with_capacity(1 + 2 * Digest32::SIZE);
+    bytes.push(INTERNAL_NODE_PREFIX);
+    bytes.extend_from_slice(left.0.as_ref());
+    if let Some(right) = right {
+        bytes.extend_from_slice(right.0.as_ref());
+    }
+    blake2b256_hash(&bytes)
+}
+
+/// Side of the node the proof is going through
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[repr(u8)]
+pub enum NodeSide {
+    /// Node is the left child (the level hash is of its right sibling)
+    Left = 0,
+    /// Node is the right child (the level hash is of its left sibling)
+    Right = 1,
+}
+
+/// Level of the Merkle proof (from the leaf up to the root)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LevelNode {
+    /// Hash of the sibling node (`None` if the node has no sibling)
+    pub hash: Option<Digest32>,
+    /// Side of the node the proof is going through
+    pub side: NodeSide,
+}
+
+/// Proof of the leaf data membership in the Merkle tree
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MerkleProof {
+    /// Leaf data
+    pub leaf_data: Vec<u8>,
+    /// Sibling hashes from the leaf up to the root
+    pub levels: Vec<LevelNode>,
+}
+
+impl MerkleProof {
+    /// Root hash of the tree the leaf data is proven to be in
+    pub fn root_hash(&self) -> Digest32 {
+        self.levels
+            .iter()
+            .fold(leaf_hash(&self.leaf_data), |prev, level| match level.side {
+                NodeSide::Left => internal_node_hash(&prev, level.hash.as_ref()),
+                NodeSide::Right => match &level.hash {
+                    Some(left) => internal_node_hash(left, Some(&prev)),
+                    // never produced by the tree, empty hash is prepended as is
+                    None => internal_node_hash(&prev, None),
+                },
+            })
+    }
+
+    /// Check the proof against the expected root hash
+    pub fn valid(&self, expected_root: &Digest32) -> bool {
+        &self.root_hash() == expected_root
+    }
+}
+
+/// Serialized as VLQ-encoded leaf data length and the leaf data, VLQ-encoded number of levels,
+/// and for each level: side byte, sibling hash length (0 or 32) and the hash
+impl SigmaSerializable for MerkleProof {
+    fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
+        w.put_usize_as_u32_unwrapped(self.leaf_data.len())?;
+        w.write_all(&self.leaf_data)?;
+        w.put_usize_as_u32_unwrapped(self.levels.len())?;
+        for level in &self.levels {
+            w.put_u8(level.side as u8)?;
+            match &level.hash {
+                Some(hash) => {
+                    w.put_u8(Digest32::SIZE as u8)?;
+                    hash.sigma_serialize(w)?;
+                }
+                None => w.put_u8(0)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
+        let leaf_data_len = r.get_u32()? as usize;
+        let mut leaf_data = Vec::with_capacity(leaf_data_len.min(LEAF_DATA_PREALLOC));
+        r.take(leaf_data_len as u64).read_to_end(&mut leaf_data)?;
+        if leaf_data.len() != leaf_data_len {
+            return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                "Merkle proof leaf data length {} exceeds the input size",
+                leaf_data_len
+            )));
+        }
+        let levels_len = r.get_u32()?;
+        let mut levels = Vec::with_capacity(levels_len.min(256) as usize);
+        for _ in 0..levels_len {
+            let side = match r.get_u8()? {
+                0 => NodeSide::Left,
+                1 => NodeSide::Right,
+                b => {
+                    return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                        "invalid Merkle proof node side: {}",
+                        b
+                    )))
+                }
+            };
+            let hash = match r.get_u8()? {
+                0 => None,
+                32 => Some(Digest32::sigma_parse(r)?),
+                len => {
+                    return Err(SigmaParsingError::ValueOutOfBounds(format!(
+                        "invalid Merkle proof hash length: {}",
+                        len
+                    )))
+                }
+            };
+            levels.push(LevelNode { hash, side });
+        }
+        Ok(MerkleProof { leaf_data, levels })
+    }
+}
+
+/// Serialized proof as `Coll[Byte]` constant (e.g. for a register or a context variable)
+impl TryFrom<MerkleProof> for Constant {
+    type Error = SigmaSerializationError;
+
+    fn try_from(proof: MerkleProof) -> Result<Self, Self::Error> {
+        Ok(proof.sigma_serialize_bytes()?.into())
+    }
+}
+
+/// Merkle tree (leaves are hashed as `0 ++ data`, nodes as `1 ++ left ++ right` where `right` is
+/// empty if missing)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MerkleTree {
+    leaves: Vec<Vec<u8>>,
+    /// Node hashes by level, from the leaves up to the root
+    levels: Vec<Vec<Digest32>>,
+}
+
+impl MerkleTree {
+    /// Build the tree over the given leaves
+    pub fn new(leaves: Vec<Vec<u8>>) -> Self {
+        let mut levels: Vec<Vec<Digest32>> = vec![];
+        if !leaves.is_empty() {
+            let mut level: Vec<Digest32> = leaves.iter().map(|l| leaf_hash(l)).collect();
+            loop {
+                let next: Vec<Digest32> = level
+                    .chunks(2)
+                    .map(|pair| internal_node_hash(&pair[0], pair.get(1)))
+                    .collect();
+                levels.push(level);
+                if next.len() == 1 {
+                    levels.push(next);
+                    break;
+                }
+                level = next;
+            }
+        }
+        MerkleTree { leaves, levels }
+    }
+
+    /// Root hash of the tree (hash of the empty byte array for the tree without leaves)
+    pub fn root_hash(&self) -> Digest32 {
+        match self.levels.last().and_then(|l| l.first()) {
+            Some(root) => root.clone(),
+            None => blake2b256_hash(&[]),
+        }
+    }
+
+    /// Proof of membership of the leaf with the given index
+    pub fn proof_by_index(&self, leaf_index: usize) -> Option<MerkleProof> {
+        let leaf_data = self.leaves.get(leaf_index)?.clone();
+        let mut idx = leaf_index;
+        let levels = self
+            .levels
+            .iter()
+            .take(self.levels.len() - 1)
+            .map(|level| {
+                let node = if idx % 2 == 1 {
+                    LevelNode {
+                        hash: level.get(idx - 1).cloned(),
+                        side: NodeSide::Right,
+                    }
+                } else {
+                    LevelNode {
+                        hash: level.get(idx + 1).cloned(),
+                        side: NodeSide::Left,
+                    }
+                };
+                idx /= 2;
+                node
+            })
+            .collect();
+        Some(MerkleProof { leaf_data, levels })
+    }
+
+    /// Proof of membership of the (first) leaf with the given data
+    pub fn proof_by_element(&self, leaf_data: &[u8]) -> Option<MerkleProof> {
+        let idx = self.leaves.iter().position(|l| l.as_slice() == leaf_data)?;
+        self.proof_by_index(idx)
+    }
+}
+
+/// Merkle tree root hash over the given leaves (hash of the empty byte array if there are none)
+pub fn merkle_tree_root(leaves: Vec<Vec<u8>>) -> Digest32 {
+    MerkleTree::new(leaves).root_hash()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ergotree_ir::mir::constant::TryExtractInto;
+    use ergotree_ir::serialization::sigma_serialize_roundtrip;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_root_hash() {
+        assert_eq!(merkle_tree_root(vec![]), blake2b256_hash(&[]));
+        let a = vec![1u8; 32];
+        let b = vec![2u8; 32];
+        let c = vec![3u8; 32];
+        assert_eq!(
+            merkle_tree_root(vec![a.clone()]),
+            internal_node_hash(&leaf_hash(&a), None)
+        );
+        let ab = internal_node_hash(&leaf_hash(&a), Some(&leaf_hash(&b)));
+        assert_eq!(merkle_tree_root(vec![a.clone(), b.clone()]), ab);
+        let c_ = internal_node_hash(&leaf_hash(&c), None);
+        assert_eq!(
+            merkle_tree_root(vec![a, b, c]),
+            internal_node_hash(&ab, Some(&c_))
+        );
+    }
+
+    #[test]
+    fn test_proof_missing_leaf() {
+        let tree = MerkleTree::new(vec![vec![1], vec![2]]);
+        assert!(tree.proof_by_index(2).is_none());
+        assert!(tree.proof_by_element(&[3]).is_none());
+        assert!(MerkleTree::new(vec![]).proof_by_index(0).is_none());
+    }
+
+    #[test]
+    fn test_proof_parse_invalid() {
+        assert!(MerkleProof::sigma_parse_bytes(&[1, 7, 1, 2, 0]).is_err());
+        assert!(MerkleProof::sigma_parse_bytes(&[1, 7, 1, 0, 31]).is_err());
+        // leaf data length u32::MAX, followed by a single byte
+        assert!(matches!(
+            MerkleProof::sigma_parse_bytes(&[0xff, 0xff, 0xff, 0xff, 0x0f, 7]),
+            Err(SigmaParsingError::ValueOutOfBounds(_))
+        ));
+        assert_eq!(
+            MerkleProof::sigma_parse_bytes(&[1, 7, 1, 0, 0]).unwrap(),
+            MerkleProof {
+                leaf_data: vec![7],
+                levels: vec![LevelNode {
+                    hash: None,
+                    side: NodeSide::Left
+                }]
+            }
+        );
+    }
+
+    proptest! {
+
+        #[test]
+        fn test_proofs(leaves in vec(vec(any::<u8>(), 0..40), 1..20)) {
+            let tree = MerkleTree::new(leaves.clone());
+            let root = tree.root_hash();
+            for (idx, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof_by_index(idx).unwrap();
+                prop_assert_eq!(&proof.leaf_data, leaf);
+                prop_assert!(proof.valid(&root));
+                let mut tampered = proof.clone();
+                tampered.leaf_data.push(0);
+                prop_assert!(!tampered.valid(&root));
+            }
+        }
+
+        #[test]
+        fn test_proof_ser_roundtrip(leaves in vec(vec(any::<u8>(), 0..40), 1..20), idx in 0..20usize) {
+            let tree = MerkleTree::new(leaves.clone());
+            let proof = tree.proof_by_index(idx % leaves.len()).unwrap();
+            prop_assert_eq![sigma_serialize_roundtrip(&proof), proof.clone()];
+            let c = Constant::try_from(proof.clone()).unwrap();
+            let bytes = c.try_extract_into::<Vec<u8>>().unwrap();
+            prop_assert_eq![MerkleProof::sigma_parse_bytes(&bytes).unwrap(), proof];
+        }
+    }
+}
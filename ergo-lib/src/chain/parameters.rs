@@ -0,0 +1,421 @@
+//! Blockchain parameters (adjustable by the miners voting)
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::votes::Votes;
+use thiserror::Error;
+
+/// Blockchain parameter (id is used in the votes and block extension keys)
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[repr(u8)]
+pub enum Parameter {
+    /// Storage fee factor (per byte per storage period)
+    StorageFeeFactor = 1,
+    /// Minimal value per byte of the serialized box
+    MinValuePerByte = 2,
+    /// Maximum block size in bytes
+    MaxBlockSize = 3,
+    /// Maximum cumulative computational cost of the block transactions
+    MaxBlockCost = 4,
+    /// Cost of a token contained in a transaction
+    TokenAccessCost = 5,
+    /// Cost of the transaction input
+    InputCost = 6,
+    /// Cost of the transaction data input
+    DataInputCost = 7,
+    /// Cost of the transaction output
+    OutputCost = 8,
+    /// Block version (changed on the soft-fork activation, not voted directly)
+    BlockVersion = 123,
+}
+
+impl Parameter {
+    /// All parameters
+    pub const ALL: [Parameter; 9] = [
+        Parameter::StorageFeeFactor,
+        Parameter::MinValuePerByte,
+        Parameter::MaxBlockSize,
+        Parameter::MaxBlockCost,
+        Parameter::TokenAccessCost,
+        Parameter::InputCost,
+        Parameter::DataInputCost,
+        Parameter::OutputCost,
+        Parameter::BlockVersion,
+    ];
+
+    /// Parameter id
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Parameter with the given id (`None` for unknown ids)
+    pub fn from_id(id: u8) -> Option<Parameter> {
+        Parameter::ALL.iter().copied().find(|p| p.id() == id)
+    }
+
+    /// Minimal value the parameter can be decreased to by the voting
+    pub fn min_value(self) -> i32 {
+        match self {
+            Parameter::MaxBlockSize | Parameter::MaxBlockCost => 16 * 1024,
+            _ => 0,
+        }
+    }
+
+    /// Maximal value the parameter can be increased to by the voting
+    pub fn max_value(self) -> i32 {
+        match self {
+            Parameter::StorageFeeFactor => 2_500_000,
+            Parameter::MinValuePerByte => 10_000,
+            _ => i32::MAX / 2,
+        }
+    }
+
+    /// Change of the parameter by a successful voting, given the current value
+    pub fn step(self, current_value: i32) -> i32 {
+        match self {
+            Parameter::StorageFeeFactor => 25_000,
+            Parameter::MinValuePerByte => 10,
+            _ => std::cmp::max(1, current_value / 100),
+        }
+    }
+}
+
+/// Miner's vote for the parameter change
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum ParameterVote {
+    /// Increase the parameter by its step
+    Increase(Parameter),
+    /// Decrease the parameter by its step
+    Decrease(Parameter),
+}
+
+impl ParameterVote {
+    /// Parse the vote byte (positive parameter id to increase, negative to decrease).
+    /// Returns `None` for no vote (0), soft-fork votes and unknown parameters.
+    pub fn from_byte(b: u8) -> Option<ParameterVote> {
+        let id = b as i8;
+        let parameter = Parameter::from_id(id.unsigned_abs())?;
+        if parameter == Parameter::BlockVersion {
+            None
+        } else if id > 0 {
+            Some(ParameterVote::Increase(parameter))
+        } else {
+            Some(ParameterVote::Decrease(parameter))
+        }
+    }
+
+    /// Encode as the vote byte
+    pub fn to_byte(self) -> u8 {
+        match self {
+            ParameterVote::Increase(p) => p.id(),
+            ParameterVote::Decrease(p) => (-(p.id() as i8)) as u8,
+        }
+    }
+
+    /// Parameter votes in the header votes (duplicates are counted once)
+    pub fn from_votes(votes: &Votes) -> Vec<ParameterVote> {
+        let mut res: Vec<ParameterVote> = vec![];
+        for vote in votes.0.iter().filter_map(|b| ParameterVote::from_byte(*b)) {
+            if !res.contains(&vote) {
+                res.push(vote);
+            }
+        }
+        res
+    }
+
+    /// Encode the parameter votes (up to 3) as the header votes
+    pub fn to_votes(votes: &[ParameterVote]) -> Votes {
+        let mut bytes = [0u8; 3];
+        bytes
+            .iter_mut()
+            .zip(votes.iter())
+            .for_each(|(b, v)| *b = v.to_byte());
+        Votes(bytes)
+    }
+}
+
+/// Errors of the parameters parsing from the block extension
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ParametersError {
+    /// Parameter value is not a 4-byte integer
+    #[error("Invalid value length {len} of the parameter {id}, expected 4 bytes")]
+    InvalidValueLength {
+        /// parameter id
+        id: u8,
+        /// value length
+        len: usize,
+    },
+}
+
+/// Blockchain parameters used by the transaction builders and validators
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Parameters {
+    /// Storage fee factor (per byte per storage period)
+    pub storage_fee_factor: i32,
+    /// Minimal value per byte of the serialized box
+    pub min_value_per_byte: i32,
+    /// Maximum block size in bytes
+    pub max_block_size: i32,
+    /// Maximum cumulative computational cost of the block transactions
+    pub max_block_cost: i32,
+    /// Cost of a token contained in a transaction
+    pub token_access_cost: i32,
+    /// Cost of the transaction input
+    pub input_cost: i32,
+    /// Cost of the transaction data input
+    pub data_input_cost: i32,
+    /// Cost of the transaction output
+    pub output_cost: i32,
+    /// Block version
+    pub block_version: i32,
+}
+
+impl Parameters {
+    /// Storage fee factor set on launch
+    pub const DEFAULT_STORAGE_FEE_FACTOR: i32 = 1_250_000;
+    /// Maximum block size set on launch
+    pub const DEFAULT_MAX_BLOCK_SIZE: i32 = 512 * 1024;
+    /// Maximum block cost set on launch
+    pub const DEFAULT_MAX_BLOCK_COST: i32 = 1_000_000;
+    /// Token access cost set on launch
+    pub const DEFAULT_TOKEN_ACCESS_COST: i32 = 100;
+    /// Input cost set on launch
+    pub const DEFAULT_INPUT_COST: i32 = 2000;
+    /// Data input cost set on launch
+    pub const DEFAULT_DATA_INPUT_COST: i32 = 100;
+    /// Output cost set on launch
+    pub const DEFAULT_OUTPUT_COST: i32 = 100;
+    /// Block version on launch
+    pub const DEFAULT_BLOCK_VERSION: i32 = 1;
+    /// Length of the voting epoch (in blocks) on the mainnet
+    pub const VOTING_EPOCH_LENGTH_MAINNET: u32 = 1024;
+    /// Prefix of the block extension keys holding the parameters (second byte is the parameter id)
+    pub const EXTENSION_KEY_PREFIX: u8 = 0x00;
+
+    /// Storage fee factor as expected by [`ergotree_interpreter::storage_rent::storage_fee`]
+    /// (negative value is treated as zero)
+    pub fn storage_fee_factor_u64(&self) -> u64 {
+        self.storage_fee_factor.max(0) as u64
+    }
+
+    /// Parameter value
+    pub fn get(&self, parameter: Parameter) -> i32 {
+        match parameter {
+            Parameter::StorageFeeFactor => self.storage_fee_factor,
+            Parameter::MinValuePerByte => self.min_value_per_byte,
+            Parameter::MaxBlockSize => self.max_block_size,
+            Parameter::MaxBlockCost => self.max_block_cost,
+            Parameter::TokenAccessCost => self.token_access_cost,
+            Parameter::InputCost => self.input_cost,
+            Parameter::DataInputCost => self.data_input_cost,
+            Parameter::OutputCost => self.output_cost,
+            Parameter::BlockVersion => self.block_version,
+        }
+    }
+
+    /// Set parameter value
+    pub fn set(&mut self, parameter: Parameter, value: i32) {
+        let field = match parameter {
+            Parameter::StorageFeeFactor => &mut self.storage_fee_factor,
+            Parameter::MinValuePerByte => &mut self.min_value_per_byte,
+            Parameter::MaxBlockSize => &mut self.max_block_size,
+            Parameter::MaxBlockCost => &mut self.max_block_cost,
+            Parameter::TokenAccessCost => &mut self.token_access_cost,
+            Parameter::InputCost => &mut self.input_cost,
+            Parameter::DataInputCost => &mut self.data_input_cost,
+            Parameter::OutputCost => &mut self.output_cost,
+            Parameter::BlockVersion => &mut self.block_version,
+        };
+        *field = value;
+    }
+
+    /// Number of votes for each parameter change in the headers of the voting epoch
+    pub fn count_votes(epoch_headers: &[Header]) -> HashMap<ParameterVote, u32> {
+        let mut counts = HashMap::new();
+        for vote in epoch_headers
+            .iter()
+            .flat_map(|h| ParameterVote::from_votes(&h.votes))
+        {
+            *counts.entry(vote).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Parameters for the next voting epoch. A parameter is changed by its step (within its
+    /// min/max bounds) if more than a half of the epoch blocks voted for the change.
+    pub fn update(&self, epoch_headers: &[Header], voting_epoch_length: u32) -> Parameters {
+        let mut updated = *self;
+        for (vote, count) in Parameters::count_votes(epoch_headers) {
+            if count <= voting_epoch_length / 2 {
+                continue;
+            }
+            match vote {
+                ParameterVote::Increase(p) => {
+                    let value = self.get(p);
+                    if value < p.max_value() {
+                        updated.set(p, value.saturating_add(p.step(value)));
+                    }
+                }
+                ParameterVote::Decrease(p) => {
+                    let value = self.get(p);
+                    if value > p.min_value() {
+                        updated.set(p, value.saturating_sub(p.step(value)));
+                    }
+                }
+            }
+        }
+        updated
+    }
+
+    /// Parse parameters from the block extension fields (written in the first block of the voting
+    /// epoch). Keys are `[0x00, parameter id]`, values are 4-byte big-endian integers. Parameters
+    /// missing in the extension have their launch values, other fields are ignored.
+    pub fn from_extension_fields(fields: &[([u8; 2], Vec<u8>)]) -> Result<Self, ParametersError> {
+        let mut params = Parameters::default();
+        for (key, value) in fields {
+            if key[0] != Parameters::EXTENSION_KEY_PREFIX {
+                continue;
+            }
+            if let Some(p) = Parameter::from_id(key[1]) {
+                let bytes: [u8; 4] = value.as_slice().try_into().map_err(|_| {
+                    ParametersError::InvalidValueLength {
+                        id: key[1],
+                        len: value.len(),
+                    }
+                })?;
+                params.set(p, i32::from_be_bytes(bytes));
+            }
+        }
+        Ok(params)
+    }
+
+    /// Block extension fields holding the parameters (see [`Parameters::from_extension_fields`])
+    pub fn to_extension_fields(&self) -> Vec<([u8; 2], Vec<u8>)> {
+        Parameter::ALL
+            .iter()
+            .map(|p| {
+                (
+                    [Parameters::EXTENSION_KEY_PREFIX, p.id()],
+                    self.get(*p).to_be_bytes().to_vec(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Parameters set on launch
+impl Default for Parameters {
+    fn default() -> Self {
+        Parameters {
+            storage_fee_factor: Parameters::DEFAULT_STORAGE_FEE_FACTOR,
+            min_value_per_byte: BoxValue::MIN_VALUE_PER_BOX_BYTE as i32,
+            max_block_size: Parameters::DEFAULT_MAX_BLOCK_SIZE,
+            max_block_cost: Parameters::DEFAULT_MAX_BLOCK_COST,
+            token_access_cost: Parameters::DEFAULT_TOKEN_ACCESS_COST,
+            input_cost: Parameters::DEFAULT_INPUT_COST,
+            data_input_cost: Parameters::DEFAULT_DATA_INPUT_COST,
+            output_cost: Parameters::DEFAULT_OUTPUT_COST,
+            block_version: Parameters::DEFAULT_BLOCK_VERSION,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+
+    fn headers_with_votes(votes: &[[u8; 3]]) -> Vec<Header> {
+        votes
+            .iter()
+            .map(|v| {
+                let mut h = force_any_val::<Header>();
+                h.votes = Votes(*v);
+                h
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_vote_bytes() {
+        assert_eq!(
+            ParameterVote::from_byte(2),
+            Some(ParameterVote::Increase(Parameter::MinValuePerByte))
+        );
+        assert_eq!(
+            ParameterVote::from_byte(0xfe),
+            Some(ParameterVote::Decrease(Parameter::MinValuePerByte))
+        );
+        assert_eq!(ParameterVote::from_byte(0), None);
+        assert_eq!(ParameterVote::from_byte(120), None);
+        assert_eq!(ParameterVote::from_byte(123), None);
+        let votes = vec![
+            ParameterVote::Decrease(Parameter::MaxBlockCost),
+            ParameterVote::Increase(Parameter::InputCost),
+        ];
+        let encoded = ParameterVote::to_votes(&votes);
+        assert_eq!(encoded, Votes([0xfc, 6, 0]));
+        assert_eq!(ParameterVote::from_votes(&encoded), votes);
+        assert_eq!(ParameterVote::from_votes(&Votes([6, 6, 0])).len(), 1);
+    }
+
+    #[test]
+    fn test_update_majority() {
+        let epoch_length = 4;
+        let headers = headers_with_votes(&[[1, 0xfc, 0], [1, 0, 0], [1, 0xfc, 0], [3, 0, 0]]);
+        let params = Parameters::default().update(&headers, epoch_length);
+        assert_eq!(
+            params.storage_fee_factor,
+            Parameters::DEFAULT_STORAGE_FEE_FACTOR + 25_000
+        );
+        // exactly a half is not enough
+        assert_eq!(params.max_block_cost, Parameters::DEFAULT_MAX_BLOCK_COST);
+        assert_eq!(params.max_block_size, Parameters::DEFAULT_MAX_BLOCK_SIZE);
+        assert_eq!(
+            Parameters::default().update(&headers[..2], epoch_length),
+            Parameters::default()
+        );
+    }
+
+    #[test]
+    fn test_update_bounds() {
+        let headers = headers_with_votes(&[[2, 0xf8, 0], [2, 0xf8, 0]]);
+        let mut params = Parameters {
+            min_value_per_byte: Parameter::MinValuePerByte.max_value(),
+            output_cost: 150,
+            ..Parameters::default()
+        };
+        let updated = params.update(&headers, 2);
+        assert_eq!(updated.min_value_per_byte, params.min_value_per_byte);
+        assert_eq!(updated.output_cost, 149);
+        params.output_cost = 0;
+        assert_eq!(params.update(&headers, 2).output_cost, 0);
+    }
+
+    #[test]
+    fn test_extension_fields_roundtrip() {
+        let params = Parameters {
+            max_block_cost: 7_030_268,
+            block_version: 2,
+            ..Parameters::default()
+        };
+        let mut fields = params.to_extension_fields();
+        // interlinks and unknown parameters are ignored
+        fields.push(([0x01, 0x00], vec![1; 33]));
+        fields.push(([0x00, 0x78], vec![0; 4]));
+        assert_eq!(Parameters::from_extension_fields(&fields).unwrap(), params);
+        assert_eq!(
+            Parameters::from_extension_fields(&[([0x00, 0x04], vec![0, 1])]),
+            Err(ParametersError::InvalidValueLength { id: 4, len: 2 })
+        );
+        assert_eq!(
+            Parameters::from_extension_fields(&[]).unwrap(),
+            Parameters::default()
+        );
+    }
+}
@@ -3,6 +3,7 @@
 mod data_input;
 pub mod input;
 pub mod unsigned;
+pub mod verify;
 
 use bounded_vec::BoundedVec;
 use ergotree_ir::chain::digest32::blake2b256_hash;
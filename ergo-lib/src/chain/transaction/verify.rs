@@ -0,0 +1,344 @@
+//! Transaction verification (checking the input proofs against the spent boxes scripts)
+
+use std::sync::Arc;
+
+use ergotree_interpreter::eval::context::Context;
+use ergotree_interpreter::eval::env::Env;
+use ergotree_interpreter::eval::EvalError;
+use ergotree_interpreter::sigma_protocol::verifier::CostLimitedVerifier;
+use ergotree_interpreter::sigma_protocol::verifier::Verifier;
+use ergotree_interpreter::sigma_protocol::verifier::VerifierError;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::serialization::SigmaSerializationError;
+use thiserror::Error;
+
+use crate::chain::ergo_state_context::ErgoStateContext;
+
+use super::Transaction;
+
+/// Errors on transaction verification
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum TxVerifyError {
+    /// Failed to find an input box in boxes_to_spend
+    #[error("Input box not found (index {0})")]
+    InputBoxNotFound(usize),
+    /// Failed to find a data input box in data_boxes
+    #[error("Data input box not found (index {0})")]
+    DataInputBoxNotFound(usize),
+    /// Error on verifying an input proof
+    #[error("Verifier error (tx input index {1}): {0}")]
+    VerifierError(VerifierError, usize),
+    /// Input proof does not satisfy the script of the spent box
+    #[error("Invalid proof (tx input index {0})")]
+    InvalidProof(usize),
+    /// Total cost of the input scripts exceeds the block cost limit (`max_block_cost` parameter)
+    #[error("Script cost limit ({0}) exceeded")]
+    CostLimitExceeded(u64),
+    /// Tx serialization failed (message to verify)
+    #[error("Transaction serialization failed: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+}
+
+/// Verifies proofs of all the transaction inputs one after another. Expired boxes spent without
+/// a proof are checked against the storage rent rules (see [`ergotree_interpreter::storage_rent`]).
+/// Total cost of the input scripts is limited by the `max_block_cost` parameter.
+/// `boxes_to_spend` and `data_boxes` might be in any order.
+/// Returns the total cost of the input scripts.
+pub fn verify_transaction(
+    tx: &Transaction,
+    boxes_to_spend: &[ErgoBox],
+    data_boxes: &[ErgoBox],
+    state_context: &ErgoStateContext,
+) -> Result<u64, TxVerifyError> {
+    let tx_ctx = TxVerificationContext::new(tx, boxes_to_spend, data_boxes, state_context)?;
+    let max_cost = tx_ctx.max_cost();
+    (0..tx.inputs.len()).try_fold(0u64, |total_cost, idx| {
+        let cost = tx_ctx.verify_input(idx, max_cost - total_cost)?;
+        // cost of an input never exceeds the limit it was verified with
+        Ok(total_cost + cost)
+    })
+}
+
+/// Verifies proofs of all the transaction inputs in parallel (in the current rayon thread pool,
+/// use `ThreadPool::install` to run it in a custom one). In case of several failed inputs the
+/// error is reported for the one with the lowest index, same as [`verify_transaction`].
+/// Every input is verified with the whole `max_block_cost` limit, the total cost is checked
+/// once all of them are verified.
+/// `boxes_to_spend` and `data_boxes` might be in any order.
+/// Returns the total cost of the input scripts.
+#[cfg(feature = "parallel")]
+pub fn verify_transaction_parallel(
+    tx: &Transaction,
+    boxes_to_spend: &[ErgoBox],
+    data_boxes: &[ErgoBox],
+    state_context: &ErgoStateContext,
+) -> Result<u64, TxVerifyError> {
+    use rayon::prelude::*;
+
+    let tx_ctx = TxVerificationContext::new(tx, boxes_to_spend, data_boxes, state_context)?;
+    let max_cost = tx_ctx.max_cost();
+    let results: Vec<Result<u64, TxVerifyError>> = (0..tx.inputs.len())
+        .into_par_iter()
+        .map(|idx| tx_ctx.verify_input(idx, max_cost))
+        .collect();
+    results.into_iter().try_fold(0u64, |total_cost, res| {
+        let cost = res?;
+        total_cost
+            .checked_add(cost)
+            .filter(|total_cost| *total_cost <= max_cost)
+            .ok_or(TxVerifyError::CostLimitExceeded(max_cost))
+    })
+}
+
+/// Data shared by the contexts of all the transaction inputs
+struct TxVerificationContext<'a> {
+    tx: &'a Transaction,
+    state_context: &'a ErgoStateContext,
+    message: Vec<u8>,
+    inputs: Vec<Arc<ErgoBox>>,
+    data_inputs: Vec<Arc<ErgoBox>>,
+    outputs: Vec<Arc<ErgoBox>>,
+}
+
+impl<'a> TxVerificationContext<'a> {
+    fn new(
+        tx: &'a Transaction,
+        boxes_to_spend: &[ErgoBox],
+        data_boxes: &[ErgoBox],
+        state_context: &'a ErgoStateContext,
+    ) -> Result<Self, TxVerifyError> {
+        let inputs = tx
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                boxes_to_spend
+                    .iter()
+                    .find(|b| b.box_id() == input.box_id)
+                    .map(|b| Arc::new(b.clone()))
+                    .ok_or(TxVerifyError::InputBoxNotFound(idx))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let data_inputs = tx
+            .data_inputs
+            .iter()
+            .flat_map(|data_inputs| data_inputs.iter())
+            .enumerate()
+            .map(|(idx, data_input)| {
+                data_boxes
+                    .iter()
+                    .find(|b| b.box_id() == data_input.box_id)
+                    .map(|b| Arc::new(b.clone()))
+                    .ok_or(TxVerifyError::DataInputBoxNotFound(idx))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TxVerificationContext {
+            tx,
+            state_context,
+            message: tx.bytes_to_sign()?,
+            inputs,
+            data_inputs,
+            outputs: tx.outputs.iter().cloned().map(Arc::new).collect(),
+        })
+    }
+
+    fn max_cost(&self) -> u64 {
+        self.state_context.parameters.max_block_cost.max(0) as u64
+    }
+
+    /// Verifies the input proof and returns the cost of the script
+    fn verify_input(&self, idx: usize, cost_limit: u64) -> Result<u64, TxVerifyError> {
+        let (input, self_box) = self
+            .tx
+            .inputs
+            .get(idx)
+            .zip(self.inputs.get(idx))
+            .ok_or(TxVerifyError::InputBoxNotFound(idx))?;
+        let ctx = Context {
+            height: self.state_context.pre_header.height,
+            self_box: self_box.clone(),
+            outputs: self.outputs.clone(),
+            data_inputs: self.data_inputs.clone(),
+            inputs: self.inputs.clone(),
+            pre_header: self.state_context.pre_header.clone(),
+            headers: self.state_context.headers.clone(),
+            extension: input.spending_proof.extension.clone(),
+        };
+        let res = CostLimitedVerifier::new(cost_limit)
+            .verify_with_storage_rent(
+                &self_box.ergo_tree,
+                &Env::empty(),
+                Arc::new(ctx),
+                input.spending_proof.proof.clone(),
+                &self.message,
+                self.state_context.parameters.storage_fee_factor_u64(),
+            )
+            .map_err(|e| match e {
+                VerifierError::EvalError(EvalError::CostError(_)) => {
+                    TxVerifyError::CostLimitExceeded(self.max_cost())
+                }
+                e => TxVerifyError::VerifierError(e, idx),
+            })?;
+        if res.result {
+            Ok(res.cost)
+        } else {
+            Err(TxVerifyError::InvalidProof(idx))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::chain::transaction::unsigned::UnsignedTransaction;
+    use crate::chain::transaction::UnsignedInput;
+    use crate::wallet::signing::sign_transaction;
+    use crate::wallet::signing::TransactionContext;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
+    use ergotree_interpreter::sigma_protocol::prover::hint::HintsBag;
+    use ergotree_interpreter::sigma_protocol::prover::TestProver;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::expr::Expr;
+    use std::convert::TryFrom;
+    use std::convert::TryInto;
+
+    fn signed_tx(secrets: &[DlogProverInput]) -> (Transaction, Vec<ErgoBox>) {
+        let boxes_to_spend: Vec<ErgoBox> = secrets
+            .iter()
+            .enumerate()
+            .map(|(idx, secret)| {
+                let tree = ErgoTree::try_from(Expr::Const(secret.public_image().into())).unwrap();
+                ErgoBox::new(
+                    BoxValue::SAFE_USER_MIN,
+                    tree,
+                    vec![],
+                    NonMandatoryRegisters::empty(),
+                    0,
+                    TxId::zero(),
+                    idx as u16,
+                )
+                .unwrap()
+            })
+            .collect();
+        let prover = TestProver {
+            secrets: secrets
+                .iter()
+                .cloned()
+                .map(PrivateInput::DlogProverInput)
+                .collect(),
+        };
+        let inputs: Vec<UnsignedInput> = boxes_to_spend
+            .iter()
+            .cloned()
+            .map(UnsignedInput::from)
+            .collect();
+        let candidate = ErgoBoxCandidateBuilder::new(
+            BoxValue::SAFE_USER_MIN,
+            boxes_to_spend[0].ergo_tree.clone(),
+            0,
+        )
+        .build()
+        .unwrap();
+        let tx = UnsignedTransaction::new(
+            inputs.try_into().unwrap(),
+            None,
+            vec![candidate].try_into().unwrap(),
+        )
+        .unwrap();
+        let tx_context = TransactionContext {
+            spending_tx: tx,
+            boxes_to_spend: boxes_to_spend.clone(),
+            data_boxes: vec![],
+        };
+        let tx = sign_transaction(&prover, tx_context, &ErgoStateContext::dummy()).unwrap();
+        (tx, boxes_to_spend)
+    }
+
+    fn swap_proofs(tx: &mut Transaction, i: usize, j: usize) {
+        let mut inputs = tx.inputs.as_vec().clone();
+        let proof_i = inputs[i].spending_proof.clone();
+        inputs[i].spending_proof = inputs[j].spending_proof.clone();
+        inputs[j].spending_proof = proof_i;
+        tx.inputs = inputs.try_into().unwrap();
+    }
+
+    #[test]
+    fn verify_signed_tx() {
+        let secrets: Vec<DlogProverInput> = (0..8).map(|_| DlogProverInput::random()).collect();
+        let (tx, mut boxes_to_spend) = signed_tx(&secrets);
+        boxes_to_spend.reverse();
+        let state_context = ErgoStateContext::dummy();
+        assert_eq!(
+            verify_transaction(&tx, &boxes_to_spend, &[], &state_context),
+            Ok(8)
+        );
+        assert_eq!(
+            verify_transaction(&tx, &boxes_to_spend[1..], &[], &state_context),
+            Err(TxVerifyError::InputBoxNotFound(7))
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn verify_signed_tx_parallel() {
+        let secrets: Vec<DlogProverInput> = (0..8).map(|_| DlogProverInput::random()).collect();
+        let (mut tx, boxes_to_spend) = signed_tx(&secrets);
+        let state_context = ErgoStateContext::dummy();
+        assert_eq!(
+            verify_transaction_parallel(&tx, &boxes_to_spend, &[], &state_context),
+            Ok(8)
+        );
+        swap_proofs(&mut tx, 6, 5);
+        swap_proofs(&mut tx, 3, 2);
+        assert_eq!(
+            verify_transaction_parallel(&tx, &boxes_to_spend, &[], &state_context),
+            Err(TxVerifyError::InvalidProof(2))
+        );
+    }
+
+    #[test]
+    fn verify_invalid_proofs() {
+        let secrets: Vec<DlogProverInput> = (0..4).map(|_| DlogProverInput::random()).collect();
+        let (mut tx, boxes_to_spend) = signed_tx(&secrets);
+        swap_proofs(&mut tx, 1, 3);
+        let state_context = ErgoStateContext::dummy();
+        assert_eq!(
+            verify_transaction(&tx, &boxes_to_spend, &[], &state_context),
+            Err(TxVerifyError::InvalidProof(1))
+        );
+    }
+
+    #[test]
+    fn verify_cost_limit() {
+        let secrets: Vec<DlogProverInput> = (0..8).map(|_| DlogProverInput::random()).collect();
+        let (tx, boxes_to_spend) = signed_tx(&secrets);
+        let mut state_context = ErgoStateContext::dummy();
+        state_context.parameters.max_block_cost = 7;
+        assert_eq!(
+            verify_transaction(&tx, &boxes_to_spend, &[], &state_context),
+            Err(TxVerifyError::CostLimitExceeded(7))
+        );
+        #[cfg(feature = "parallel")]
+        assert_eq!(
+            verify_transaction_parallel(&tx, &boxes_to_spend, &[], &state_context),
+            Err(TxVerifyError::CostLimitExceeded(7))
+        );
+    }
+
+    #[test]
+    fn interpreter_types_are_thread_safe() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Transaction>();
+        assert_send_sync::<ErgoBox>();
+        assert_send_sync::<ErgoTree>();
+        assert_send_sync::<Context>();
+        assert_send_sync::<ErgoStateContext>();
+        assert_send_sync::<HintsBag>();
+        assert_send_sync::<TestProver>();
+    }
+}
@@ -0,0 +1,223 @@
+//! Widely used contracts (timelocks, AgeUSD, miner fee) and helpers
+//! to spend them
+
+pub mod age_usd;
+pub mod miner_fee;
+pub mod timelock;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::address::AddressEncoderError;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::ergo_box::RegisterValueError;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmountError;
+use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::contract_template::ContractParameter;
+use ergotree_ir::contract_template::ContractTemplate;
+use ergotree_ir::contract_template::ContractTemplateError;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
+use ergotree_ir::mir::constant::TryExtractFrom;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::mir::expr::InvalidArgumentError;
+use ergotree_ir::serialization::SigmaParsingError;
+use ergotree_ir::serialization::SigmaSerializationError;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use thiserror::Error;
+
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::chain::transaction::DataInput;
+use crate::wallet::box_selector::sum_tokens_from_boxes;
+use crate::wallet::box_selector::sum_value;
+use crate::wallet::box_selector::BoxSelection;
+use crate::wallet::box_selector::ErgoBoxAssetsData;
+use crate::wallet::tx_builder::TxBuilder;
+
+/// Errors on the contract spending
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ContractError {
+    /// Box is not guarded by the expected contract
+    #[error("Box is not guarded by the expected contract")]
+    UnexpectedContract,
+    /// Box register is empty or has unexpected type
+    #[error("Box register error: {0}")]
+    RegisterValueError(#[from] RegisterValueError),
+    /// Box does not have the expected token (or enough of it)
+    #[error("Missing token: {0:?}")]
+    MissingToken(TokenId),
+    /// Box does not have the tokens the contract expects
+    #[error("Unexpected box tokens: {0}")]
+    UnexpectedTokens(String),
+    /// Arithmetic overflow (of the values taken from the boxes or the given amounts)
+    #[error("Arithmetic overflow: {0}")]
+    ArithmeticOverflow(String),
+    /// Not enough coins
+    #[error("Not enough coins({0} nanoERGs are missing)")]
+    NotEnoughCoins(u64),
+    /// Spending conditions of the contract are not met
+    #[error("Contract conditions are not met: {0}")]
+    ConditionsNotMet(String),
+    /// ErgoBoxCandidate error
+    #[error("ErgoBoxCandidateBuilder error: {0}")]
+    ErgoBoxCandidateBuilderError(#[from] ErgoBoxCandidateBuilderError),
+    /// Box value error
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Token amount error
+    #[error("Token amount error: {0}")]
+    TokenAmountError(#[from] TokenAmountError),
+    /// Contract parameter value is out of bounds
+    #[error("Invalid contract parameter: {0}")]
+    InvalidParameter(String),
+    /// Contract expression building error
+    #[error("Invalid contract expression: {0}")]
+    InvalidExpr(#[from] InvalidArgumentError),
+    /// ErgoTree error
+    #[error("ErgoTree error: {0}")]
+    ErgoTreeError(#[from] ErgoTreeError),
+    /// Contract template error
+    #[error("Contract template error: {0}")]
+    ContractTemplateError(#[from] ContractTemplateError),
+    /// Address parsing error
+    #[error("Address error: {0}")]
+    AddressEncoderError(#[from] AddressEncoderError),
+    /// Sigma serialization error
+    #[error("Sigma serialization error: {0}")]
+    SigmaSerializationError(#[from] SigmaSerializationError),
+    /// Sigma parsing error
+    #[error("Sigma parsing error: {0}")]
+    SigmaParsingError(#[from] SigmaParsingError),
+}
+
+/// Contract boxes to spend (with the context extensions their scripts expect), data inputs and
+/// outputs their scripts require
+#[derive(PartialEq, Debug, Clone)]
+pub struct ContractSpend {
+    /// Boxes to spend with their context extensions
+    pub inputs: Vec<(ErgoBox, ContextExtension)>,
+    /// Data inputs
+    pub data_inputs: Vec<DataInput>,
+    /// Outputs (in the order the scripts expect them)
+    pub outputs: Vec<ErgoBoxCandidate>,
+}
+
+impl ContractSpend {
+    /// Combine the spends into one transaction (inputs and outputs are concatenated)
+    pub fn merge(self, other: ContractSpend) -> ContractSpend {
+        ContractSpend {
+            inputs: [self.inputs, other.inputs].concat(),
+            data_inputs: [self.data_inputs, other.data_inputs].concat(),
+            outputs: [self.outputs, other.outputs].concat(),
+        }
+    }
+
+    /// Transaction builder spending the contract boxes along with `wallet_boxes` (which pay the
+    /// fee and cover the missing coins and tokens), sending what is left to `change_address`
+    pub fn tx_builder(
+        self,
+        wallet_boxes: Vec<ErgoBox>,
+        current_height: u32,
+        fee_amount: BoxValue,
+        change_address: Address,
+        min_change_value: BoxValue,
+    ) -> Result<TxBuilder<ErgoBox>, ContractError> {
+        let mut boxes: Vec<ErgoBox> = self.inputs.iter().map(|(b, _)| b.clone()).collect();
+        boxes.extend(wallet_boxes);
+        let input_value = sum_value(boxes.as_slice());
+        let output_value = sum_value(self.outputs.as_slice())
+            .checked_add(*fee_amount.as_u64())
+            .ok_or_else(|| ContractError::ArithmeticOverflow("outputs value".to_string()))?;
+        let change_value = input_value
+            .checked_sub(output_value)
+            .ok_or_else(|| ContractError::NotEnoughCoins(output_value - input_value))?;
+        let mut change_tokens = sum_tokens_from_boxes(boxes.as_slice());
+        for (token_id, amount) in sum_tokens_from_boxes(self.outputs.as_slice()) {
+            let input_amount = change_tokens
+                .remove(&token_id)
+                .ok_or_else(|| ContractError::MissingToken(token_id.clone()))?;
+            if input_amount < amount {
+                return Err(ContractError::MissingToken(token_id));
+            } else if input_amount > amount {
+                change_tokens.insert(token_id, input_amount.checked_sub(&amount)?);
+            }
+        }
+        let change_boxes = if change_value == 0 && change_tokens.is_empty() {
+            vec![]
+        } else {
+            vec![ErgoBoxAssetsData {
+                value: change_value.try_into()?,
+                tokens: change_tokens.into_iter().map(Token::from).collect(),
+            }]
+        };
+        let mut tx_builder = TxBuilder::new(
+            BoxSelection {
+                boxes,
+                change_boxes,
+            },
+            self.outputs,
+            current_height,
+            fee_amount,
+            change_address,
+            min_change_value,
+        );
+        tx_builder.set_data_inputs(self.data_inputs);
+        for (b, extension) in self.inputs {
+            tx_builder.set_context_extension(b.box_id(), extension);
+        }
+        Ok(tx_builder)
+    }
+}
+
+/// Spend of the contract box without additional requirements (its value goes to the change)
+fn spend_box(b: &ErgoBox) -> ContractSpend {
+    ContractSpend {
+        inputs: vec![(b.clone(), ContextExtension::empty())],
+        data_inputs: vec![],
+        outputs: vec![],
+    }
+}
+
+/// Token at the given index of the box tokens
+fn token_at(b: &ErgoBox, index: usize) -> Result<Token, ContractError> {
+    b.tokens
+        .get(index)
+        .cloned()
+        .ok_or_else(|| ContractError::UnexpectedTokens(format!("no token at index {}", index)))
+}
+
+/// Template of the contract created from the given instance
+fn template(
+    instance: ErgoTree,
+    parameters: &[(&str, usize)],
+) -> Result<ContractTemplate, ContractError> {
+    Ok(ContractTemplate::new(
+        instance,
+        parameters
+            .iter()
+            .map(|(name, index)| ContractParameter::new(name, *index))
+            .collect(),
+    )?)
+}
+
+/// Value of the contract parameter extracted with the template
+fn parameter<T: TryExtractFrom<Literal>>(
+    values: &HashMap<String, Constant>,
+    name: &str,
+) -> Option<T> {
+    values.get(name)?.clone().try_extract_into::<T>().ok()
+}
+
+/// Public key parameter extracted with the template
+fn pk_parameter(values: &HashMap<String, Constant>, name: &str) -> Option<ProveDlog> {
+    ProveDlog::try_from(values.get(name)?.v.clone()).ok()
+}
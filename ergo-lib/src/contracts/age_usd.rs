@@ -0,0 +1,550 @@
+//! AgeUSD (v0.4) stablecoin bank box, exchange pricing and spend builder. The bank box is spent
+//! (and re-created as the first output) together with the receipt output (second output) holding
+//! the exchanged amounts, the oracle pool box is the first data input.
+
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::BoxRegisters;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::FromBoxRegisters;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+use ergotree_ir::chain::ergo_box::RegisterValueError;
+use ergotree_ir::chain::token::Token;
+
+use crate::chain::contract::Contract;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::transaction::DataInput;
+
+use super::token_at;
+use super::ContractError;
+use super::ContractSpend;
+
+/// Bank contract parameters (constants of the deployed contract)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AgeUsdParameters {
+    /// Exchange fee (percent of the exchanged value)
+    pub fee_percent: i64,
+    /// Reserve coin price (in nanoERGs) when there are no reserve coins or no equity
+    pub rc_default_price: i64,
+    /// Minimal reserve ratio (percent)
+    pub min_reserve_ratio_percent: i64,
+    /// Maximal reserve ratio (percent), applied after the cooling off height
+    pub max_reserve_ratio_percent: i64,
+    /// Height until which reserve coins can be minted regardless of the maximal reserve ratio
+    pub cooling_off_height: u32,
+    /// Minimal value of the bank box (in nanoERGs)
+    pub min_storage_rent: i64,
+}
+
+impl AgeUsdParameters {
+    /// Parameters of the SigUSD bank on the mainnet
+    pub fn sig_usd() -> Self {
+        AgeUsdParameters {
+            fee_percent: 2,
+            rc_default_price: 1000000,
+            min_reserve_ratio_percent: 400,
+            max_reserve_ratio_percent: 800,
+            cooling_off_height: 460000,
+            min_storage_rent: 10000000,
+        }
+    }
+}
+
+/// Exchange with the bank
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AgeUsdAction {
+    /// Mint the given amount of stable coins
+    MintStableCoin(i64),
+    /// Redeem the given amount of stable coins
+    RedeemStableCoin(i64),
+    /// Mint the given amount of reserve coins
+    MintReserveCoin(i64),
+    /// Redeem the given amount of reserve coins
+    RedeemReserveCoin(i64),
+}
+
+/// Bank box registers, R4 - stable coins in circulation (Long), R5 - reserve coins in
+/// circulation (Long)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AgeUsdBankRegisters {
+    /// Stable coins in circulation
+    pub sc_circulating: i64,
+    /// Reserve coins in circulation
+    pub rc_circulating: i64,
+}
+
+impl FromBoxRegisters for AgeUsdBankRegisters {
+    fn from_box_registers<B: BoxRegisters>(b: &B) -> Result<Self, RegisterValueError> {
+        Ok(AgeUsdBankRegisters {
+            sc_circulating: b.get_register_as(NonMandatoryRegisterId::R4.into())?,
+            rc_circulating: b.get_register_as(NonMandatoryRegisterId::R5.into())?,
+        })
+    }
+}
+
+/// Bank box (tokens: stable coins, reserve coins, bank NFT; value is the reserve)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AgeUsdBank {
+    /// Box
+    pub ergo_box: ErgoBox,
+    /// Registers
+    pub registers: AgeUsdBankRegisters,
+    /// Stable coins held by the bank
+    pub stable_coins: Token,
+    /// Reserve coins held by the bank
+    pub reserve_coins: Token,
+    /// Bank NFT
+    pub bank_nft: Token,
+}
+
+impl TryFrom<ErgoBox> for AgeUsdBank {
+    type Error = ContractError;
+
+    fn try_from(ergo_box: ErgoBox) -> Result<Self, Self::Error> {
+        Ok(AgeUsdBank {
+            registers: ergo_box.registers_as()?,
+            stable_coins: token_at(&ergo_box, 0)?,
+            reserve_coins: token_at(&ergo_box, 1)?,
+            bank_nft: token_at(&ergo_box, 2)?,
+            ergo_box,
+        })
+    }
+}
+
+impl AgeUsdBank {
+    /// Base currency (nanoERGs) reserve
+    pub fn reserve(&self) -> i64 {
+        self.ergo_box.value.as_i64()
+    }
+
+    /// Base currency needed to cover the stable coins in circulation, limited by the reserve.
+    /// `rate` is the price of one stable coin (in nanoERGs).
+    pub fn liabilities(&self, rate: i64) -> i64 {
+        self.registers
+            .sc_circulating
+            .saturating_mul(rate)
+            .min(self.reserve())
+            .max(0)
+    }
+
+    /// Reserve not covering the stable coins
+    pub fn equity(&self, rate: i64) -> i64 {
+        self.reserve() - self.liabilities(rate)
+    }
+
+    /// Stable coin price (in nanoERGs)
+    pub fn sc_nominal_price(&self, rate: i64) -> i64 {
+        if self.registers.sc_circulating == 0 {
+            rate
+        } else {
+            rate.min(self.liabilities(rate) / self.registers.sc_circulating)
+        }
+    }
+
+    /// Reserve coin price (in nanoERGs)
+    pub fn rc_nominal_price(&self, rate: i64, parameters: &AgeUsdParameters) -> i64 {
+        let equity = self.equity(rate);
+        if self.registers.rc_circulating == 0 || equity == 0 {
+            parameters.rc_default_price
+        } else {
+            equity / self.registers.rc_circulating
+        }
+    }
+
+    /// Exchange with the bank at the rate of the oracle pool box (R4 is the price of one USD in
+    /// nanoERGs, a stable coin is one cent). Stable coins minted (or reserve coins redeemed) go to
+    /// the receipt box of `receiver`; the redeemed coins and the ERGs for the minted ones are
+    /// expected to be provided by the wallet boxes (see [`ContractSpend::tx_builder`]).
+    pub fn exchange(
+        &self,
+        parameters: &AgeUsdParameters,
+        oracle_box: &ErgoBox,
+        action: AgeUsdAction,
+        receiver: &Address,
+        height: u32,
+    ) -> Result<ContractSpend, ContractError> {
+        let overflow = || ContractError::ArithmeticOverflow("AgeUSD exchange".to_string());
+        let rate = oracle_box.get_register_as::<i64>(NonMandatoryRegisterId::R4.into())? / 100;
+        let (is_sc_exchange, delta) = match action {
+            AgeUsdAction::MintStableCoin(amount) => (true, Some(amount)),
+            AgeUsdAction::RedeemStableCoin(amount) => (true, amount.checked_neg()),
+            AgeUsdAction::MintReserveCoin(amount) => (false, Some(amount)),
+            AgeUsdAction::RedeemReserveCoin(amount) => (false, amount.checked_neg()),
+        };
+        let delta = delta.ok_or_else(overflow)?;
+        if delta == 0 {
+            return Err(ContractError::ConditionsNotMet(
+                "exchange amount is zero".to_string(),
+            ));
+        }
+        let price = if is_sc_exchange {
+            self.sc_nominal_price(rate)
+        } else {
+            self.rc_nominal_price(rate, parameters)
+        };
+        let reserve_delta = price.checked_mul(delta).ok_or_else(overflow)?;
+        let fee = reserve_delta
+            .checked_mul(parameters.fee_percent)
+            .and_then(|f| (f / 100).checked_abs())
+            .ok_or_else(overflow)?;
+        let reserve_delta_with_fee = reserve_delta.checked_add(fee).ok_or_else(overflow)?;
+        let (sc_delta, rc_delta) = if is_sc_exchange {
+            (delta, 0)
+        } else {
+            (0, delta)
+        };
+        let sc_circulating_out = self
+            .registers
+            .sc_circulating
+            .checked_add(sc_delta)
+            .ok_or_else(overflow)?;
+        let rc_circulating_out = self
+            .registers
+            .rc_circulating
+            .checked_add(rc_delta)
+            .ok_or_else(overflow)?;
+        if sc_circulating_out < 0 || rc_circulating_out < 0 {
+            return Err(ContractError::ConditionsNotMet(
+                "redeemed amount exceeds the circulating supply".to_string(),
+            ));
+        }
+        let reserve_out = self
+            .reserve()
+            .checked_add(reserve_delta_with_fee)
+            .ok_or_else(overflow)?;
+        if reserve_out < parameters.min_storage_rent {
+            return Err(ContractError::ConditionsNotMet(
+                "bank reserve would drop below the minimal storage rent".to_string(),
+            ));
+        }
+        self.check_reserve_ratio(
+            parameters,
+            rate,
+            reserve_out,
+            sc_circulating_out,
+            is_sc_exchange,
+            delta,
+            height,
+        )?;
+
+        let mut bank_out = ErgoBoxCandidateBuilder::new(
+            BoxValue::try_from(reserve_out)?,
+            self.ergo_box.ergo_tree.clone(),
+            height,
+        );
+        bank_out.add_token(Token {
+            token_id: self.stable_coins.token_id.clone(),
+            amount: amount_after(&self.stable_coins, sc_delta)?.try_into()?,
+        });
+        bank_out.add_token(Token {
+            token_id: self.reserve_coins.token_id.clone(),
+            amount: amount_after(&self.reserve_coins, rc_delta)?.try_into()?,
+        });
+        bank_out.add_token(self.bank_nft.clone());
+        bank_out.set_register_value(NonMandatoryRegisterId::R4, sc_circulating_out.into());
+        bank_out.set_register_value(NonMandatoryRegisterId::R5, rc_circulating_out.into());
+
+        let mut receipt = ErgoBoxCandidateBuilder::new(
+            BoxValue::SAFE_USER_MIN,
+            Contract::pay_to_address(receiver)?.ergo_tree(),
+            height,
+        );
+        if delta > 0 {
+            let token = if is_sc_exchange {
+                &self.stable_coins
+            } else {
+                &self.reserve_coins
+            };
+            receipt.add_token(Token {
+                token_id: token.token_id.clone(),
+                amount: (delta as u64).try_into()?,
+            });
+        }
+        receipt.set_register_value(NonMandatoryRegisterId::R4, delta.into());
+        receipt.set_register_value(NonMandatoryRegisterId::R5, reserve_delta_with_fee.into());
+        let min_receipt_value = receipt.calc_min_box_value()?;
+        let receipt_value = reserve_delta_with_fee
+            .checked_neg()
+            .ok_or_else(overflow)?
+            .max(min_receipt_value.as_i64());
+        receipt.set_value(BoxValue::try_from(receipt_value)?);
+
+        Ok(ContractSpend {
+            inputs: vec![(self.ergo_box.clone(), ContextExtension::empty())],
+            data_inputs: vec![DataInput {
+                box_id: oracle_box.box_id(),
+            }],
+            outputs: vec![bank_out.build()?, receipt.build()?],
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_reserve_ratio(
+        &self,
+        parameters: &AgeUsdParameters,
+        rate: i64,
+        reserve_out: i64,
+        sc_circulating_out: i64,
+        is_sc_exchange: bool,
+        delta: i64,
+        height: u32,
+    ) -> Result<(), ContractError> {
+        let max_reserve_ratio_percent = if height > parameters.cooling_off_height {
+            parameters.max_reserve_ratio_percent
+        } else {
+            i64::MAX
+        };
+        let reserve_needed_out = sc_circulating_out.saturating_mul(rate);
+        let reserve_ratio_percent_out = if reserve_needed_out == 0 {
+            max_reserve_ratio_percent
+        } else {
+            reserve_out.saturating_mul(100) / reserve_needed_out
+        };
+        let (is_valid, limit) = match (is_sc_exchange, delta > 0) {
+            (true, false) => (true, ""),
+            (false, true) => (
+                reserve_ratio_percent_out <= max_reserve_ratio_percent,
+                "above the maximal",
+            ),
+            _ => (
+                reserve_ratio_percent_out >= parameters.min_reserve_ratio_percent,
+                "below the minimal",
+            ),
+        };
+        if is_valid {
+            Ok(())
+        } else {
+            Err(ContractError::ConditionsNotMet(format!(
+                "reserve ratio {}% would be {} one",
+                reserve_ratio_percent_out, limit
+            )))
+        }
+    }
+}
+
+/// Amount of the bank token after `delta` of it is given out by the bank
+fn amount_after(token: &Token, delta: i64) -> Result<u64, ContractError> {
+    i64::try_from(*token.amount.as_u64())
+        .ok()
+        .and_then(|amount| amount.checked_sub(delta))
+        .and_then(|amount| u64::try_from(amount).ok())
+        .ok_or_else(|| {
+            ContractError::ArithmeticOverflow(format!("amount of token {:?}", token.token_id))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_ir::chain::token::TokenId;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::ergo_tree::ErgoTree;
+    use ergotree_ir::mir::constant::Constant;
+    use sigma_test_util::force_any_val;
+    use std::collections::HashMap;
+
+    fn new_box(
+        value: u64,
+        tokens: Vec<Token>,
+        registers: Vec<(NonMandatoryRegisterId, Constant)>,
+    ) -> ErgoBox {
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            tokens,
+            registers
+                .into_iter()
+                .collect::<HashMap<_, _>>()
+                .try_into()
+                .unwrap(),
+            0,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn token(amount: u64) -> Token {
+        Token {
+            token_id: force_any_val::<TokenId>(),
+            amount: amount.try_into().unwrap(),
+        }
+    }
+
+    // 1 USD = 0.5 ERG, so a stable coin (1 cent) is 5000000 nanoERGs
+    fn oracle_box() -> ErgoBox {
+        new_box(
+            10000000,
+            vec![token(1)],
+            vec![(NonMandatoryRegisterId::R4, 500000000i64.into())],
+        )
+    }
+
+    // 1000 stable coins (liabilities 5 ERG), 100 reserve coins, 100 ERG reserve
+    fn bank() -> AgeUsdBank {
+        AgeUsdBank::try_from(new_box(
+            100000000000,
+            vec![token(1000000), token(1000000), token(1)],
+            vec![
+                (NonMandatoryRegisterId::R4, 1000i64.into()),
+                (NonMandatoryRegisterId::R5, 100i64.into()),
+            ],
+        ))
+        .unwrap()
+    }
+
+    fn receiver() -> Address {
+        Address::P2Pk(DlogProverInput::random().public_image())
+    }
+
+    #[test]
+    fn prices() {
+        let bank = bank();
+        let rate = 5000000;
+        assert_eq!(bank.liabilities(rate), 5000000000);
+        assert_eq!(bank.equity(rate), 95000000000);
+        assert_eq!(bank.sc_nominal_price(rate), 5000000);
+        assert_eq!(
+            bank.rc_nominal_price(rate, &AgeUsdParameters::sig_usd()),
+            950000000
+        );
+    }
+
+    #[test]
+    fn mint_stable_coins() {
+        let bank = bank();
+        let oracle_box = oracle_box();
+        let spend = bank
+            .exchange(
+                &AgeUsdParameters::sig_usd(),
+                &oracle_box,
+                AgeUsdAction::MintStableCoin(100),
+                &receiver(),
+                500000,
+            )
+            .unwrap();
+        let bank_out = &spend.outputs[0];
+        let receipt = &spend.outputs[1];
+        // 100 * 5000000 + 2% fee
+        assert_eq!(bank_out.value.as_i64(), 100000000000 + 510000000);
+        assert_eq!(*bank_out.tokens[0].amount.as_u64(), 1000000 - 100);
+        assert_eq!(
+            bank_out.registers_as::<AgeUsdBankRegisters>(),
+            Ok(AgeUsdBankRegisters {
+                sc_circulating: 1100,
+                rc_circulating: 100
+            })
+        );
+        assert_eq!(
+            receipt.tokens[0],
+            Token {
+                token_id: bank.stable_coins.token_id.clone(),
+                amount: 100u64.try_into().unwrap()
+            }
+        );
+        assert_eq!(
+            receipt.get_register_as::<i64>(NonMandatoryRegisterId::R5.into()),
+            Ok(510000000)
+        );
+        assert_eq!(spend.data_inputs[0].box_id, oracle_box.box_id());
+    }
+
+    #[test]
+    fn redeem_reserve_coins() {
+        let bank = bank();
+        let spend = bank
+            .exchange(
+                &AgeUsdParameters::sig_usd(),
+                &oracle_box(),
+                AgeUsdAction::RedeemReserveCoin(10),
+                &receiver(),
+                500000,
+            )
+            .unwrap();
+        let receipt = &spend.outputs[1];
+        // 10 * 950000000 - 2% fee
+        assert_eq!(receipt.value.as_i64(), 9310000000);
+        assert_eq!(
+            receipt.get_register_as::<i64>(NonMandatoryRegisterId::R4.into()),
+            Ok(-10)
+        );
+        assert_eq!(spend.outputs[0].value.as_i64(), 100000000000 - 9310000000);
+        assert_eq!(*spend.outputs[0].tokens[1].amount.as_u64(), 1000000 + 10);
+    }
+
+    #[test]
+    fn reserve_ratio_limits() {
+        let bank = bank();
+        let parameters = AgeUsdParameters::sig_usd();
+        // reserve ratio is 2000%, minting reserve coins is allowed only during cooling off
+        let mint_rc = |height| {
+            bank.exchange(
+                &parameters,
+                &oracle_box(),
+                AgeUsdAction::MintReserveCoin(1),
+                &receiver(),
+                height,
+            )
+        };
+        assert!(mint_rc(460000).is_ok());
+        assert!(matches!(
+            mint_rc(460001),
+            Err(ContractError::ConditionsNotMet(_))
+        ));
+        // minting 5000 stable coins (25 ERG liabilities) leaves the reserve ratio ~500%
+        assert!(bank
+            .exchange(
+                &parameters,
+                &oracle_box(),
+                AgeUsdAction::MintStableCoin(5000),
+                &receiver(),
+                500000,
+            )
+            .is_ok());
+        assert!(matches!(
+            bank.exchange(
+                &parameters,
+                &oracle_box(),
+                AgeUsdAction::MintStableCoin(10000),
+                &receiver(),
+                500000,
+            ),
+            Err(ContractError::ConditionsNotMet(_))
+        ));
+    }
+    #[test]
+    fn exchange_overflow() {
+        let parameters = AgeUsdParameters::sig_usd();
+        let exchange = |bank: &AgeUsdBank, action| {
+            bank.exchange(&parameters, &oracle_box(), action, &receiver(), 500000)
+        };
+        let bank = bank();
+        for action in [
+            AgeUsdAction::RedeemStableCoin(i64::MIN),
+            AgeUsdAction::MintStableCoin(i64::MAX),
+            // price * amount fits, adding the fee does not
+            AgeUsdAction::MintStableCoin(i64::MAX / 5000000),
+        ] {
+            assert!(matches!(
+                exchange(&bank, action),
+                Err(ContractError::ArithmeticOverflow(_))
+            ));
+        }
+        let bank_max_circulating = AgeUsdBank {
+            registers: AgeUsdBankRegisters {
+                sc_circulating: i64::MAX,
+                rc_circulating: 100,
+            },
+            ..bank
+        };
+        assert!(matches!(
+            exchange(&bank_max_circulating, AgeUsdAction::MintStableCoin(1)),
+            Err(ContractError::ArithmeticOverflow(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! Miner fee contract, i.e. the contract of the transaction fee boxes, which can only be
+//! collected by the block miner into the miner reward box (spendable after the reward delay)
+
+use ergotree_ir::chain::address::AddressEncoder;
+use ergotree_ir::chain::address::NetworkPrefix;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use ergotree_ir::util::AsVecI8;
+
+use crate::constants::MINERS_FEE_MAINNET_ADDRESS;
+
+use super::parameter;
+use super::template;
+use super::ContractError;
+
+/// Reward delay (in blocks) of the miner fee contract used on the mainnet
+pub const MAINNET_REWARD_DELAY: i32 = 720;
+
+/// Index of the serialized reward script in the fee contract constants
+const REWARD_SCRIPT_INDEX: usize = 2;
+/// Index of the reward delay in the reward script constants
+const REWARD_DELAY_INDEX: usize = 0;
+/// Index of the miner public key in the reward script constants
+const MINER_PK_INDEX: usize = 1;
+
+/// Miner fee contract, `sigmaProp(HEIGHT == OUTPUTS(0).creationInfo._1 && OUTPUTS.size == 1 &&
+/// OUTPUTS(0).propositionBytes == substConstants(rewardScript, Coll(1), Coll(minerPk)))`,
+/// where the reward script is `sigmaProp(HEIGHT >= SELF.creationInfo._1 + rewardDelay) &&
+/// minerPk`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MinerFeeContract {
+    /// Number of blocks after which the miner can spend the reward box
+    pub reward_delay: i32,
+}
+
+impl MinerFeeContract {
+    /// Contract used on the mainnet
+    pub fn mainnet() -> Self {
+        MinerFeeContract {
+            reward_delay: MAINNET_REWARD_DELAY,
+        }
+    }
+
+    /// Contract tree
+    pub fn ergo_tree(&self) -> Result<ErgoTree, ContractError> {
+        let reward_script = self.reward_script_template()?.sigma_serialize_bytes()?;
+        with_constant(
+            mainnet_tree()?,
+            REWARD_SCRIPT_INDEX,
+            reward_script.as_vec_i8().into(),
+        )
+    }
+
+    /// Parse the contract parameters from the tree
+    pub fn from_ergo_tree(tree: &ErgoTree) -> Option<MinerFeeContract> {
+        let values = template(
+            mainnet_tree().ok()?,
+            &[("rewardScript", REWARD_SCRIPT_INDEX)],
+        )
+        .ok()?
+        .match_tree(tree)?;
+        let reward_script =
+            ErgoTree::sigma_parse_bytes(&parameter::<Vec<u8>>(&values, "rewardScript")?).ok()?;
+        let reward_values = template(
+            MinerFeeContract::mainnet().reward_script_template().ok()?,
+            &[("rewardDelay", REWARD_DELAY_INDEX)],
+        )
+        .ok()?
+        .match_tree(&reward_script)?;
+        Some(MinerFeeContract {
+            reward_delay: parameter(&reward_values, "rewardDelay")?,
+        })
+    }
+
+    /// Script of the miner reward box the fee boxes are collected into
+    pub fn reward_script(&self, miner_pk: &ProveDlog) -> Result<ErgoTree, ContractError> {
+        with_constant(
+            self.reward_script_template()?,
+            MINER_PK_INDEX,
+            miner_pk.clone().into(),
+        )
+    }
+
+    /// Reward script with the placeholder miner public key
+    fn reward_script_template(&self) -> Result<ErgoTree, ContractError> {
+        let bytes: Vec<u8> = mainnet_tree()?
+            .get_constant(REWARD_SCRIPT_INDEX)
+            .map_err(ErgoTreeError::from)?
+            .and_then(|c| c.try_extract_into().ok())
+            .ok_or(ContractError::UnexpectedContract)?;
+        with_constant(
+            ErgoTree::sigma_parse_bytes(&bytes)?,
+            REWARD_DELAY_INDEX,
+            self.reward_delay.into(),
+        )
+    }
+}
+
+/// Tree with the constant at the given index replaced (the index and the type of the new value
+/// are checked by the contract template)
+fn with_constant(tree: ErgoTree, index: usize, value: Constant) -> Result<ErgoTree, ContractError> {
+    let values = std::iter::once(("value".to_string(), value)).collect();
+    Ok(template(tree, &[("value", index)])?.instantiate(&values)?)
+}
+
+fn mainnet_tree() -> Result<ErgoTree, ContractError> {
+    Ok(AddressEncoder::new(NetworkPrefix::Mainnet)
+        .parse_address_from_str(MINERS_FEE_MAINNET_ADDRESS)?
+        .script()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::timelock::HeightTimelock;
+    use crate::wallet::tx_builder::new_miner_fee_box;
+    use crate::wallet::tx_builder::SUGGESTED_TX_FEE;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+
+    #[test]
+    fn parse_mainnet_fee_box() {
+        let fee_box = new_miner_fee_box(SUGGESTED_TX_FEE(), 0).unwrap();
+        assert_eq!(
+            MinerFeeContract::from_ergo_tree(&fee_box.ergo_tree),
+            Some(MinerFeeContract::mainnet())
+        );
+        assert_eq!(
+            MinerFeeContract::mainnet().ergo_tree().unwrap(),
+            fee_box.ergo_tree
+        );
+    }
+
+    #[test]
+    fn custom_reward_delay() {
+        let contract = MinerFeeContract { reward_delay: 10 };
+        let tree = contract.ergo_tree().unwrap();
+        assert_ne!(tree, mainnet_tree().unwrap());
+        assert_eq!(MinerFeeContract::from_ergo_tree(&tree), Some(contract));
+        let timelock = HeightTimelock {
+            recipient: DlogProverInput::random().public_image(),
+            unlock_height: 10,
+        };
+        assert_eq!(
+            MinerFeeContract::from_ergo_tree(&timelock.ergo_tree().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn reward_script() {
+        let miner_pk = DlogProverInput::random().public_image();
+        let reward_script = MinerFeeContract::mainnet()
+            .reward_script(&miner_pk)
+            .unwrap();
+        assert_eq!(
+            reward_script.get_constant(MINER_PK_INDEX).unwrap(),
+            Some(miner_pk.into())
+        );
+        assert_eq!(
+            reward_script.get_constant(REWARD_DELAY_INDEX).unwrap(),
+            Some(MAINNET_REWARD_DELAY.into())
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Height timelock and pay-to-public-key with deadline refund
+
+use std::convert::TryFrom;
+
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::bin_op::BinOp;
+use ergotree_ir::mir::bin_op::RelationOp;
+use ergotree_ir::mir::block::BlockValue;
+use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::global_vars::GlobalVars;
+use ergotree_ir::mir::sigma_and::SigmaAnd;
+use ergotree_ir::mir::sigma_or::SigmaOr;
+use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
+use ergotree_ir::mir::val_def::ValDef;
+use ergotree_ir::mir::val_use::ValUse;
+use ergotree_ir::sigma_protocol::dlog_group;
+use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+use ergotree_ir::types::stype::SType;
+
+use super::parameter;
+use super::pk_parameter;
+use super::spend_box;
+use super::template;
+use super::ContractError;
+use super::ContractSpend;
+
+/// Box spendable by the recipient after the given height,
+/// `sigmaProp(HEIGHT >= unlockHeight) && recipient`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HeightTimelock {
+    /// Public key of the recipient
+    pub recipient: ProveDlog,
+    /// First height the box can be spent at
+    pub unlock_height: i32,
+}
+
+impl HeightTimelock {
+    /// Contract tree
+    pub fn ergo_tree(&self) -> Result<ErgoTree, ContractError> {
+        let expr: Expr = SigmaAnd::new(vec![
+            height_condition(RelationOp::Ge, Expr::Const(self.unlock_height.into()))?,
+            Expr::Const(self.recipient.clone().into()),
+        ])?
+        .into();
+        Ok(ErgoTree::try_from(expr)?)
+    }
+
+    /// Parse the contract parameters from the tree
+    pub fn from_ergo_tree(tree: &ErgoTree) -> Option<HeightTimelock> {
+        let sample = HeightTimelock {
+            recipient: dlog_group::generator().into(),
+            unlock_height: 0,
+        };
+        let values = template(
+            sample.ergo_tree().ok()?,
+            &[("unlockHeight", 0), ("recipient", 1)],
+        )
+        .ok()?
+        .match_tree(tree)?;
+        Some(HeightTimelock {
+            recipient: pk_parameter(&values, "recipient")?,
+            unlock_height: parameter(&values, "unlockHeight")?,
+        })
+    }
+
+    /// Spend the box guarded by this contract (its value goes to the change, the recipient
+    /// signs the transaction)
+    pub fn spend(&self, b: &ErgoBox, height: u32) -> Result<ContractSpend, ContractError> {
+        if HeightTimelock::from_ergo_tree(&b.ergo_tree).as_ref() != Some(self) {
+            return Err(ContractError::UnexpectedContract);
+        }
+        if (height as i64) < self.unlock_height as i64 {
+            return Err(ContractError::ConditionsNotMet(format!(
+                "box is locked until height {}",
+                self.unlock_height
+            )));
+        }
+        Ok(spend_box(b))
+    }
+}
+
+/// Box spendable by the recipient until the deadline (inclusive), and by the sender after it,
+/// `(recipient && sigmaProp(HEIGHT <= deadline)) || (sender && sigmaProp(HEIGHT > deadline))`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct P2pkWithRefund {
+    /// Public key of the recipient
+    pub recipient: ProveDlog,
+    /// Public key of the sender (refund receiver)
+    pub sender: ProveDlog,
+    /// Last height the recipient can spend the box at
+    pub deadline: i32,
+}
+
+impl P2pkWithRefund {
+    /// Contract tree
+    pub fn ergo_tree(&self) -> Result<ErgoTree, ContractError> {
+        let deadline: Expr = ValUse {
+            val_id: 1.into(),
+            tpe: SType::SInt,
+        }
+        .into();
+        let claim: Expr = SigmaAnd::new(vec![
+            Expr::Const(self.recipient.clone().into()),
+            height_condition(RelationOp::Le, deadline.clone())?,
+        ])?
+        .into();
+        let refund: Expr = SigmaAnd::new(vec![
+            Expr::Const(self.sender.clone().into()),
+            height_condition(RelationOp::Gt, deadline)?,
+        ])?
+        .into();
+        let expr: Expr = BlockValue {
+            items: vec![ValDef {
+                id: 1.into(),
+                rhs: Box::new(Expr::Const(self.deadline.into())),
+            }
+            .into()],
+            result: Box::new(SigmaOr::new(vec![claim, refund])?.into()),
+        }
+        .into();
+        Ok(ErgoTree::try_from(expr)?)
+    }
+
+    /// Parse the contract parameters from the tree
+    pub fn from_ergo_tree(tree: &ErgoTree) -> Option<P2pkWithRefund> {
+        let sample = P2pkWithRefund {
+            recipient: dlog_group::generator().into(),
+            sender: dlog_group::generator().into(),
+            deadline: 0,
+        };
+        let values = template(
+            sample.ergo_tree().ok()?,
+            &[("deadline", 0), ("recipient", 1), ("sender", 2)],
+        )
+        .ok()?
+        .match_tree(tree)?;
+        Some(P2pkWithRefund {
+            recipient: pk_parameter(&values, "recipient")?,
+            sender: pk_parameter(&values, "sender")?,
+            deadline: parameter(&values, "deadline")?,
+        })
+    }
+
+    /// Spend the box by the recipient (its value goes to the change, the recipient signs the
+    /// transaction)
+    pub fn claim(&self, b: &ErgoBox, height: u32) -> Result<ContractSpend, ContractError> {
+        self.check_box(b)?;
+        if height as i64 > self.deadline as i64 {
+            return Err(ContractError::ConditionsNotMet(format!(
+                "deadline {} has passed",
+                self.deadline
+            )));
+        }
+        Ok(spend_box(b))
+    }
+
+    /// Spend the box by the sender (its value goes to the change, the sender signs the
+    /// transaction)
+    pub fn refund(&self, b: &ErgoBox, height: u32) -> Result<ContractSpend, ContractError> {
+        self.check_box(b)?;
+        if height as i64 <= self.deadline as i64 {
+            return Err(ContractError::ConditionsNotMet(format!(
+                "refund is possible after the deadline {}",
+                self.deadline
+            )));
+        }
+        Ok(spend_box(b))
+    }
+
+    fn check_box(&self, b: &ErgoBox) -> Result<(), ContractError> {
+        if P2pkWithRefund::from_ergo_tree(&b.ergo_tree).as_ref() == Some(self) {
+            Ok(())
+        } else {
+            Err(ContractError::UnexpectedContract)
+        }
+    }
+}
+
+/// `sigmaProp(HEIGHT <op> height)`
+fn height_condition(op: RelationOp, height: Expr) -> Result<Expr, ContractError> {
+    Ok(BoolToSigmaProp::try_build(
+        BinOp {
+            kind: op.into(),
+            left: Box::new(GlobalVars::Height.into()),
+            right: Box::new(height),
+        }
+        .into(),
+    )?
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::ergo_state_context::ErgoStateContext;
+    use crate::wallet::secret_key::SecretKey;
+    use crate::wallet::signing::TransactionContext;
+    use crate::wallet::Wallet;
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_ir::chain::address::Address;
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use std::convert::TryInto;
+
+    fn contract_box(tree: ErgoTree) -> ErgoBox {
+        ErgoBox::new(
+            100000000u64.try_into().unwrap(),
+            tree,
+            vec![],
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn sign(spend: ContractSpend, secret: &DlogProverInput, height: u32) -> bool {
+        let recipient = Address::P2Pk(secret.public_image());
+        let boxes_to_spend = spend.inputs.iter().map(|(b, _)| b.clone()).collect();
+        let unsigned_tx = spend
+            .tx_builder(
+                vec![],
+                height,
+                BoxValue::SAFE_USER_MIN,
+                recipient,
+                BoxValue::SAFE_USER_MIN,
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut state_context = ErgoStateContext::dummy();
+        state_context.pre_header.height = height;
+        let wallet = Wallet::from_secrets(vec![SecretKey::DlogSecretKey(secret.clone())]);
+        wallet
+            .sign_transaction(
+                TransactionContext {
+                    spending_tx: unsigned_tx,
+                    boxes_to_spend,
+                    data_boxes: vec![],
+                },
+                &state_context,
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn height_timelock() {
+        let secret = DlogProverInput::random();
+        let contract = HeightTimelock {
+            recipient: secret.public_image(),
+            unlock_height: 100,
+        };
+        let tree = contract.ergo_tree().unwrap();
+        assert_eq!(
+            HeightTimelock::from_ergo_tree(&tree),
+            Some(contract.clone())
+        );
+        assert_eq!(P2pkWithRefund::from_ergo_tree(&tree), None);
+        let b = contract_box(tree);
+        assert!(matches!(
+            contract.spend(&b, 99),
+            Err(ContractError::ConditionsNotMet(_))
+        ));
+        assert!(sign(contract.spend(&b, 100).unwrap(), &secret, 100));
+        // the script does not allow spending before the unlock height
+        assert!(!sign(spend_box(&b), &secret, 99));
+    }
+
+    #[test]
+    fn p2pk_with_refund() {
+        let recipient = DlogProverInput::random();
+        let sender = DlogProverInput::random();
+        let contract = P2pkWithRefund {
+            recipient: recipient.public_image(),
+            sender: sender.public_image(),
+            deadline: 100,
+        };
+        let tree = contract.ergo_tree().unwrap();
+        assert_eq!(
+            P2pkWithRefund::from_ergo_tree(&tree),
+            Some(contract.clone())
+        );
+        let b = contract_box(tree);
+        assert!(sign(contract.claim(&b, 100).unwrap(), &recipient, 100));
+        assert!(contract.refund(&b, 100).is_err());
+        assert!(!sign(spend_box(&b), &sender, 100));
+        assert!(sign(contract.refund(&b, 101).unwrap(), &sender, 101));
+        assert!(contract.claim(&b, 101).is_err());
+        assert!(!sign(spend_box(&b), &recipient, 101));
+    }
+}
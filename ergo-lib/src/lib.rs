@@ -15,6 +15,7 @@
 
 pub mod chain;
 pub mod constants;
+pub mod contracts;
 pub mod wallet;
 
 /// Re-exported types from dependencies
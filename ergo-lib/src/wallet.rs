@@ -1,14 +1,26 @@
 //! Wallet-related features for Ergo
 
 pub mod box_selector;
+pub mod ext_secret_key;
+pub mod fee_estimator;
+pub mod mnemonic;
 pub mod secret_key;
+pub mod secret_storage;
 pub mod signing;
+pub mod storage_rent;
 pub mod tx_builder;
+pub mod utxo_set;
 
 use ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
+use ergotree_interpreter::sigma_protocol::prover::NonceModeProver;
 use ergotree_interpreter::sigma_protocol::prover::Prover;
-use ergotree_interpreter::sigma_protocol::prover::TestProver;
+use ergotree_interpreter::sigma_protocol::NonceMode;
+use ext_secret_key::{DerivationPath, ExtSecretKey, ExtSecretKeyError};
+use mnemonic::Mnemonic;
 use secret_key::SecretKey;
+use secret_storage::SecretStorageError;
+#[cfg(feature = "json")]
+use secret_storage::SecretStorageSource;
 use signing::{sign_transaction, TxSigningError};
 use thiserror::Error;
 
@@ -28,6 +40,12 @@ pub enum WalletError {
     /// Error on tx signing
     #[error("Transaction signing error: {0}")]
     TxSigningError(TxSigningError),
+    /// Error on reading/decrypting the secret storage
+    #[error("Secret storage error: {0}")]
+    SecretStorageError(#[from] SecretStorageError),
+    /// Error on the master key derivation
+    #[error("Key derivation error: {0}")]
+    ExtSecretKeyError(#[from] ExtSecretKeyError),
 }
 
 impl From<TxSigningError> for WalletError {
@@ -39,14 +57,53 @@ impl From<TxSigningError> for WalletError {
 impl Wallet {
     /// Create Wallet from secrets
     pub fn from_secrets(secrets: Vec<SecretKey>) -> Wallet {
-        let prover = TestProver {
-            secrets: secrets.into_iter().map(PrivateInput::from).collect(),
-        };
+        let prover = NonceModeProver::new(
+            secrets.into_iter().map(PrivateInput::from).collect(),
+            NonceMode::default(),
+        );
         Wallet {
             prover: Box::new(prover),
         }
     }
 
+    /// Set the source of the nonces used in the proofs ([`NonceMode::Random`] by default)
+    pub fn set_nonce_mode(&mut self, nonce_mode: NonceMode) {
+        let secrets = self.prover.secrets().to_vec();
+        self.prover = Box::new(NonceModeProver::new(secrets, nonce_mode));
+    }
+
+    /// Create Wallet from the mnemonic phrase (and mnemonic password, empty if not used).
+    /// The secret is the first EIP-3 key (m/44'/429'/0'/0/0), the same one the reference node wallet uses.
+    /// Fails if the seed yields an invalid master key (negligible probability, less than 1 in 2^127).
+    pub fn from_mnemonic(
+        mnemonic_phrase: &str,
+        mnemonic_pass: &str,
+    ) -> Result<Wallet, WalletError> {
+        let seed = Mnemonic::to_seed(mnemonic_phrase, mnemonic_pass);
+        let master_key = ExtSecretKey::derive_master(seed.as_ref())?;
+        Ok(Wallet::from_master_key(&master_key, false))
+    }
+
+    /// Create Wallet from the encrypted secret storage (file or its contents) in the JSON format
+    /// of the reference node wallet. The secret is the first EIP-3 key (m/44'/429'/0'/0/0).
+    #[cfg(feature = "json")]
+    pub fn from_encrypted_storage<'a, S: Into<SecretStorageSource<'a>>>(
+        source: S,
+        password: &str,
+    ) -> Result<Wallet, WalletError> {
+        let storage = source.into().read()?;
+        let master_key = storage.decrypt_master_key(password)?;
+        Ok(Wallet::from_master_key(
+            &master_key,
+            storage.use_pre_1627_key_derivation(),
+        ))
+    }
+
+    fn from_master_key(master_key: &ExtSecretKey, use_pre_1627_key_derivation: bool) -> Wallet {
+        let key = master_key.derive(&DerivationPath::eip3(0, 0), use_pre_1627_key_derivation);
+        Wallet::from_secrets(vec![key.secret_key()])
+    }
+
     /// Signs a transaction
     pub fn sign_transaction(
         &self,
@@ -56,3 +113,38 @@ impl Wallet {
         sign_transaction(self.prover.as_ref(), tx_context, state_context).map_err(WalletError::from)
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "json")]
+mod tests {
+    use super::*;
+    use secret_storage::{EncryptedSecret, EncryptionSettings};
+
+    #[test]
+    fn from_encrypted_storage_wrong_password() {
+        let settings = EncryptionSettings {
+            c: 1000,
+            ..Default::default()
+        };
+        let storage =
+            EncryptedSecret::from_mnemonic("edge talent poet", "", "pass", settings).unwrap();
+        let json = serde_json::to_vec(&storage).unwrap();
+        assert!(Wallet::from_encrypted_storage(json.as_slice(), "pass").is_ok());
+        assert_eq!(
+            Wallet::from_encrypted_storage(json.as_slice(), "wrong pass").err(),
+            Some(WalletError::SecretStorageError(
+                SecretStorageError::DecryptionFailed
+            ))
+        );
+    }
+
+    #[test]
+    fn set_nonce_mode() {
+        let mut wallet = Wallet::from_mnemonic("edge talent poet", "").unwrap();
+        assert_eq!(wallet.prover.nonce_mode(), NonceMode::Random);
+        let secrets = wallet.prover.secrets().to_vec();
+        wallet.set_nonce_mode(NonceMode::Deterministic);
+        assert_eq!(wallet.prover.nonce_mode(), NonceMode::Deterministic);
+        assert_eq!(wallet.prover.secrets(), secrets.as_slice());
+    }
+}
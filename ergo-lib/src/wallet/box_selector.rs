@@ -1,8 +1,14 @@
 //! Box selection for transaction inputs
 
+mod branch_and_bound;
+mod ordered;
+mod random_improve;
 mod simple;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::convert::TryInto;
 
+pub use branch_and_bound::*;
 use ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
 use ergotree_ir::chain::ergo_box::BoxId;
@@ -10,7 +16,10 @@ use ergotree_ir::chain::ergo_box::ErgoBox;
 use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
 use ergotree_ir::chain::token::Token;
 use ergotree_ir::chain::token::TokenAmount;
+use ergotree_ir::chain::token::TokenAmountError;
 use ergotree_ir::chain::token::TokenId;
+pub use ordered::*;
+pub use random_improve::*;
 pub use simple::*;
 
 use thiserror::Error;
@@ -52,6 +61,14 @@ pub enum BoxSelectorError {
     /// BoxValue out of bounds
     #[error("BoxValue out of bounds")]
     BoxValueError(BoxValueError),
+
+    /// Token amount out of bounds
+    #[error("TokenAmount out of bounds: {0}")]
+    TokenAmountError(TokenAmountError),
+
+    /// Target balance and tokens cannot be reached within the maximum number of inputs
+    #[error("Selection requires more than {0} inputs")]
+    MaxInputsExceeded(usize),
 }
 
 impl From<BoxValueError> for BoxSelectorError {
@@ -60,6 +77,251 @@ impl From<BoxValueError> for BoxSelectorError {
     }
 }
 
+impl From<TokenAmountError> for BoxSelectorError {
+    fn from(e: TokenAmountError) -> Self {
+        BoxSelectorError::TokenAmountError(e)
+    }
+}
+
+/// Options shared by the box selectors (except [`SimpleBoxSelector`])
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct BoxSelectorOptions {
+    /// Maximum number of boxes to select (no limit if None)
+    pub max_inputs: Option<usize>,
+    /// Consider boxes that do not hold tokens other than target tokens first, so that unrelated
+    /// tokens are not dragged into the change
+    pub prefer_boxes_without_extra_tokens: bool,
+}
+
+impl Default for BoxSelectorOptions {
+    fn default() -> Self {
+        BoxSelectorOptions {
+            max_inputs: None,
+            prefer_boxes_without_extra_tokens: true,
+        }
+    }
+}
+
+/// Returns true if the box holds any token which is not among the target tokens
+fn has_extra_tokens<T: ErgoBoxAssets>(b: &T, target_tokens: &HashMap<TokenId, u64>) -> bool {
+    b.tokens()
+        .iter()
+        .any(|t| !target_tokens.contains_key(&t.token_id))
+}
+
+/// Moves the boxes without extra tokens (see [`has_extra_tokens`]) to the front (if enabled in
+/// options), preserving the order otherwise
+fn order_by_extra_tokens<T: ErgoBoxAssets>(
+    inputs: &mut [T],
+    target_tokens: &[Token],
+    options: &BoxSelectorOptions,
+) -> Result<(), BoxSelectorError> {
+    if options.prefer_boxes_without_extra_tokens {
+        let target_tokens = sum_token_amounts(target_tokens)?;
+        inputs.sort_by_key(|b| has_extra_tokens(b, &target_tokens));
+    }
+    Ok(())
+}
+
+/// Returns the total token amounts as u64 (with overflow check)
+fn sum_token_amounts(ts: &[Token]) -> Result<HashMap<TokenId, u64>, TokenAmountError> {
+    let mut res: HashMap<TokenId, u64> = HashMap::new();
+    ts.iter().try_for_each(|t| add_token_amount(&mut res, t))?;
+    Ok(res)
+}
+
+fn add_token_amount(
+    amounts: &mut HashMap<TokenId, u64>,
+    t: &Token,
+) -> Result<(), TokenAmountError> {
+    let amt = amounts.entry(t.token_id.clone()).or_insert(0);
+    *amt = amt
+        .checked_add(*t.amount.as_u64())
+        .ok_or(TokenAmountError::Overflow)?;
+    Ok(())
+}
+
+/// Running totals of the selected boxes compared to the target balance and tokens
+#[derive(Clone)]
+struct SelectionTotals {
+    target_balance: u64,
+    target_tokens: HashMap<TokenId, u64>,
+    value: u64,
+    tokens: HashMap<TokenId, u64>,
+}
+
+impl SelectionTotals {
+    fn new(target_balance: BoxValue, target_tokens: &[Token]) -> Result<Self, BoxSelectorError> {
+        Ok(SelectionTotals {
+            target_balance: *target_balance.as_u64(),
+            target_tokens: sum_token_amounts(target_tokens)?,
+            value: 0,
+            tokens: HashMap::new(),
+        })
+    }
+
+    fn add<T: ErgoBoxAssets>(&mut self, b: &T) -> Result<(), BoxSelectorError> {
+        self.value = self
+            .value
+            .checked_add(*b.value().as_u64())
+            .ok_or(BoxValueError::Overflow)?;
+        b.tokens()
+            .iter()
+            .try_for_each(|t| add_token_amount(&mut self.tokens, t))?;
+        Ok(())
+    }
+
+    fn missing_value(&self) -> u64 {
+        self.target_balance.saturating_sub(self.value)
+    }
+
+    fn missing_tokens(&self) -> HashMap<TokenId, u64> {
+        self.target_tokens
+            .iter()
+            .filter_map(|(id, target_amt)| {
+                let amt = self.tokens.get(id).cloned().unwrap_or(0);
+                if amt < *target_amt {
+                    Some((id.clone(), target_amt - amt))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn change_value(&self) -> u64 {
+        self.value.saturating_sub(self.target_balance)
+    }
+
+    fn change_tokens(&self) -> HashMap<TokenId, u64> {
+        self.tokens
+            .iter()
+            .filter_map(|(id, amt)| {
+                let target_amt = self.target_tokens.get(id).cloned().unwrap_or(0);
+                if *amt > target_amt {
+                    Some((id.clone(), amt - target_amt))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn has_change(&self) -> bool {
+        self.change_value() > 0 || !self.change_tokens().is_empty()
+    }
+
+    /// Change (if any) is too small to be put in a box
+    fn change_is_too_small(&self) -> bool {
+        self.has_change() && self.change_value() < *BoxValue::SAFE_USER_MIN.as_u64()
+    }
+
+    fn is_complete(&self) -> bool {
+        self.missing_value() == 0 && self.missing_tokens().is_empty() && !self.change_is_too_small()
+    }
+
+    /// Returns true if adding the box brings the selection closer to completion
+    fn is_needed<T: ErgoBoxAssets>(&self, b: &T) -> bool {
+        self.missing_value() > 0
+            || self.change_is_too_small()
+            || b.tokens().iter().any(|t| {
+                self.tokens.get(&t.token_id).cloned().unwrap_or(0)
+                    < self.target_tokens.get(&t.token_id).cloned().unwrap_or(0)
+            })
+    }
+}
+
+/// Selects boxes in the given order (skipping the boxes that are not needed) until the target
+/// balance and tokens are reached and the change (if any) is enough for a change box
+fn select_in_order<T: ErgoBoxAssets>(
+    inputs: Vec<T>,
+    target_balance: BoxValue,
+    target_tokens: &[Token],
+    max_inputs: Option<usize>,
+) -> Result<BoxSelection<T>, BoxSelectorError> {
+    let mut totals = SelectionTotals::new(target_balance, target_tokens)?;
+    let (selected, _) = accumulate_in_order(inputs, &mut totals, max_inputs)?;
+    make_selection(selected, target_balance, target_tokens)
+}
+
+/// Accumulates boxes in the given order (see [`select_in_order`]) updating the totals.
+/// Returns selected and the rest of the boxes
+fn accumulate_in_order<T: ErgoBoxAssets>(
+    inputs: Vec<T>,
+    totals: &mut SelectionTotals,
+    max_inputs: Option<usize>,
+) -> Result<(Vec<T>, Vec<T>), BoxSelectorError> {
+    let mut selected: Vec<T> = vec![];
+    let mut rest: Vec<T> = vec![];
+    for b in inputs {
+        if totals.is_complete() || !totals.is_needed(&b) {
+            rest.push(b);
+            continue;
+        }
+        if let Some(max_inputs) = max_inputs {
+            if selected.len() >= max_inputs {
+                return Err(BoxSelectorError::MaxInputsExceeded(max_inputs));
+            }
+        }
+        totals.add(&b)?;
+        selected.push(b);
+    }
+    Ok((selected, rest))
+}
+
+/// Checks that the selected boxes cover target balance and tokens and calculates the change
+fn make_selection<T: ErgoBoxAssets>(
+    selected: Vec<T>,
+    target_balance: BoxValue,
+    target_tokens: &[Token],
+) -> Result<BoxSelection<T>, BoxSelectorError> {
+    let mut totals = SelectionTotals::new(target_balance, target_tokens)?;
+    selected.iter().try_for_each(|b| totals.add(b))?;
+    if totals.missing_value() > 0 {
+        return Err(BoxSelectorError::NotEnoughCoins(totals.missing_value()));
+    }
+    let missing_tokens = totals.missing_tokens();
+    if !missing_tokens.is_empty() {
+        return Err(BoxSelectorError::NotEnoughTokens(
+            missing_tokens
+                .into_iter()
+                .map(|(id, amt)| Ok(Token::from((id, amt.try_into()?))))
+                .collect::<Result<Vec<Token>, TokenAmountError>>()?,
+        ));
+    }
+    if totals.change_is_too_small() {
+        return Err(BoxSelectorError::NotEnoughCoins(
+            BoxValue::SAFE_USER_MIN.as_u64() - totals.change_value(),
+        ));
+    }
+    let change_boxes = if totals.has_change() {
+        let change_tokens = totals.change_tokens();
+        // keep the order in which tokens appear in the selected boxes (deterministic change)
+        let mut change_token_ids: Vec<TokenId> = vec![];
+        selected.iter().flat_map(|b| b.tokens()).for_each(|t| {
+            if change_tokens.contains_key(&t.token_id) && !change_token_ids.contains(&t.token_id) {
+                change_token_ids.push(t.token_id);
+            }
+        });
+        vec![ErgoBoxAssetsData {
+            value: BoxValue::try_from(totals.change_value())?,
+            tokens: change_token_ids
+                .into_iter()
+                .map(|id| {
+                    let amt = change_tokens[&id];
+                    Ok(Token::from((id, amt.try_into()?)))
+                })
+                .collect::<Result<Vec<Token>, TokenAmountError>>()?,
+        }]
+    } else {
+        vec![]
+    };
+    Ok(BoxSelection {
+        boxes: selected,
+        change_boxes,
+    })
+}
+
 /// Assets that ErgoBox holds
 pub trait ErgoBoxAssets {
     /// Box value
@@ -0,0 +1,294 @@
+//! Branch-and-bound box selector, looks for inputs that exactly match the target (no change)
+
+use std::collections::HashMap;
+
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenId;
+
+use super::has_extra_tokens;
+use super::make_selection;
+use super::sum_token_amounts;
+use super::BoxSelectorError;
+use super::BoxSelectorOptions;
+use super::ErgoBoxAssets;
+use super::LargestFirstBoxSelector;
+use super::{BoxSelection, BoxSelector};
+
+/// Searches (depth-first, with pruning) for a set of inputs whose value and tokens exactly match
+/// the target balance and tokens, so that no change box is needed. Only the boxes without
+/// extra tokens (other than target tokens) take part in the search.
+/// If no exact match is found within [`BranchAndBoundBoxSelector::MAX_TRIES`] steps, falls back to
+/// [`LargestFirstBoxSelector`] with the same options.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct BranchAndBoundBoxSelector {
+    options: BoxSelectorOptions,
+}
+
+impl BranchAndBoundBoxSelector {
+    /// Maximum number of search steps before falling back to [`LargestFirstBoxSelector`]
+    pub const MAX_TRIES: usize = 100_000;
+
+    /// Create new instance with default options
+    pub fn new() -> Self {
+        BranchAndBoundBoxSelector::default()
+    }
+
+    /// Create new instance with the given options
+    pub fn with_options(options: BoxSelectorOptions) -> Self {
+        BranchAndBoundBoxSelector { options }
+    }
+}
+
+/// State of the exact match search over the candidates (sorted by value in descending order)
+struct ExactMatchSearch<'a> {
+    values: &'a [u64],
+    tokens: &'a [HashMap<TokenId, u64>],
+    // sum of the values of the candidates starting from the given index
+    remaining_values: Vec<u64>,
+    target_balance: u64,
+    target_tokens: &'a HashMap<TokenId, u64>,
+    max_inputs: usize,
+    tries: usize,
+}
+
+impl<'a> ExactMatchSearch<'a> {
+    fn new(
+        values: &'a [u64],
+        tokens: &'a [HashMap<TokenId, u64>],
+        target_balance: u64,
+        target_tokens: &'a HashMap<TokenId, u64>,
+        max_inputs: Option<usize>,
+    ) -> Self {
+        let mut remaining_values = vec![0u64; values.len() + 1];
+        for idx in (0..values.len()).rev() {
+            remaining_values[idx] = remaining_values[idx + 1].saturating_add(values[idx]);
+        }
+        ExactMatchSearch {
+            values,
+            tokens,
+            remaining_values,
+            target_balance,
+            target_tokens,
+            max_inputs: max_inputs.unwrap_or(usize::MAX),
+            tries: 0,
+        }
+    }
+
+    /// Returns indices of the candidates that exactly match the target (if found)
+    fn find(&mut self) -> Option<Vec<usize>> {
+        let mut selected: Vec<usize> = vec![];
+        let mut tokens: HashMap<TokenId, u64> = HashMap::new();
+        // explicit stack instead of recursion, the include branch is explored first
+        let mut stack = vec![SearchStep::Visit { idx: 0, value: 0 }];
+        while let Some(step) = stack.pop() {
+            match step {
+                SearchStep::Visit { idx, value } => {
+                    if value == self.target_balance && &tokens == self.target_tokens {
+                        return Some(selected);
+                    }
+                    self.tries += 1;
+                    if idx >= self.values.len()
+                        || self.tries > BranchAndBoundBoxSelector::MAX_TRIES
+                        || selected.len() >= self.max_inputs
+                        || value.saturating_add(self.remaining_values[idx]) < self.target_balance
+                    {
+                        continue;
+                    }
+                    // exclude the candidate
+                    stack.push(SearchStep::Visit {
+                        idx: idx + 1,
+                        value,
+                    });
+                    // include the candidate
+                    let new_value = value.saturating_add(self.values[idx]);
+                    if new_value <= self.target_balance && self.tokens_fit(&tokens, idx) {
+                        for (id, amt) in &self.tokens[idx] {
+                            let new_amt = tokens.entry(id.clone()).or_insert(0);
+                            *new_amt = new_amt.saturating_add(*amt);
+                        }
+                        selected.push(idx);
+                        stack.push(SearchStep::Deselect { idx });
+                        stack.push(SearchStep::Visit {
+                            idx: idx + 1,
+                            value: new_value,
+                        });
+                    }
+                }
+                SearchStep::Deselect { idx } => {
+                    selected.pop();
+                    for (id, amt) in &self.tokens[idx] {
+                        if let Some(cur_amt) = tokens.get_mut(id) {
+                            *cur_amt = cur_amt.saturating_sub(*amt);
+                            if *cur_amt == 0 {
+                                tokens.remove(id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns true if adding the tokens of the given candidate does not exceed the target tokens
+    fn tokens_fit(&self, tokens: &HashMap<TokenId, u64>, idx: usize) -> bool {
+        self.tokens[idx].iter().all(|(id, amt)| {
+            tokens
+                .get(id)
+                .cloned()
+                .unwrap_or(0)
+                .checked_add(*amt)
+                .map(|new_amt| new_amt <= self.target_tokens.get(id).cloned().unwrap_or(0))
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// Pending step of the exact match search
+enum SearchStep {
+    /// Decide on the candidate with the given index having the given value of selected candidates
+    Visit { idx: usize, value: u64 },
+    /// Undo the selection of the candidate with the given index
+    Deselect { idx: usize },
+}
+
+impl<T: ErgoBoxAssets> BoxSelector<T> for BranchAndBoundBoxSelector {
+    fn select(
+        &self,
+        inputs: Vec<T>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<T>, BoxSelectorError> {
+        let target_token_amounts = sum_token_amounts(target_tokens)?;
+        let mut candidates: Vec<usize> = (0..inputs.len())
+            .filter(|idx| !has_extra_tokens(&inputs[*idx], &target_token_amounts))
+            .collect();
+        candidates.sort_by_key(|idx| std::cmp::Reverse(*inputs[*idx].value().as_u64()));
+        let values: Vec<u64> = candidates
+            .iter()
+            .map(|idx| *inputs[*idx].value().as_u64())
+            .collect();
+        let tokens = candidates
+            .iter()
+            .map(|idx| sum_token_amounts(inputs[*idx].tokens().as_slice()))
+            .collect::<Result<Vec<HashMap<TokenId, u64>>, _>>()?;
+        let exact_match = ExactMatchSearch::new(
+            &values,
+            &tokens,
+            *target_balance.as_u64(),
+            &target_token_amounts,
+            self.options.max_inputs,
+        )
+        .find();
+        match exact_match {
+            Some(selected) => {
+                let selected_input_indices: Vec<usize> =
+                    selected.into_iter().map(|i| candidates[i]).collect();
+                let selected_inputs: Vec<T> = inputs
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(idx, _)| selected_input_indices.contains(idx))
+                    .map(|(_, b)| b)
+                    .collect();
+                make_selection(selected_inputs, target_balance, target_tokens)
+            }
+            None => LargestFirstBoxSelector::with_options(self.options).select(
+                inputs,
+                target_balance,
+                target_tokens,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use proptest::{collection::vec, prelude::*};
+    use sigma_test_util::force_any_val;
+
+    use crate::wallet::box_selector::sum_tokens_from_boxes;
+    use crate::wallet::box_selector::sum_value;
+    use crate::wallet::box_selector::ErgoBoxAssetsData;
+
+    use super::*;
+
+    fn assets(value: u64, tokens: Vec<Token>) -> ErgoBoxAssetsData {
+        ErgoBoxAssetsData {
+            value: value.try_into().unwrap(),
+            tokens,
+        }
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let token = force_any_val::<Token>();
+        let inputs = vec![
+            assets(10_000_000, vec![]),
+            assets(7_000_000, vec![token.clone()]),
+            assets(5_000_000, vec![]),
+            assets(3_000_000, vec![]),
+            assets(2_000_000, vec![force_any_val::<Token>()]),
+        ];
+        let selection = BranchAndBoundBoxSelector::new()
+            .select(inputs.clone(), 15_000_000u64.try_into().unwrap(), &[token])
+            .unwrap();
+        assert!(selection.change_boxes.is_empty());
+        assert_eq!(
+            selection.boxes,
+            vec![inputs[1].clone(), inputs[2].clone(), inputs[3].clone()]
+        );
+    }
+
+    #[test]
+    fn test_fallback_without_exact_match() {
+        let inputs = vec![assets(10_000_000, vec![]), assets(5_000_000, vec![])];
+        let selection = BranchAndBoundBoxSelector::new()
+            .select(inputs.clone(), 7_000_000u64.try_into().unwrap(), &[])
+            .unwrap();
+        assert_eq!(selection.boxes, vec![inputs[0].clone()]);
+        assert_eq!(
+            selection.change_boxes,
+            vec![assets(3_000_000, vec![])],
+            "fallback selection should have change"
+        );
+    }
+
+    #[test]
+    fn test_many_candidates() {
+        // the search depth is not limited by the call stack
+        let inputs: Vec<ErgoBoxAssetsData> = (0..20_000)
+            .map(|_| assets(BoxValue::MIN_RAW * 2, vec![]))
+            .collect();
+        let target_balance: BoxValue = (BoxValue::MIN_RAW * 2 * 10_000).try_into().unwrap();
+        let selection = BranchAndBoundBoxSelector::new()
+            .select(inputs, target_balance, &[])
+            .unwrap();
+        assert!(selection.change_boxes.is_empty());
+        assert_eq!(selection.boxes.len(), 10_000);
+    }
+
+    proptest! {
+
+        #[test]
+        fn test_select_subset_sum(inputs in
+                                  vec(any_with::<ErgoBoxAssetsData>(
+                                      (BoxValue::MIN_RAW * 1000 .. BoxValue::MIN_RAW * 10000).into()), 1..10),
+                                  subset in vec(any::<bool>(), 10)) {
+            let inputs: Vec<ErgoBoxAssetsData> =
+                inputs.into_iter().map(|b| ErgoBoxAssetsData { value: b.value, tokens: vec![] }).collect();
+            let subset_value: u64 = inputs.iter().zip(subset.iter())
+                .filter(|(_, included)| **included)
+                .map(|(b, _)| *b.value.as_u64())
+                .sum();
+            prop_assume!(subset_value > 0);
+            let target_balance: BoxValue = subset_value.try_into().unwrap();
+            let selection = BranchAndBoundBoxSelector::new().select(inputs, target_balance, &[]).unwrap();
+            prop_assert!(selection.change_boxes.is_empty());
+            prop_assert_eq!(sum_value(selection.boxes.as_slice()), subset_value);
+            prop_assert!(sum_tokens_from_boxes(selection.boxes.as_slice()).is_empty());
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Box selectors that collect inputs in the order of their value
+
+use std::cmp::Reverse;
+
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::token::Token;
+
+use super::order_by_extra_tokens;
+use super::select_in_order;
+use super::BoxSelectorError;
+use super::BoxSelectorOptions;
+use super::ErgoBoxAssets;
+use super::{BoxSelection, BoxSelector};
+
+/// Collects inputs starting from the largest (by value) until target balance and tokens are
+/// reached. Tends to select the fewest inputs.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct LargestFirstBoxSelector {
+    options: BoxSelectorOptions,
+}
+
+impl LargestFirstBoxSelector {
+    /// Create new instance with default options
+    pub fn new() -> Self {
+        LargestFirstBoxSelector::default()
+    }
+
+    /// Create new instance with the given options
+    pub fn with_options(options: BoxSelectorOptions) -> Self {
+        LargestFirstBoxSelector { options }
+    }
+}
+
+impl<T: ErgoBoxAssets> BoxSelector<T> for LargestFirstBoxSelector {
+    fn select(
+        &self,
+        inputs: Vec<T>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<T>, BoxSelectorError> {
+        let mut sorted_inputs = inputs;
+        sorted_inputs.sort_by_key(|b| Reverse(*b.value().as_u64()));
+        order_by_extra_tokens(&mut sorted_inputs, target_tokens, &self.options)?;
+        select_in_order(
+            sorted_inputs,
+            target_balance,
+            target_tokens,
+            self.options.max_inputs,
+        )
+    }
+}
+
+/// Collects inputs starting from the smallest (by value) until target balance and tokens are
+/// reached. Consolidates dust boxes by selecting as many small inputs as needed (use
+/// [`BoxSelectorOptions::max_inputs`] to limit the transaction size).
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct SmallestFirstBoxSelector {
+    options: BoxSelectorOptions,
+}
+
+impl SmallestFirstBoxSelector {
+    /// Create new instance with default options
+    pub fn new() -> Self {
+        SmallestFirstBoxSelector::default()
+    }
+
+    /// Create new instance with the given options
+    pub fn with_options(options: BoxSelectorOptions) -> Self {
+        SmallestFirstBoxSelector { options }
+    }
+}
+
+impl<T: ErgoBoxAssets> BoxSelector<T> for SmallestFirstBoxSelector {
+    fn select(
+        &self,
+        inputs: Vec<T>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<T>, BoxSelectorError> {
+        let mut sorted_inputs = inputs;
+        sorted_inputs.sort_by_key(|b| *b.value().as_u64());
+        order_by_extra_tokens(&mut sorted_inputs, target_tokens, &self.options)?;
+        select_in_order(
+            sorted_inputs,
+            target_balance,
+            target_tokens,
+            self.options.max_inputs,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ergotree_ir::chain::ergo_box::box_value::checked_sum;
+    use ergotree_ir::chain::token::TokenId;
+    use proptest::{collection::vec, prelude::*};
+    use sigma_test_util::force_any_val;
+    use std::convert::TryInto;
+
+    use crate::wallet::box_selector::sum_tokens_from_boxes;
+    use crate::wallet::box_selector::sum_value;
+    use crate::wallet::box_selector::ErgoBoxAssetsData;
+
+    use super::*;
+
+    fn assets(value: u64, tokens: Vec<Token>) -> ErgoBoxAssetsData {
+        ErgoBoxAssetsData {
+            value: value.try_into().unwrap(),
+            tokens,
+        }
+    }
+
+    #[test]
+    fn test_largest_first_order() {
+        let inputs = vec![
+            assets(*BoxValue::SAFE_USER_MIN.as_u64() * 2, vec![]),
+            assets(*BoxValue::SAFE_USER_MIN.as_u64() * 10, vec![]),
+            assets(*BoxValue::SAFE_USER_MIN.as_u64() * 5, vec![]),
+        ];
+        let target_balance = *BoxValue::SAFE_USER_MIN.as_u64() * 6;
+        let selection = LargestFirstBoxSelector::new()
+            .select(inputs.clone(), target_balance.try_into().unwrap(), &[])
+            .unwrap();
+        assert_eq!(selection.boxes, vec![inputs[1].clone()]);
+        let selection = SmallestFirstBoxSelector::new()
+            .select(inputs.clone(), target_balance.try_into().unwrap(), &[])
+            .unwrap();
+        assert_eq!(selection.boxes, vec![inputs[0].clone(), inputs[2].clone()]);
+    }
+
+    #[test]
+    fn test_max_inputs() {
+        let inputs = vec![
+            assets(*BoxValue::SAFE_USER_MIN.as_u64(), vec![]),
+            assets(*BoxValue::SAFE_USER_MIN.as_u64(), vec![]),
+            assets(*BoxValue::SAFE_USER_MIN.as_u64(), vec![]),
+        ];
+        let s = SmallestFirstBoxSelector::with_options(BoxSelectorOptions {
+            max_inputs: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(
+            s.select(
+                inputs,
+                (*BoxValue::SAFE_USER_MIN.as_u64() * 3).try_into().unwrap(),
+                &[]
+            ),
+            Err(BoxSelectorError::MaxInputsExceeded(2))
+        );
+    }
+
+    #[test]
+    fn test_prefer_boxes_without_extra_tokens() {
+        let inputs = vec![
+            assets(
+                *BoxValue::SAFE_USER_MIN.as_u64() * 10,
+                vec![force_any_val::<Token>()],
+            ),
+            assets(*BoxValue::SAFE_USER_MIN.as_u64() * 5, vec![]),
+        ];
+        let selection = LargestFirstBoxSelector::new()
+            .select(inputs.clone(), BoxValue::SAFE_USER_MIN, &[])
+            .unwrap();
+        assert_eq!(selection.boxes, vec![inputs[1].clone()]);
+        assert!(selection.change_boxes[0].tokens.is_empty());
+        let selection = LargestFirstBoxSelector::with_options(BoxSelectorOptions {
+            prefer_boxes_without_extra_tokens: false,
+            ..Default::default()
+        })
+        .select(inputs.clone(), BoxValue::SAFE_USER_MIN, &[])
+        .unwrap();
+        assert_eq!(selection.boxes, vec![inputs[0].clone()]);
+    }
+
+    proptest! {
+
+        #[test]
+        fn test_select_not_enough_value(inputs in
+                                        vec(any_with::<ErgoBoxAssetsData>(
+                                            (BoxValue::MIN_RAW * 1000 .. BoxValue::MIN_RAW * 10000).into()), 1..10)) {
+            let all_inputs_val = checked_sum(inputs.iter().map(|b| b.value)).unwrap();
+            let balance_too_much = all_inputs_val.checked_add(&BoxValue::SAFE_USER_MIN).unwrap();
+            prop_assert!(LargestFirstBoxSelector::new().select(inputs.clone(), balance_too_much, &[]).is_err());
+            prop_assert!(SmallestFirstBoxSelector::new().select(inputs, balance_too_much, &[]).is_err());
+        }
+
+        #[test]
+        fn test_select_value_and_token(inputs in
+                                       vec(any_with::<ErgoBoxAssetsData>(
+                                           (BoxValue::MIN_RAW * 1000 .. BoxValue::MIN_RAW * 10000).into()), 1..10),
+                                       target_balance in
+                                       any_with::<BoxValue>((BoxValue::MIN_RAW * 100 .. BoxValue::MIN_RAW * 800).into()),
+                                       target_token_amount in 1..100u64,
+                                       largest_first in any::<bool>()) {
+            let all_input_tokens = sum_tokens_from_boxes(inputs.as_slice());
+            prop_assume!(!all_input_tokens.is_empty());
+            let target_token_id = all_input_tokens.keys().collect::<Vec<&TokenId>>().first().cloned().unwrap();
+            let input_token_amount = *all_input_tokens.get(target_token_id).unwrap();
+            prop_assume!(*input_token_amount.as_u64() >= target_token_amount);
+            let target_token = Token {token_id: target_token_id.clone(), amount: target_token_amount.try_into().unwrap()};
+            let selection = if largest_first {
+                LargestFirstBoxSelector::new().select(inputs, target_balance, std::slice::from_ref(&target_token))
+            } else {
+                SmallestFirstBoxSelector::new().select(inputs, target_balance, std::slice::from_ref(&target_token))
+            }.unwrap();
+            let out_box = ErgoBoxAssetsData {value: target_balance, tokens: vec![target_token]};
+            let mut change_boxes_plus_out = vec![out_box];
+            change_boxes_plus_out.append(&mut selection.change_boxes.clone());
+            prop_assert_eq!(sum_value(selection.boxes.as_slice()),
+                            sum_value(change_boxes_plus_out.as_slice()),
+                            "total value of the selected boxes should equal target balance + total value in change boxes");
+            prop_assert_eq!(sum_tokens_from_boxes(selection.boxes.as_slice()),
+                            sum_tokens_from_boxes(change_boxes_plus_out.as_slice()),
+                            "all tokens from selected boxes should equal all tokens from the change boxes + target tokens");
+            prop_assert!(selection.change_boxes.iter().all(|b| b.value >= BoxValue::SAFE_USER_MIN));
+        }
+    }
+}
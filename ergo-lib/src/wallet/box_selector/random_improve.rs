@@ -0,0 +1,159 @@
+//! Random-improve box selector
+
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::token::Token;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::accumulate_in_order;
+use super::has_extra_tokens;
+use super::make_selection;
+use super::order_by_extra_tokens;
+use super::BoxSelectorError;
+use super::BoxSelectorOptions;
+use super::ErgoBoxAssets;
+use super::SelectionTotals;
+use super::{BoxSelection, BoxSelector};
+
+/// Random-improve selection (as in Cardano wallets):
+/// 1. collects randomly picked inputs until target balance and tokens are reached;
+/// 2. improves the selection by adding random inputs (without extra tokens) while the selected
+///    value gets closer to the twice the target balance (but not above three times the target balance).
+///
+/// The resulting change is comparable to the payment, which keeps the UTXO set healthy
+/// (avoids dust accumulation) and makes the payments less distinguishable from the change.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct RandomImproveBoxSelector {
+    options: BoxSelectorOptions,
+    seed: Option<u64>,
+}
+
+impl RandomImproveBoxSelector {
+    /// Create new instance with default options
+    pub fn new() -> Self {
+        RandomImproveBoxSelector::default()
+    }
+
+    /// Create new instance with the given options
+    pub fn with_options(options: BoxSelectorOptions) -> Self {
+        RandomImproveBoxSelector {
+            options,
+            seed: None,
+        }
+    }
+
+    /// Use the given seed for the random number generator (reproducible selection)
+    pub fn with_seed(self, seed: u64) -> Self {
+        RandomImproveBoxSelector {
+            seed: Some(seed),
+            ..self
+        }
+    }
+}
+
+impl<T: ErgoBoxAssets> BoxSelector<T> for RandomImproveBoxSelector {
+    fn select(
+        &self,
+        inputs: Vec<T>,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<T>, BoxSelectorError> {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut shuffled_inputs = inputs;
+        shuffled_inputs.shuffle(&mut rng);
+        order_by_extra_tokens(&mut shuffled_inputs, target_tokens, &self.options)?;
+        let mut totals = SelectionTotals::new(target_balance, target_tokens)?;
+        let (mut selected, rest) =
+            accumulate_in_order(shuffled_inputs, &mut totals, self.options.max_inputs)?;
+        if totals.is_complete() {
+            let target = *target_balance.as_u64() as u128;
+            let ideal = target * 2;
+            let upper_bound = target * 3;
+            for b in rest {
+                if self
+                    .options
+                    .max_inputs
+                    .map(|max_inputs| selected.len() >= max_inputs)
+                    .unwrap_or(false)
+                {
+                    break;
+                }
+                if has_extra_tokens(&b, &totals.target_tokens) {
+                    continue;
+                }
+                let mut improved_totals = totals.clone();
+                improved_totals.add(&b)?;
+                let value = totals.value as u128;
+                let improved_value = improved_totals.value as u128;
+                if improved_value <= upper_bound
+                    && improved_value.abs_diff(ideal) < value.abs_diff(ideal)
+                    && !improved_totals.change_is_too_small()
+                {
+                    totals = improved_totals;
+                    selected.push(b);
+                }
+            }
+        }
+        make_selection(selected, target_balance, target_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use proptest::{collection::vec, prelude::*};
+
+    use crate::wallet::box_selector::sum_tokens_from_boxes;
+    use crate::wallet::box_selector::sum_value;
+    use crate::wallet::box_selector::ErgoBoxAssetsData;
+
+    use super::*;
+
+    #[test]
+    fn test_improve_towards_twice_the_target() {
+        let inputs: Vec<ErgoBoxAssetsData> = (0..10)
+            .map(|_| ErgoBoxAssetsData {
+                value: 10_000_000u64.try_into().unwrap(),
+                tokens: vec![],
+            })
+            .collect();
+        let selection = RandomImproveBoxSelector::new()
+            .with_seed(42)
+            .select(inputs, 30_000_000u64.try_into().unwrap(), &[])
+            .unwrap();
+        assert_eq!(selection.boxes.len(), 6);
+        assert_eq!(selection.change_boxes.len(), 1);
+        assert_eq!(*selection.change_boxes[0].value.as_u64(), 30_000_000);
+    }
+
+    proptest! {
+
+        #[test]
+        fn test_select_value(inputs in
+                             vec(any_with::<ErgoBoxAssetsData>(
+                                 (BoxValue::MIN_RAW * 1000 .. BoxValue::MIN_RAW * 10000).into()), 2..10),
+                             seed in any::<u64>()) {
+            let all_inputs_val = sum_value(inputs.as_slice());
+            let target_balance: BoxValue = (all_inputs_val / 2).try_into().unwrap();
+            let s = RandomImproveBoxSelector::new().with_seed(seed);
+            let selection = s.select(inputs.clone(), target_balance, &[]).unwrap();
+            prop_assert_eq!(&s.select(inputs, target_balance, &[]).unwrap(), &selection,
+                            "selection with the same seed should be reproducible");
+            let out_box = ErgoBoxAssetsData {value: target_balance, tokens: vec![]};
+            let mut change_boxes_plus_out = vec![out_box];
+            change_boxes_plus_out.append(&mut selection.change_boxes.clone());
+            prop_assert_eq!(sum_value(selection.boxes.as_slice()),
+                            sum_value(change_boxes_plus_out.as_slice()),
+                            "total value of the selected boxes should equal target balance + total value in change boxes");
+            prop_assert_eq!(sum_tokens_from_boxes(selection.boxes.as_slice()),
+                            sum_tokens_from_boxes(change_boxes_plus_out.as_slice()),
+                            "all tokens from selected boxes should equal all tokens from the change boxes + target tokens");
+            prop_assert!(sum_value(selection.boxes.as_slice()) <= *target_balance.as_u64() * 3);
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! Extended private key operations according to BIP-32 and EIP-3
+
+use std::fmt;
+
+use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+use ergotree_ir::serialization::SigmaSerializable;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha512;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use super::secret_key::SecretKey;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Key of the HMAC used to derive the master key from the seed
+const BITCOIN_SEED: &[u8] = b"Bitcoin seed";
+
+/// Chain code length in bytes
+pub const CHAIN_CODE_LENGTH: usize = 32;
+
+/// Purpose index of the EIP-3 derivation path (BIP-44)
+pub const PURPOSE: u32 = 44;
+
+/// Ergo coin type index of the EIP-3 derivation path (SLIP-44)
+pub const ERG_COIN_TYPE: u32 = 429;
+
+/// Index of the child key in the derivation path
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChildIndex {
+    /// Hardened index, derived from the parent private key
+    Hardened(u32),
+    /// Normal (non-hardened) index, derived from the parent public key
+    Normal(u32),
+}
+
+impl ChildIndex {
+    /// Bit set in the serialized index of a hardened child
+    pub const HARDENED_BIT: u32 = 0x8000_0000;
+
+    /// Serialized index (hardened indices have the highest bit set)
+    pub fn to_bits(self) -> u32 {
+        match self {
+            ChildIndex::Hardened(i) => i | ChildIndex::HARDENED_BIT,
+            ChildIndex::Normal(i) => i,
+        }
+    }
+
+    /// Whether the index is hardened
+    pub fn is_hardened(self) -> bool {
+        matches!(self, ChildIndex::Hardened(_))
+    }
+
+    fn next(self) -> ChildIndex {
+        match self {
+            ChildIndex::Hardened(i) => ChildIndex::Hardened(i + 1),
+            ChildIndex::Normal(i) => ChildIndex::Normal(i + 1),
+        }
+    }
+}
+
+impl fmt::Display for ChildIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChildIndex::Hardened(i) => write!(f, "{}'", i),
+            ChildIndex::Normal(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+/// Path of the key in the key tree, starting from the master key
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct DerivationPath(Vec<ChildIndex>);
+
+impl DerivationPath {
+    /// Create a path from the child indices (master key is an empty path)
+    pub fn new(indices: Vec<ChildIndex>) -> DerivationPath {
+        DerivationPath(indices)
+    }
+
+    /// EIP-3 path m/44'/429'/account'/0/address_index
+    pub fn eip3(account: u32, address_index: u32) -> DerivationPath {
+        DerivationPath(vec![
+            ChildIndex::Hardened(PURPOSE),
+            ChildIndex::Hardened(ERG_COIN_TYPE),
+            ChildIndex::Hardened(account),
+            ChildIndex::Normal(0),
+            ChildIndex::Normal(address_index),
+        ])
+    }
+
+    /// Child indices of the path
+    pub fn indices(&self) -> &[ChildIndex] {
+        &self.0
+    }
+
+    fn extended(&self, index: ChildIndex) -> DerivationPath {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        DerivationPath(indices)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors on the extended secret key derivation
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ExtSecretKeyError {
+    /// Seed produced a master key outside of the group order range (or zero)
+    #[error("Seed does not produce a valid master key")]
+    InvalidMasterKey,
+}
+
+/// Secret key with the chain code, allowing to derive child keys (BIP-32)
+#[derive(PartialEq, Debug, Clone)]
+pub struct ExtSecretKey {
+    private_input: DlogProverInput,
+    chain_code: Zeroizing<[u8; CHAIN_CODE_LENGTH]>,
+    path: DerivationPath,
+}
+
+impl ExtSecretKey {
+    /// Derive the master key from the seed (see [`super::mnemonic::Mnemonic::to_seed`])
+    pub fn derive_master(seed: &[u8]) -> Result<ExtSecretKey, ExtSecretKeyError> {
+        let (key, chain_code) = hmac_sha512(BITCOIN_SEED, &[seed]);
+        match DlogProverInput::from_bytes(&key) {
+            Some(private_input) if !is_zero(&private_input) => Ok(ExtSecretKey {
+                private_input,
+                chain_code,
+                path: DerivationPath::default(),
+            }),
+            _ => Err(ExtSecretKeyError::InvalidMasterKey),
+        }
+    }
+
+    /// Derive the child key. If the index produces an invalid key the next index is used, as
+    /// BIP-32 prescribes. `use_pre_1627_key_derivation` reproduces the reference node wallet
+    /// derivation prior to the fix of https://github.com/ergoplatform/ergo/issues/1627
+    /// (secret key bytes are not padded to 32 bytes when deriving hardened children).
+    pub fn child(&self, index: ChildIndex, use_pre_1627_key_derivation: bool) -> ExtSecretKey {
+        let parent_key_bytes = Zeroizing::new(self.private_input.to_bytes());
+        let key_data: Zeroizing<Vec<u8>> = if index.is_hardened() {
+            let key_bytes: &[u8] = if use_pre_1627_key_derivation {
+                let leading_zeros = parent_key_bytes.iter().take_while(|b| **b == 0).count();
+                &parent_key_bytes[leading_zeros..]
+            } else {
+                parent_key_bytes.as_ref()
+            };
+            Zeroizing::new([&[0u8], key_bytes].concat())
+        } else {
+            // EcPoint serialization may fail only on IO errors
+            Zeroizing::new(
+                self.private_input
+                    .public_image()
+                    .h
+                    .sigma_serialize_bytes()
+                    .unwrap(),
+            )
+        };
+        let (child_key_proto, chain_code) = hmac_sha512(
+            self.chain_code.as_ref(),
+            &[key_data.as_ref(), &index.to_bits().to_be_bytes()],
+        );
+        match DlogProverInput::from_bytes(&child_key_proto) {
+            Some(tweak) => {
+                let private_input = DlogProverInput::from(tweak.w + self.private_input.w);
+                if is_zero(&private_input) {
+                    self.child(index.next(), use_pre_1627_key_derivation)
+                } else {
+                    ExtSecretKey {
+                        private_input,
+                        chain_code,
+                        path: self.path.extended(index),
+                    }
+                }
+            }
+            None => self.child(index.next(), use_pre_1627_key_derivation),
+        }
+    }
+
+    /// Derive the key at the given path relative to this key
+    pub fn derive(&self, path: &DerivationPath, use_pre_1627_key_derivation: bool) -> ExtSecretKey {
+        path.indices().iter().fold(self.clone(), |key, index| {
+            key.child(*index, use_pre_1627_key_derivation)
+        })
+    }
+
+    /// Secret key
+    pub fn secret_key(&self) -> SecretKey {
+        self.private_input.clone().into()
+    }
+
+    /// Chain code
+    pub fn chain_code(&self) -> &[u8; CHAIN_CODE_LENGTH] {
+        &self.chain_code
+    }
+
+    /// Path of this key from the master key
+    pub fn path(&self) -> &DerivationPath {
+        &self.path
+    }
+}
+
+fn is_zero(private_input: &DlogProverInput) -> bool {
+    private_input.to_bytes() == [0u8; DlogProverInput::SIZE_BYTES]
+}
+
+/// HMAC-SHA512 of the concatenated data, split into the key and the chain code halves
+#[allow(clippy::type_complexity)]
+fn hmac_sha512(
+    key: &[u8],
+    data: &[&[u8]],
+) -> (
+    Zeroizing<[u8; DlogProverInput::SIZE_BYTES]>,
+    Zeroizing<[u8; CHAIN_CODE_LENGTH]>,
+) {
+    // HMAC accepts keys of any size
+    let mut mac = HmacSha512::new_from_slice(key).unwrap();
+    for d in data {
+        mac.update(d);
+    }
+    let output = Zeroizing::new(mac.finalize().into_bytes().to_vec());
+    let mut left = Zeroizing::new([0u8; DlogProverInput::SIZE_BYTES]);
+    let mut right = Zeroizing::new([0u8; CHAIN_CODE_LENGTH]);
+    left.copy_from_slice(&output[..DlogProverInput::SIZE_BYTES]);
+    right.copy_from_slice(&output[DlogProverInput::SIZE_BYTES..]);
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::mnemonic::Mnemonic;
+
+    fn secret_hex(key: &ExtSecretKey) -> String {
+        base16::encode_lower(&key.secret_key().to_bytes())
+    }
+
+    #[test]
+    fn bip32_test_vector1() {
+        // from https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki#test-vector-1
+        let seed = base16::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtSecretKey::derive_master(&seed).unwrap();
+        assert_eq!(
+            secret_hex(&master),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        let child = master.child(ChildIndex::Hardened(0), false);
+        assert_eq!(
+            secret_hex(&child),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+        let grandchild = child.child(ChildIndex::Normal(1), false);
+        assert_eq!(
+            secret_hex(&grandchild),
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+        );
+        assert_eq!(grandchild.path().to_string(), "m/0'/1");
+    }
+
+    #[test]
+    fn ergo_node_key_tree_derivation() {
+        // from the reference node ExtendedSecretKeySpec (keys are Base58-encoded there)
+        let seed = Mnemonic::to_seed("edge talent poet tortoise trumpet dose", "");
+        let master = ExtSecretKey::derive_master(seed.as_ref()).unwrap();
+        assert_eq!(
+            secret_hex(&master),
+            "392f75ad23278b3cd7b060d900138f20f8cba89abb259b5dcf5d9830b49d8e38"
+        );
+        let cases = [
+            (
+                ChildIndex::Normal(1),
+                "a877b1907527fc0b7595efeae85f7026c7d44815314ab9ab13a4d48d63281460",
+            ),
+            (
+                ChildIndex::Normal(2),
+                "8186b7c22735c6f1f472b2578fbc3529a23d80bbe2aac71f6f7ffc83691d08ae",
+            ),
+            (
+                ChildIndex::Hardened(2),
+                "b9d19e2139eb033523d19154bfb6ea24aaf6d257bc7b2974211f1e5642305243",
+            ),
+        ];
+        cases.iter().fold(master, |parent, (index, expected)| {
+            let child = parent.child(*index, false);
+            assert_eq!(&secret_hex(&child), expected);
+            child
+        });
+    }
+
+    #[test]
+    fn derive_eip3_path() {
+        let seed = Mnemonic::to_seed("edge talent poet tortoise trumpet dose", "");
+        let master = ExtSecretKey::derive_master(seed.as_ref()).unwrap();
+        let path = DerivationPath::eip3(0, 0);
+        assert_eq!(path.to_string(), "m/44'/429'/0'/0/0");
+        let key = master.derive(&path, false);
+        assert_eq!(key.path(), &path);
+        let key_step_by_step = master
+            .child(ChildIndex::Hardened(44), false)
+            .child(ChildIndex::Hardened(429), false)
+            .child(ChildIndex::Hardened(0), false)
+            .child(ChildIndex::Normal(0), false)
+            .child(ChildIndex::Normal(0), false);
+        assert_eq!(key, key_step_by_step);
+    }
+}
@@ -0,0 +1,307 @@
+//! Transaction fee estimation based on the transaction size and the cost of the input scripts
+
+use std::convert::TryFrom;
+
+use ergotree_interpreter::sigma_protocol;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjecture;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
+
+use crate::chain::parameters::Parameters;
+
+use super::tx_builder::TxBuilder;
+use super::tx_builder::TxBuilderError;
+use super::tx_builder::SUGGESTED_TX_FEE;
+
+/// Cost of ProveDlog verification, `proveDlogEvalCost` of the reference implementation cost table
+/// (sigmastate `CostTable`): `groupElementConst + constCost + 2 * expCost + multiplyGroup`
+pub const DLOG_VERIFICATION_COST: u64 = 1 + 10 + 2 * 5000 + 50;
+/// Cost of ProveDhTuple verification, `proveDHTupleEvalCost` of the reference implementation cost
+/// table (sigmastate `CostTable`): `proveDlogEvalCost * 4`
+pub const DHT_VERIFICATION_COST: u64 = DLOG_VERIFICATION_COST * 4;
+/// Rough heuristic for the cost of the script deserialization and evaluation per byte of the
+/// serialized script. It is NOT a consensus value: neither the reference cost table (which costs
+/// every evaluated operation) nor [`Parameters`] have a per-byte script cost. The actual cost of a
+/// script can be higher or lower, so the estimated fee is only an approximation.
+pub const SCRIPT_COST_PER_BYTE: u64 = 10;
+/// Cost of the transaction input ([`Parameters::DEFAULT_INPUT_COST`])
+pub const INPUT_COST: u64 = Parameters::DEFAULT_INPUT_COST as u64;
+/// Cost of the transaction data input ([`Parameters::DEFAULT_DATA_INPUT_COST`])
+pub const DATA_INPUT_COST: u64 = Parameters::DEFAULT_DATA_INPUT_COST as u64;
+/// Cost of the transaction output ([`Parameters::DEFAULT_OUTPUT_COST`])
+pub const OUTPUT_COST: u64 = Parameters::DEFAULT_OUTPUT_COST as u64;
+/// Maximum number of fee re-estimation rounds in [`build_with_estimated_fee`]
+pub const MAX_FEE_ESTIMATION_ROUNDS: usize = 10;
+
+/// Fee policy, i.e. how much to pay for the transaction size and the cost of its scripts
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct FeePolicy {
+    /// nanoERGs per byte of the signed transaction
+    pub fee_per_byte: u64,
+    /// nanoERGs per 1000 units of the transaction cost
+    pub fee_per_kilo_cost: u64,
+    /// minimal fee, paid even if the size and cost based fee is lower
+    pub min_fee: BoxValue,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        FeePolicy {
+            fee_per_byte: 1000,
+            fee_per_kilo_cost: 100,
+            min_fee: SUGGESTED_TX_FEE(),
+        }
+    }
+}
+
+impl FeePolicy {
+    /// Fee for the transaction of the given size (in bytes) and cost
+    pub fn fee(&self, tx_size_bytes: usize, tx_cost: u64) -> Result<BoxValue, TxBuilderError> {
+        let size_fee = (tx_size_bytes as u64).saturating_mul(self.fee_per_byte);
+        let cost_fee = tx_cost.saturating_mul(self.fee_per_kilo_cost) / 1000;
+        let fee = size_fee.saturating_add(cost_fee);
+        if fee > *self.min_fee.as_u64() {
+            Ok(BoxValue::try_from(fee)?)
+        } else {
+            Ok(self.min_fee)
+        }
+    }
+}
+
+/// Fee estimated for the transaction
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct FeeEstimate {
+    /// Estimated size of the signed transaction in bytes
+    pub tx_size_bytes: usize,
+    /// Estimated cost of the transaction
+    pub tx_cost: u64,
+    /// Fee according to the fee policy
+    pub fee: BoxValue,
+}
+
+/// Sigma propositions the script is expected to be reduced to. Empty if the script is not a
+/// sigma proposition and has no sigma propositions among its (segregated) constants.
+fn expected_sigma_propositions(tree: &ErgoTree) -> Vec<SigmaBoolean> {
+    if let Ok(expr) = tree.proposition() {
+        if let Expr::Const(Constant {
+            v: Literal::SigmaProp(sp),
+            ..
+        }) = expr.as_ref()
+        {
+            return vec![sp.value().clone()];
+        }
+    }
+    let constants_len = tree.constants_len().unwrap_or(0);
+    (0..constants_len)
+        .filter_map(|idx| match tree.get_constant(idx) {
+            Ok(Some(Constant {
+                v: Literal::SigmaProp(sp),
+                ..
+            })) => Some(sp.value().clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Expected size of the spending proof for the box guarded by the given script.
+/// For scripts that are not sigma propositions the proof of all the sigma propositions found
+/// among the script constants is assumed (as if they were combined with OR), and if there are none
+/// the size of P2PK proof is assumed.
+pub fn estimate_proof_size(tree: &ErgoTree) -> usize {
+    let sigma_props = expected_sigma_propositions(tree);
+    if sigma_props.is_empty() {
+        sigma_protocol::SOUNDNESS_BYTES + sigma_protocol::GROUP_SIZE
+    } else {
+        // proof for OR of the propositions takes exactly the sum of their proof sizes
+        sigma_props.iter().map(sigma_protocol::proof_size).sum()
+    }
+}
+
+fn verification_cost(sb: &SigmaBoolean) -> u64 {
+    match sb {
+        SigmaBoolean::TrivialProp(_) => 0,
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDlog(_)) => {
+            DLOG_VERIFICATION_COST
+        }
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDhTuple(_)) => {
+            DHT_VERIFICATION_COST
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(cand)) => {
+            cand.items.iter().map(verification_cost).sum()
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(cor)) => {
+            cor.items.iter().map(verification_cost).sum()
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(ct)) => {
+            ct.items.iter().map(verification_cost).sum()
+        }
+    }
+}
+
+/// Approximate cost of the script evaluation and verification of its spending proof
+/// (with the same assumptions about the proof as in [`estimate_proof_size`]). The evaluation cost
+/// is the script size times the [`SCRIPT_COST_PER_BYTE`] heuristic, the actual script is not evaluated.
+pub fn estimate_script_cost(tree: &ErgoTree) -> Result<u64, TxBuilderError> {
+    let sigma_props = expected_sigma_propositions(tree);
+    let proof_verification_cost = if sigma_props.is_empty() {
+        DLOG_VERIFICATION_COST
+    } else {
+        sigma_props.iter().map(verification_cost).sum()
+    };
+    let script_size = tree.sigma_serialize_bytes()?.len() as u64;
+    Ok(script_size * SCRIPT_COST_PER_BYTE + proof_verification_cost)
+}
+
+/// Estimate size, cost and fee (according to the given fee policy) of the transaction built with
+/// the given builder (input, data input and output costs are taken from the builder's parameters)
+pub fn estimate_fee(
+    tx_builder: &TxBuilder<ErgoBox>,
+    fee_policy: &FeePolicy,
+) -> Result<FeeEstimate, TxBuilderError> {
+    let tx_size_bytes = tx_builder
+        .estimate_tx_size_bytes_with_proof_sizes(|b| estimate_proof_size(&b.ergo_tree))?;
+    let tx = tx_builder.clone().build()?;
+    let scripts_cost = tx_builder
+        .box_selection()
+        .boxes
+        .iter()
+        .map(|b| estimate_script_cost(&b.ergo_tree))
+        .sum::<Result<u64, TxBuilderError>>()?;
+    let parameters = tx_builder.parameters();
+    let tx_cost = scripts_cost
+        + tx.inputs.len() as u64 * parameters.input_cost.max(0) as u64
+        + tx.data_inputs.map(|d| d.len()).unwrap_or(0) as u64
+            * parameters.data_input_cost.max(0) as u64
+        + tx.output_candidates.len() as u64 * parameters.output_cost.max(0) as u64;
+    Ok(FeeEstimate {
+        tx_size_bytes,
+        tx_cost,
+        fee: fee_policy.fee(tx_size_bytes, tx_cost)?,
+    })
+}
+
+/// Builds the transaction builder with the fee estimated according to the given fee policy.
+/// `make_tx_builder` selects the inputs (covering outputs and the given fee) and creates
+/// the builder. Since the fee affects the box selection (and so the change and the transaction size)
+/// the fee is re-estimated until it's stable (up to [`MAX_FEE_ESTIMATION_ROUNDS`] times).
+pub fn build_with_estimated_fee<F>(
+    fee_policy: &FeePolicy,
+    make_tx_builder: F,
+) -> Result<TxBuilder<ErgoBox>, TxBuilderError>
+where
+    F: Fn(BoxValue) -> Result<TxBuilder<ErgoBox>, TxBuilderError>,
+{
+    let mut fee = fee_policy.min_fee;
+    for _ in 0..MAX_FEE_ESTIMATION_ROUNDS {
+        let tx_builder = make_tx_builder(fee)?;
+        let estimate = estimate_fee(&tx_builder, fee_policy)?;
+        if estimate.fee <= fee {
+            return Ok(tx_builder);
+        }
+        fee = estimate.fee;
+    }
+    Err(TxBuilderError::FeeEstimationNotConverged(*fee.as_u64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_ir::chain::address::Address;
+    use ergotree_ir::chain::ergo_box::box_value::checked_sum;
+    use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::mir::sigma_and::SigmaAnd;
+    use proptest::{collection::vec, prelude::*};
+    use sigma_test_util::force_any_val;
+
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::wallet::box_selector::BoxSelector;
+    use crate::wallet::box_selector::SimpleBoxSelector;
+
+    use super::*;
+
+    fn p2pk_box(value: u64) -> ErgoBox {
+        let pk = DlogProverInput::random().public_image();
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            ErgoTree::try_from(Expr::Const(pk.into())).unwrap(),
+            vec![],
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_estimate_proof_size() {
+        let pk1 = DlogProverInput::random().public_image();
+        let pk2 = DlogProverInput::random().public_image();
+        let p2pk_size = sigma_protocol::SOUNDNESS_BYTES + sigma_protocol::GROUP_SIZE;
+        let p2pk_tree = ErgoTree::try_from(Expr::Const(pk1.clone().into())).unwrap();
+        assert_eq!(estimate_proof_size(&p2pk_tree), p2pk_size);
+        let and_tree: ErgoTree = Expr::from(
+            SigmaAnd::new(vec![Expr::Const(pk1.into()), Expr::Const(pk2.into())]).unwrap(),
+        )
+        .try_into()
+        .unwrap();
+        // not a constant sigma proposition, the worst case (OR of the constants) is assumed
+        assert_eq!(estimate_proof_size(&and_tree), 2 * p2pk_size);
+        assert!(estimate_script_cost(&and_tree).unwrap() > 2 * DLOG_VERIFICATION_COST);
+    }
+
+    #[test]
+    fn test_fee_policy_min_fee() {
+        let policy = FeePolicy::default();
+        assert_eq!(policy.fee(1, 1).unwrap(), policy.min_fee);
+        assert_eq!(
+            *policy.fee(10_000, 0).unwrap().as_u64(),
+            10_000 * policy.fee_per_byte
+        );
+    }
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_build_with_estimated_fee(input_values in vec(BoxValue::MIN_RAW * 5000 .. BoxValue::MIN_RAW * 10000, 1..10),
+                                         fee_per_byte in 1000..20000u64,
+                                         change_address in any::<Address>()) {
+            let inputs: Vec<ErgoBox> = input_values.into_iter().map(p2pk_box).collect();
+            let all_inputs = checked_sum(inputs.iter().map(|b| b.value)).unwrap();
+            let out_value: BoxValue = (*all_inputs.as_u64() / 2).try_into().unwrap();
+            let out_box: ErgoBoxCandidate =
+                ErgoBoxCandidateBuilder::new(out_value, force_any_val::<ErgoTree>(), 1).build().unwrap();
+            let fee_policy = FeePolicy { fee_per_byte, ..Default::default() };
+            let tx_builder = build_with_estimated_fee(&fee_policy, |fee| {
+                let target_balance = out_value.checked_add(&fee)?;
+                let box_selection = SimpleBoxSelector::new().select(inputs.clone(), target_balance, &[])?;
+                Ok(TxBuilder::new(
+                    box_selection,
+                    vec![out_box.clone()],
+                    1,
+                    fee,
+                    change_address.clone(),
+                    BoxValue::SAFE_USER_MIN,
+                ))
+            }).unwrap();
+            let estimate = estimate_fee(&tx_builder, &fee_policy).unwrap();
+            prop_assert!(tx_builder.fee_amount() >= estimate.fee);
+            prop_assert!(tx_builder.fee_amount() >= fee_policy.min_fee);
+            prop_assert!(*tx_builder.fee_amount().as_u64() >= estimate.tx_size_bytes as u64 * fee_per_byte);
+            prop_assert!(tx_builder.build().is_ok());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Mnemonic operations according to BIP32/BIP39
+
+use hmac::Hmac;
+use sha2::Sha512;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroizing;
+
+/// Length of the seed derived from the mnemonic, in bytes
+pub const SEED_LENGTH: usize = 64;
+
+/// Seed derived from the mnemonic phrase, zeroized on drop
+pub type MnemonicSeed = Zeroizing<[u8; SEED_LENGTH]>;
+
+/// Mnemonic type
+pub struct Mnemonic;
+
+impl Mnemonic {
+    /// Number of PBKDF2 rounds used to derive the seed
+    pub const PBKDF2_ITERATIONS: u32 = 2048;
+
+    /// Convert a mnemonic phrase (and an optional mnemonic password, empty if not used) into a seed,
+    /// the same way the reference node wallet does it
+    pub fn to_seed(mnemonic_phrase: &str, mnemonic_pass: &str) -> MnemonicSeed {
+        let normalized_phrase = Zeroizing::new(mnemonic_phrase.nfkd().collect::<String>());
+        let normalized_salt = Zeroizing::new(
+            format!("mnemonic{}", mnemonic_pass)
+                .nfkd()
+                .collect::<String>(),
+        );
+        let mut seed = Zeroizing::new([0u8; SEED_LENGTH]);
+        pbkdf2::pbkdf2::<Hmac<Sha512>>(
+            normalized_phrase.as_bytes(),
+            normalized_salt.as_bytes(),
+            Mnemonic::PBKDF2_ITERATIONS,
+            seed.as_mut(),
+        );
+        seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bip39_test_vector() {
+        // from https://github.com/trezor/python-mnemonic/blob/master/vectors.json
+        let seed = Mnemonic::to_seed(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "TREZOR",
+        );
+        assert_eq!(
+            base16::encode_lower(seed.as_ref()),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+}
@@ -1,13 +1,28 @@
 //! Secret types
+use std::convert::TryFrom;
+use std::convert::TryInto;
+
+use ergotree_interpreter::sigma_protocol::private_input::DhTupleProverInput;
 use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
 use ergotree_interpreter::sigma_protocol::private_input::PrivateInput;
 use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::address::AddressError;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::expr::Expr;
+use thiserror::Error;
 
 /// Types of secrets
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(into = "SecretKeyJson", try_from = "SecretKeyJson")
+)]
 #[derive(PartialEq, Debug, Clone)]
 pub enum SecretKey {
     /// Secret exponent of a group element, i.e. secret w such as h = g^^w, where g is group generator, h is a public key.
     DlogSecretKey(DlogProverInput),
+    /// Diffie-Hellman tuple and secret w such as u = g^^w and v = h^^w
+    DhtSecretKey(DhTupleProverInput),
 }
 
 impl SecretKey {
@@ -16,15 +31,28 @@ impl SecretKey {
         SecretKey::DlogSecretKey(DlogProverInput::random())
     }
 
+    /// Generates random DhTupleProverInput
+    pub fn random_dht() -> SecretKey {
+        SecretKey::DhtSecretKey(DhTupleProverInput::random())
+    }
+
     /// Parse DlogSecretKey from bytes (SEC-1-encoded scalar)
     pub fn dlog_from_bytes(bytes: &[u8; DlogProverInput::SIZE_BYTES]) -> Option<SecretKey> {
         DlogProverInput::from_bytes(bytes).map(SecretKey::DlogSecretKey)
     }
 
-    /// Address (encoded public image)
-    pub fn get_address_from_public_image(&self) -> Address {
+    /// Parse DhtSecretKey from bytes (SEC-1-encoded scalar followed by the points g, h, u, v)
+    pub fn dht_from_bytes(bytes: &[u8; DhTupleProverInput::SIZE_BYTES]) -> Option<SecretKey> {
+        DhTupleProverInput::from_bytes(bytes).map(SecretKey::DhtSecretKey)
+    }
+
+    /// Address (encoded public image), P2PK for Dlog secret and P2S for Diffie-Hellman tuple
+    pub fn get_address_from_public_image(&self) -> Result<Address, AddressError> {
         match self {
-            SecretKey::DlogSecretKey(dpi) => Address::P2Pk(dpi.public_image()),
+            SecretKey::DlogSecretKey(dpi) => Ok(Address::P2Pk(dpi.public_image())),
+            SecretKey::DhtSecretKey(dht) => Address::recreate_from_ergo_tree(&ErgoTree::try_from(
+                Expr::Const(dht.public_image().clone().into()),
+            )?),
         }
     }
 
@@ -32,6 +60,7 @@ impl SecretKey {
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
             SecretKey::DlogSecretKey(key) => key.to_bytes().to_vec(),
+            SecretKey::DhtSecretKey(key) => key.to_bytes().to_vec(),
         }
     }
 }
@@ -40,6 +69,7 @@ impl From<SecretKey> for PrivateInput {
     fn from(s: SecretKey) -> Self {
         match s {
             SecretKey::DlogSecretKey(dpi) => PrivateInput::DlogProverInput(dpi),
+            SecretKey::DhtSecretKey(dht) => PrivateInput::DhTupleProverInput(dht),
         }
     }
 }
@@ -50,6 +80,86 @@ impl From<DlogProverInput> for SecretKey {
     }
 }
 
+impl From<DhTupleProverInput> for SecretKey {
+    fn from(pi: DhTupleProverInput) -> Self {
+        SecretKey::DhtSecretKey(pi)
+    }
+}
+
+/// Errors on parsing SecretKey
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SecretKeyParsingError {
+    /// Invalid Base16 encoding
+    #[error("Invalid Base16 encoding: {0}")]
+    Base16DecodingError(String),
+    /// Unexpected size of the encoded key
+    #[error("Unexpected size of the encoded key: {0}")]
+    InvalidSize(usize),
+    /// Encoded bytes are not a valid secret
+    #[error("Invalid secret")]
+    InvalidSecret,
+}
+
+/// JSON representation of SecretKey
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum SecretKeyJson {
+    /// Dlog secret, Base16-encoded SEC-1 scalar
+    #[serde(rename = "dlog")]
+    Dlog {
+        /// Base16-encoded secret
+        secret: String,
+    },
+    /// Diffie-Hellman tuple secret, Base16-encoded SEC-1 scalar followed by the points g, h, u, v
+    #[serde(rename = "dht")]
+    Dht {
+        /// Base16-encoded secret and Diffie-Hellman tuple
+        secret: String,
+    },
+}
+
+#[cfg(feature = "json")]
+impl From<SecretKey> for SecretKeyJson {
+    fn from(sk: SecretKey) -> Self {
+        let secret = base16::encode_lower(&sk.to_bytes());
+        match sk {
+            SecretKey::DlogSecretKey(_) => SecretKeyJson::Dlog { secret },
+            SecretKey::DhtSecretKey(_) => SecretKeyJson::Dht { secret },
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<SecretKeyJson> for SecretKey {
+    type Error = SecretKeyParsingError;
+
+    fn try_from(json: SecretKeyJson) -> Result<Self, Self::Error> {
+        let decode = |secret: &str| {
+            base16::decode(secret)
+                .map_err(|e| SecretKeyParsingError::Base16DecodingError(e.to_string()))
+        };
+        match json {
+            SecretKeyJson::Dlog { secret } => {
+                let bytes = decode(&secret)?;
+                let sized_bytes: [u8; DlogProverInput::SIZE_BYTES] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| SecretKeyParsingError::InvalidSize(bytes.len()))?;
+                SecretKey::dlog_from_bytes(&sized_bytes).ok_or(SecretKeyParsingError::InvalidSecret)
+            }
+            SecretKeyJson::Dht { secret } => {
+                let bytes = decode(&secret)?;
+                let sized_bytes: [u8; DhTupleProverInput::SIZE_BYTES] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| SecretKeyParsingError::InvalidSize(bytes.len()))?;
+                SecretKey::dht_from_bytes(&sized_bytes).ok_or(SecretKeyParsingError::InvalidSecret)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,4 +172,35 @@ mod tests {
             SecretKey::dlog_from_bytes(&sk.to_bytes().as_slice().try_into().unwrap()).unwrap();
         assert_eq!(sk, sk_copy);
     }
+
+    #[test]
+    fn dht_roundtrip() {
+        let sk = SecretKey::random_dht();
+        let sk_copy =
+            SecretKey::dht_from_bytes(&sk.to_bytes().as_slice().try_into().unwrap()).unwrap();
+        assert_eq!(sk, sk_copy);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip() {
+        for sk in [SecretKey::random_dlog(), SecretKey::random_dht()] {
+            let json = serde_json::to_string(&sk).unwrap();
+            let sk_copy: SecretKey = serde_json::from_str(&json).unwrap();
+            assert_eq!(sk, sk_copy);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_invalid_dht() {
+        let mut bytes = SecretKey::random_dht().to_bytes();
+        // tamper with the secret so that the tuple does not match it anymore
+        bytes[31] ^= 1;
+        let json = format!(
+            "{{\"type\": \"dht\", \"secret\": \"{}\"}}",
+            base16::encode_lower(&bytes)
+        );
+        assert!(serde_json::from_str::<SecretKey>(&json).is_err());
+    }
 }
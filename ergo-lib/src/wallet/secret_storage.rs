@@ -0,0 +1,505 @@
+//! Encrypted secret storage, compatible with the keystore (JSON secret storage) of the reference node wallet
+
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
+use std::convert::TryInto;
+#[cfg(feature = "json")]
+use std::path::Path;
+
+use aes_gcm::aead::generic_array::typenum::U16;
+use aes_gcm::aes::{Aes128, Aes256};
+use aes_gcm::{AeadInPlace, AesGcm, NewAead};
+use hmac::Hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Sha256, Sha512};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use super::ext_secret_key::{ExtSecretKey, ExtSecretKeyError};
+use super::mnemonic::Mnemonic;
+
+/// Salt length in bytes
+pub const SALT_LENGTH: usize = 32;
+
+/// AES-GCM initialization vector length in bytes (as used by the reference node)
+pub const IV_LENGTH: usize = 16;
+
+/// AES-GCM authentication tag length in bytes
+pub const AUTH_TAG_LENGTH: usize = 16;
+
+type Aes128Gcm = AesGcm<Aes128, U16>;
+type Aes256Gcm = AesGcm<Aes256, U16>;
+
+/// Parameters of the key derivation from the password
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EncryptionSettings {
+    /// Pseudo-random function of PBKDF2 ("HmacSHA256" or "HmacSHA512")
+    pub prf: String,
+    /// Number of PBKDF2 iterations
+    pub c: u32,
+    /// Derived key length in bits (128 or 256)
+    #[cfg_attr(feature = "json", serde(rename = "dkLen"))]
+    pub dk_len: u32,
+}
+
+impl Default for EncryptionSettings {
+    /// Settings used by default in the reference node
+    fn default() -> Self {
+        EncryptionSettings {
+            prf: "HmacSHA256".to_string(),
+            c: 128_000,
+            dk_len: 256,
+        }
+    }
+}
+
+/// Errors on encryption/decryption of the secret storage
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum SecretStorageError {
+    /// Failed to read the storage file
+    #[error("IO error: {0}")]
+    IoError(String),
+    /// Invalid JSON
+    #[error("JSON parsing error: {0}")]
+    JsonError(String),
+    /// Invalid Base16 encoding
+    #[error("Invalid Base16 encoding: {0}")]
+    Base16DecodingError(String),
+    /// Pseudo-random function is not supported
+    #[error("Unsupported pseudo-random function: {0}")]
+    UnsupportedPrf(String),
+    /// Derived key length is not supported
+    #[error("Unsupported derived key length (bits): {0}")]
+    UnsupportedKeyLength(u32),
+    /// Unexpected initialization vector length
+    #[error("Invalid IV length: {0}")]
+    InvalidIvLength(usize),
+    /// Unexpected authentication tag length
+    #[error("Invalid authentication tag length: {0}")]
+    InvalidAuthTagLength(usize),
+    /// Authentication failed (wrong password or corrupted storage)
+    #[error("Decryption failed, wrong password or corrupted storage")]
+    DecryptionFailed,
+    /// Decrypted seed does not produce a valid master key
+    #[error("Master key error: {0}")]
+    ExtSecretKeyError(#[from] ExtSecretKeyError),
+}
+
+/// Seed encrypted with AES-GCM under the key derived from the password with PBKDF2
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "json",
+    serde(into = "EncryptedSecretJson", try_from = "EncryptedSecretJson")
+)]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EncryptedSecret {
+    /// Encrypted seed
+    pub cipher_text: Vec<u8>,
+    /// PBKDF2 salt
+    pub salt: Vec<u8>,
+    /// AES-GCM initialization vector
+    pub iv: Vec<u8>,
+    /// AES-GCM authentication tag
+    pub auth_tag: Vec<u8>,
+    /// Key derivation parameters
+    pub cipher_params: EncryptionSettings,
+    /// Whether keys should be derived the way the node did before the fix of
+    /// https://github.com/ergoplatform/ergo/issues/1627 (`None` for storages created before the fix)
+    pub use_pre_1627_key_derivation: Option<bool>,
+}
+
+impl EncryptedSecret {
+    /// Encrypt the seed with the key derived from the password
+    pub fn encrypt_seed(
+        seed: &[u8],
+        password: &str,
+        settings: EncryptionSettings,
+    ) -> Result<EncryptedSecret, SecretStorageError> {
+        let mut salt = vec![0u8; SALT_LENGTH];
+        let mut iv = vec![0u8; IV_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut iv);
+        let key = derive_encryption_key(password, &salt, &settings)?;
+        let mut cipher_text = seed.to_vec();
+        let auth_tag = match settings.dk_len {
+            128 => encrypt_in_place::<Aes128Gcm>(&key, &iv, &mut cipher_text),
+            _ => encrypt_in_place::<Aes256Gcm>(&key, &iv, &mut cipher_text),
+        }?;
+        Ok(EncryptedSecret {
+            cipher_text,
+            salt,
+            iv,
+            auth_tag,
+            cipher_params: settings,
+            use_pre_1627_key_derivation: Some(false),
+        })
+    }
+
+    /// Encrypt the seed derived from the mnemonic phrase (and mnemonic password, empty if not used)
+    pub fn from_mnemonic(
+        mnemonic_phrase: &str,
+        mnemonic_pass: &str,
+        password: &str,
+        settings: EncryptionSettings,
+    ) -> Result<EncryptedSecret, SecretStorageError> {
+        let seed = Mnemonic::to_seed(mnemonic_phrase, mnemonic_pass);
+        EncryptedSecret::encrypt_seed(seed.as_ref(), password, settings)
+    }
+
+    /// Decrypt the seed, it is zeroized on drop
+    pub fn decrypt_seed(&self, password: &str) -> Result<Zeroizing<Vec<u8>>, SecretStorageError> {
+        let key = derive_encryption_key(password, &self.salt, &self.cipher_params)?;
+        let mut seed = Zeroizing::new(self.cipher_text.clone());
+        match self.cipher_params.dk_len {
+            128 => decrypt_in_place::<Aes128Gcm>(&key, &self.iv, &self.auth_tag, &mut seed),
+            _ => decrypt_in_place::<Aes256Gcm>(&key, &self.iv, &self.auth_tag, &mut seed),
+        }?;
+        Ok(seed)
+    }
+
+    /// Decrypt the seed and derive the master key from it
+    pub fn decrypt_master_key(&self, password: &str) -> Result<ExtSecretKey, SecretStorageError> {
+        let seed = self.decrypt_seed(password)?;
+        Ok(ExtSecretKey::derive_master(&seed)?)
+    }
+
+    /// Whether child keys should be derived the way the node did before the fix of
+    /// https://github.com/ergoplatform/ergo/issues/1627 (storages that predate the flag need it)
+    pub fn use_pre_1627_key_derivation(&self) -> bool {
+        self.use_pre_1627_key_derivation.unwrap_or(true)
+    }
+}
+
+/// Location of the encrypted secret storage in the node JSON format
+#[cfg(feature = "json")]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SecretStorageSource<'a> {
+    /// Path to the storage file
+    File(&'a Path),
+    /// Contents of the storage file
+    Bytes(&'a [u8]),
+}
+
+#[cfg(feature = "json")]
+impl<'a> SecretStorageSource<'a> {
+    /// Read and parse the storage
+    pub fn read(&self) -> Result<EncryptedSecret, SecretStorageError> {
+        let parse = |bytes: &[u8]| {
+            serde_json::from_slice(bytes).map_err(|e| SecretStorageError::JsonError(e.to_string()))
+        };
+        match self {
+            SecretStorageSource::File(path) => {
+                let bytes =
+                    std::fs::read(path).map_err(|e| SecretStorageError::IoError(e.to_string()))?;
+                parse(&bytes)
+            }
+            SecretStorageSource::Bytes(bytes) => parse(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'a> From<&'a Path> for SecretStorageSource<'a> {
+    fn from(path: &'a Path) -> Self {
+        SecretStorageSource::File(path)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'a> From<&'a [u8]> for SecretStorageSource<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        SecretStorageSource::Bytes(bytes)
+    }
+}
+
+/// JSON representation of EncryptedSecret (the node secret storage file format)
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedSecretJson {
+    /// Base16-encoded encrypted seed
+    pub cipher_text: String,
+    /// Base16-encoded salt
+    pub salt: String,
+    /// Base16-encoded initialization vector
+    pub iv: String,
+    /// Base16-encoded authentication tag
+    pub auth_tag: String,
+    /// Key derivation parameters
+    pub cipher_params: EncryptionSettings,
+    /// Key derivation flag, absent in storages created before it was introduced
+    #[serde(
+        rename = "usePre1627KeyDerivation",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub use_pre_1627_key_derivation: Option<bool>,
+}
+
+#[cfg(feature = "json")]
+impl From<EncryptedSecret> for EncryptedSecretJson {
+    fn from(s: EncryptedSecret) -> Self {
+        EncryptedSecretJson {
+            cipher_text: base16::encode_lower(&s.cipher_text),
+            salt: base16::encode_lower(&s.salt),
+            iv: base16::encode_lower(&s.iv),
+            auth_tag: base16::encode_lower(&s.auth_tag),
+            cipher_params: s.cipher_params,
+            use_pre_1627_key_derivation: s.use_pre_1627_key_derivation,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<EncryptedSecretJson> for EncryptedSecret {
+    type Error = SecretStorageError;
+
+    fn try_from(json: EncryptedSecretJson) -> Result<Self, Self::Error> {
+        let decode = |s: &str| {
+            base16::decode(s).map_err(|e| SecretStorageError::Base16DecodingError(e.to_string()))
+        };
+        Ok(EncryptedSecret {
+            cipher_text: decode(&json.cipher_text)?,
+            salt: decode(&json.salt)?,
+            iv: decode(&json.iv)?,
+            auth_tag: decode(&json.auth_tag)?,
+            cipher_params: json.cipher_params,
+            use_pre_1627_key_derivation: json.use_pre_1627_key_derivation,
+        })
+    }
+}
+
+fn derive_encryption_key(
+    password: &str,
+    salt: &[u8],
+    settings: &EncryptionSettings,
+) -> Result<Zeroizing<Vec<u8>>, SecretStorageError> {
+    if settings.dk_len != 128 && settings.dk_len != 256 {
+        return Err(SecretStorageError::UnsupportedKeyLength(settings.dk_len));
+    }
+    let mut key = Zeroizing::new(vec![0u8; settings.dk_len as usize / 8]);
+    match settings.prf.as_str() {
+        "HmacSHA256" => {
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, settings.c, &mut key)
+        }
+        "HmacSHA512" => {
+            pbkdf2::pbkdf2::<Hmac<Sha512>>(password.as_bytes(), salt, settings.c, &mut key)
+        }
+        prf => return Err(SecretStorageError::UnsupportedPrf(prf.to_string())),
+    };
+    Ok(key)
+}
+
+fn encrypt_in_place<C: NewAead + AeadInPlace<NonceSize = U16>>(
+    key: &[u8],
+    iv: &[u8],
+    buffer: &mut [u8],
+) -> Result<Vec<u8>, SecretStorageError> {
+    let iv: [u8; IV_LENGTH] = iv
+        .try_into()
+        .map_err(|_| SecretStorageError::InvalidIvLength(iv.len()))?;
+    let cipher = C::new_from_slice(key)
+        .map_err(|_| SecretStorageError::UnsupportedKeyLength(key.len() as u32 * 8))?;
+    // fails only if the plaintext exceeds the maximum size allowed by AES-GCM (~64GB)
+    let tag = cipher
+        .encrypt_in_place_detached(&iv.into(), &[], buffer)
+        .unwrap();
+    Ok(tag.to_vec())
+}
+
+fn decrypt_in_place<C: NewAead + AeadInPlace<NonceSize = U16, TagSize = U16>>(
+    key: &[u8],
+    iv: &[u8],
+    auth_tag: &[u8],
+    buffer: &mut [u8],
+) -> Result<(), SecretStorageError> {
+    let iv: [u8; IV_LENGTH] = iv
+        .try_into()
+        .map_err(|_| SecretStorageError::InvalidIvLength(iv.len()))?;
+    let auth_tag: [u8; AUTH_TAG_LENGTH] = auth_tag
+        .try_into()
+        .map_err(|_| SecretStorageError::InvalidAuthTagLength(auth_tag.len()))?;
+    let cipher = C::new_from_slice(key)
+        .map_err(|_| SecretStorageError::UnsupportedKeyLength(key.len() as u32 * 8))?;
+    cipher
+        .decrypt_in_place_detached(&iv.into(), &[], buffer, &auth_tag.into())
+        .map_err(|_| SecretStorageError::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "edge talent poet tortoise trumpet dose";
+
+    /// Low iteration count to keep the tests fast
+    fn test_settings(prf: &str, dk_len: u32) -> EncryptionSettings {
+        EncryptionSettings {
+            prf: prf.to_string(),
+            c: 1000,
+            dk_len,
+        }
+    }
+
+    #[test]
+    fn encrypt_decrypt_roundtrip() {
+        let seed = Mnemonic::to_seed(MNEMONIC, "");
+        for (prf, dk_len) in [
+            ("HmacSHA256", 256),
+            ("HmacSHA512", 256),
+            ("HmacSHA256", 128),
+        ] {
+            let storage =
+                EncryptedSecret::encrypt_seed(seed.as_ref(), "pass", test_settings(prf, dk_len))
+                    .unwrap();
+            assert_ne!(storage.cipher_text, seed.to_vec());
+            assert_eq!(
+                storage.decrypt_seed("pass").unwrap().as_slice(),
+                seed.as_ref()
+            );
+        }
+    }
+
+    #[test]
+    fn decrypt_master_key() {
+        let storage =
+            EncryptedSecret::from_mnemonic(MNEMONIC, "", "pass", test_settings("HmacSHA256", 256))
+                .unwrap();
+        let seed = Mnemonic::to_seed(MNEMONIC, "");
+        assert_eq!(
+            storage.decrypt_master_key("pass").unwrap(),
+            ExtSecretKey::derive_master(seed.as_ref()).unwrap()
+        );
+        assert!(!storage.use_pre_1627_key_derivation());
+    }
+
+    #[test]
+    fn wrong_password() {
+        let storage =
+            EncryptedSecret::from_mnemonic(MNEMONIC, "", "pass", test_settings("HmacSHA256", 256))
+                .unwrap();
+        assert_eq!(
+            storage.decrypt_seed("wrong pass"),
+            Err(SecretStorageError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn tampered_cipher_text() {
+        let mut storage =
+            EncryptedSecret::from_mnemonic(MNEMONIC, "", "pass", test_settings("HmacSHA256", 256))
+                .unwrap();
+        storage.cipher_text[0] ^= 1;
+        assert_eq!(
+            storage.decrypt_seed("pass"),
+            Err(SecretStorageError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let seed = Mnemonic::to_seed(MNEMONIC, "");
+        assert_eq!(
+            EncryptedSecret::encrypt_seed(seed.as_ref(), "pass", test_settings("HmacSHA1", 256)),
+            Err(SecretStorageError::UnsupportedPrf("HmacSHA1".to_string()))
+        );
+        assert_eq!(
+            EncryptedSecret::encrypt_seed(seed.as_ref(), "pass", test_settings("HmacSHA256", 512)),
+            Err(SecretStorageError::UnsupportedKeyLength(512))
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn node_json_format() {
+        let storage =
+            EncryptedSecret::from_mnemonic(MNEMONIC, "", "pass", EncryptionSettings::default())
+                .unwrap();
+        let json = serde_json::to_value(&storage).unwrap();
+        assert_eq!(json["cipherParams"]["prf"], "HmacSHA256");
+        assert_eq!(json["cipherParams"]["c"], 128_000);
+        assert_eq!(json["cipherParams"]["dkLen"], 256);
+        assert_eq!(json["usePre1627KeyDerivation"], false);
+        for field in ["cipherText", "salt", "iv", "authTag"] {
+            assert!(json[field].is_string());
+        }
+        let bytes = serde_json::to_vec(&storage).unwrap();
+        let parsed = SecretStorageSource::Bytes(&bytes).read().unwrap();
+        assert_eq!(parsed, storage);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn decrypt_independently_encrypted_storage() {
+        // encrypted outside of this crate (PBKDF2 and AES-GCM of the Python `cryptography` package)
+        // in the node keystore format: the seed of MNEMONIC with the password
+        // "correct horse battery staple" and the node default cipher parameters
+        let json = r#"{
+            "cipherText": "57a370e5e1f5fb155b89a5f7a77f85b3dcabfec055948dbca36976232b9fd6cb605b105c6e5068ad2e1368fdd1bb887808494f31fa2298189cc07c5df4f7471e",
+            "salt": "024dc5ec59324156ea76474b2c60d144f0dc58217c53807f200107f4050d19ca",
+            "iv": "10983715c38341cd3dcc3f947c213948",
+            "authTag": "54e7a26b8cdd3df6b13c179e12a424ec",
+            "cipherParams": { "prf": "HmacSHA256", "c": 128000, "dkLen": 256 },
+            "usePre1627KeyDerivation": false
+        }"#;
+        let storage = SecretStorageSource::Bytes(json.as_bytes()).read().unwrap();
+        assert_eq!(storage.cipher_params, EncryptionSettings::default());
+        assert_eq!(
+            storage
+                .decrypt_seed("correct horse battery staple")
+                .unwrap()
+                .as_slice(),
+            Mnemonic::to_seed(MNEMONIC, "").as_ref()
+        );
+        // master key of MNEMONIC from the reference node ExtendedSecretKeySpec
+        let master_key = storage
+            .decrypt_master_key("correct horse battery staple")
+            .unwrap();
+        assert_eq!(
+            base16::encode_lower(&master_key.secret_key().to_bytes()),
+            "392f75ad23278b3cd7b060d900138f20f8cba89abb259b5dcf5d9830b49d8e38"
+        );
+        assert_eq!(
+            storage.decrypt_seed("pass"),
+            Err(SecretStorageError::DecryptionFailed)
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_without_key_derivation_flag() {
+        let json = r#"{
+            "cipherText": "00",
+            "salt": "00",
+            "iv": "00",
+            "authTag": "00",
+            "cipherParams": { "prf": "HmacSHA256", "c": 1000, "dkLen": 256 }
+        }"#;
+        let storage = SecretStorageSource::Bytes(json.as_bytes()).read().unwrap();
+        assert_eq!(storage.use_pre_1627_key_derivation, None);
+        assert!(storage.use_pre_1627_key_derivation());
+        assert_eq!(
+            storage.decrypt_seed("pass"),
+            Err(SecretStorageError::InvalidIvLength(1))
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn read_from_file() {
+        let storage =
+            EncryptedSecret::from_mnemonic(MNEMONIC, "", "pass", test_settings("HmacSHA256", 256))
+                .unwrap();
+        let path = std::env::temp_dir().join("ergo_lib_secret_storage_test.json");
+        std::fs::write(&path, serde_json::to_vec(&storage).unwrap()).unwrap();
+        let parsed = SecretStorageSource::File(&path).read();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(parsed.unwrap(), storage);
+        assert!(matches!(
+            SecretStorageSource::File(&path).read(),
+            Err(SecretStorageError::IoError(_))
+        ));
+    }
+}
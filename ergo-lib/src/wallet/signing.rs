@@ -1,11 +1,16 @@
 //! Transaction signing
 
 use ergotree_interpreter::sigma_protocol::prover::hint::HintsBag;
+use ergotree_ir::chain::address::Address;
 use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::mir::constant::Constant;
 use ergotree_ir::serialization::SigmaSerializationError;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::chain::transaction::Input;
+use crate::chain::transaction::UnsignedInput;
 use crate::chain::{
     ergo_state_context::ErgoStateContext,
     transaction::{unsigned::UnsignedTransaction, Transaction},
@@ -13,8 +18,8 @@ use crate::chain::{
 
 use ergotree_interpreter::eval::context::Context;
 use ergotree_interpreter::eval::env::Env;
+use ergotree_interpreter::sigma_protocol::prover::Prover;
 use ergotree_interpreter::sigma_protocol::prover::ProverError;
-use ergotree_interpreter::sigma_protocol::prover::{ContextExtension, Prover};
 use thiserror::Error;
 
 /// Errors on transaction signing
@@ -32,6 +37,12 @@ pub enum TxSigningError {
     /// Tx serialization failed (id calculation)
     #[error("Transaction serialization failed: {0}")]
     SerializationError(#[from] SigmaSerializationError),
+    /// Script of the P2SH input box is not found among the provided scripts
+    #[error("P2SH script not found for the input box (tx input index {0})")]
+    P2SHScriptNotFound(usize),
+    /// Failed to serialize the script for P2SH address
+    #[error("P2SH script error: {0}")]
+    P2SHScriptError(#[from] ErgoTreeError),
 }
 
 /// Transaction and an additional info required for signing
@@ -52,6 +63,12 @@ pub fn make_context(
     self_index: usize,
 ) -> Result<Context, TxSigningError> {
     let height = state_ctx.pre_header.height;
+    let extension = tx_ctx
+        .spending_tx
+        .inputs
+        .get(self_index)
+        .map(|input| input.extension.clone())
+        .ok_or_else(|| TxSigningError::ContextError("self_index is out of bounds".to_string()))?;
     let self_box = tx_ctx
         .boxes_to_spend
         .get(self_index)
@@ -65,15 +82,15 @@ pub fn make_context(
         .map(|(idx, b)| ErgoBox::from_box_candidate(b, tx_ctx.spending_tx.id(), idx as u16))
         .collect::<Result<Vec<ErgoBox>, SigmaSerializationError>>()?;
     let data_inputs: Vec<ErgoBox> = tx_ctx.data_boxes.clone();
-    let self_box_ir = Rc::new(self_box);
-    let outputs_ir = outputs.into_iter().map(Rc::new).collect();
+    let self_box_ir = Arc::new(self_box);
+    let outputs_ir = outputs.into_iter().map(Arc::new).collect();
     let inputs_ir = tx_ctx
         .boxes_to_spend
         .clone()
         .into_iter()
-        .map(Rc::new)
+        .map(Arc::new)
         .collect();
-    let data_inputs_ir = data_inputs.into_iter().map(Rc::new).collect();
+    let data_inputs_ir = data_inputs.into_iter().map(Arc::new).collect();
     Ok(Context {
         height,
         self_box: self_box_ir,
@@ -81,11 +98,58 @@ pub fn make_context(
         data_inputs: data_inputs_ir,
         inputs: inputs_ir,
         pre_header: state_ctx.pre_header.clone(),
-        extension: ContextExtension::empty(),
+        extension,
         headers: state_ctx.headers.clone(),
     })
 }
 
+/// Puts the serialized script into the context extension (with id [`Address::P2SH_SCRIPT_VAR_ID`])
+/// of every input that spends a box guarded by a P2SH address.
+/// `scripts` - scripts from which the P2SH addresses of the spent boxes were created
+pub fn add_p2sh_scripts(
+    tx_context: TransactionContext,
+    scripts: &[ErgoTree],
+) -> Result<TransactionContext, TxSigningError> {
+    let p2sh_scripts = scripts
+        .iter()
+        .map(|script| {
+            Ok((
+                Address::p2sh_from_ergo_tree(script)?,
+                Address::p2sh_script_bytes(script)?,
+            ))
+        })
+        .collect::<Result<Vec<(Address, Vec<u8>)>, ErgoTreeError>>()?;
+    let tx = tx_context.spending_tx.clone();
+    let inputs = tx.inputs.enumerated().try_mapped(
+        |(idx, input)| -> Result<UnsignedInput, TxSigningError> {
+            let input_box = tx_context
+                .boxes_to_spend
+                .iter()
+                .find(|b| b.box_id() == input.box_id)
+                .ok_or(TxSigningError::InputBoxNotFound(idx))?;
+            match Address::recreate_from_ergo_tree(&input_box.ergo_tree) {
+                Ok(address @ Address::P2SH(_)) => {
+                    let (_, script_bytes) = p2sh_scripts
+                        .iter()
+                        .find(|(p2sh_address, _)| *p2sh_address == address)
+                        .ok_or(TxSigningError::P2SHScriptNotFound(idx))?;
+                    let mut extension = input.extension;
+                    extension.values.insert(
+                        Address::P2SH_SCRIPT_VAR_ID,
+                        Constant::from(script_bytes.clone()),
+                    );
+                    Ok(UnsignedInput::new(input.box_id, extension))
+                }
+                _ => Ok(input),
+            }
+        },
+    )?;
+    Ok(TransactionContext {
+        spending_tx: UnsignedTransaction::new(inputs, tx.data_inputs, tx.output_candidates)?,
+        ..tx_context
+    })
+}
+
 /// Signs a transaction (generating proofs for inputs)
 pub fn sign_transaction(
     prover: &dyn Prover,
@@ -100,7 +164,7 @@ pub fn sign_transaction(
             .iter()
             .find(|b| b.box_id() == input.box_id)
         {
-            let ctx = Rc::new(make_context(state_context, &tx_context, idx)?);
+            let ctx = Arc::new(make_context(state_context, &tx_context, idx)?);
             prover
                 .prove(
                     &input_box.ergo_tree,
@@ -145,11 +209,13 @@ mod tests {
     use crate::chain::{
         ergo_box::box_builder::ErgoBoxCandidateBuilder, transaction::UnsignedInput,
     };
+    use crate::wallet::secret_key::SecretKey;
+    use crate::wallet::Wallet;
     use ergotree_ir::ergo_tree::ErgoTree;
     use ergotree_ir::mir::expr::Expr;
     use std::convert::TryFrom;
     use std::convert::TryInto;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     fn verify_tx_proofs(
         tx: &Transaction,
@@ -165,7 +231,7 @@ mod tests {
             let res = verifier.verify(
                 &b.ergo_tree,
                 &Env::empty(),
-                Rc::new(force_any_val::<Context>()),
+                Arc::new(force_any_val::<Context>()),
                 input.spending_proof.proof.clone(),
                 &message,
             )?;
@@ -211,6 +277,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_p2sh_tx_signing() {
+        let secret = force_any_val::<DlogProverInput>();
+        let script = ErgoTree::try_from(Expr::Const(secret.public_image().into())).unwrap();
+        let p2sh_address = Address::p2sh_from_ergo_tree(&script).unwrap();
+        let input_box = ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            p2sh_address.script().unwrap(),
+            vec![],
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let candidate = ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, script.clone(), 0)
+            .build()
+            .unwrap();
+        let tx = UnsignedTransaction::new(
+            vec![UnsignedInput::from(input_box.clone())]
+                .try_into()
+                .unwrap(),
+            None,
+            vec![candidate].try_into().unwrap(),
+        )
+        .unwrap();
+        let tx_context = TransactionContext {
+            spending_tx: tx,
+            boxes_to_spend: vec![input_box.clone()],
+            data_boxes: vec![],
+        };
+        assert_eq!(
+            add_p2sh_scripts(tx_context.clone(), &[]),
+            Err(TxSigningError::P2SHScriptNotFound(0))
+        );
+        let tx_context = add_p2sh_scripts(tx_context, &[script]).unwrap();
+        let prover = TestProver {
+            secrets: vec![PrivateInput::DlogProverInput(secret)],
+        };
+        let state_context = ErgoStateContext::dummy();
+        let signed_tx = sign_transaction(&prover, tx_context.clone(), &state_context).unwrap();
+        let input = signed_tx.inputs.first();
+        assert!(input
+            .spending_proof
+            .extension
+            .values
+            .contains_key(&Address::P2SH_SCRIPT_VAR_ID));
+        let ctx = make_context(&state_context, &tx_context, 0).unwrap();
+        let res = TestVerifier
+            .verify(
+                &input_box.ergo_tree,
+                &Env::empty(),
+                Arc::new(ctx),
+                input.spending_proof.proof.clone(),
+                &signed_tx.bytes_to_sign().unwrap(),
+            )
+            .unwrap();
+        assert!(res.result);
+    }
+
+    #[test]
+    fn test_dht_tx_signing() {
+        let secret = SecretKey::random_dht();
+        let input_box = ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            secret
+                .get_address_from_public_image()
+                .unwrap()
+                .script()
+                .unwrap(),
+            vec![],
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap();
+        let candidate =
+            ErgoBoxCandidateBuilder::new(BoxValue::SAFE_USER_MIN, force_any_val::<ErgoTree>(), 0)
+                .build()
+                .unwrap();
+        let tx = UnsignedTransaction::new(
+            vec![UnsignedInput::from(input_box.clone())]
+                .try_into()
+                .unwrap(),
+            None,
+            vec![candidate].try_into().unwrap(),
+        )
+        .unwrap();
+        let tx_context = TransactionContext {
+            spending_tx: tx,
+            boxes_to_spend: vec![input_box.clone()],
+            data_boxes: vec![],
+        };
+        let state_context = ErgoStateContext::dummy();
+        let wallet = Wallet::from_secrets(vec![secret]);
+        let signed_tx = wallet
+            .sign_transaction(tx_context.clone(), &state_context)
+            .unwrap();
+        let ctx = make_context(&state_context, &tx_context, 0).unwrap();
+        let res = TestVerifier
+            .verify(
+                &input_box.ergo_tree,
+                &Env::empty(),
+                Arc::new(ctx),
+                signed_tx.inputs.first().spending_proof.proof.clone(),
+                &signed_tx.bytes_to_sign().unwrap(),
+            )
+            .unwrap();
+        assert!(res.result);
+    }
+
     #[test]
     fn test_proof_from_mainnet() {
         use crate::chain::transaction::Transaction;
@@ -303,7 +481,7 @@ mod tests {
         let ver_res = verifier.verify(
             &ergo_tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             tx.inputs.get(1).unwrap().spending_proof.proof.clone(),
             message.as_slice(),
         );
@@ -0,0 +1,449 @@
+//! Builder of the transactions collecting the storage rent from the expired boxes
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use bounded_vec::BoundedVecOutOfBounds;
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
+use ergotree_interpreter::storage_rent;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmountError;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaSerializationError;
+use thiserror::Error;
+
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilderError;
+use crate::chain::parameters::Parameters;
+use crate::chain::transaction::input::prover_result::ProverResult;
+use crate::chain::transaction::Input;
+use crate::chain::transaction::Transaction;
+
+/// Errors of StorageRentTxBuilder
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum StorageRentTxBuilderError {
+    /// Box is not old enough to collect the storage rent
+    #[error("Box {0:?} is not expired at height {1}")]
+    BoxNotExpired(BoxId, u32),
+    /// Invalid arguments
+    #[error("Invalid arguments: {0}")]
+    InvalidArgs(String),
+    /// Box value error
+    #[error("Box value error: {0}")]
+    BoxValueError(#[from] BoxValueError),
+    /// Collected value is below the minimum value of the rent collector box
+    #[error("Collected value {collected} is below the minimum box value {min_box_value:?}")]
+    CollectedValueTooLow {
+        /// collected storage fees and values of the completely spent boxes
+        collected: u64,
+        /// minimum value of the rent collector box
+        min_box_value: BoxValue,
+    },
+    /// Token amount error
+    #[error("Token amount error: {0}")]
+    TokenAmountError(#[from] TokenAmountError),
+    /// Error on building the output box
+    #[error("ErgoBoxCandidateBuilder error: {0}")]
+    ErgoBoxCandidateBuilderError(#[from] ErgoBoxCandidateBuilderError),
+    /// Serialization error
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] SigmaSerializationError),
+    /// Too many inputs or outputs
+    #[error("Invalid number of inputs or outputs: {0}")]
+    BoundedVecOutOfBounds(#[from] BoundedVecOutOfBounds),
+}
+
+/// Builder of the transaction spending the expired boxes (no proofs are needed). Every box is
+/// recreated with the value reduced by the storage fee (keeping the script, tokens and registers),
+/// boxes with the value not covering the fee are spent completely. Collected fees (and tokens of
+/// the completely spent boxes) are sent to the rent collector.
+#[derive(Clone)]
+pub struct StorageRentTxBuilder {
+    expired_boxes: Vec<ErgoBox>,
+    current_height: u32,
+    rent_collector: ErgoTree,
+    parameters: Parameters,
+}
+
+impl StorageRentTxBuilder {
+    /// Creates new StorageRentTxBuilder
+    /// `expired_boxes` - boxes older than [`storage_rent::STORAGE_PERIOD`] at `current_height`,
+    /// `current_height` - height of the block the transaction is to be included in,
+    /// `rent_collector` - script guarding the box with the collected fees
+    pub fn new(expired_boxes: Vec<ErgoBox>, current_height: u32, rent_collector: ErgoTree) -> Self {
+        StorageRentTxBuilder {
+            expired_boxes,
+            current_height,
+            rent_collector,
+            parameters: Parameters::default(),
+        }
+    }
+
+    /// Set current blockchain parameters (storage fee factor and min value per byte)
+    pub fn set_parameters(&mut self, parameters: Parameters) {
+        self.parameters = parameters;
+    }
+
+    /// Storage fee of the box with the current parameters
+    pub fn storage_fee(&self, b: &ErgoBox) -> Result<u64, StorageRentTxBuilderError> {
+        Ok(storage_rent::storage_fee(
+            b,
+            self.parameters.storage_fee_factor_u64(),
+        )?)
+    }
+
+    fn candidate_builder(
+        &self,
+        value: u64,
+        ergo_tree: ErgoTree,
+    ) -> Result<ErgoBoxCandidateBuilder, StorageRentTxBuilderError> {
+        let mut builder =
+            ErgoBoxCandidateBuilder::new(value.try_into()?, ergo_tree, self.current_height);
+        builder.set_parameters(&self.parameters);
+        Ok(builder)
+    }
+
+    /// Box recreating the expired box with the given value (`None` if the value is below the
+    /// minimum box value)
+    fn recreated_box(
+        &self,
+        b: &ErgoBox,
+        value: u64,
+    ) -> Result<Option<ErgoBoxCandidate>, StorageRentTxBuilderError> {
+        if value < BoxValue::MIN_RAW {
+            return Ok(None);
+        }
+        let mut builder = self.candidate_builder(value, b.ergo_tree.clone())?;
+        b.tokens.iter().for_each(|t| builder.add_token(t.clone()));
+        HashMap::from(b.additional_registers.clone())
+            .into_iter()
+            .for_each(|(id, v)| builder.set_register_value(id, v));
+        match builder.build() {
+            Ok(recreated) => Ok(Some(recreated)),
+            Err(ErgoBoxCandidateBuilderError::BoxValueTooLow { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Rent collector box with the collected value and tokens
+    fn collector_box(
+        &self,
+        collected: u64,
+        tokens: Vec<Token>,
+    ) -> Result<ErgoBoxCandidate, StorageRentTxBuilderError> {
+        if collected < BoxValue::MIN_RAW {
+            return Err(StorageRentTxBuilderError::CollectedValueTooLow {
+                collected,
+                min_box_value: BoxValue::MIN,
+            });
+        }
+        let mut builder = self.candidate_builder(collected, self.rent_collector.clone())?;
+        tokens.into_iter().for_each(|t| builder.add_token(t));
+        builder.build().map_err(|e| match e {
+            ErgoBoxCandidateBuilderError::BoxValueTooLow { min_box_value, .. } => {
+                StorageRentTxBuilderError::CollectedValueTooLow {
+                    collected,
+                    min_box_value,
+                }
+            }
+            e => e.into(),
+        })
+    }
+
+    /// Build the transaction. Boxes that cannot be recreated with the value reduced by the
+    /// storage fee (it's below the minimum box value) are skipped.
+    pub fn build(self) -> Result<Transaction, StorageRentTxBuilderError> {
+        if self.expired_boxes.is_empty() {
+            return Err(StorageRentTxBuilderError::InvalidArgs(
+                "expired boxes are empty".to_string(),
+            ));
+        }
+        let mut outputs: Vec<ErgoBoxCandidate> = vec![];
+        // spent boxes with the index of the recreated box (`None` for completely spent boxes)
+        let mut spent: Vec<(&ErgoBox, Option<usize>)> = vec![];
+        let mut collected_value: u64 = 0;
+        let mut collected_tokens: Vec<Token> = vec![];
+        for b in &self.expired_boxes {
+            if !storage_rent::is_expired(b, self.current_height) {
+                return Err(StorageRentTxBuilderError::BoxNotExpired(
+                    b.box_id(),
+                    self.current_height,
+                ));
+            }
+            let value = *b.value.as_u64();
+            let fee = self.storage_fee(b)?;
+            if value > fee {
+                if let Some(recreated) = self.recreated_box(b, value - fee)? {
+                    spent.push((b, Some(outputs.len())));
+                    outputs.push(recreated);
+                    collected_value += fee;
+                }
+            } else {
+                spent.push((b, None));
+                collected_value += value;
+                for t in &b.tokens {
+                    match collected_tokens
+                        .iter_mut()
+                        .find(|c| c.token_id == t.token_id)
+                    {
+                        Some(c) => c.amount = c.amount.checked_add(&t.amount)?,
+                        None => collected_tokens.push(t.clone()),
+                    }
+                }
+            }
+        }
+        if spent.is_empty() {
+            return Err(StorageRentTxBuilderError::InvalidArgs(
+                "none of the expired boxes can be recreated above the minimum box value"
+                    .to_string(),
+            ));
+        }
+        let collector_index = outputs.len();
+        outputs.push(self.collector_box(collected_value, collected_tokens)?);
+        if outputs.len() > i16::MAX as usize {
+            return Err(StorageRentTxBuilderError::InvalidArgs(
+                "too many outputs".to_string(),
+            ));
+        }
+
+        let inputs: Vec<Input> = spent
+            .into_iter()
+            .map(|(b, idx)| {
+                let mut extension = ContextExtension::empty();
+                extension.values.insert(
+                    storage_rent::STORAGE_INDEX_VAR_ID,
+                    (idx.unwrap_or(collector_index) as i16).into(),
+                );
+                Input::new(
+                    b.box_id(),
+                    ProverResult {
+                        proof: ProofBytes::Empty,
+                        extension,
+                    },
+                )
+            })
+            .collect();
+        Ok(Transaction::new(
+            inputs.try_into()?,
+            None,
+            outputs.try_into()?,
+        )?)
+    }
+}
+
+/// Value of the box after the storage fee is collected with the given parameters (`None` if the
+/// fee is not covered by the box value)
+pub fn value_after_storage_fee(
+    b: &ErgoBox,
+    parameters: &Parameters,
+) -> Result<Option<BoxValue>, StorageRentTxBuilderError> {
+    let fee = storage_rent::storage_fee(b, parameters.storage_fee_factor_u64())?;
+    match b.value.as_u64().checked_sub(fee) {
+        Some(value) if value > 0 => Ok(Some(value.try_into()?)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::token::TokenId;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::mir::constant::TryExtractInto;
+    use sigma_test_util::force_any_val;
+
+    use crate::chain::ergo_state_context::ErgoStateContext;
+    use crate::chain::transaction::verify::verify_transaction;
+    use crate::chain::transaction::verify::TxVerifyError;
+
+    use super::*;
+
+    const HEIGHT: u32 = storage_rent::STORAGE_PERIOD + 1000;
+
+    fn old_box(value: u64, tokens: Vec<Token>) -> ErgoBox {
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            tokens,
+            NonMandatoryRegisters::new(
+                vec![(NonMandatoryRegisterId::R4, 7i64.into())]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn state_context() -> ErgoStateContext {
+        let mut ctx = ErgoStateContext::dummy();
+        ctx.pre_header.height = HEIGHT;
+        ctx
+    }
+
+    #[test]
+    fn test_build_and_verify() {
+        let token = Token {
+            token_id: force_any_val::<TokenId>(),
+            amount: 10.try_into().unwrap(),
+        };
+        let boxes = vec![
+            old_box(10_000_000_000, vec![token.clone()]),
+            old_box(1_000_000, vec![token.clone()]),
+            old_box(2_000_000, vec![]),
+        ];
+        let collector = force_any_val::<ErgoTree>();
+        let builder = StorageRentTxBuilder::new(boxes.clone(), HEIGHT, collector.clone());
+        let fee = builder.storage_fee(&boxes[0]).unwrap();
+        let tx = builder.build().unwrap();
+        assert_eq!(tx.outputs.len(), 2);
+        let recreated = &tx.outputs[0];
+        assert_eq!(*recreated.value.as_u64(), 10_000_000_000 - fee);
+        assert_eq!(recreated.creation_height, HEIGHT);
+        assert_eq!(recreated.tokens, boxes[0].tokens);
+        assert_eq!(
+            recreated.additional_registers,
+            boxes[0].additional_registers
+        );
+        let collected = &tx.outputs[1];
+        assert_eq!(collected.ergo_tree, collector);
+        assert_eq!(*collected.value.as_u64(), fee + 3_000_000);
+        assert_eq!(collected.tokens, vec![token]);
+        let indices: Vec<i16> = tx
+            .inputs
+            .iter()
+            .map(|i| {
+                i.spending_proof.extension.values[&storage_rent::STORAGE_INDEX_VAR_ID]
+                    .clone()
+                    .try_extract_into::<i16>()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(indices, vec![0, 1, 1]);
+        assert_eq!(
+            verify_transaction(&tx, &boxes, &[], &state_context()),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test_verify_invalid_rent_collection() {
+        let boxes = vec![old_box(10_000_000_000, vec![])];
+        let tx = StorageRentTxBuilder::new(boxes.clone(), HEIGHT, force_any_val::<ErgoTree>())
+            .build()
+            .unwrap();
+        // taking more than the storage fee
+        let mut outputs = tx.output_candidates.as_vec().clone();
+        outputs[0].value = outputs[0]
+            .value
+            .checked_sub(&BoxValue::SAFE_USER_MIN)
+            .unwrap();
+        outputs[1].value = outputs[1]
+            .value
+            .checked_add(&BoxValue::SAFE_USER_MIN)
+            .unwrap();
+        let tx = Transaction::new(tx.inputs, None, outputs.try_into().unwrap()).unwrap();
+        assert_eq!(
+            verify_transaction(&tx, &boxes, &[], &state_context()),
+            Err(TxVerifyError::InvalidProof(0))
+        );
+    }
+
+    #[test]
+    fn test_skip_boxes_below_min_value() {
+        let builder = StorageRentTxBuilder::new(vec![], HEIGHT, force_any_val::<ErgoTree>());
+        let b = old_box(10_000_000_000, vec![]);
+        let with_value = |value: u64| {
+            ErgoBox::new(
+                value.try_into().unwrap(),
+                b.ergo_tree.clone(),
+                vec![],
+                b.additional_registers.clone(),
+                b.creation_height,
+                b.transaction_id.clone(),
+                b.index,
+            )
+            .unwrap()
+        };
+        // recreated box value would be 1000 nanoERGs (box size depends on the value)
+        let mut below_min = b.clone();
+        while *below_min.value.as_u64() != builder.storage_fee(&below_min).unwrap() + 1000 {
+            below_min = with_value(builder.storage_fee(&below_min).unwrap() + 1000);
+        }
+        let boxes = vec![
+            below_min.clone(),
+            old_box(10_000_000_000, vec![]),
+            old_box(2_000_000, vec![]),
+        ];
+        let tx = StorageRentTxBuilder::new(boxes.clone(), HEIGHT, force_any_val::<ErgoTree>())
+            .build()
+            .unwrap();
+        assert_eq!(tx.inputs.len(), 2);
+        assert!(tx.inputs.iter().all(|i| i.box_id != below_min.box_id()));
+        assert_eq!(
+            verify_transaction(&tx, &boxes[1..], &[], &state_context()),
+            Ok(0)
+        );
+        assert!(matches!(
+            StorageRentTxBuilder::new(vec![below_min], HEIGHT, force_any_val::<ErgoTree>()).build(),
+            Err(StorageRentTxBuilderError::InvalidArgs(_))
+        ));
+    }
+
+    #[test]
+    fn test_collected_value_too_low() {
+        let token = Token {
+            token_id: force_any_val::<TokenId>(),
+            amount: 10.try_into().unwrap(),
+        };
+        // spent completely, the collector box holding the token needs more than the minimal value
+        let b = old_box(BoxValue::MIN_RAW, vec![token]);
+        assert!(matches!(
+            StorageRentTxBuilder::new(vec![b], HEIGHT, force_any_val::<ErgoTree>()).build(),
+            Err(StorageRentTxBuilderError::CollectedValueTooLow {
+                collected: BoxValue::MIN_RAW,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_not_expired() {
+        let b = old_box(10_000_000_000, vec![]);
+        assert_eq!(
+            StorageRentTxBuilder::new(vec![b.clone()], HEIGHT - 1000, force_any_val::<ErgoTree>())
+                .build()
+                .map(|_| ()),
+            Err(StorageRentTxBuilderError::BoxNotExpired(
+                b.box_id(),
+                HEIGHT - 1000
+            ))
+        );
+    }
+
+    #[test]
+    fn test_value_after_storage_fee() {
+        let params = Parameters::default();
+        let b = old_box(10_000_000_000, vec![]);
+        let fee = storage_rent::storage_fee(&b, params.storage_fee_factor_u64()).unwrap();
+        assert_eq!(
+            value_after_storage_fee(&b, &params).unwrap(),
+            Some((10_000_000_000 - fee).try_into().unwrap())
+        );
+        assert_eq!(
+            value_after_storage_fee(&old_box(1_000_000, vec![]), &params).unwrap(),
+            None
+        );
+    }
+}
@@ -1,11 +1,13 @@
 //! Builder for an UnsignedTransaction
 
 use ergotree_ir::chain::ergo_box::box_value::BoxValueError;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::TryInto;
 
 use bounded_vec::BoundedVecOutOfBounds;
 use ergotree_interpreter::sigma_protocol;
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
 use ergotree_interpreter::sigma_protocol::prover::ProofBytes;
 use ergotree_ir::chain::address::Address;
 use ergotree_ir::chain::address::AddressEncoder;
@@ -14,19 +16,24 @@ use ergotree_ir::chain::ergo_box::box_value::BoxValue;
 use ergotree_ir::chain::ergo_box::BoxId;
 use ergotree_ir::chain::ergo_box::ErgoBoxCandidate;
 use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenAmount;
+use ergotree_ir::chain::token::TokenAmountError;
 use ergotree_ir::chain::token::TokenId;
 use ergotree_ir::serialization::{SigmaParsingError, SigmaSerializable, SigmaSerializationError};
 use thiserror::Error;
 
 use crate::chain::contract::Contract;
 use crate::chain::ergo_box::box_builder::{ErgoBoxCandidateBuilder, ErgoBoxCandidateBuilderError};
+use crate::chain::parameters::Parameters;
 use crate::chain::transaction::unsigned::UnsignedTransaction;
 use crate::chain::transaction::{DataInput, Input, Transaction, UnsignedInput};
 use crate::constants::MINERS_FEE_MAINNET_ADDRESS;
 
+use super::box_selector::sum_tokens;
 use super::box_selector::sum_tokens_from_boxes;
 use super::box_selector::sum_value;
 use super::box_selector::ErgoBoxAssets;
+use super::box_selector::ErgoBoxAssetsData;
 use super::box_selector::ErgoBoxId;
 use super::box_selector::{BoxSelection, BoxSelectorError};
 
@@ -40,6 +47,9 @@ pub struct TxBuilder<S: ErgoBoxAssets> {
     fee_amount: BoxValue,
     change_address: Address,
     min_change_value: BoxValue,
+    token_burn_permit: Vec<Token>,
+    context_extensions: HashMap<BoxId, ContextExtension>,
+    parameters: Parameters,
 }
 
 impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
@@ -67,6 +77,9 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
             fee_amount,
             change_address,
             min_change_value,
+            token_burn_permit: vec![],
+            context_extensions: HashMap::new(),
+            parameters: Parameters::default(),
         }
     }
 
@@ -105,18 +118,69 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
         self.min_change_value
     }
 
+    /// Get tokens permitted to be burned
+    pub fn token_burn_permit(&self) -> Vec<Token> {
+        self.token_burn_permit.clone()
+    }
+
+    /// Get context extension of the input box (empty if not set)
+    pub fn context_extension(&self, box_id: &BoxId) -> ContextExtension {
+        self.context_extensions
+            .get(box_id)
+            .cloned()
+            .unwrap_or_else(ContextExtension::empty)
+    }
+
+    /// Set context extension (variables available to the script) of the input box
+    pub fn set_context_extension(&mut self, box_id: BoxId, context_extension: ContextExtension) {
+        self.context_extensions.insert(box_id, context_extension);
+    }
+
+    /// Get blockchain parameters used for the created boxes (change, miner's fee) and the cost
+    /// estimation (launch parameters if not set)
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+
+    /// Set current blockchain parameters (e.g. from [`crate::chain::ergo_state_context::ErgoStateContext`])
+    pub fn set_parameters(&mut self, parameters: Parameters) {
+        self.parameters = parameters;
+    }
+
     /// Set transaction's data inputs
     pub fn set_data_inputs(&mut self, data_inputs: Vec<DataInput>) {
         self.data_inputs = data_inputs;
     }
 
+    /// Permit burning of the given tokens (exact amounts). The tokens are subtracted from the
+    /// change (unless they are already excluded from it, e.g. via target tokens of the box
+    /// selection). Building fails if any input tokens are burned without permission.
+    pub fn set_token_burn_permit(&mut self, tokens: Vec<Token>) {
+        self.token_burn_permit = tokens;
+    }
+
     /// Estimated serialized transaction size in bytes after signing (assuming P2PK box spending)
     pub fn estimate_tx_size_bytes(&self) -> Result<usize, TxBuilderError> {
+        // size of ProveDlog's proof (P2PK box spending) as it's the most often used proof
+        self.estimate_tx_size_bytes_with_proof_sizes(|_| {
+            sigma_protocol::SOUNDNESS_BYTES + sigma_protocol::GROUP_SIZE
+        })
+    }
+
+    /// Estimated serialized transaction size in bytes after signing, where `proof_size` returns
+    /// the expected size of the spending proof for the given input box
+    /// (see [`ergotree_interpreter::sigma_protocol::proof_size`])
+    pub fn estimate_tx_size_bytes_with_proof_sizes<F: Fn(&S) -> usize>(
+        &self,
+        proof_size: F,
+    ) -> Result<usize, TxBuilderError> {
         let tx = self.build_tx()?;
-        let inputs = tx.inputs.mapped(|ui| {
-            // mock proof of the size of ProveDlog's proof (P2PK box spending)
-            // as it's the most often used proof
-            let proof = ProofBytes::Some(vec![0u8, sigma_protocol::SOUNDNESS_BYTES as u8]);
+        let inputs = tx.inputs.enumerated().mapped(|(idx, ui)| {
+            // mock proof of the expected size
+            let proof = match self.box_selection.boxes.get(idx).map(&proof_size) {
+                Some(size) if size > 0 => ProofBytes::Some(vec![0u8; size]),
+                _ => ProofBytes::Empty,
+            };
             Input::new(
                 ui.box_id.clone(),
                 crate::chain::transaction::input::prover_result::ProverResult {
@@ -159,8 +223,7 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
         let mut output_candidates = self.output_candidates.clone();
         let change_address_ergo_tree = Contract::pay_to_address(&self.change_address)?.ergo_tree();
         let change_boxes: Result<Vec<ErgoBoxCandidate>, ErgoBoxCandidateBuilderError> = self
-            .box_selection
-            .change_boxes
+            .change_without_burned_tokens()
             .iter()
             .filter(|b| b.value >= self.min_change_value)
             .map(|b| {
@@ -169,6 +232,7 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
                     change_address_ergo_tree.clone(),
                     self.current_height,
                 );
+                candidate.set_parameters(&self.parameters);
                 for token in &b.tokens() {
                     candidate.add_token(token.clone());
                 }
@@ -185,7 +249,11 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
             ));
         }
         // add miner's fee
-        let miner_fee_box = new_miner_fee_box(self.fee_amount, self.current_height)?;
+        let miner_fee_box = new_miner_fee_box_with_parameters(
+            self.fee_amount,
+            self.current_height,
+            &self.parameters,
+        )?;
         output_candidates.push(miner_fee_box);
         if output_candidates.len() > Transaction::MAX_OUTPUTS_COUNT {
             return Err(TxBuilderError::InvalidArgs("too many outputs".to_string()));
@@ -221,13 +289,64 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
                     _ => Err(TxBuilderError::NotEnoughTokens(vec![output_token.clone()])),
                 }
             })?;
+        // check that the burned tokens (inputs - outputs) exactly match the permit
+        let output_token_amounts = sum_tokens(output_tokens_without_minted.as_slice());
+        let permitted_token_amounts = sum_tokens(self.token_burn_permit.as_slice());
+        let mut burned_without_permit: Vec<Token> = vec![];
+        for (token_id, input_amount) in &input_tokens {
+            let burned = input_amount.as_u64()
+                - output_token_amounts
+                    .get(token_id)
+                    .map(|a| *a.as_u64())
+                    .unwrap_or(0);
+            let permitted = permitted_token_amounts
+                .get(token_id)
+                .map(|a| *a.as_u64())
+                .unwrap_or(0);
+            if burned > permitted {
+                burned_without_permit.push(Token::from((
+                    token_id.clone(),
+                    (burned - permitted).try_into()?,
+                )));
+            }
+        }
+        if !burned_without_permit.is_empty() {
+            return Err(TxBuilderError::TokensBurnedWithoutPermit(
+                burned_without_permit,
+            ));
+        }
+        let mut permit_exceeded: Vec<Token> = vec![];
+        for (token_id, permitted_amount) in &permitted_token_amounts {
+            let burned = input_tokens
+                .get(token_id)
+                .map(|a| *a.as_u64())
+                .unwrap_or(0)
+                .saturating_sub(
+                    output_token_amounts
+                        .get(token_id)
+                        .map(|a| *a.as_u64())
+                        .unwrap_or(0),
+                );
+            if *permitted_amount.as_u64() > burned {
+                permit_exceeded.push(Token::from((
+                    token_id.clone(),
+                    (permitted_amount.as_u64() - burned).try_into()?,
+                )));
+            }
+        }
+        if !permit_exceeded.is_empty() {
+            return Err(TxBuilderError::TokenBurnPermitExceeded(permit_exceeded));
+        }
 
         Ok(UnsignedTransaction::new(
             self.box_selection
                 .boxes
-                .clone()
-                .into_iter()
-                .map(UnsignedInput::from)
+                .iter()
+                .map(|b| {
+                    let box_id = b.box_id();
+                    let extension = self.context_extension(&box_id);
+                    UnsignedInput::new(box_id, extension)
+                })
                 .collect::<Vec<UnsignedInput>>()
                 .try_into()?,
             self.data_inputs.clone().try_into().ok(),
@@ -235,6 +354,46 @@ impl<S: ErgoBoxAssets + ErgoBoxId + Clone> TxBuilder<S> {
         )?)
     }
 
+    /// Change boxes of the box selection with the permitted token burns subtracted, except
+    /// the amounts already missing from the change (not sent anywhere)
+    fn change_without_burned_tokens(&self) -> Vec<ErgoBoxAssetsData> {
+        let mut change_boxes = self.box_selection.change_boxes.clone();
+        if self.token_burn_permit.is_empty() {
+            return change_boxes;
+        }
+        let input_tokens = sum_tokens_from_boxes(self.box_selection.boxes.as_slice());
+        let output_tokens = sum_tokens_from_boxes(self.output_candidates.as_slice());
+        let change_tokens = sum_tokens_from_boxes(change_boxes.as_slice());
+        for (token_id, permitted_amount) in sum_tokens(self.token_burn_permit.as_slice()) {
+            let amount_of = |tokens: &HashMap<TokenId, TokenAmount>| {
+                tokens.get(&token_id).map(|a| *a.as_u64()).unwrap_or(0)
+            };
+            let already_burned = amount_of(&input_tokens)
+                .saturating_sub(amount_of(&output_tokens))
+                .saturating_sub(amount_of(&change_tokens));
+            let mut to_burn = permitted_amount.as_u64().saturating_sub(already_burned);
+            for change_box in change_boxes.iter_mut() {
+                change_box.tokens = change_box
+                    .tokens
+                    .iter()
+                    .filter_map(|t| {
+                        if t.token_id != token_id || to_burn == 0 {
+                            return Some(t.clone());
+                        }
+                        let amount = *t.amount.as_u64();
+                        let burned = amount.min(to_burn);
+                        to_burn -= burned;
+                        (amount - burned).try_into().ok().map(|amount| Token {
+                            token_id: t.token_id.clone(),
+                            amount,
+                        })
+                    })
+                    .collect();
+            }
+        }
+        change_boxes
+    }
+
     /// Build the unsigned transaction
     pub fn build(self) -> Result<UnsignedTransaction, TxBuilderError> {
         self.build_tx()
@@ -251,13 +410,25 @@ pub fn SUGGESTED_TX_FEE() -> BoxValue {
 pub fn new_miner_fee_box(
     fee_amount: BoxValue,
     creation_height: u32,
+) -> Result<ErgoBoxCandidate, ErgoBoxCandidateBuilderError> {
+    new_miner_fee_box_with_parameters(fee_amount, creation_height, &Parameters::default())
+}
+
+/// Create a box with miner's contract and a given value, checking the box value against the given
+/// blockchain parameters
+pub fn new_miner_fee_box_with_parameters(
+    fee_amount: BoxValue,
+    creation_height: u32,
+    parameters: &Parameters,
 ) -> Result<ErgoBoxCandidate, ErgoBoxCandidateBuilderError> {
     let address_encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
     let miner_fee_address = address_encoder
         .parse_address_from_str(MINERS_FEE_MAINNET_ADDRESS)
         .unwrap();
     let ergo_tree = miner_fee_address.script().unwrap();
-    ErgoBoxCandidateBuilder::new(fee_amount, ergo_tree, creation_height).build()
+    let mut builder = ErgoBoxCandidateBuilder::new(fee_amount, ergo_tree, creation_height);
+    builder.set_parameters(parameters);
+    builder.build()
 }
 
 /// Errors of TxBuilder
@@ -290,6 +461,18 @@ pub enum TxBuilderError {
     /// Invalid Tx input count
     #[error("Invalid tx inputs count: {0}")]
     InvalidInputsCount(#[from] BoundedVecOutOfBounds),
+    /// Token amount error
+    #[error("Token amount error: {0}")]
+    TokenAmountError(#[from] TokenAmountError),
+    /// Input tokens are burned without permission (see [`TxBuilder::set_token_burn_permit`])
+    #[error("Tokens burned without permission: {0:?}")]
+    TokensBurnedWithoutPermit(Vec<Token>),
+    /// Permitted to burn more tokens than actually burned
+    #[error("Token burn permit exceeds burned tokens by: {0:?}")]
+    TokenBurnPermitExceeded(Vec<Token>),
+    /// Estimated fee did not stabilize (see [`super::fee_estimator::build_with_estimated_fee`])
+    #[error("Fee estimation did not converge (last estimated fee: {0} nanoERGs)")]
+    FeeEstimationNotConverged(u64),
 }
 
 #[cfg(test)]
@@ -395,7 +578,7 @@ mod tests {
             ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0);
         let out_box = box_builder.build().unwrap();
         let outputs = vec![out_box];
-        let tx_builder = TxBuilder::new(
+        let mut tx_builder = TxBuilder::new(
             box_selection,
             outputs,
             0,
@@ -403,6 +586,7 @@ mod tests {
             force_any_val::<Address>(),
             BoxValue::SAFE_USER_MIN,
         );
+        tx_builder.set_token_burn_permit(target_tokens);
         let tx = tx_builder.build().unwrap();
         assert!(
             tx.output_candidates.get(0).unwrap().tokens().is_empty(),
@@ -410,6 +594,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_burn_token_without_permit() {
+        let token_pair = Token {
+            token_id: force_any_val::<TokenId>(),
+            amount: 100.try_into().unwrap(),
+        };
+        let input_box = ErgoBox::new(
+            10000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            vec![token_pair.clone()],
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let tx_fee = BoxValue::SAFE_USER_MIN;
+        let out_box_value = BoxValue::SAFE_USER_MIN;
+        let target_balance = out_box_value.checked_add(&tx_fee).unwrap();
+        let target_tokens = vec![Token {
+            amount: 10.try_into().unwrap(),
+            ..token_pair
+        }];
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], target_balance, target_tokens.as_slice())
+            .unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let tx_builder = TxBuilder::new(
+            box_selection,
+            vec![out_box],
+            0,
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        assert_eq!(
+            tx_builder.build(),
+            Err(TxBuilderError::TokensBurnedWithoutPermit(target_tokens)),
+            "expected error on burning tokens without permit"
+        );
+    }
+
+    #[test]
+    fn test_burn_token_from_change() {
+        let token_pair = Token {
+            token_id: force_any_val::<TokenId>(),
+            amount: 100.try_into().unwrap(),
+        };
+        let input_box = ErgoBox::new(
+            10000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            vec![token_pair.clone()],
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let tx_fee = BoxValue::SAFE_USER_MIN;
+        let out_box_value = BoxValue::SAFE_USER_MIN;
+        let target_balance = out_box_value.checked_add(&tx_fee).unwrap();
+        // burned tokens are not among the target tokens, so all of them go to change
+        let box_selection = SimpleBoxSelector::new()
+            .select(vec![input_box], target_balance, &[])
+            .unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            box_selection,
+            vec![out_box],
+            0,
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        let burn_token = Token {
+            amount: 10.try_into().unwrap(),
+            ..token_pair.clone()
+        };
+        tx_builder.set_token_burn_permit(vec![burn_token]);
+        let tx = tx_builder.clone().build().unwrap();
+        let change_box = tx.output_candidates.get(1).unwrap();
+        assert_eq!(
+            change_box.tokens(),
+            vec![Token {
+                amount: 90.try_into().unwrap(),
+                ..token_pair.clone()
+            }],
+            "expected the rest of the tokens in the change box"
+        );
+        let too_much = Token {
+            amount: 101.try_into().unwrap(),
+            ..token_pair.clone()
+        };
+        tx_builder.set_token_burn_permit(vec![too_much]);
+        assert_eq!(
+            tx_builder.build(),
+            Err(TxBuilderError::TokenBurnPermitExceeded(vec![Token {
+                amount: 1.try_into().unwrap(),
+                ..token_pair
+            }]))
+        );
+    }
+
     #[test]
     fn test_mint_token() {
         let input_box = ErgoBox::new(
@@ -565,6 +856,84 @@ mod tests {
         assert!(tx_builder.estimate_tx_size_bytes().unwrap() > 0);
     }
 
+    #[test]
+    fn test_context_extension() {
+        let input = ErgoBox::new(
+            10000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            vec![],
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let tx_fee = super::SUGGESTED_TX_FEE();
+        let out_box_value = input.value.checked_sub(&tx_fee).unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            BoxSelection {
+                boxes: vec![input.clone()],
+                change_boxes: vec![],
+            },
+            vec![out_box],
+            0,
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        let mut extension = ContextExtension::empty();
+        extension.values.insert(0, 1i32.into());
+        tx_builder.set_context_extension(input.box_id(), extension.clone());
+        let tx = tx_builder.build().unwrap();
+        assert_eq!(tx.inputs.first().extension, extension);
+    }
+
+    #[test]
+    fn test_parameters_min_value_per_byte() {
+        let input = ErgoBox::new(
+            10000000i64.try_into().unwrap(),
+            force_any_val::<ErgoTree>(),
+            vec![],
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap();
+        let tx_fee = super::SUGGESTED_TX_FEE();
+        let out_box_value = input.value.checked_sub(&tx_fee).unwrap();
+        let out_box = ErgoBoxCandidateBuilder::new(out_box_value, force_any_val::<ErgoTree>(), 0)
+            .build()
+            .unwrap();
+        let mut tx_builder = TxBuilder::new(
+            BoxSelection {
+                boxes: vec![input],
+                change_boxes: vec![],
+            },
+            vec![out_box],
+            0,
+            tx_fee,
+            force_any_val::<Address>(),
+            BoxValue::SAFE_USER_MIN,
+        );
+        assert_eq!(tx_builder.parameters(), Parameters::default());
+        assert!(tx_builder.clone().build().is_ok());
+        // miner's fee box value is too low for the raised min value per byte
+        tx_builder.set_parameters(Parameters {
+            min_value_per_byte: 100_000,
+            ..Parameters::default()
+        });
+        assert!(matches!(
+            tx_builder.build(),
+            Err(TxBuilderError::ErgoBoxCandidateBuilderError(
+                ErgoBoxCandidateBuilderError::BoxValueTooLow { .. }
+            ))
+        ));
+    }
+
     proptest! {
 
         #![proptest_config(ProptestConfig::with_cases(16))]
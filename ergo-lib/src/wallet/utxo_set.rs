@@ -0,0 +1,445 @@
+//! In-memory set of the unspent boxes (tracked client-side) with the transactions application
+//! and rollback
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use ergotree_ir::chain::address::Address;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::BoxId;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::token::Token;
+use ergotree_ir::chain::token::TokenId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::serialization::SigmaParsingError;
+use thiserror::Error;
+
+use crate::chain::transaction::Transaction;
+
+use super::box_selector::BoxSelection;
+use super::box_selector::BoxSelector;
+use super::box_selector::BoxSelectorError;
+
+/// Errors of UtxoSet
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum UtxoSetError {
+    /// Transaction input is not in the set (already spent or unknown)
+    #[error("Input box {0:?} is not in the UTXO set")]
+    InputBoxNotFound(BoxId),
+    /// Transaction spends the same box twice
+    #[error("Input box {0:?} is spent more than once")]
+    DoubleSpend(BoxId),
+    /// Transaction output is already in the set
+    #[error("Output box {0:?} is already in the UTXO set")]
+    DuplicateOutput(BoxId),
+    /// Transaction height is lower than the height of the last applied transaction
+    #[error("Height {height} is lower than the current height {current_height}")]
+    InvalidHeight {
+        /// height of the applied transaction
+        height: u32,
+        /// height of the last applied transaction
+        current_height: u32,
+    },
+    /// Rollback height is below the lowest height the set can be rolled back to
+    #[error(
+        "Cannot roll back to height {height}, the lowest height to roll back to is {min_height}"
+    )]
+    RollbackTooDeep {
+        /// requested rollback height
+        height: u32,
+        /// initial height of the set or the last pruned height
+        min_height: u32,
+    },
+    /// Address script parsing error
+    #[error("Address script parsing error: {0}")]
+    AddressScriptError(#[from] SigmaParsingError),
+}
+
+/// Changes made by the applied transaction (to be reverted on rollback)
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct AppliedTransaction {
+    height: u32,
+    spent: Vec<(u64, ErgoBox)>,
+    created: Vec<BoxId>,
+}
+
+/// In-memory UTXO set keyed by [`BoxId`]. Transactions (confirmed or not) are applied one after
+/// another with [`UtxoSet::apply_transaction`] and can be undone with [`UtxoSet::rollback_to`],
+/// so that the wallet can chain unconfirmed transactions spending the outputs of the previous ones.
+/// Boxes are listed in the order they were added to the set.
+/// Changes of every applied transaction are kept until they are dropped with [`UtxoSet::prune`].
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct UtxoSet {
+    /// boxes with the sequence number of their insertion
+    boxes: HashMap<BoxId, (u64, ErgoBox)>,
+    next_seq: u64,
+    height: u32,
+    /// lowest height the set can be rolled back to
+    min_rollback_height: u32,
+    applied: Vec<AppliedTransaction>,
+}
+
+impl UtxoSet {
+    /// Create the set with the given unspent boxes at the given height
+    /// (transactions applied before this height cannot be rolled back)
+    pub fn new(boxes: Vec<ErgoBox>, height: u32) -> Result<UtxoSet, UtxoSetError> {
+        let mut set = UtxoSet {
+            height,
+            min_rollback_height: height,
+            ..Default::default()
+        };
+        for b in boxes {
+            set.insert(b)?;
+        }
+        Ok(set)
+    }
+
+    fn insert(&mut self, b: ErgoBox) -> Result<(), UtxoSetError> {
+        let box_id = b.box_id();
+        if self.boxes.contains_key(&box_id) {
+            return Err(UtxoSetError::DuplicateOutput(box_id));
+        }
+        self.boxes.insert(box_id, (self.next_seq, b));
+        self.next_seq += 1;
+        Ok(())
+    }
+
+    /// Height of the last applied transaction (or the initial height)
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of the unspent boxes
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+
+    /// Returns true if there are no unspent boxes
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    /// Unspent box with the given id
+    pub fn get(&self, box_id: &BoxId) -> Option<&ErgoBox> {
+        self.boxes.get(box_id).map(|(_, b)| b)
+    }
+
+    /// Returns true if the box with the given id is unspent
+    pub fn contains(&self, box_id: &BoxId) -> bool {
+        self.boxes.contains_key(box_id)
+    }
+
+    fn filter_boxes<F: Fn(&ErgoBox) -> bool>(&self, predicate: F) -> Vec<&ErgoBox> {
+        let mut boxes: Vec<&(u64, ErgoBox)> =
+            self.boxes.values().filter(|(_, b)| predicate(b)).collect();
+        boxes.sort_by_key(|(seq, _)| *seq);
+        boxes.into_iter().map(|(_, b)| b).collect()
+    }
+
+    /// All unspent boxes
+    pub fn boxes(&self) -> Vec<&ErgoBox> {
+        self.filter_boxes(|_| true)
+    }
+
+    /// Unspent boxes guarded by the given script
+    pub fn boxes_by_ergo_tree(&self, ergo_tree: &ErgoTree) -> Vec<&ErgoBox> {
+        self.filter_boxes(|b| &b.ergo_tree == ergo_tree)
+    }
+
+    /// Unspent boxes guarded by the script of the given address
+    pub fn boxes_by_address(&self, address: &Address) -> Result<Vec<&ErgoBox>, UtxoSetError> {
+        let ergo_tree = address.script()?;
+        Ok(self.boxes_by_ergo_tree(&ergo_tree))
+    }
+
+    /// Unspent boxes holding the given token
+    pub fn boxes_by_token_id(&self, token_id: &TokenId) -> Vec<&ErgoBox> {
+        self.filter_boxes(|b| b.tokens.iter().any(|t| &t.token_id == token_id))
+    }
+
+    /// Apply the transaction included in the block at the given height (or to be included, for
+    /// unconfirmed transactions): remove the inputs and add the outputs. The set is not changed if
+    /// any of the inputs is not in the set. Data inputs are not checked.
+    pub fn apply_transaction(&mut self, tx: &Transaction, height: u32) -> Result<(), UtxoSetError> {
+        if height < self.height {
+            return Err(UtxoSetError::InvalidHeight {
+                height,
+                current_height: self.height,
+            });
+        }
+        let mut spent_ids = HashSet::new();
+        for input in tx.inputs.iter() {
+            if !self.boxes.contains_key(&input.box_id) {
+                return Err(UtxoSetError::InputBoxNotFound(input.box_id.clone()));
+            }
+            if !spent_ids.insert(input.box_id.clone()) {
+                return Err(UtxoSetError::DoubleSpend(input.box_id.clone()));
+            }
+        }
+        // outputs are created with `ErgoBox::from_box_candidate` on the transaction creation
+        if let Some(b) = tx
+            .outputs
+            .iter()
+            .find(|b| self.boxes.contains_key(&b.box_id()) && !spent_ids.contains(&b.box_id()))
+        {
+            return Err(UtxoSetError::DuplicateOutput(b.box_id()));
+        }
+        let spent = tx
+            .inputs
+            .iter()
+            .filter_map(|input| self.boxes.remove(&input.box_id))
+            .collect();
+        let mut created = Vec::with_capacity(tx.outputs.len());
+        for b in &tx.outputs {
+            created.push(b.box_id());
+            self.insert(b.clone())?;
+        }
+        self.applied.push(AppliedTransaction {
+            height,
+            spent,
+            created,
+        });
+        self.height = height;
+        Ok(())
+    }
+
+    /// Lowest height the set can be rolled back to (the initial height or the last pruned height)
+    pub fn min_rollback_height(&self) -> u32 {
+        self.min_rollback_height
+    }
+
+    /// Undo all transactions applied at the heights above the given height (e.g. on the chain
+    /// reorganization or dropped unconfirmed transactions). Fails without changing the set if
+    /// the height is below [`UtxoSet::min_rollback_height`].
+    pub fn rollback_to(&mut self, height: u32) -> Result<(), UtxoSetError> {
+        if height < self.min_rollback_height {
+            return Err(UtxoSetError::RollbackTooDeep {
+                height,
+                min_height: self.min_rollback_height,
+            });
+        }
+        while let Some(tx) = self.applied.last() {
+            if tx.height <= height {
+                break;
+            }
+            if let Some(tx) = self.applied.pop() {
+                tx.created.iter().for_each(|id| {
+                    self.boxes.remove(id);
+                });
+                for (seq, b) in tx.spent {
+                    self.boxes.insert(b.box_id(), (seq, b));
+                }
+            }
+        }
+        self.height = self.height.min(height);
+        Ok(())
+    }
+
+    /// Drop the changes of the transactions applied at or below the given height (e.g. once the
+    /// blocks are deep enough not to be reorganized), they cannot be rolled back afterwards.
+    /// Heights above the current height are treated as the current height.
+    pub fn prune(&mut self, height: u32) {
+        let height = height.min(self.height);
+        if height <= self.min_rollback_height {
+            return;
+        }
+        self.applied.retain(|tx| tx.height > height);
+        self.min_rollback_height = height;
+    }
+
+    /// Select boxes from the unspent boxes (in the order they were added to the set) to satisfy
+    /// the target balance and tokens
+    pub fn select<S: BoxSelector<ErgoBox>>(
+        &self,
+        selector: &S,
+        target_balance: BoxValue,
+        target_tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, BoxSelectorError> {
+        selector.select(
+            self.boxes().into_iter().cloned().collect(),
+            target_balance,
+            target_tokens,
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryInto;
+
+    use ergotree_interpreter::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+    use ergotree_ir::chain::digest32::Digest32;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use ergotree_ir::serialization::SigmaSerializable;
+    use sigma_test_util::force_any_val;
+
+    use crate::chain::contract::Contract;
+    use crate::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use crate::chain::transaction::input::prover_result::ProverResult;
+    use crate::chain::transaction::Input;
+    use crate::wallet::box_selector::SimpleBoxSelector;
+
+    use super::*;
+
+    fn new_box(value: u64, ergo_tree: &ErgoTree, tokens: Vec<Token>) -> ErgoBox {
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            ergo_tree.clone(),
+            tokens,
+            NonMandatoryRegisters::empty(),
+            1,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn p2pk_tree() -> ErgoTree {
+        Contract::pay_to_address(&Address::P2Pk(DlogProverInput::random().public_image()))
+            .unwrap()
+            .ergo_tree()
+    }
+
+    fn spend(inputs: &[&ErgoBox], value: u64, ergo_tree: &ErgoTree) -> Transaction {
+        let inputs: Vec<Input> = inputs
+            .iter()
+            .map(|b| {
+                Input::new(
+                    b.box_id(),
+                    ProverResult {
+                        proof: force_any_val(),
+                        extension: ContextExtension::empty(),
+                    },
+                )
+            })
+            .collect();
+        let output = ErgoBoxCandidateBuilder::new(value.try_into().unwrap(), ergo_tree.clone(), 10)
+            .build()
+            .unwrap();
+        Transaction::new(
+            inputs.try_into().unwrap(),
+            None,
+            vec![output].try_into().unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_and_rollback() {
+        let tree = force_any_val::<ErgoTree>();
+        let b1 = new_box(1_000_000_000, &tree, vec![]);
+        let b2 = new_box(2_000_000_000, &tree, vec![]);
+        let mut set = UtxoSet::new(vec![b1.clone(), b2.clone()], 10).unwrap();
+        let tx1 = spend(&[&b1], 900_000_000, &tree);
+        set.apply_transaction(&tx1, 11).unwrap();
+        // chained unconfirmed transaction
+        let tx2 = spend(&[&tx1.outputs[0], &b2], 2_800_000_000, &tree);
+        set.apply_transaction(&tx2, 12).unwrap();
+        assert_eq!(set.boxes(), vec![&tx2.outputs[0]]);
+        assert_eq!(set.height(), 12);
+
+        assert_eq!(
+            set.apply_transaction(&tx1, 12),
+            Err(UtxoSetError::InputBoxNotFound(b1.box_id()))
+        );
+        assert_eq!(
+            set.apply_transaction(&spend(&[&tx2.outputs[0]], 1_000_000, &tree), 11),
+            Err(UtxoSetError::InvalidHeight {
+                height: 11,
+                current_height: 12
+            })
+        );
+
+        set.rollback_to(11).unwrap();
+        assert_eq!(set.height(), 11);
+        assert_eq!(set.boxes(), vec![&b2, &tx1.outputs[0]]);
+        set.rollback_to(10).unwrap();
+        assert_eq!(set.boxes(), vec![&b1, &b2]);
+        assert_eq!(set.height(), 10);
+        assert_eq!(
+            set.rollback_to(9),
+            Err(UtxoSetError::RollbackTooDeep {
+                height: 9,
+                min_height: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let tree = force_any_val::<ErgoTree>();
+        let b1 = new_box(1_000_000_000, &tree, vec![]);
+        let b2 = new_box(2_000_000_000, &tree, vec![]);
+        let mut set = UtxoSet::new(vec![b1.clone(), b2.clone()], 10).unwrap();
+        let tx1 = spend(&[&b1], 900_000_000, &tree);
+        set.apply_transaction(&tx1, 11).unwrap();
+        let tx2 = spend(&[&b2], 1_900_000_000, &tree);
+        set.apply_transaction(&tx2, 12).unwrap();
+        set.prune(11);
+        assert_eq!(set.applied.len(), 1);
+        assert_eq!(set.min_rollback_height(), 11);
+        let before = set.clone();
+        assert_eq!(
+            set.rollback_to(10),
+            Err(UtxoSetError::RollbackTooDeep {
+                height: 10,
+                min_height: 11
+            })
+        );
+        assert_eq!(set, before);
+        set.rollback_to(11).unwrap();
+        assert_eq!(set.boxes(), vec![&b2, &tx1.outputs[0]]);
+        // pruning above the current height and below the last pruned height
+        set.prune(100);
+        assert_eq!(set.min_rollback_height(), 11);
+        set.prune(5);
+        assert_eq!(set.min_rollback_height(), 11);
+        assert!(set.applied.is_empty());
+    }
+
+    #[test]
+    fn test_double_spend() {
+        let tree = force_any_val::<ErgoTree>();
+        let b = new_box(1_000_000_000, &tree, vec![]);
+        let mut set = UtxoSet::new(vec![b.clone()], 0).unwrap();
+        let before = set.clone();
+        assert_eq!(
+            set.apply_transaction(&spend(&[&b, &b], 900_000_000, &tree), 1),
+            Err(UtxoSetError::DoubleSpend(b.box_id()))
+        );
+        assert_eq!(set, before);
+    }
+
+    #[test]
+    fn test_queries_and_selection() {
+        // distinct trees and token ids (arbitrary ones may coincide)
+        let tree = p2pk_tree();
+        let other_tree = p2pk_tree();
+        let token = Token {
+            token_id: Digest32::from([1u8; 32]).into(),
+            amount: 5.try_into().unwrap(),
+        };
+        let b1 = new_box(1_000_000_000, &tree, vec![token.clone()]);
+        let b2 = new_box(2_000_000_000, &other_tree, vec![]);
+        let set = UtxoSet::new(vec![b1.clone(), b2.clone()], 0).unwrap();
+        assert_eq!(set.boxes_by_ergo_tree(&tree), vec![&b1]);
+        assert_eq!(set.boxes_by_token_id(&token.token_id), vec![&b1]);
+        assert!(set
+            .boxes_by_token_id(&Digest32::from([2u8; 32]).into())
+            .is_empty());
+        let address = Address::P2S(other_tree.sigma_serialize_bytes().unwrap());
+        assert_eq!(set.boxes_by_address(&address).unwrap(), vec![&b2]);
+
+        let selection = set
+            .select(
+                &SimpleBoxSelector::new(),
+                1_500_000_000u64.try_into().unwrap(),
+                &[token],
+            )
+            .unwrap();
+        assert_eq!(selection.boxes, vec![b1, b2]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use ergotree_interpreter::eval::context::Context;
+use ergotree_interpreter::eval::env::Env;
+use ergotree_interpreter::eval::Evaluator;
+use ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+use ergotree_interpreter::sigma_protocol::verifier::TestVerifier;
+use ergotree_ir::chain::address::AddressEncoder;
+use ergotree_ir::chain::address::NetworkPrefix;
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::preheader::PreHeader;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::mir::bin_op::ArithOp;
+use ergotree_ir::mir::bin_op::BinOp;
+use ergotree_ir::mir::bin_op::RelationOp;
+use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
+use ergotree_ir::mir::coll_fold::Fold;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::func_value::FuncArg;
+use ergotree_ir::mir::func_value::FuncValue;
+use ergotree_ir::mir::select_field::SelectField;
+use ergotree_ir::mir::val_def::ValId;
+use ergotree_ir::mir::val_use::ValUse;
+use ergotree_ir::types::stuple::STuple;
+use ergotree_ir::types::stype::SType;
+
+/// Simplified AgeUSD bank contract (see `contracts.rs`)
+const AGE_USD_BANK_ADDRESS: &str = "7Nq5tKsVYCgneNgEfA2BJKwGsWozezNLhCNsRBihcHVFkDTuTThd4Qt1bi7NfCK1HuuVfjksMrEftV6MEFajjuyp1TMD2PX7SYWvkg9zH4CtgpdoBjekCNXs5XawxXnW6FT7GCqXTpJUP2TkkuqBh1df99PTigehys36uZz9wQnkrJXrv3mw3Yy4CM622qe5wdqLtpEonjazEmsw8weqEYegDyfJnswDvDkLPXtcCB86i19jik4fnSTtCcYj3jpWCQ7WL5dZn1ivs5JGRsR2ioNCRiZd3Gu1zJBgbHkMg41Z6VeCRWXjGY99BUtgtQiepSHGHajFCVcFAHhVxccdVUPCxGeEL6c2dNx6qzEkVfTfHs5qBgJewR8KCZTCVTurNBHeqCSVdxnfFvhW3f72cNrae5E1UhTAXU2iX4LZMHQsKyefY24Aq1b1srTyRWLpixjbcezFqA2TKjGSn1p1ruxbR7AQpW24ByPKT9sFE9ii4qNeXDnLcGtAAGS9FC5SD1s516a4NCu6v9zZfTvRKGkCwt78J8DEVnhTbttjcsvqFsUXQrvAv7TGVsaT4mL6B7F5BhRoZwFkgRXqFUVCWvgqJrwwjFRtbc5aZz";
+
+/// Height of the bank box spending
+const HEIGHT: u32 = 377771;
+
+fn age_usd_bank() -> ErgoTree {
+    AddressEncoder::new(NetworkPrefix::Mainnet)
+        .parse_address_from_str(AGE_USD_BANK_ADDRESS)
+        .unwrap()
+        .script()
+        .unwrap()
+}
+
+/// Context of the transaction spending the bank box and re-creating it as the first output
+fn bank_context(bank_tree: ErgoTree) -> Context {
+    let bank_box = Arc::new(
+        ErgoBox::new(
+            BoxValue::try_from(100000000u64).unwrap(),
+            bank_tree,
+            vec![],
+            NonMandatoryRegisters::empty(),
+            HEIGHT,
+            TxId::zero(),
+            0,
+        )
+        .unwrap(),
+    );
+    Context {
+        height: HEIGHT,
+        self_box: bank_box.clone(),
+        outputs: vec![bank_box.clone()],
+        data_inputs: vec![],
+        inputs: vec![bank_box],
+        pre_header: PreHeader::dummy(),
+        headers: std::array::from_fn(|_| Header::dummy()),
+        extension: ContextExtension::empty(),
+    }
+}
+
+/// `sigmaProp(coll.fold(0L, { (acc, x) => acc + x }) == sum)`
+fn sum_fold(coll: Vec<i64>) -> Expr {
+    let sum: i64 = coll.iter().sum();
+    let arg_tpe: SType = STuple::pair(SType::SLong, SType::SLong).into();
+    let arg: Expr = ValUse {
+        val_id: ValId(1),
+        tpe: arg_tpe.clone(),
+    }
+    .into();
+    let body: Expr = BinOp {
+        kind: ArithOp::Plus.into(),
+        left: Box::new(
+            SelectField::new(arg.clone(), 1u8.try_into().unwrap())
+                .unwrap()
+                .into(),
+        ),
+        right: Box::new(
+            SelectField::new(arg, 2u8.try_into().unwrap())
+                .unwrap()
+                .into(),
+        ),
+    }
+    .into();
+    let fold_op = FuncValue::new(
+        vec![FuncArg {
+            idx: ValId(1),
+            tpe: arg_tpe,
+        }],
+        body,
+    );
+    let fold: Expr = Fold::new(Expr::Const(coll.into()), 0i64.into(), fold_op.into())
+        .unwrap()
+        .into();
+    BoolToSigmaProp {
+        input: Box::new(
+            BinOp {
+                kind: RelationOp::Eq.into(),
+                left: Box::new(fold),
+                right: Box::new(sum.into()),
+            }
+            .into(),
+        ),
+    }
+    .into()
+}
+
+fn eval_benchmark(c: &mut Criterion) {
+    let bank_tree = age_usd_bank();
+    let age_usd = bank_tree.proposition().unwrap();
+    let ctx = Arc::new(bank_context(bank_tree));
+    c.bench_function("age_usd_bank_reduce_to_crypto", |b| {
+        b.iter(|| {
+            TestVerifier
+                .reduce_to_crypto(black_box(age_usd.as_ref()), &Env::empty(), ctx.clone())
+                .unwrap()
+        })
+    });
+    let fold = sum_fold((0..10000).collect());
+    c.bench_function("fold_10k_longs", |b| {
+        b.iter(|| {
+            TestVerifier
+                .reduce_to_crypto(black_box(&fold), &Env::empty(), ctx.clone())
+                .unwrap()
+        })
+    }); // the environment is not cloned for each element, so the time is the same as with the
+        // empty environment above
+    let large_env = (2..1002).fold(Env::empty(), |env, id| env.extend(ValId(id), 1i64.into()));
+    c.bench_function("fold_10k_longs_1k_vals_env", |b| {
+        b.iter(|| {
+            TestVerifier
+                .reduce_to_crypto(black_box(&fold), &large_env, ctx.clone())
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, eval_benchmark);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
 //! Interpreter
-use std::rc::Rc;
+use std::sync::Arc;
 
 use ergotree_ir::ergo_tree::ErgoTreeError;
 use ergotree_ir::mir::constant::TryExtractFromError;
@@ -149,40 +149,40 @@ pub struct ReductionResult {
 
 /// Interpreter
 pub trait Evaluator {
+    /// Maximum cost of the expression evaluation (no limit if `None`)
+    fn cost_limit(&self) -> Option<u64> {
+        None
+    }
+
     /// Evaluate the given expression by reducing it to SigmaBoolean value.
     fn reduce_to_crypto(
         &self,
         expr: &Expr,
         env: &Env,
-        ctx: Rc<Context>,
+        ctx: Arc<Context>,
     ) -> Result<ReductionResult, EvalError> {
-        let cost_accum = CostAccumulator::new(0, None);
+        let cost_accum = CostAccumulator::new(0, self.cost_limit());
         let mut ectx = EvalContext::new(ctx, cost_accum);
-        expr.eval(env, &mut ectx)
-            .and_then(|v| -> Result<ReductionResult, EvalError> {
-                match v {
-                    Value::Boolean(b) => Ok(ReductionResult {
-                        sigma_prop: SigmaBoolean::TrivialProp(b),
-                        cost: 0,
-                    }),
-                    Value::SigmaProp(sp) => Ok(ReductionResult {
-                        sigma_prop: sp.value().clone(),
-                        cost: 0,
-                    }),
-                    _ => Err(EvalError::InvalidResultType),
-                }
-            })
+        let sigma_prop = match expr.eval(env, &mut ectx)? {
+            Value::Boolean(b) => SigmaBoolean::TrivialProp(b),
+            Value::SigmaProp(sp) => sp.value().clone(),
+            _ => return Err(EvalError::InvalidResultType),
+        };
+        Ok(ReductionResult {
+            sigma_prop,
+            cost: ectx.cost_accum.total(),
+        })
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct EvalContext {
-    pub(crate) ctx: Rc<Context>,
+    pub(crate) ctx: Arc<Context>,
     pub(crate) cost_accum: CostAccumulator,
 }
 
 impl EvalContext {
-    pub fn new(ctx: Rc<Context>, cost_accum: CostAccumulator) -> Self {
+    pub fn new(ctx: Arc<Context>, cost_accum: CostAccumulator) -> Self {
         EvalContext { ctx, cost_accum }
     }
 }
@@ -338,11 +338,11 @@ pub(crate) mod tests {
     use sigma_test_util::force_any_val;
 
     pub fn eval_out_wo_ctx<T: TryExtractFrom<Value>>(expr: &Expr) -> T {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         eval_out(expr, ctx)
     }
 
-    pub fn eval_out<T: TryExtractFrom<Value>>(expr: &Expr, ctx: Rc<Context>) -> T {
+    pub fn eval_out<T: TryExtractFrom<Value>>(expr: &Expr, ctx: Arc<Context>) -> T {
         let cost_accum = CostAccumulator::new(0, None);
         let mut ectx = EvalContext::new(ctx, cost_accum);
         expr.eval(&Env::empty(), &mut ectx)
@@ -353,7 +353,7 @@ pub(crate) mod tests {
 
     pub fn try_eval_out<T: TryExtractFrom<Value>>(
         expr: &Expr,
-        ctx: Rc<Context>,
+        ctx: Arc<Context>,
     ) -> Result<T, EvalError> {
         let cost_accum = CostAccumulator::new(0, None);
         let mut ectx = EvalContext::new(ctx, cost_accum);
@@ -362,7 +362,7 @@ pub(crate) mod tests {
     }
 
     pub fn try_eval_out_wo_ctx<T: TryExtractFrom<Value>>(expr: &Expr) -> Result<T, EvalError> {
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         try_eval_out(expr, ctx)
     }
 }
@@ -309,7 +309,7 @@ mod tests {
     use num_traits::Bounded;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     fn check_eq_neq(left: Constant, right: Constant) -> bool {
         let eq_op: Expr = BinOp {
@@ -318,14 +318,14 @@ mod tests {
             right: Box::new(right.clone().into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let neq_op: Expr = BinOp {
             kind: BinOpKind::Relation(RelationOp::NEq),
             left: Box::new(left.into()),
             right: Box::new(right.into()),
         }
         .into();
-        let ctx1 = Rc::new(force_any_val::<Context>());
+        let ctx1 = Arc::new(force_any_val::<Context>());
         eval_out::<bool>(&eq_op, ctx) && !eval_out::<bool>(&neq_op, ctx1)
     }
 
@@ -394,7 +394,7 @@ mod tests {
             ),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert!(eval_out::<bool>(&e, ctx));
     }
 
@@ -414,7 +414,7 @@ mod tests {
             ),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert!(!eval_out::<bool>(&e, ctx));
     }
 
@@ -429,7 +429,7 @@ mod tests {
             right: Box::new(right.into().into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         try_eval_out::<T>(&expr, ctx)
     }
 
@@ -444,7 +444,7 @@ mod tests {
             right: Box::new(right.into().into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         try_eval_out::<T>(&expr, ctx)
     }
 
@@ -455,7 +455,7 @@ mod tests {
             right: Box::new(right.into().into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         eval_out::<bool>(&expr, ctx)
     }
 
@@ -466,7 +466,7 @@ mod tests {
             right: Box::new(right.into().into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         eval_out::<bool>(&expr, ctx)
     }
 
@@ -11,8 +11,8 @@ impl Evaluable for ByIndex {
     fn eval(&self, env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
         let input_v = self.input.eval(env, ctx)?;
         let index_v = self.index.eval(env, ctx)?;
-        let normalized_input_vals: Vec<Value> = match input_v {
-            Value::Coll(coll) => Ok(coll.as_vec()),
+        let input_coll = match input_v {
+            Value::Coll(coll) => Ok(coll),
             _ => Err(EvalError::UnexpectedValue(format!(
                 "ByIndex: expected input to be Value::Coll, got: {0:?}",
                 input_v
@@ -21,19 +21,17 @@ impl Evaluable for ByIndex {
         match self.default.clone() {
             Some(default) => {
                 let default_v = default.eval(env, ctx)?;
-                Ok(normalized_input_vals
+                Ok(input_coll
                     .get(index_v.try_extract_into::<i32>()? as usize)
-                    .cloned()
                     .unwrap_or(default_v))
             }
-            None => normalized_input_vals
+            None => input_coll
                 .get(index_v.clone().try_extract_into::<i32>()? as usize)
-                .cloned()
                 .ok_or_else(|| {
                     EvalError::Misc(format!(
                         "ByIndex: index {0:?} out of bounds for collection size {1:?}",
                         index_v,
-                        normalized_input_vals.len()
+                        input_coll.len()
                     ))
                 }),
         }
@@ -52,16 +50,16 @@ mod tests {
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
     use crate::eval::tests::eval_out_wo_ctx;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval() {
         let expr: Expr = ByIndex::new(GlobalVars::Outputs.into(), Expr::Const(0i32.into()), None)
             .unwrap()
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
-            eval_out::<Rc<ErgoBox>>(&expr, ctx.clone()).box_id(),
+            eval_out::<Arc<ErgoBox>>(&expr, ctx.clone()).box_id(),
             ctx.outputs.get(0).unwrap().box_id()
         );
     }
@@ -12,6 +12,7 @@ impl Evaluable for Exists {
         let input_v = self.input.eval(env, ctx)?;
         let condition_v = self.condition.eval(env, ctx)?;
         let input_v_clone = input_v.clone();
+        let mut lambda_env = env.clone();
         let mut condition_call = |arg: Value| match &condition_v {
             Value::Lambda(func_value) => {
                 let func_arg = func_value.args.first().ok_or_else(|| {
@@ -19,15 +20,15 @@ impl Evaluable for Exists {
                         "Exists: evaluated condition has empty arguments list".to_string(),
                     )
                 })?;
-                let env1 = env.clone().extend(func_arg.idx, arg);
-                func_value.body.eval(&env1, ctx)
+                lambda_env.insert(func_arg.idx, arg);
+                func_value.body.eval(&lambda_env, ctx)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected Exists::condition to be Value::FuncValue got: {0:?}",
                 input_v_clone
             ))),
         };
-        let normalized_input_vals: Vec<Value> = match input_v {
+        let input_coll = match input_v {
             Value::Coll(coll) => {
                 if *coll.elem_tpe() != self.elem_tpe {
                     return Err(EvalError::UnexpectedValue(format!(
@@ -36,7 +37,7 @@ impl Evaluable for Exists {
                         coll.elem_tpe()
                     )));
                 };
-                Ok(coll.as_vec())
+                Ok(coll)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected Map input to be Value::Coll, got: {0:?}",
@@ -44,7 +45,7 @@ impl Evaluable for Exists {
             ))),
         }?;
 
-        for item in normalized_input_vals {
+        for item in input_coll.iter() {
             let res = condition_call(item)?.try_extract_into::<bool>()?;
             if res {
                 return Ok(true.into());
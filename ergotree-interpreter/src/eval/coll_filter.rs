@@ -13,6 +13,7 @@ impl Evaluable for Filter {
         let input_v = self.input.eval(env, ctx)?;
         let condition_v = self.condition.eval(env, ctx)?;
         let input_v_clone = input_v.clone();
+        let mut lambda_env = env.clone();
         let mut condition_call = |arg: Value| match &condition_v {
             Value::Lambda(func_value) => {
                 let func_arg = func_value.args.first().ok_or_else(|| {
@@ -20,15 +21,15 @@ impl Evaluable for Filter {
                         "Filter: evaluated condition has empty arguments list".to_string(),
                     )
                 })?;
-                let env1 = env.clone().extend(func_arg.idx, arg);
-                func_value.body.eval(&env1, ctx)
+                lambda_env.insert(func_arg.idx, arg);
+                func_value.body.eval(&lambda_env, ctx)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected Filter::condition to be Value::FuncValue got: {0:?}",
                 input_v_clone
             ))),
         };
-        let normalized_input_vals: Vec<Value> = match input_v {
+        let input_coll = match input_v {
             Value::Coll(coll) => {
                 if *coll.elem_tpe() != self.elem_tpe {
                     return Err(EvalError::UnexpectedValue(format!(
@@ -37,7 +38,7 @@ impl Evaluable for Filter {
                         coll.elem_tpe()
                     )));
                 };
-                Ok(coll.as_vec())
+                Ok(coll)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected Map input to be Value::Coll, got: {0:?}",
@@ -45,22 +46,12 @@ impl Evaluable for Filter {
             ))),
         }?;
 
-        let items_conditions: Vec<bool> = normalized_input_vals
-            .clone()
-            .into_iter()
-            .map(|item| {
-                condition_call(item).and_then(|res| {
-                    res.try_extract_into::<bool>()
-                        .map_err(EvalError::TryExtractFrom)
-                })
-            })
-            .collect::<Result<Vec<bool>, EvalError>>()?;
-        let filtered_items = normalized_input_vals
-            .into_iter()
-            .zip(items_conditions)
-            .filter(|(_, condition)| *condition)
-            .map(|(item, _)| item)
-            .collect();
+        let mut filtered_items = Vec::new();
+        for item in input_coll.iter() {
+            if condition_call(item.clone())?.try_extract_into::<bool>()? {
+                filtered_items.push(item);
+            }
+        }
         Ok(Value::Coll(CollKind::from_vec(
             self.elem_tpe.clone(),
             filtered_items,
@@ -72,7 +63,7 @@ impl Evaluable for Filter {
 #[allow(clippy::panic)]
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
@@ -128,12 +119,12 @@ mod tests {
             )
             .unwrap()
             .into();
-            let ctx = Rc::new(ctx);
+            let ctx = Arc::new(ctx);
             assert_eq!(
-                eval_out::<Vec<Rc<ErgoBox>>>(&expr, ctx.clone()),
+                eval_out::<Vec<Arc<ErgoBox>>>(&expr, ctx.clone()),
                 ctx.data_inputs.clone()
                     .into_iter()
-                    .filter(| b| 1 <= b.value.as_i64()).collect::<Vec<Rc<ErgoBox>>>()
+                    .filter(| b| 1 <= b.value.as_i64()).collect::<Vec<Arc<ErgoBox>>>()
             );
         }
     }
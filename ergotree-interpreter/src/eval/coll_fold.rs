@@ -14,14 +14,15 @@ impl Evaluable for Fold {
         let zero_v = self.zero.eval(env, ctx)?;
         let fold_op_v = self.fold_op.eval(env, ctx)?;
         let input_v_clone = input_v.clone();
+        let mut lambda_env = env.clone();
         let mut fold_op_call = |arg: Value| match &fold_op_v {
             Value::Lambda(func_value) => {
                 let func_arg = func_value
                     .args
                     .first()
                     .ok_or_else(|| EvalError::NotFound("empty argument for fold op".to_string()))?;
-                let env1 = env.clone().extend(func_arg.idx, arg);
-                func_value.body.eval(&env1, ctx)
+                lambda_env.insert(func_arg.idx, arg);
+                func_value.body.eval(&lambda_env, ctx)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected fold_op to be Value::FuncValue got: {0:?}",
@@ -57,7 +58,7 @@ impl Evaluable for Fold {
 #[cfg(test)]
 mod tests {
     use std::convert::TryInto;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
@@ -123,7 +124,7 @@ mod tests {
             )
             .unwrap()
             .into();
-            let ctx = Rc::new(ctx);
+            let ctx = Arc::new(ctx);
             assert_eq!(
                 eval_out::<i64>(&expr, ctx.clone()),
                 ctx.data_inputs
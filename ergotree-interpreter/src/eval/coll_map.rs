@@ -12,15 +12,16 @@ impl Evaluable for Map {
         let input_v = self.input.eval(env, ctx)?;
         let mapper_v = self.mapper.eval(env, ctx)?;
         let input_v_clone = input_v.clone();
-        let mut mapper_call = |arg: Value| match &mapper_v {
+        let mut lambda_env = env.clone();
+        let mapper_call = |arg: Value| match &mapper_v {
             Value::Lambda(func_value) => {
                 let func_arg = func_value.args.first().ok_or_else(|| {
                     EvalError::NotFound(
                         "Map: evaluated mapper has empty arguments list".to_string(),
                     )
                 })?;
-                let env1 = env.clone().extend(func_arg.idx, arg);
-                func_value.body.eval(&env1, ctx)
+                lambda_env.insert(func_arg.idx, arg);
+                func_value.body.eval(&lambda_env, ctx)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected mapper to be Value::FuncValue got: {0:?}",
@@ -37,7 +38,7 @@ impl Evaluable for Map {
                 )
             })?
             .clone();
-        let normalized_input_vals: Vec<Value> = match input_v {
+        let input_coll = match input_v {
             Value::Coll(coll) => {
                 if *coll.elem_tpe() != mapper_input_tpe {
                     return Err(EvalError::UnexpectedValue(format!(
@@ -46,16 +47,16 @@ impl Evaluable for Map {
                         coll.elem_tpe()
                     )));
                 };
-                Ok(coll.as_vec())
+                Ok(coll)
             }
             _ => Err(EvalError::UnexpectedValue(format!(
                 "expected Map input to be Value::Coll, got: {0:?}",
                 input_v
             ))),
         }?;
-        normalized_input_vals
+        input_coll
             .iter()
-            .map(|item| mapper_call(item.clone()))
+            .map(mapper_call)
             .collect::<Result<Vec<Value>, EvalError>>()
             .map(|values| {
                 CollKind::from_vec(self.out_elem_tpe(), values).map_err(EvalError::TryExtractFrom)
@@ -69,7 +70,7 @@ impl Evaluable for Map {
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
@@ -124,7 +125,7 @@ mod tests {
             )
             .unwrap()
             .into();
-            let ctx = Rc::new(ctx);
+            let ctx = Arc::new(ctx);
             assert_eq!(
                 eval_out::<Vec<i64>>(&expr, ctx.clone()),
                 ctx.data_inputs
@@ -9,14 +9,14 @@ use crate::eval::Evaluable;
 impl Evaluable for SizeOf {
     fn eval(&self, env: &Env, ctx: &mut EvalContext) -> Result<Value, EvalError> {
         let input_v = self.input.eval(env, ctx)?;
-        let normalized_input_vals: Vec<Value> = match input_v {
-            Value::Coll(coll) => Ok(coll.as_vec()),
+        let input_len = match input_v {
+            Value::Coll(coll) => Ok(coll.len()),
             _ => Err(EvalError::UnexpectedValue(format!(
                 "SizeOf: expected input to be Value::Coll, got: {0:?}",
                 input_v
             ))),
         }?;
-        Ok((normalized_input_vals.len() as i32).into())
+        Ok((input_len as i32).into())
     }
 }
 
@@ -30,14 +30,14 @@ mod tests {
     use ergotree_ir::mir::global_vars::GlobalVars;
     use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval() {
         let expr: Expr = SizeOf::try_build(GlobalVars::Outputs.into())
             .unwrap()
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
             eval_out::<i32>(&expr, ctx.clone()),
             ctx.outputs.len() as i32
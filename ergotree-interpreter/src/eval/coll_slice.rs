@@ -1,6 +1,5 @@
 use ergotree_ir::mir::coll_slice::Slice;
 use ergotree_ir::mir::constant::TryExtractInto;
-use ergotree_ir::mir::value::CollKind;
 use ergotree_ir::mir::value::Value;
 
 use crate::eval::env::Env;
@@ -13,8 +12,8 @@ impl Evaluable for Slice {
         let input_v = self.input.eval(env, ctx)?;
         let from_v = self.from.eval(env, ctx)?;
         let until_v = self.until.eval(env, ctx)?;
-        let input_vec: Vec<Value> = match input_v {
-            Value::Coll(coll) => Ok(coll.as_vec()),
+        let input_coll = match input_v {
+            Value::Coll(coll) => Ok(coll),
             _ => Err(EvalError::UnexpectedValue(format!(
                 "Slice: expected input to be Value::Coll, got: {0:?}",
                 input_v
@@ -22,14 +21,16 @@ impl Evaluable for Slice {
         }?;
         let from = from_v.try_extract_into::<i32>()?;
         let until = until_v.try_extract_into::<i32>()?;
-        match input_vec.get(from as usize..until as usize) {
-            Some(slice) => Ok(Value::Coll(CollKind::from_vec(self.tpe(), slice.to_vec())?)),
-            None => Err(EvalError::Misc(format!(
+        let (from_idx, until_idx) = (from as usize, until as usize);
+        if from_idx <= until_idx && until_idx <= input_coll.len() {
+            Ok(Value::Coll(input_coll.slice(from_idx, until_idx)))
+        } else {
+            Err(EvalError::Misc(format!(
                 "Slice: indices {0:?}..{1:?} out of bounds for collection size {2:?}",
                 from,
                 until,
-                input_vec.len()
-            ))),
+                input_coll.len()
+            )))
         }
     }
 }
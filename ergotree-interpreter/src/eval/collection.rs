@@ -24,11 +24,11 @@ impl Evaluable for Collection {
                             .into_iter()
                             .map(|i| i.try_extract_into::<i8>())
                             .collect();
-                        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes?)))
+                        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes?.into())))
                     }
                     _ => Value::Coll(CollKind::WrappedColl {
                         elem_tpe: elem_tpe.clone(),
-                        items: items_v?,
+                        items: items_v?.into(),
                     }),
                 }
             }
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::sigma_protocol::prover::ContextExtension;
 use ergotree_ir::chain::ergo_box::ErgoBox;
@@ -11,13 +11,13 @@ pub struct Context {
     /// Current height
     pub height: u32,
     /// Box that contains the script we're evaluating (from spending transaction inputs)
-    pub self_box: Rc<ErgoBox>,
+    pub self_box: Arc<ErgoBox>,
     /// Spending transaction outputs
-    pub outputs: Vec<Rc<ErgoBox>>,
+    pub outputs: Vec<Arc<ErgoBox>>,
     /// Spending transaction data inputs
-    pub data_inputs: Vec<Rc<ErgoBox>>,
+    pub data_inputs: Vec<Arc<ErgoBox>>,
     /// Spending transaction inputs
-    pub inputs: Vec<Rc<ErgoBox>>,
+    pub inputs: Vec<Arc<ErgoBox>>,
     /// Pre header of current block
     pub pre_header: PreHeader,
     /// Fixed number of last block headers in descending order (first header is the newest one)
@@ -70,10 +70,10 @@ mod arbitrary {
                     )| {
                         Self {
                             height,
-                            self_box: Rc::new(self_box),
-                            outputs: outputs.into_iter().map(Rc::new).collect(),
-                            data_inputs: data_inputs.into_iter().map(Rc::new).collect(),
-                            inputs: inputs.into_iter().map(Rc::new).collect(),
+                            self_box: Arc::new(self_box),
+                            outputs: outputs.into_iter().map(Arc::new).collect(),
+                            data_inputs: data_inputs.into_iter().map(Arc::new).collect(),
+                            inputs: inputs.into_iter().map(Arc::new).collect(),
                             pre_header,
                             extension,
                             headers,
@@ -38,4 +38,8 @@ impl CostAccumulator {
         }
         Ok(())
     }
+
+    pub fn total(&self) -> u64 {
+        self.accum
+    }
 }
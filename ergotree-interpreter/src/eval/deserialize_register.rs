@@ -61,7 +61,7 @@ impl Evaluable for DeserializeRegister {
 #[cfg(test)]
 mod tests {
 
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     use ergotree_ir::chain::ergo_box::ErgoBox;
     use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
@@ -83,7 +83,7 @@ mod tests {
         let ctx = force_any_val::<Context>();
         Context {
             height: 0u32,
-            self_box: Rc::new(self_box),
+            self_box: Arc::new(self_box),
             ..ctx
         }
     }
@@ -108,7 +108,7 @@ mod tests {
         }
         .into();
         let ctx = make_ctx_with_self_box(b);
-        assert!(try_eval_out::<bool>(&expr, Rc::new(ctx)).unwrap());
+        assert!(try_eval_out::<bool>(&expr, Arc::new(ctx)).unwrap());
     }
 
     #[test]
@@ -123,7 +123,7 @@ mod tests {
         }
         .into();
         let ctx = make_ctx_with_self_box(b.clone());
-        assert!(try_eval_out::<Value>(&expr, Rc::new(ctx)).is_err());
+        assert!(try_eval_out::<Value>(&expr, Arc::new(ctx)).is_err());
 
         // default with wrong type provided
         let expr: Expr = DeserializeRegister {
@@ -133,7 +133,7 @@ mod tests {
         }
         .into();
         let ctx = make_ctx_with_self_box(b.clone());
-        assert!(try_eval_out::<i32>(&expr, Rc::new(ctx)).is_err());
+        assert!(try_eval_out::<i32>(&expr, Arc::new(ctx)).is_err());
 
         // default provided
         let expr: Expr = DeserializeRegister {
@@ -143,7 +143,7 @@ mod tests {
         }
         .into();
         let ctx = make_ctx_with_self_box(b);
-        assert_eq!(try_eval_out::<i32>(&expr, Rc::new(ctx)).unwrap(), 1i32);
+        assert_eq!(try_eval_out::<i32>(&expr, Arc::new(ctx)).unwrap(), 1i32);
     }
 
     #[test]
@@ -159,7 +159,7 @@ mod tests {
         }
         .into();
         let ctx = make_ctx_with_self_box(b);
-        assert!(try_eval_out::<Value>(&expr, Rc::new(ctx)).is_err());
+        assert!(try_eval_out::<Value>(&expr, Arc::new(ctx)).is_err());
     }
 
     #[test]
@@ -177,6 +177,6 @@ mod tests {
         }
         .into();
         let ctx = make_ctx_with_self_box(b);
-        assert!(try_eval_out::<bool>(&expr, Rc::new(ctx)).is_err());
+        assert!(try_eval_out::<bool>(&expr, Arc::new(ctx)).is_err());
     }
 }
@@ -3,34 +3,83 @@ use std::collections::HashMap;
 use ergotree_ir::mir::val_def::ValId;
 use ergotree_ir::mir::value::Value;
 
-/// Environment for the interpreter
+/// Environment for the interpreter.
+/// Slot vector indexed by `ValId` (ids are assigned sequentially by the compiler, so the vector is
+/// dense and the lookup is a single index operation). Ids from [`Env::MAX_SLOTS`] on (allowed in
+/// the serialized trees, but not produced by the compiler) are stored in a map instead, so that a
+/// large id does not allocate a huge vector.
+/// Collection and option operations clone the environment once and overwrite the lambda
+/// argument slot (see [`Env::insert`]) for each element.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Env {
-    store: HashMap<ValId, Value>,
+    slots: Vec<Option<Value>>,
+    overflow: HashMap<ValId, Value>,
 }
 
 impl Env {
+    /// Number of ids stored in the slot vector
+    pub const MAX_SLOTS: usize = 4096;
+
     /// Empty environment
     pub fn empty() -> Env {
         Env {
-            store: HashMap::new(),
+            slots: Vec::new(),
+            overflow: HashMap::new(),
         }
     }
 
     /// Extend this environment (create new) with added element
     pub fn extend(&self, idx: ValId, v: Value) -> Env {
-        let mut new_store = self.store.clone();
-        new_store.insert(idx, v);
-        Env { store: new_store }
+        let mut new_env = self.clone();
+        new_env.insert(idx, v);
+        new_env
     }
 
     /// Insert a Value for the given ValId
     pub fn insert(&mut self, idx: ValId, v: Value) {
-        self.store.insert(idx, v);
+        let slot = idx.0 as usize;
+        if slot < Env::MAX_SLOTS {
+            if slot >= self.slots.len() {
+                self.slots.resize(slot + 1, None);
+            }
+            self.slots[slot] = Some(v);
+        } else {
+            self.overflow.insert(idx, v);
+        }
     }
 
     /// Get an element
     pub fn get(&self, idx: ValId) -> Option<&Value> {
-        self.store.get(&idx)
+        let slot = idx.0 as usize;
+        if slot < Env::MAX_SLOTS {
+            self.slots.get(slot).and_then(Option::as_ref)
+        } else {
+            self.overflow.get(&idx)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shadowing() {
+        let env = Env::empty().extend(ValId(1), 1i32.into());
+        let env2 = env
+            .extend(ValId(2), 2i32.into())
+            .extend(ValId(1), 3i32.into());
+        assert_eq!(env.get(ValId(1)), Some(&1i32.into()));
+        assert_eq!(env.get(ValId(2)), None);
+        assert_eq!(env2.get(ValId(1)), Some(&3i32.into()));
+        assert_eq!(env2.get(ValId(2)), Some(&2i32.into()));
+    }
+
+    #[test]
+    fn large_id() {
+        let env = Env::empty().extend(ValId(u32::MAX), 1i32.into());
+        assert_eq!(env.get(ValId(u32::MAX)), Some(&1i32.into()));
+        assert_eq!(env.get(ValId(1)), None);
+        assert!(env.slots.is_empty());
     }
 }
@@ -45,7 +45,7 @@ mod tests {
     use num_traits::Num;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     proptest! {
 
@@ -66,7 +66,7 @@ mod tests {
             }
             .into();
 
-            let ctx = Rc::new(force_any_val::<Context>());
+            let ctx = Arc::new(force_any_val::<Context>());
             assert_eq!(eval_out::<EcPoint>(&expr, ctx), expected_exp);
         }
     }
@@ -81,7 +81,7 @@ mod tests {
         }
         .into();
 
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert!(try_eval_out::<EcPoint>(&expr, ctx).is_err());
     }
 }
@@ -30,7 +30,7 @@ mod tests {
     use ergotree_ir::mir::global_vars::GlobalVars;
     use ergotree_ir::util::AsVecI8;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval() {
@@ -38,7 +38,7 @@ mod tests {
             input: Box::new(GlobalVars::SelfBox.into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
             eval_out::<Vec<i8>>(&e, ctx.clone()),
             ctx.self_box.sigma_serialize_bytes().unwrap().as_vec_i8()
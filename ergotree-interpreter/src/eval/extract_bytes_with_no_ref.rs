@@ -28,7 +28,7 @@ mod tests {
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::global_vars::GlobalVars;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval() {
@@ -36,7 +36,7 @@ mod tests {
             input: Box::new(GlobalVars::SelfBox.into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
             eval_out::<Vec<i8>>(&e, ctx.clone()),
             ctx.self_box.bytes_without_ref().unwrap()
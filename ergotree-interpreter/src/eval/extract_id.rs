@@ -31,7 +31,7 @@ mod tests {
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::mir::global_vars::GlobalVars;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval() {
@@ -39,7 +39,7 @@ mod tests {
             input: Box::new(GlobalVars::SelfBox.into()),
         }
         .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let bytes: Vec<i8> = ctx.self_box.box_id().into();
         assert_eq!(eval_out::<Vec<i8>>(&e, ctx), bytes);
     }
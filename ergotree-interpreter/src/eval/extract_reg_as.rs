@@ -1,5 +1,5 @@
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use ergotree_ir::chain::ergo_box::ErgoBox;
 use ergotree_ir::mir::constant::TryExtractInto;
@@ -16,7 +16,7 @@ impl Evaluable for ExtractRegisterAs {
         let ir_box = self
             .input
             .eval(env, ctx)?
-            .try_extract_into::<Rc<ErgoBox>>()?;
+            .try_extract_into::<Arc<ErgoBox>>()?;
         Ok(Value::Opt(Box::new(
             ir_box
                 .get_register(self.register_id.try_into().map_err(|e| {
@@ -42,7 +42,7 @@ mod tests {
     use ergotree_ir::mir::unary_op::OneArgOpTryBuild;
     use ergotree_ir::types::stype::SType;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval_box_get_reg_r0() {
@@ -54,7 +54,7 @@ mod tests {
         .unwrap()
         .into();
         let option_get_expr: Expr = OptionGet::try_build(get_reg_expr).unwrap().into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let v = eval_out::<i64>(&option_get_expr, ctx.clone());
         assert_eq!(v, ctx.self_box.value.as_i64());
     }
@@ -30,17 +30,17 @@ mod tests {
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::types::stype::SType;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     const VAR_IDX: u8 = 3;
     const VAR_VAL: i32 = 123;
 
     /// Prepare context with single extension variable
-    fn prepare_context() -> Rc<Context> {
+    fn prepare_context() -> Arc<Context> {
         let mut ctx = force_any_val::<Context>();
         ctx.extension.values.clear();
         ctx.extension.values.insert(VAR_IDX, VAR_VAL.into());
-        Rc::new(ctx)
+        Arc::new(ctx)
     }
 
     /// Normal evaluation
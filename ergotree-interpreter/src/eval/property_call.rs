@@ -24,16 +24,16 @@ mod tests {
     use ergotree_ir::mir::expr::Expr;
     use ergotree_ir::types::scontext;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval_context_data_inputs() {
         let pc: Expr = PropertyCall::new(Expr::Context, scontext::DATA_INPUTS_PROPERTY.clone())
             .unwrap()
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
-            eval_out::<Vec<Rc<ErgoBox>>>(&pc, ctx.clone()),
+            eval_out::<Vec<Arc<ErgoBox>>>(&pc, ctx.clone()),
             ctx.data_inputs
         );
     }
@@ -22,8 +22,9 @@ use super::EvalFn;
 
 pub(crate) static DIGEST_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     let avl_tree_data = obj.try_extract_into::<AvlTreeData>()?;
+    let digest: Vec<i8> = avl_tree_data.digest.into();
     Ok(Value::Coll(CollKind::NativeColl(NativeColl::CollByte(
-        avl_tree_data.digest.into(),
+        digest.into(),
     ))))
 };
 
@@ -235,7 +236,7 @@ mod tests {
                 SType::SColl(Box::new(SType::SByte)),
             )))),
             v: Literal::Coll(CollKind::WrappedColl {
-                items: vec![pair1, pair2, pair3],
+                items: vec![pair1, pair2, pair3].into(),
                 elem_tpe: SType::STuple(STuple::pair(
                     SType::SColl(Box::new(SType::SByte)),
                     SType::SColl(Box::new(SType::SByte)),
@@ -277,7 +278,7 @@ mod tests {
 
             let res = eval_out_wo_ctx::<Value>(&expr);
             if let Value::Coll(CollKind::NativeColl(NativeColl::CollByte(b))) = res {
-                assert_eq!(b, digest);
+                assert_eq!(b.to_vec(), digest);
             } else {
                 unreachable!();
             }
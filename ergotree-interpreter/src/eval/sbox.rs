@@ -1,5 +1,5 @@
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::eval::EvalError;
 
@@ -11,13 +11,13 @@ use super::EvalFn;
 
 pub(crate) static VALUE_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
     Ok(Value::Long(
-        obj.try_extract_into::<Rc<ErgoBox>>()?.value.as_i64(),
+        obj.try_extract_into::<Arc<ErgoBox>>()?.value.as_i64(),
     ))
 };
 
 pub(crate) static GET_REG_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
     Ok(Value::Opt(Box::new(
-        obj.try_extract_into::<Rc<ErgoBox>>()?
+        obj.try_extract_into::<Arc<ErgoBox>>()?
             .get_register(
                 args.get(0)
                     .cloned()
@@ -36,7 +36,7 @@ pub(crate) static GET_REG_EVAL_FN: EvalFn = |_env, _ctx, obj, args| {
 };
 
 pub(crate) static TOKENS_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
-    let res: Value = obj.try_extract_into::<Rc<ErgoBox>>()?.tokens_raw().into();
+    let res: Value = obj.try_extract_into::<Arc<ErgoBox>>()?.tokens_raw().into();
     Ok(res)
 };
 
@@ -52,14 +52,14 @@ mod tests {
 
     use crate::eval::context::Context;
     use crate::eval::tests::eval_out;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval_box_value() {
         let expr: Expr = PropertyCall::new(GlobalVars::SelfBox.into(), sbox::VALUE_METHOD.clone())
             .unwrap()
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
             eval_out::<i64>(&expr, ctx.clone()),
             ctx.self_box.value.as_i64()
@@ -71,7 +71,7 @@ mod tests {
         let expr: Expr = PropertyCall::new(GlobalVars::SelfBox.into(), sbox::TOKENS_METHOD.clone())
             .unwrap()
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(
             eval_out::<Vec<(Vec<i8>, i64)>>(&expr, ctx.clone()),
             ctx.self_box.tokens_raw()
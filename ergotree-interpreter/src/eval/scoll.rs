@@ -49,12 +49,13 @@ pub(crate) static FLATMAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
             input_v_clone
         ))),
     }?;
+    let mut lambda_env = env.clone();
     let mut lambda_call = |arg: Value| {
         let func_arg = lambda.args.first().ok_or_else(|| {
             EvalError::NotFound("flatmap: lambda has empty arguments list".to_string())
         })?;
-        let env1 = env.clone().extend(func_arg.idx, arg);
-        lambda.body.eval(&env1, ctx)
+        lambda_env.insert(func_arg.idx, arg);
+        lambda.body.eval(&lambda_env, ctx)
     };
     let mapper_input_tpe = lambda
         .args
@@ -55,7 +55,8 @@ pub(crate) static HEADERS_EVAL_FN: EvalFn = |_env, ctx, obj, _args| {
             .clone()
             .map(Box::new)
             .map(Value::Header)
-            .to_vec(),
+            .to_vec()
+            .into(),
         elem_tpe: SType::SHeader,
     }))
 };
@@ -83,7 +84,7 @@ mod tests {
     use ergotree_ir::mir::property_call::PropertyCall;
     use ergotree_ir::types::scontext;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     fn make_ctx_inputs_includes_self_box() -> Context {
         let ctx = force_any_val::<Context>();
@@ -103,7 +104,7 @@ mod tests {
             PropertyCall::new(Expr::Context, scontext::SELF_BOX_INDEX_PROPERTY.clone())
                 .unwrap()
                 .into();
-        let rc = Rc::new(make_ctx_inputs_includes_self_box());
+        let rc = Arc::new(make_ctx_inputs_includes_self_box());
         assert_eq!(eval_out::<i32>(&expr, rc), 1);
     }
 
@@ -112,7 +113,7 @@ mod tests {
         let expr: Expr = PropertyCall::new(Expr::Context, scontext::HEADERS_PROPERTY.clone())
             .expect("internal error: `headers` method has parameters length != 1")
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<[Header; 10]>(&expr, ctx.clone()), ctx.headers);
     }
 
@@ -121,7 +122,7 @@ mod tests {
         let expr: Expr = PropertyCall::new(Expr::Context, scontext::PRE_HEADER_PROPERTY.clone())
             .unwrap()
             .into();
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<PreHeader>(&expr, ctx.clone()), ctx.pre_header);
     }
 }
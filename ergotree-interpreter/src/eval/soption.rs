@@ -19,12 +19,13 @@ pub(crate) static MAP_EVAL_FN: EvalFn = |env, ctx, obj, args| {
             input_v_clone
         ))),
     }?;
+    let mut lambda_env = env.clone();
     let mut lambda_call = |arg: Value| {
         let func_arg = lambda.args.first().ok_or_else(|| {
             EvalError::NotFound("map: lambda has empty arguments list".to_string())
         })?;
-        let env1 = env.clone().extend(func_arg.idx, arg);
-        lambda.body.eval(&env1, ctx)
+        lambda_env.insert(func_arg.idx, arg);
+        lambda.body.eval(&lambda_env, ctx)
     };
     let normalized_input_val: Option<Value> = match input_v {
         Value::Opt(opt) => Ok(*opt),
@@ -54,12 +55,13 @@ pub(crate) static FILTER_EVAL_FN: EvalFn = |env, ctx, obj, args| {
             input_v_clone
         ))),
     }?;
+    let mut lambda_env = env.clone();
     let mut predicate_call = |arg: Value| {
         let func_arg = lambda.args.first().ok_or_else(|| {
             EvalError::NotFound("filter: lambda has empty arguments list".to_string())
         })?;
-        let env1 = env.clone().extend(func_arg.idx, arg);
-        lambda.body.eval(&env1, ctx)
+        lambda_env.insert(func_arg.idx, arg);
+        lambda.body.eval(&lambda_env, ctx)
     };
     let normalized_input_val: Option<Value> = match input_v {
         Value::Opt(opt) => Ok(*opt),
@@ -42,7 +42,7 @@ pub(crate) static VOTES_EVAL_FN: EvalFn = |_env, _ctx, obj, _args| {
 #[allow(clippy::expect_used)]
 mod tests {
     use std::convert::{TryFrom, TryInto};
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     use ergotree_ir::{
         chain::{block_id::BlockId, votes::Votes},
@@ -87,7 +87,7 @@ mod tests {
     #[test]
     fn test_eval_version() {
         let expr = create_get_preheader_property_expr(spreheader::VERSION_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.version as i8;
         assert_eq!(expected, eval_out::<i8>(&expr, ctx.clone()));
     }
@@ -95,7 +95,7 @@ mod tests {
     #[test]
     fn test_eval_parent_id() {
         let expr = create_get_preheader_property_expr(spreheader::PARENT_ID_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.parent_id.clone();
         let actual = {
             let bs = eval_out::<Vec<i8>>(&expr, ctx.clone());
@@ -107,7 +107,7 @@ mod tests {
     #[test]
     fn test_eval_timestamp() {
         let expr = create_get_preheader_property_expr(spreheader::TIMESTAMP_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.timestamp as i64;
         let actual = eval_out::<i64>(&expr, ctx.clone());
         assert_eq!(expected, actual);
@@ -116,7 +116,7 @@ mod tests {
     #[test]
     fn test_eval_n_bits() {
         let expr = create_get_preheader_property_expr(spreheader::N_BITS_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.n_bits as i64;
         let actual = eval_out::<i64>(&expr, ctx.clone());
         assert_eq!(expected as i64, actual);
@@ -125,7 +125,7 @@ mod tests {
     #[test]
     fn test_eval_height() {
         let expr = create_get_preheader_property_expr(spreheader::HEIGHT_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.height as i32;
         let actual = eval_out::<i32>(&expr, ctx.clone());
         assert_eq!(expected, actual);
@@ -134,7 +134,7 @@ mod tests {
     #[test]
     fn test_eval_miner_pk() {
         let expr = create_get_preheader_property_expr(spreheader::MINER_PK_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.miner_pk.clone();
         let actual = {
             let pk = eval_out::<EcPoint>(&expr, ctx.clone());
@@ -146,7 +146,7 @@ mod tests {
     #[test]
     fn test_eval_votes() {
         let expr = create_get_preheader_property_expr(spreheader::VOTES_PROPERTY.clone());
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         let expected = ctx.pre_header.votes.clone();
         let actual = {
             let votes_bytes = eval_out::<Vec<i8>>(&expr, ctx.clone()).as_vec_u8();
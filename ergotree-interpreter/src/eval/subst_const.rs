@@ -28,8 +28,8 @@ impl Evaluable for SubstConstants {
 
         let new_constants = if let Value::Coll(CollKind::WrappedColl { items, .. }) = new_values_v {
             let mut items_const = vec![];
-            for v in items {
-                let c = Constant::try_from(v).map_err(EvalError::Misc)?;
+            for v in items.iter() {
+                let c = Constant::try_from(v.clone()).map_err(EvalError::Misc)?;
                 items_const.push(c);
             }
             items_const
@@ -67,7 +67,7 @@ impl Evaluable for SubstConstants {
                 }
             }
             Ok(Value::Coll(CollKind::NativeColl(NativeColl::CollByte(
-                ergo_tree.sigma_serialize_bytes()?.as_vec_i8(),
+                ergo_tree.sigma_serialize_bytes()?.as_vec_i8().into(),
             ))))
         } else {
             Err(EvalError::Misc(format!(
@@ -8,7 +8,8 @@ use crate::eval::EvalContext;
 use crate::eval::EvalError;
 use crate::eval::Evaluable;
 
-fn helper_xor(mut x: Vec<i8>, y: Vec<i8>) -> Vec<i8> {
+fn helper_xor(x: &[i8], y: &[i8]) -> Vec<i8> {
+    let mut x = x.to_vec();
     x.iter_mut().zip(y.iter()).for_each(|(x1, x2)| *x1 ^= *x2);
     x
 }
@@ -23,7 +24,7 @@ impl Evaluable for Xor {
                 Value::Coll(CollKind::NativeColl(NativeColl::CollByte(l_byte))),
                 Value::Coll(CollKind::NativeColl(NativeColl::CollByte(r_byte))),
             ) => {
-                let xor = helper_xor(l_byte, r_byte);
+                let xor = helper_xor(&l_byte, &r_byte);
                 Ok(xor.into())
             }
             _ => Err(EvalError::UnexpectedValue(format!(
@@ -44,7 +45,7 @@ mod tests {
     use ergotree_ir::mir::expr::Expr;
     use proptest::prelude::*;
     use sigma_test_util::force_any_val;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn eval_1_xor_0() {
@@ -58,7 +59,7 @@ mod tests {
         }
         .into();
 
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<Vec<i8>>(&expr, ctx), expected_xor);
     }
 
@@ -74,7 +75,7 @@ mod tests {
         }
         .into();
 
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<Vec<i8>>(&expr, ctx), expected_xor);
     }
 
@@ -90,7 +91,7 @@ mod tests {
         }
         .into();
 
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<Vec<i8>>(&expr, ctx), expected_xor);
     }
 
@@ -106,7 +107,7 @@ mod tests {
         }
         .into();
 
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<Vec<i8>>(&expr, ctx), expected_xor);
     }
 
@@ -122,7 +123,7 @@ mod tests {
         }
         .into();
 
-        let ctx = Rc::new(force_any_val::<Context>());
+        let ctx = Arc::new(force_any_val::<Context>());
         assert_eq!(eval_out::<Vec<i8>>(&expr, ctx), expected_xor);
     }
 
@@ -131,7 +132,7 @@ mod tests {
         #[test]
         fn eval_any(left_bytes in any::<Vec<i8>>(), right_bytes in any::<Vec<i8>>()) {
 
-            let expected_xor = helper_xor(left_bytes.clone(), right_bytes.clone());
+            let expected_xor = helper_xor(&left_bytes, &right_bytes);
 
             let expr: Expr = Xor {
                 left: Box::new(Expr::Const(left_bytes.into())),
@@ -139,7 +140,7 @@ mod tests {
             }
             .into();
 
-            let ctx = Rc::new(force_any_val::<Context>());
+            let ctx = Arc::new(force_any_val::<Context>());
             assert_eq!(eval_out::<Vec<i8>>(&expr, ctx), expected_xor);
         }
     }
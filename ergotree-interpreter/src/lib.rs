@@ -21,4 +21,6 @@ mod contracts;
 mod util;
 
 pub mod eval;
+pub mod optimizer;
 pub mod sigma_protocol;
+pub mod storage_rent;
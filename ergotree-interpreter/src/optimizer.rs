@@ -0,0 +1,618 @@
+//! ErgoTree (MIR) optimizer: constant folding, constant propagation, dead code elimination and
+//! boolean/sigma propositions simplification.
+//!
+//! Every rewrite preserves the evaluation result, including evaluation errors: an expression
+//! is folded only if it evaluates successfully and an expression that might fail is never
+//! dropped, unless the interpreter would not evaluate it either (e.g. untaken `If` branch).
+
+use std::convert::Infallible;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+use ergotree_ir::chain::header::Header;
+use ergotree_ir::chain::preheader::PreHeader;
+use ergotree_ir::chain::tx_id::TxId;
+use ergotree_ir::ergo_tree::ErgoTree;
+use ergotree_ir::ergo_tree::ErgoTreeError;
+use ergotree_ir::ergo_tree::ErgoTreeHeader;
+use ergotree_ir::mir::bin_op::BinOp;
+use ergotree_ir::mir::bin_op::BinOpKind;
+use ergotree_ir::mir::bin_op::LogicalOp;
+use ergotree_ir::mir::block::BlockValue;
+use ergotree_ir::mir::constant::Constant;
+use ergotree_ir::mir::constant::Literal;
+use ergotree_ir::mir::expr::Expr;
+use ergotree_ir::mir::if_op::If;
+use ergotree_ir::mir::logical_not::LogicalNot;
+use ergotree_ir::mir::sigma_and::SigmaAnd;
+use ergotree_ir::mir::sigma_or::SigmaOr;
+use ergotree_ir::mir::val_def::ValId;
+use ergotree_ir::mir::visitor::children;
+use ergotree_ir::mir::visitor::children_mut;
+use ergotree_ir::mir::visitor::fold;
+use ergotree_ir::mir::visitor::visit;
+use ergotree_ir::mir::visitor::Folder;
+use ergotree_ir::mir::visitor::Visitor;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjectureItems;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaProp;
+
+use crate::eval::context::Context;
+use crate::eval::cost_accum::CostAccumulator;
+use crate::eval::env::Env;
+use crate::eval::EvalContext;
+use crate::eval::Evaluable;
+use crate::sigma_protocol::prover::ContextExtension;
+
+/// Optimize the expression, returning an equivalent (same evaluation result) expression
+pub fn optimize(expr: Expr) -> Expr {
+    let mut optimizer = Optimizer {
+        ctx: folding_context(),
+    };
+    optimizer.optimize(expr)
+}
+
+/// Optimize the root expression of the tree, keeping the tree header
+pub fn optimize_ergo_tree(tree: &ErgoTree) -> Result<ErgoTree, ErgoTreeError> {
+    let expr = optimize(tree.proposition()?.as_ref().clone());
+    ErgoTree::new(tree.header().clone(), &expr)
+}
+
+struct Optimizer {
+    /// Context used for the constant folding (`None` if it could not be built, which disables
+    /// the folding)
+    ctx: Option<Arc<Context>>,
+}
+
+impl Optimizer {
+    fn optimize(&mut self, expr: Expr) -> Expr {
+        match fold(expr, self) {
+            Ok(expr) => expr,
+            Err(e) => match e {},
+        }
+    }
+
+    fn simplify(&mut self, expr: Expr) -> Expr {
+        let expr = match expr {
+            Expr::BlockValue(block) => self.simplify_block(block),
+            Expr::If(op) => simplify_if(op),
+            Expr::BinOp(op) => simplify_logical(op),
+            Expr::LogicalNot(op) => match *op.input {
+                Expr::LogicalNot(inner) => *inner.input,
+                input => Expr::LogicalNot(LogicalNot {
+                    input: Box::new(input),
+                }),
+            },
+            Expr::SigmaAnd(op) => simplify_sigma_and(op),
+            Expr::SigmaOr(op) => simplify_sigma_or(op),
+            expr => expr,
+        };
+        self.fold_constant(expr)
+    }
+
+    /// Inline the constant bindings (unless it makes the tree larger) and remove the unused
+    /// bindings that cannot fail
+    fn simplify_block(&mut self, block: BlockValue) -> Expr {
+        let mut items = block.items;
+        let mut result = *block.result;
+        let mut substituted = false;
+        let mut i = 0;
+        while i < items.len() {
+            if let Expr::ValDef(vd) = &items[i] {
+                let (id, rhs) = (vd.id, vd.rhs.as_ref());
+                let use_count: usize = items[i + 1..]
+                    .iter()
+                    .chain(std::iter::once(&result))
+                    .map(|e| use_count(e, id))
+                    .sum();
+                if let Some(c) = inlinable_const(rhs, use_count) {
+                    items
+                        .iter_mut()
+                        .skip(i + 1)
+                        .for_each(|item| substitute(item, id, &c));
+                    substitute(&mut result, id, &c);
+                    items.remove(i);
+                    substituted = true;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+        if substituted {
+            // inlined constants might be folded further
+            items = items.into_iter().map(|item| self.optimize(item)).collect();
+            result = self.optimize(result);
+        }
+        for i in (0..items.len()).rev() {
+            let unused = match &items[i] {
+                Expr::ValDef(vd) => {
+                    cannot_fail(&vd.rhs)
+                        && items[i + 1..]
+                            .iter()
+                            .chain(std::iter::once(&result))
+                            .all(|e| use_count(e, vd.id) == 0)
+                }
+                _ => false,
+            };
+            if unused {
+                items.remove(i);
+            }
+        }
+        if items.is_empty() {
+            result
+        } else {
+            Expr::BlockValue(BlockValue {
+                items,
+                result: Box::new(result),
+            })
+        }
+    }
+
+    /// Replace the expression with the constant it evaluates to, if all its operands are
+    /// constants and the constant is not larger than the expression
+    fn fold_constant(&self, expr: Expr) -> Expr {
+        let ctx = match &self.ctx {
+            Some(ctx) => ctx.clone(),
+            None => return expr,
+        };
+        if !is_foldable(&expr) {
+            return expr;
+        }
+        let mut ectx = EvalContext::new(ctx, CostAccumulator::new(0, None));
+        let folded = expr
+            .eval(&Env::empty(), &mut ectx)
+            .ok()
+            .and_then(|v| Constant::try_from(v).ok())
+            // conversion from the value might not infer the exact type (e.g. for options)
+            .filter(|c| c.tpe == expr.tpe())
+            .filter(
+                |c| match (c.sigma_serialize_bytes(), expr.sigma_serialize_bytes()) {
+                    (Ok(c_bytes), Ok(expr_bytes)) => c_bytes.len() <= expr_bytes.len(),
+                    _ => false,
+                },
+            );
+        match folded {
+            Some(c) => Expr::Const(c),
+            None => expr,
+        }
+    }
+}
+
+impl Folder for Optimizer {
+    type Error = Infallible;
+
+    fn fold_post(&mut self, expr: Expr) -> Result<Expr, Self::Error> {
+        Ok(self.simplify(expr))
+    }
+}
+
+fn simplify_if(op: If) -> Expr {
+    match const_bool(&op.condition) {
+        Some(true) => *op.true_branch,
+        Some(false) => *op.false_branch,
+        None => Expr::If(op),
+    }
+}
+
+/// Simplify `&&` and `||` with a constant operand (right operand is evaluated lazily)
+fn simplify_logical(op: BinOp) -> Expr {
+    let logical_op = match op.kind {
+        BinOpKind::Logical(logical_op) => logical_op,
+        _ => return Expr::BinOp(op),
+    };
+    let BinOp { kind, left, right } = op;
+    match (logical_op, const_bool(&left), const_bool(&right)) {
+        (LogicalOp::And, Some(true), _) | (LogicalOp::Or, Some(false), _) => *right,
+        (LogicalOp::And, Some(false), _) | (LogicalOp::Or, Some(true), _) => *left,
+        (LogicalOp::And, _, Some(true)) | (LogicalOp::Or, _, Some(false)) => *left,
+        (LogicalOp::And, _, Some(false)) | (LogicalOp::Or, _, Some(true)) if cannot_fail(&left) => {
+            *right
+        }
+        _ => Expr::BinOp(BinOp { kind, left, right }),
+    }
+}
+
+fn simplify_sigma_and(op: SigmaAnd) -> Expr {
+    match simplify_conjecture(op.items.into(), true) {
+        Ok(expr) => expr,
+        Err(items) => Expr::SigmaAnd(SigmaAnd { items }),
+    }
+}
+
+fn simplify_sigma_or(op: SigmaOr) -> Expr {
+    match simplify_conjecture(op.items.into(), false) {
+        Ok(expr) => expr,
+        Err(items) => Expr::SigmaOr(SigmaOr { items }),
+    }
+}
+
+/// Remove the neutral items (`true` for AND, `false` for OR) and fold to the absorbing item
+/// (`false` for AND, `true` for OR) if the rest of the items cannot fail. Returns the
+/// remaining items if there are at least two of them.
+fn simplify_conjecture(
+    items: Vec<Expr>,
+    neutral: bool,
+) -> Result<Expr, SigmaConjectureItems<Expr>> {
+    if items.iter().any(|it| trivial_prop(it) == Some(!neutral)) && items.iter().all(cannot_fail) {
+        return Ok(trivial_prop_expr(!neutral));
+    }
+    let mut items: Vec<Expr> = items
+        .into_iter()
+        .filter(|it| trivial_prop(it) != Some(neutral))
+        .collect();
+    if items.len() <= 1 {
+        return Ok(items.pop().unwrap_or_else(|| trivial_prop_expr(neutral)));
+    }
+    #[allow(clippy::unwrap_used)]
+    // there are at least two items and no more than in the original conjecture
+    Err(SigmaConjectureItems::from_vec(items).unwrap())
+}
+
+fn trivial_prop_expr(b: bool) -> Expr {
+    Expr::Const(SigmaProp::new(SigmaBoolean::TrivialProp(b)).into())
+}
+
+fn const_bool(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Const(Constant {
+            v: Literal::Boolean(b),
+            ..
+        }) => Some(*b),
+        _ => None,
+    }
+}
+
+fn trivial_prop(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Const(Constant {
+            v: Literal::SigmaProp(sp),
+            ..
+        }) => match sp.value() {
+            SigmaBoolean::TrivialProp(b) => Some(*b),
+            _ => None,
+        },
+        Expr::BoolToSigmaProp(op) => const_bool(&op.input),
+        _ => None,
+    }
+}
+
+/// Expressions whose evaluation never fails
+fn cannot_fail(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Const(_)
+            | Expr::ValUse(_)
+            | Expr::GlobalVars(_)
+            | Expr::Context
+            | Expr::Global
+            | Expr::FuncValue(_)
+    )
+}
+
+/// Expressions with constant operands only and evaluation independent of the context
+fn is_foldable(expr: &Expr) -> bool {
+    match expr {
+        Expr::FuncValue(_) | Expr::DeserializeRegister(_) => false,
+        _ => {
+            let children = children(expr);
+            !children.is_empty() && children.iter().all(|c| matches!(c, Expr::Const(_)))
+        }
+    }
+}
+
+/// Constant of the binding, if inlining it into every use does not make the tree larger
+fn inlinable_const(rhs: &Expr, use_count: usize) -> Option<Constant> {
+    match rhs {
+        Expr::Const(c) => {
+            let c_size = c.sigma_serialize_bytes().ok()?.len();
+            // ValDef (op code, id and rhs) vs. ValUse (op code and id) in every use
+            let (def_size, use_size) = (2 + c_size, 2);
+            (use_count * c_size <= def_size + use_count * use_size).then(|| c.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Number of references to the given binding in the expression (shadowing is not taken into
+/// account, so it might overestimate)
+fn use_count(expr: &Expr, id: ValId) -> usize {
+    struct UseCounter {
+        id: ValId,
+        count: usize,
+    }
+
+    impl Visitor for UseCounter {
+        fn visit_pre(&mut self, expr: &Expr) -> bool {
+            if let Expr::ValUse(op) = expr {
+                if op.val_id == self.id {
+                    self.count += 1;
+                }
+            }
+            true
+        }
+    }
+
+    let mut counter = UseCounter { id, count: 0 };
+    visit(expr, &mut counter);
+    counter.count
+}
+
+/// Replace references to the given binding with the constant, respecting the shadowing by
+/// function arguments and nested bindings
+fn substitute(expr: &mut Expr, id: ValId, c: &Constant) {
+    match expr {
+        Expr::ValUse(op) if op.val_id == id => *expr = Expr::Const(c.clone()),
+        Expr::FuncValue(op) if op.args().iter().any(|arg| arg.idx == id) => (),
+        Expr::BlockValue(block) => {
+            for item in block.items.iter_mut() {
+                match item {
+                    Expr::ValDef(vd) if vd.id == id => {
+                        substitute(&mut vd.rhs, id, c);
+                        return;
+                    }
+                    _ => substitute(item, id, c),
+                }
+            }
+            substitute(&mut block.result, id, c);
+        }
+        _ => children_mut(expr)
+            .into_iter()
+            .for_each(|child| substitute(child, id, c)),
+    }
+}
+
+/// Context for the evaluation of the constant expressions (which does not access it)
+fn folding_context() -> Option<Arc<Context>> {
+    let tree = ErgoTree::new(ErgoTreeHeader::v0(false), &Expr::Const(true.into())).ok()?;
+    let self_box = ErgoBox::new(
+        BoxValue::SAFE_USER_MIN,
+        tree,
+        vec![],
+        NonMandatoryRegisters::empty(),
+        0,
+        TxId::zero(),
+        0,
+    )
+    .ok()?;
+    Some(Arc::new(Context {
+        height: 0,
+        self_box: Arc::new(self_box),
+        outputs: vec![],
+        data_inputs: vec![],
+        inputs: vec![],
+        pre_header: PreHeader::dummy(),
+        headers: vec![Header::dummy(); 10].try_into().ok()?,
+        extension: ContextExtension::empty(),
+    }))
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::tests::eval_out;
+    use crate::eval::tests::try_eval_out_wo_ctx;
+    use ergotree_ir::mir::bin_op::ArithOp;
+    use ergotree_ir::mir::bin_op::RelationOp;
+    use ergotree_ir::mir::bool_to_sigma::BoolToSigmaProp;
+    use ergotree_ir::mir::global_vars::GlobalVars;
+    use ergotree_ir::mir::val_def::ValDef;
+    use ergotree_ir::mir::val_use::ValUse;
+    use ergotree_ir::types::stype::SType;
+    use sigma_test_util::force_any_val;
+
+    fn bin_op(kind: BinOpKind, left: Expr, right: Expr) -> Expr {
+        BinOp {
+            kind,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+        .into()
+    }
+
+    fn height_gt(e: Expr) -> Expr {
+        bin_op(RelationOp::Gt.into(), GlobalVars::Height.into(), e)
+    }
+
+    fn div_by_zero() -> Expr {
+        bin_op(ArithOp::Divide.into(), 1i32.into(), 0i32.into())
+    }
+
+    fn prop(e: Expr) -> Expr {
+        BoolToSigmaProp { input: e.into() }.into()
+    }
+
+    fn val_def(id: u32, rhs: Expr) -> Expr {
+        ValDef {
+            id: ValId(id),
+            rhs: rhs.into(),
+        }
+        .into()
+    }
+
+    fn val_use(id: u32, tpe: SType) -> Expr {
+        ValUse {
+            val_id: ValId(id),
+            tpe,
+        }
+        .into()
+    }
+
+    #[test]
+    fn fold_arithmetic() {
+        let sum = bin_op(ArithOp::Plus.into(), 1i32.into(), 2i32.into());
+        let expr = prop(height_gt(bin_op(
+            ArithOp::Multiply.into(),
+            sum,
+            3i32.into(),
+        )));
+        let optimized = optimize(expr.clone());
+        assert_eq!(optimized, prop(height_gt(9i32.into())));
+        assert!(
+            optimized.sigma_serialize_bytes().unwrap().len()
+                < expr.sigma_serialize_bytes().unwrap().len()
+        );
+        assert_eq!(optimize(prop(true.into())), trivial_prop_expr(true));
+    }
+
+    #[test]
+    fn preserve_errors() {
+        let expr = height_gt(div_by_zero());
+        assert_eq!(optimize(expr.clone()), expr);
+        assert!(try_eval_out_wo_ctx::<bool>(&optimize(expr.clone())).is_err());
+        // right operand is not evaluated
+        let lazy_and = bin_op(LogicalOp::And.into(), false.into(), expr.clone());
+        assert_eq!(optimize(lazy_and), false.into());
+        let fail_and = bin_op(LogicalOp::And.into(), expr.clone(), false.into());
+        assert_eq!(optimize(fail_and.clone()), fail_and);
+        let and = bin_op(LogicalOp::And.into(), expr.clone(), true.into());
+        assert_eq!(optimize(and), expr);
+    }
+
+    #[test]
+    fn if_with_constant_condition() {
+        let expr: Expr = If {
+            condition: bin_op(RelationOp::Lt.into(), 1i32.into(), 2i32.into()).into(),
+            true_branch: Box::new(GlobalVars::Height.into()),
+            false_branch: div_by_zero().into(),
+        }
+        .into();
+        assert_eq!(optimize(expr), GlobalVars::Height.into());
+    }
+
+    #[test]
+    fn double_negation() {
+        let expr: Expr = LogicalNot {
+            input: Box::new(
+                LogicalNot {
+                    input: height_gt(1i32.into()).into(),
+                }
+                .into(),
+            ),
+        }
+        .into();
+        assert_eq!(optimize(expr), height_gt(1i32.into()));
+    }
+
+    #[test]
+    fn block_bindings() {
+        let expr: Expr = BlockValue {
+            items: vec![
+                val_def(1, bin_op(ArithOp::Plus.into(), 2i32.into(), 3i32.into())),
+                val_def(2, div_by_zero()),
+                val_def(3, GlobalVars::Height.into()),
+            ],
+            result: height_gt(val_use(1, SType::SInt)).into(),
+        }
+        .into();
+        // failing binding is kept, although it's unused
+        let expected: Expr = BlockValue {
+            items: vec![val_def(2, div_by_zero())],
+            result: height_gt(5i32.into()).into(),
+        }
+        .into();
+        assert_eq!(optimize(expr), expected);
+
+        let expr: Expr = BlockValue {
+            items: vec![
+                val_def(1, 5i32.into()),
+                val_def(2, GlobalVars::Height.into()),
+            ],
+            result: bin_op(
+                RelationOp::Gt.into(),
+                val_use(2, SType::SInt),
+                bin_op(ArithOp::Plus.into(), val_use(1, SType::SInt), 1i32.into()),
+            )
+            .into(),
+        }
+        .into();
+        let optimized = optimize(expr.clone());
+        let ctx = Arc::new(force_any_val::<Context>());
+        assert_eq!(
+            eval_out::<bool>(&optimized, ctx.clone()),
+            eval_out::<bool>(&expr, ctx)
+        );
+        let expected: Expr = BlockValue {
+            items: vec![val_def(2, GlobalVars::Height.into())],
+            result: bin_op(RelationOp::Gt.into(), val_use(2, SType::SInt), 6i32.into()).into(),
+        }
+        .into();
+        assert_eq!(optimized, expected);
+    }
+
+    #[test]
+    fn large_constant_binding_is_not_duplicated() {
+        let coll: Constant = vec![1i64; 100].into();
+        let expr: Expr = BlockValue {
+            items: vec![val_def(1, coll.into())],
+            result: bin_op(
+                RelationOp::Eq.into(),
+                val_use(1, SType::SColl(SType::SLong.into())),
+                val_use(1, SType::SColl(SType::SLong.into())),
+            )
+            .into(),
+        }
+        .into();
+        assert_eq!(optimize(expr.clone()), expr);
+    }
+
+    #[test]
+    fn sigma_conjectures() {
+        let x = prop(height_gt(1i32.into()));
+        let y = prop(height_gt(2i32.into()));
+        let and: Expr = SigmaAnd::new(vec![prop(true.into()), x.clone()])
+            .unwrap()
+            .into();
+        assert_eq!(optimize(and), x);
+        let and: Expr = SigmaAnd::new(vec![prop(true.into()), x.clone(), y.clone()])
+            .unwrap()
+            .into();
+        assert_eq!(
+            optimize(and),
+            SigmaAnd::new(vec![x.clone(), y.clone()]).unwrap().into()
+        );
+        // x might fail
+        let and: Expr = SigmaAnd::new(vec![x.clone(), prop(false.into())])
+            .unwrap()
+            .into();
+        assert_eq!(
+            optimize(and),
+            SigmaAnd::new(vec![x.clone(), trivial_prop_expr(false)])
+                .unwrap()
+                .into()
+        );
+        let or: Expr = SigmaOr::new(vec![prop(false.into()), x.clone()])
+            .unwrap()
+            .into();
+        assert_eq!(optimize(or), x);
+        let or: Expr = SigmaOr::new(vec![prop(true.into()), prop(false.into())])
+            .unwrap()
+            .into();
+        assert_eq!(optimize(or), trivial_prop_expr(true));
+    }
+
+    #[test]
+    fn optimize_tree() {
+        let expr = prop(bin_op(
+            LogicalOp::Or.into(),
+            height_gt(bin_op(ArithOp::Plus.into(), 1i32.into(), 2i32.into())),
+            false.into(),
+        ));
+        let tree = ErgoTree::new(ErgoTreeHeader::v1(true), &expr).unwrap();
+        let optimized = optimize_ergo_tree(&tree).unwrap();
+        assert_eq!(optimized.header(), tree.header());
+        assert_eq!(
+            optimized.proposition().unwrap().as_ref(),
+            &prop(height_gt(3i32.into()))
+        );
+        assert!(
+            optimized.sigma_serialize_bytes().unwrap().len()
+                < tree.sigma_serialize_bytes().unwrap().len()
+        );
+    }
+}
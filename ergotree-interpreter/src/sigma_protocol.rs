@@ -11,11 +11,15 @@ mod crypto_utils;
 mod dht_protocol;
 mod dlog_protocol;
 mod fiat_shamir;
+mod nonce;
 mod proof_tree;
 mod sig_serializer;
 mod unchecked_tree;
 mod unproven_tree;
 
+pub use nonce::NonceMode;
+pub use sig_serializer::proof_size;
+
 use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
 use k256::Scalar;
 
@@ -56,9 +60,9 @@ impl ProverMessage for FirstProverMessage {
 }
 
 /** Size of the binary representation of any group element (2 ^ groupSizeBits == <number of elements in a group>) */
-pub(crate) const GROUP_SIZE_BITS: usize = 256;
+pub const GROUP_SIZE_BITS: usize = 256;
 /** Number of bytes to represent any group element as byte array */
-pub(crate) const GROUP_SIZE: usize = GROUP_SIZE_BITS / 8;
+pub const GROUP_SIZE: usize = GROUP_SIZE_BITS / 8;
 
 /// Byte array of Group size (32 bytes)
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -42,7 +42,6 @@ pub(crate) mod interactive_prover {
     use std::ops::Mul;
 
     use super::*;
-    use crate::sigma_protocol::crypto_utils;
     use crate::sigma_protocol::private_input::DhTupleProverInput;
     use crate::sigma_protocol::Challenge;
     use ergotree_ir::sigma_protocol::dlog_group;
@@ -52,14 +51,12 @@ pub(crate) mod interactive_prover {
     /// Step 5 from <https://ergoplatform.org/docs/ErgoScript.pdf>
     /// For every leaf marked “simulated”, use the simulator of the sigma protocol for that leaf
     /// to compute the commitment "a" and the response "z", given the challenge "e" that
-    /// is already stored in the leaf
+    /// is already stored in the leaf (random z <- Zq is sampled by the caller)
     pub(crate) fn simulate(
         public_input: &ProveDhTuple,
         challenge: &Challenge,
+        z: Scalar,
     ) -> (FirstDhTupleProverMessage, SecondDhTupleProverMessage) {
-        //SAMPLE a random z <- Zq
-        let z = dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng());
-
         // COMPUTE a = g^z*u^(-e) and b = h^z*v^{-e}  (where -e here means -e mod q)
         let e: Scalar = challenge.clone().into();
         let minus_e = e.negate();
@@ -77,13 +74,13 @@ pub(crate) mod interactive_prover {
 
     /// Step 6 from <https://ergoplatform.org/docs/ErgoScript.pdf>
     /// For every leaf marked “real”, use the first prover step of the sigma protocol for
-    /// that leaf to compute the necessary randomness "r" and the commitment "a"
+    /// that leaf to compute the commitment "a" for the given randomness "r"
     ///
     /// In this case (DH tuple) "a" is also a tuple
     pub(crate) fn first_message(
         public_input: &ProveDhTuple,
+        r: Scalar,
     ) -> (Scalar, FirstDhTupleProverMessage) {
-        let r = dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng());
         let a = dlog_group::exponentiate(&public_input.g, &r);
         let b = dlog_group::exponentiate(&public_input.h, &r);
         (r, FirstDhTupleProverMessage::new(a, b))
@@ -41,7 +41,6 @@ pub(crate) mod interactive_prover {
     use std::ops::Mul;
 
     use super::{FirstDlogProverMessage, SecondDlogProverMessage};
-    use crate::sigma_protocol::crypto_utils;
     use crate::sigma_protocol::{private_input::DlogProverInput, Challenge};
     use ergotree_ir::sigma_protocol::dlog_group;
     use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
@@ -51,14 +50,12 @@ pub(crate) mod interactive_prover {
     /// Step 5 from <https://ergoplatform.org/docs/ErgoScript.pdf>
     /// For every leaf marked “simulated”, use the simulator of the sigma protocol for that leaf
     /// to compute the commitment "a" and the response "z", given the challenge "e" that
-    /// is already stored in the leaf
+    /// is already stored in the leaf (random z <- Zq is sampled by the caller)
     pub(crate) fn simulate(
         public_input: &ProveDlog,
         challenge: &Challenge,
+        z: Scalar,
     ) -> (FirstDlogProverMessage, SecondDlogProverMessage) {
-        //SAMPLE a random z <- Zq
-        let z = dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng());
-
         //COMPUTE a = g^z*h^(-e)  (where -e here means -e mod q)
         let e: Scalar = challenge.clone().into();
         let minus_e = e.negate();
@@ -73,9 +70,8 @@ pub(crate) mod interactive_prover {
 
     /// Step 6 from <https://ergoplatform.org/docs/ErgoScript.pdf>
     /// For every leaf marked “real”, use the first prover step of the sigma protocol for
-    /// that leaf to compute the necessary randomness "r" and the commitment "a"
-    pub(crate) fn first_message() -> (Scalar, FirstDlogProverMessage) {
-        let r = dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng());
+    /// that leaf to compute the commitment "a" for the given randomness "r"
+    pub(crate) fn first_message(r: Scalar) -> (Scalar, FirstDlogProverMessage) {
         let g = dlog_group::generator();
         let a = dlog_group::exponentiate(&g, &r);
         (r, FirstDlogProverMessage(a.into()))
@@ -123,7 +119,9 @@ pub(crate) mod interactive_prover {
 mod tests {
     use super::super::*;
     use super::*;
+    use crate::sigma_protocol::crypto_utils;
     use crate::sigma_protocol::private_input::DlogProverInput;
+    use ergotree_ir::sigma_protocol::dlog_group;
 
     use proptest::prelude::*;
 
@@ -135,7 +133,9 @@ mod tests {
         #[cfg(feature = "arbitrary")]
         fn test_compute_commitment(secret in any::<DlogProverInput>(), challenge in any::<Challenge>()) {
             let pk = secret.public_image();
-            let (r, commitment) = interactive_prover::first_message();
+            let (r, commitment) = interactive_prover::first_message(
+                dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng()),
+            );
             let second_message = interactive_prover::second_message(&secret, r, &challenge);
             let a = interactive_prover::compute_commitment(&pk, &challenge, &second_message);
             prop_assert_eq!(a, *commitment.0);
@@ -0,0 +1,190 @@
+//! Nonces of the sigma protocols (commitment randomness, simulated challenges and responses)
+
+use std::convert::TryFrom;
+
+use blake2::digest::{Update, VariableOutput};
+use blake2::VarBlake2b;
+use elliptic_curve::group::ff::PrimeField;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::sigma_protocol::dlog_group;
+use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
+use k256::Scalar;
+use zeroize::Zeroizing;
+
+use super::challenge::Challenge;
+use super::crypto_utils;
+use super::fiat_shamir::FiatShamirHash;
+use super::private_input::PrivateInput;
+use super::prover::ProverError;
+use super::unproven_tree::NodePosition;
+use super::SOUNDNESS_BYTES;
+
+/// Domain separation tag of the deterministic nonce derivation
+const DOMAIN_TAG: &[u8] = b"sigma-rust/deterministic-nonce/v1";
+
+/// Source of the nonces used by the prover
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum NonceMode {
+    /// Fresh values from the cryptographically secure RNG
+    #[default]
+    Random,
+    /// Values derived (RFC 6979-style) with the keyed hash of the prover secrets, the statement
+    /// being proven, the message and the node position in the proof tree. Proofs are reproducible
+    /// and do not rely on the quality of the RNG. Not supported for distributed signing (with hints).
+    Deterministic,
+}
+
+/// What the nonce is used for (the same node never needs two nonces with the same purpose)
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum NoncePurpose {
+    /// Randomness "r" of the real leaf commitment
+    Commitment = 0,
+    /// Response "z" of the simulated leaf
+    SimulatedResponse = 1,
+    /// Challenge of the simulated node
+    SimulatedChallenge = 2,
+}
+
+/// Generates nonces for the proof of the given statement and message
+pub(crate) enum NonceGenerator {
+    Random,
+    Deterministic {
+        /// hash of the prover secrets
+        key: Zeroizing<[u8; 32]>,
+        /// hash of the statement and the message
+        context: [u8; 32],
+    },
+}
+
+impl NonceGenerator {
+    pub(crate) fn new(
+        mode: NonceMode,
+        secrets: &[PrivateInput],
+        statement: &SigmaBoolean,
+        message: &[u8],
+    ) -> Result<NonceGenerator, ProverError> {
+        Ok(match mode {
+            NonceMode::Random => NonceGenerator::Random,
+            NonceMode::Deterministic => {
+                let mut secrets_bytes = Zeroizing::new(DOMAIN_TAG.to_vec());
+                for secret in secrets {
+                    match secret {
+                        PrivateInput::DlogProverInput(dl) => {
+                            secrets_bytes.extend_from_slice(&dl.to_bytes())
+                        }
+                        PrivateInput::DhTupleProverInput(dht) => {
+                            secrets_bytes.extend_from_slice(&dht.to_bytes())
+                        }
+                    }
+                }
+                let statement_bytes = statement
+                    .sigma_serialize_bytes()
+                    .map_err(|e| ProverError::Unexpected(e.to_string()))?;
+                let mut context_bytes = DOMAIN_TAG.to_vec();
+                context_bytes.extend_from_slice(&(statement_bytes.len() as u32).to_be_bytes());
+                context_bytes.extend_from_slice(&statement_bytes);
+                context_bytes.extend_from_slice(message);
+                NonceGenerator::Deterministic {
+                    key: Zeroizing::new(*sigma_util::hash::blake2b256_hash(&secrets_bytes)),
+                    context: *sigma_util::hash::blake2b256_hash(&context_bytes),
+                }
+            }
+        })
+    }
+
+    /// Scalar in the range [1, n), where n is the group order
+    pub(crate) fn scalar(&self, position: &NodePosition, purpose: NoncePurpose) -> Scalar {
+        match self {
+            NonceGenerator::Random => {
+                dlog_group::random_scalar_in_group_range(crypto_utils::secure_rng())
+            }
+            NonceGenerator::Deterministic { .. } => {
+                // rejection sampling, the probability of a retry is negligible (~2^-128)
+                let mut counter = 0u32;
+                loop {
+                    let bytes = Zeroizing::new(self.derive(position, purpose, counter));
+                    let scalar = Scalar::from_repr((*bytes).into());
+                    match scalar {
+                        Some(s) if !bool::from(s.is_zero()) => return s,
+                        _ => counter += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Challenge of the simulated node
+    pub(crate) fn challenge(&self, position: &NodePosition) -> Challenge {
+        match self {
+            NonceGenerator::Random => Challenge::secure_random(),
+            NonceGenerator::Deterministic { .. } => {
+                let bytes = self.derive(position, NoncePurpose::SimulatedChallenge, 0);
+                #[allow(clippy::unwrap_used)] // since the size is correct
+                FiatShamirHash::try_from(&bytes[..SOUNDNESS_BYTES])
+                    .unwrap()
+                    .into()
+            }
+        }
+    }
+
+    fn derive(&self, position: &NodePosition, purpose: NoncePurpose, counter: u32) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        if let NonceGenerator::Deterministic { key, context } = self {
+            let mut hasher = VarBlake2b::new_keyed(key.as_ref(), 32);
+            hasher.update(context);
+            hasher.update((position.positions().len() as u32).to_be_bytes());
+            for p in position.positions() {
+                hasher.update((*p as u32).to_be_bytes());
+            }
+            hasher.update([purpose as u8]);
+            hasher.update(counter.to_be_bytes());
+            hasher.finalize_variable(|hash| out.copy_from_slice(hash));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::sigma_protocol::private_input::DlogProverInput;
+
+    fn generator(secret: &DlogProverInput, message: &[u8]) -> NonceGenerator {
+        NonceGenerator::new(
+            NonceMode::Deterministic,
+            &[secret.clone().into()],
+            &secret.public_image().into(),
+            message,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn deterministic_nonces_depend_on_all_inputs() {
+        let secret = DlogProverInput::random();
+        let other_secret = DlogProverInput::random();
+        let root = NodePosition::crypto_tree_prefix();
+        let nonce = generator(&secret, b"msg").scalar(&root, NoncePurpose::Commitment);
+        assert_eq!(
+            nonce,
+            generator(&secret, b"msg").scalar(&root, NoncePurpose::Commitment)
+        );
+        assert_ne!(
+            nonce,
+            generator(&secret, b"msg2").scalar(&root, NoncePurpose::Commitment)
+        );
+        assert_ne!(
+            nonce,
+            generator(&other_secret, b"msg").scalar(&root, NoncePurpose::Commitment)
+        );
+        assert_ne!(
+            nonce,
+            generator(&secret, b"msg").scalar(&root.child(0), NoncePurpose::Commitment)
+        );
+        assert_ne!(
+            nonce,
+            generator(&secret, b"msg").scalar(&root, NoncePurpose::SimulatedResponse)
+        );
+    }
+}
@@ -2,7 +2,9 @@
 use std::convert::TryInto;
 
 use elliptic_curve::group::ff::PrimeField;
+use ergotree_ir::serialization::SigmaSerializable;
 use ergotree_ir::sigma_protocol::dlog_group;
+use ergotree_ir::sigma_protocol::dlog_group::EcPoint;
 use ergotree_ir::sigma_protocol::sigma_boolean::ProveDhTuple;
 use ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
 
@@ -93,6 +95,9 @@ pub struct DhTupleProverInput {
 }
 
 impl DhTupleProverInput {
+    /// Size in bytes of the secret and the Diffie-Hellman tuple (see [`Self::to_bytes`])
+    pub const SIZE_BYTES: usize = DlogProverInput::SIZE_BYTES + 4 * EcPoint::GROUP_SIZE;
+
     /// Create random secret and Diffie-Hellman tuple
     #[allow(clippy::many_single_char_names)]
     pub fn random() -> DhTupleProverInput {
@@ -112,6 +117,58 @@ impl DhTupleProverInput {
     pub fn public_image(&self) -> &ProveDhTuple {
         &self.common_input
     }
+
+    /// Create from the secret and Diffie-Hellman tuple.
+    /// Returns None if the tuple does not match the secret (`u != g^w` or `v != h^w`)
+    pub fn new(w: Scalar, common_input: ProveDhTuple) -> Option<DhTupleProverInput> {
+        if dlog_group::exponentiate(&common_input.g, &w) == *common_input.u
+            && dlog_group::exponentiate(&common_input.h, &w) == *common_input.v
+        {
+            Some(DhTupleProverInput { w, common_input })
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to parse the given byte array as an SEC-1-encoded scalar(secret) followed by
+    /// the points `g`, `h`, `u`, `v` of the Diffie-Hellman tuple.
+    /// Returns None if the secret or the points are invalid or the tuple does not match the secret.
+    pub fn from_bytes(bytes: &[u8; DhTupleProverInput::SIZE_BYTES]) -> Option<DhTupleProverInput> {
+        let (w_bytes, points_bytes) = bytes.split_at(DlogProverInput::SIZE_BYTES);
+        let w_bytes: [u8; DlogProverInput::SIZE_BYTES] = w_bytes.try_into().ok()?;
+        let w = Scalar::from_repr(w_bytes.into())?;
+        let points = points_bytes
+            .chunks(EcPoint::GROUP_SIZE)
+            .map(|point_bytes| EcPoint::sigma_parse_bytes(point_bytes).ok())
+            .collect::<Option<Vec<EcPoint>>>()?;
+        match points.as_slice() {
+            [g, h, u, v] => DhTupleProverInput::new(
+                w,
+                ProveDhTuple::new(g.clone(), h.clone(), u.clone(), v.clone()),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Byte representation of the secret (SEC-1-encoded scalar) followed by the points
+    /// `g`, `h`, `u`, `v` of the Diffie-Hellman tuple
+    #[allow(clippy::unwrap_used)]
+    pub fn to_bytes(&self) -> [u8; DhTupleProverInput::SIZE_BYTES] {
+        let mut bytes = Vec::with_capacity(DhTupleProverInput::SIZE_BYTES);
+        let w_bytes: [u8; DlogProverInput::SIZE_BYTES] = self.w.to_bytes().into();
+        bytes.extend_from_slice(&w_bytes);
+        for point in [
+            &self.common_input.g,
+            &self.common_input.h,
+            &self.common_input.u,
+            &self.common_input.v,
+        ] {
+            // EcPoint serialization fails only on IO errors of the underlying writer
+            bytes.extend(point.sigma_serialize_bytes().unwrap());
+        }
+        // size is guaranteed by the sizes of the secret and the points
+        bytes.try_into().unwrap()
+    }
 }
 
 /// Private inputs (secrets)
@@ -185,4 +242,20 @@ pub(crate) mod arbitrary {
 
 #[cfg(test)]
 #[cfg(feature = "arbitrary")]
-mod tests {}
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #[test]
+        fn dht_bytes_roundtrip(v in any::<DhTupleProverInput>()) {
+            prop_assert_eq![DhTupleProverInput::from_bytes(&v.to_bytes()), Some(v)];
+        }
+
+        #[test]
+        fn dht_tuple_mismatch(v in any::<DhTupleProverInput>(), other in any::<DhTupleProverInput>()) {
+            prop_assert_eq![DhTupleProverInput::new(v.w, other.common_input), None];
+        }
+    }
+}
@@ -8,6 +8,8 @@ pub mod hint;
 use crate::sigma_protocol::dht_protocol;
 use crate::sigma_protocol::fiat_shamir::fiat_shamir_hash_fn;
 use crate::sigma_protocol::fiat_shamir::fiat_shamir_tree_to_bytes;
+use crate::sigma_protocol::nonce::NonceGenerator;
+use crate::sigma_protocol::nonce::NoncePurpose;
 use crate::sigma_protocol::proof_tree::ProofTree;
 use crate::sigma_protocol::unchecked_tree::UncheckedDhTuple;
 use crate::sigma_protocol::unproven_tree::CandUnproven;
@@ -15,11 +17,12 @@ use crate::sigma_protocol::unproven_tree::CorUnproven;
 use crate::sigma_protocol::unproven_tree::NodePosition;
 use crate::sigma_protocol::unproven_tree::UnprovenDhTuple;
 use crate::sigma_protocol::Challenge;
+use crate::sigma_protocol::NonceMode;
 use crate::sigma_protocol::UnprovenLeaf;
 use ergotree_ir::sigma_protocol::sigma_boolean::SigmaBoolean;
 use ergotree_ir::sigma_protocol::sigma_boolean::SigmaConjectureItems;
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub use context_extension::*;
 use ergotree_ir::ergo_tree::ErgoTree;
@@ -84,6 +87,9 @@ pub enum ProverError {
     /// Not yet implemented
     #[error("not yet implemented: {0}")]
     NotYetImplemented(String),
+    /// Deterministic nonces cannot be used for distributed signing
+    #[error("Deterministic nonces are not supported with hints (distributed signing)")]
+    DeterministicNoncesWithHints,
 }
 
 impl From<ErgoTreeError> for ProverError {
@@ -103,6 +109,11 @@ pub trait Prover: Evaluator {
     /// Secrets of the prover
     fn secrets(&self) -> &[PrivateInput];
 
+    /// Source of the nonces (commitment randomness, simulated challenges and responses)
+    fn nonce_mode(&self) -> NonceMode {
+        NonceMode::Random
+    }
+
     /// The comments in this section are taken from the algorithm for the
     /// Sigma-protocol prover as described in the ErgoScript white-paper
     /// <https://ergoplatform.org/docs/ErgoScript.pdf>, Appendix A
@@ -112,11 +123,12 @@ pub trait Prover: Evaluator {
         &self,
         tree: &ErgoTree,
         env: &Env,
-        ctx: Rc<Context>,
+        ctx: Arc<Context>,
         message: &[u8],
         hints_bag: &HintsBag,
     ) -> Result<ProverResult, ProverError> {
         let expr = tree.proposition()?;
+        let ctx_ext = ctx.extension.clone();
         let unchecked_tree_opt = self
             .reduce_to_crypto(expr.as_ref(), env, ctx)
             .map_err(ProverError::EvalError)
@@ -124,8 +136,14 @@ pub trait Prover: Evaluator {
                 SigmaBoolean::TrivialProp(true) => Ok(None),
                 SigmaBoolean::TrivialProp(false) => Err(ProverError::ReducedToFalse),
                 sb => {
+                    let nonce_mode = self.nonce_mode();
+                    if nonce_mode == NonceMode::Deterministic && !hints_bag.is_empty() {
+                        return Err(ProverError::DeterministicNoncesWithHints);
+                    }
+                    let nonces = NonceGenerator::new(nonce_mode, self.secrets(), &sb, message)?;
                     let tree = convert_to_unproven(sb)?;
-                    let unchecked_tree = prove_to_unchecked(self, tree, message, hints_bag)?;
+                    let unchecked_tree =
+                        prove_to_unchecked(self, tree, message, hints_bag, &nonces)?;
                     Ok(Some(unchecked_tree))
                 }
             })?;
@@ -135,7 +153,7 @@ pub trait Prover: Evaluator {
         };
         Ok(ProverResult {
             proof,
-            extension: ContextExtension::empty(),
+            extension: ctx_ext,
         })
     }
 }
@@ -154,6 +172,7 @@ fn prove_to_unchecked<P: Prover + ?Sized>(
     unproven_tree: UnprovenTree,
     message: &[u8],
     hints_bag: &HintsBag,
+    nonces: &NonceGenerator,
 ) -> Result<UncheckedTree, ProverError> {
     // Prover Step 1: Mark as real everything the prover can prove
     let step1 = mark_real(prover, unproven_tree, hints_bag)?;
@@ -173,7 +192,7 @@ fn prove_to_unchecked<P: Prover + ?Sized>(
 
     // Prover Steps 4, 5, and 6 together: find challenges for simulated nodes; simulate simulated leaves;
     // compute commitments for real leaves
-    let step6 = simulate_and_commit(step3, hints_bag)?;
+    let step6 = simulate_and_commit(step3, hints_bag, nonces)?;
     // dbg!(&step6);
 
     // Prover Steps 7: convert the relevant information in the tree (namely, tree structure, node types,
@@ -375,6 +394,7 @@ fn polish_simulated<P: Prover + ?Sized>(
 fn simulate_and_commit(
     unproven_tree: UnprovenTree,
     hints_bag: &HintsBag,
+    nonces: &NonceGenerator,
 ) -> Result<UnprovenTree, ProverError> {
     proof_tree::rewrite(unproven_tree.into(), &|tree| {
         match tree {
@@ -402,7 +422,7 @@ fn simulate_and_commit(
                                 .into_iter()
                                 .find(|p| p.position() == c.position())
                                 .map(|p| p.challenge().clone())
-                                .unwrap_or_else(Challenge::secure_random);
+                                .unwrap_or_else(|| nonces.challenge(c.position()));
                             c.with_challenge(new_challenge)
                         }
                     })
@@ -455,7 +475,10 @@ fn simulate_and_commit(
                         .clone()
                         .into_iter()
                         .skip(1)
-                        .map(|it| it.with_challenge(Challenge::secure_random()))
+                        .map(|it| {
+                            let challenge = nonces.challenge(it.position());
+                            it.with_challenge(challenge)
+                        })
                         .collect();
                     let mut xored_challenge = challenge;
                     for it in &tail {
@@ -522,6 +545,7 @@ fn simulate_and_commit(
                                 let (fm, sm) = dlog_protocol::interactive_prover::simulate(
                                     &us.proposition,
                                     &challenge,
+                                    nonces.scalar(&us.position, NoncePurpose::SimulatedResponse),
                                 );
                                 Ok(ProofTree::UncheckedTree(
                                     UncheckedSchnorr {
@@ -537,8 +561,9 @@ fn simulate_and_commit(
                             }
                         } else {
                             // Step 6 (real leaf -- compute the commitment a)
-                            let (r, commitment) =
-                                dlog_protocol::interactive_prover::first_message();
+                            let (r, commitment) = dlog_protocol::interactive_prover::first_message(
+                                nonces.scalar(&us.position, NoncePurpose::Commitment),
+                            );
                             Ok(ProofTree::UnprovenTree(
                                 UnprovenSchnorr {
                                     commitment_opt: Some(commitment),
@@ -583,6 +608,7 @@ fn simulate_and_commit(
                                 let (fm, sm) = dht_protocol::interactive_prover::simulate(
                                     &dhu.proposition,
                                     &dhu_challenge,
+                                    nonces.scalar(&dhu.position, NoncePurpose::SimulatedResponse),
                                 );
                                 Ok(UncheckedDhTuple {
                                     proposition: dhu.proposition.clone(),
@@ -596,8 +622,10 @@ fn simulate_and_commit(
                             }
                         } else {
                             // Step 6 -- compute the commitment
-                            let (r, fm) =
-                                dht_protocol::interactive_prover::first_message(&dhu.proposition);
+                            let (r, fm) = dht_protocol::interactive_prover::first_message(
+                                &dhu.proposition,
+                                nonces.scalar(&dhu.position, NoncePurpose::Commitment),
+                            );
                             Ok(UnprovenDhTuple {
                                 commitment_opt: Some(fm),
                                 randomness_opt: Some(r),
@@ -908,12 +936,41 @@ impl Prover for TestProver {
     }
 }
 
+/// Prover with the given source of the nonces (see [`NonceMode`])
+pub struct NonceModeProver {
+    secrets: Vec<PrivateInput>,
+    nonce_mode: NonceMode,
+}
+
+impl NonceModeProver {
+    /// Create prover with the secrets to be used in proofs generation and the nonce mode
+    pub fn new(secrets: Vec<PrivateInput>, nonce_mode: NonceMode) -> Self {
+        NonceModeProver {
+            secrets,
+            nonce_mode,
+        }
+    }
+}
+
+impl Evaluator for NonceModeProver {}
+impl Prover for NonceModeProver {
+    fn secrets(&self) -> &[PrivateInput] {
+        self.secrets.as_ref()
+    }
+
+    fn nonce_mode(&self) -> NonceMode {
+        self.nonce_mode
+    }
+}
+
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::sigma_protocol::private_input::DhTupleProverInput;
     use crate::sigma_protocol::private_input::DlogProverInput;
+    use crate::sigma_protocol::proof_size;
+    use crate::sigma_protocol::prover::hint::{CommitmentHint, Hint, SimulatedCommitment};
     use ergotree_ir::mir::constant::Constant;
     use ergotree_ir::mir::constant::Literal;
     use ergotree_ir::mir::expr::Expr;
@@ -922,7 +979,7 @@ mod tests {
     use ergotree_ir::types::stype::SType;
     use sigma_test_util::force_any_val;
     use std::convert::TryFrom;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn test_prove_true_prop() {
@@ -937,7 +994,7 @@ mod tests {
         let res = prover.prove(
             &bool_true_tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -958,7 +1015,7 @@ mod tests {
         let res = prover.prove(
             &bool_false_tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -979,7 +1036,7 @@ mod tests {
         let res = prover.prove(
             &tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -1005,7 +1062,7 @@ mod tests {
         let res = prover.prove(
             &tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -1037,7 +1094,7 @@ mod tests {
         let res = prover.prove(
             &tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -1062,7 +1119,7 @@ mod tests {
         let res = prover.prove(
             &tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -1094,7 +1151,7 @@ mod tests {
         let res = prover.prove(
             &tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
@@ -1114,11 +1171,97 @@ mod tests {
         let res = prover.prove(
             &tree,
             &Env::empty(),
-            Rc::new(force_any_val::<Context>()),
+            Arc::new(force_any_val::<Context>()),
             message.as_slice(),
             &HintsBag::empty(),
         );
         assert!(res.is_ok());
         assert_ne!(res.unwrap().proof, ProofBytes::Empty);
     }
+
+    #[test]
+    fn test_proof_size() {
+        let secret1 = DlogProverInput::random();
+        let secret2 = DlogProverInput::random();
+        let secret3 = DhTupleProverInput::random();
+        let pk1 = secret1.public_image();
+        let pk2 = secret2.public_image();
+        let pi3 = secret3.public_image();
+        let exprs: Vec<Expr> = vec![
+            Expr::Const(pk1.clone().into()),
+            Expr::Const(pi3.clone().into()),
+            SigmaAnd::new(vec![
+                Expr::Const(pk1.clone().into()),
+                SigmaOr::new(vec![
+                    Expr::Const(pk2.clone().into()),
+                    Expr::Const(pi3.clone().into()),
+                ])
+                .unwrap()
+                .into(),
+            ])
+            .unwrap()
+            .into(),
+            SigmaOr::new(vec![
+                Expr::Const(pk1.into()),
+                SigmaAnd::new(vec![
+                    Expr::Const(pk2.into()),
+                    Expr::Const(pi3.clone().into()),
+                ])
+                .unwrap()
+                .into(),
+            ])
+            .unwrap()
+            .into(),
+        ];
+        let prover = TestProver {
+            secrets: vec![secret1.into(), secret2.into(), secret3.into()],
+        };
+        let message = vec![0u8; 100];
+        for expr in exprs {
+            let tree: ErgoTree = expr.clone().try_into().unwrap();
+            let ctx = Arc::new(force_any_val::<Context>());
+            let sb = prover
+                .reduce_to_crypto(&expr, &Env::empty(), ctx.clone())
+                .unwrap()
+                .sigma_prop;
+            let proof: Vec<u8> = prover
+                .prove(
+                    &tree,
+                    &Env::empty(),
+                    ctx,
+                    message.as_slice(),
+                    &HintsBag::empty(),
+                )
+                .unwrap()
+                .proof
+                .into();
+            assert_eq!(proof.len(), proof_size(&sb));
+        }
+    }
+
+    #[test]
+    fn test_deterministic_nonces_with_hints() {
+        let secret = DlogProverInput::random();
+        let pk = secret.public_image();
+        let tree: ErgoTree = Expr::Const(pk.clone().into()).try_into().unwrap();
+        let prover = NonceModeProver::new(vec![secret.into()], NonceMode::Deterministic);
+        let (_, commitment) =
+            dlog_protocol::interactive_prover::first_message(DlogProverInput::random().w);
+        let mut hints_bag = HintsBag::empty();
+        hints_bag.add_hint(Hint::CommitmentHint(CommitmentHint::SimulatedCommitment(
+            SimulatedCommitment {
+                image: pk.into(),
+                commitment: commitment.into(),
+                position: NodePosition::crypto_tree_prefix(),
+            },
+        )));
+        let res = prover.prove(
+            &tree,
+            &Env::empty(),
+            Arc::new(force_any_val::<Context>()),
+            &[0u8; 100],
+            &hints_bag,
+        );
+        assert_eq!(res.err(), Some(ProverError::DeterministicNoncesWithHints));
+    }
 }
@@ -158,6 +158,16 @@ impl HintsBag {
         HintsBag { hints: vec![] }
     }
 
+    /// Add a hint to the bag
+    pub fn add_hint(&mut self, hint: Hint) {
+        self.hints.push(hint);
+    }
+
+    /// Whether the bag has no hints
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+
     /// Commitments from all CommitmentHints in the bag
     pub fn commitments(&self) -> Vec<CommitmentHint> {
         self.hints
@@ -39,6 +39,43 @@ pub(crate) fn serialize_sig(tree: UncheckedTree) -> ProofBytes {
     ProofBytes::Some(data)
 }
 
+/// Size (in bytes) of the proof (as serialized by the prover) for the given sigma proposition.
+/// Returns 0 for trivial propositions (no proof needed).
+pub fn proof_size(sb: &SigmaBoolean) -> usize {
+    match sb {
+        SigmaBoolean::TrivialProp(_) => 0,
+        _ => SOUNDNESS_BYTES + proof_size_without_challenge(sb),
+    }
+}
+
+fn proof_size_without_challenge(sb: &SigmaBoolean) -> usize {
+    match sb {
+        SigmaBoolean::TrivialProp(_) => 0,
+        SigmaBoolean::ProofOfKnowledge(_) => GROUP_SIZE,
+        SigmaBoolean::SigmaConjecture(conj) => match conj {
+            // children's challenges are equal to the challenge of this node
+            SigmaConjecture::Cand(cand) => {
+                cand.items.iter().map(proof_size_without_challenge).sum()
+            }
+            // challenges of all children except the last one
+            SigmaConjecture::Cor(cor) => cor
+                .items
+                .iter()
+                .map(|item| SOUNDNESS_BYTES + proof_size_without_challenge(item))
+                .sum::<usize>()
+                .saturating_sub(SOUNDNESS_BYTES),
+            // polynomial coefficients (children's challenges are computed by the verifier)
+            SigmaConjecture::Cthreshold(ct) => {
+                (ct.items.len().saturating_sub(ct.n as usize)) * SOUNDNESS_BYTES
+                    + ct.items
+                        .iter()
+                        .map(proof_size_without_challenge)
+                        .sum::<usize>()
+            }
+        },
+    }
+}
+
 /// Recursively traverses the given node and serializes challenges and prover messages to the given writer.
 /// Note, sigma propositions and commitments are not serialized.
 /// Returns the proof bytes containing all the serialized challenges and prover messages (aka `z` values)
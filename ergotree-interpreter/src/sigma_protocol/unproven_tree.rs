@@ -369,6 +369,10 @@ impl NodePosition {
         positions.push(child_idx);
         NodePosition { positions }
     }
+
+    pub(crate) fn positions(&self) -> &[usize] {
+        &self.positions
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
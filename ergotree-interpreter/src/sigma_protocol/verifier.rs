@@ -1,6 +1,6 @@
 //! Verifier
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::dht_protocol;
 use super::dht_protocol::FirstDhTupleProverMessage;
@@ -18,6 +18,7 @@ use super::{
 use crate::eval::context::Context;
 use crate::eval::env::Env;
 use crate::eval::{EvalError, Evaluator};
+use crate::storage_rent;
 use dlog_protocol::FirstDlogProverMessage;
 use ergotree_ir::ergo_tree::ErgoTree;
 use ergotree_ir::ergo_tree::ErgoTreeError;
@@ -63,13 +64,13 @@ pub trait Verifier: Evaluator {
         &self,
         tree: &ErgoTree,
         env: &Env,
-        ctx: Rc<Context>,
+        ctx: Arc<Context>,
         proof: ProofBytes,
         message: &[u8],
     ) -> Result<VerificationResult, VerifierError> {
         let expr = tree.proposition()?;
-        let cprop = self.reduce_to_crypto(expr.as_ref(), env, ctx)?.sigma_prop;
-        let res: bool = match cprop {
+        let reduction = self.reduce_to_crypto(expr.as_ref(), env, ctx)?;
+        let res: bool = match reduction.sigma_prop {
             SigmaBoolean::TrivialProp(b) => b,
             sb => {
                 match proof {
@@ -85,9 +86,31 @@ pub trait Verifier: Evaluator {
         };
         Ok(VerificationResult {
             result: res,
-            cost: 0,
+            cost: reduction.cost,
         })
     }
+
+    /// Same as [`Verifier::verify`], but if no proof is given for the expired box (see
+    /// [`storage_rent::is_storage_rent_spending`]) the storage rent spending rules are checked
+    /// instead of the script
+    fn verify_with_storage_rent(
+        &self,
+        tree: &ErgoTree,
+        env: &Env,
+        ctx: Arc<Context>,
+        proof: ProofBytes,
+        message: &[u8],
+        storage_fee_factor: u64,
+    ) -> Result<VerificationResult, VerifierError> {
+        if proof == ProofBytes::Empty && storage_rent::is_storage_rent_spending(ctx.as_ref()) {
+            Ok(VerificationResult {
+                result: storage_rent::check_storage_rent_output(ctx.as_ref(), storage_fee_factor)?,
+                cost: 0,
+            })
+        } else {
+            self.verify(tree, env, ctx, proof, message)
+        }
+    }
 }
 
 /// Perform Verifier Steps 4-6
@@ -142,12 +165,34 @@ fn compute_commitments(sp: UncheckedTree) -> UncheckedTree {
     }
 }
 
-/// Test Verifier implementation
+/// Test Verifier implementation (script evaluation cost is not limited)
 pub struct TestVerifier;
 
 impl Evaluator for TestVerifier {}
 impl Verifier for TestVerifier {}
 
+/// Verifier that fails the script evaluation with [`EvalError::CostError`] once its cost goes
+/// over the given limit
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct CostLimitedVerifier {
+    cost_limit: u64,
+}
+
+impl CostLimitedVerifier {
+    /// Create a verifier with the given script evaluation cost limit
+    pub fn new(cost_limit: u64) -> Self {
+        CostLimitedVerifier { cost_limit }
+    }
+}
+
+impl Evaluator for CostLimitedVerifier {
+    fn cost_limit(&self) -> Option<u64> {
+        Some(self.cost_limit)
+    }
+}
+
+impl Verifier for CostLimitedVerifier {}
+
 #[allow(clippy::unwrap_used)]
 #[allow(clippy::panic)]
 #[cfg(test)]
@@ -157,7 +202,8 @@ mod tests {
 
     use crate::sigma_protocol::private_input::{DhTupleProverInput, DlogProverInput, PrivateInput};
     use crate::sigma_protocol::prover::hint::HintsBag;
-    use crate::sigma_protocol::prover::{Prover, TestProver};
+    use crate::sigma_protocol::prover::{NonceModeProver, Prover, TestProver};
+    use crate::sigma_protocol::NonceMode;
 
     use super::*;
     use ergotree_ir::mir::expr::Expr;
@@ -191,14 +237,14 @@ mod tests {
             };
             let res = prover.prove(&tree,
                 &Env::empty(),
-                Rc::new(force_any_val::<Context>()),
+                Arc::new(force_any_val::<Context>()),
                 message.as_slice(),
                 &HintsBag::empty());
             let proof = res.unwrap().proof;
             let verifier = TestVerifier;
             prop_assert_eq!(verifier.verify(&tree,
                                             &Env::empty(),
-                                            Rc::new(force_any_val::<Context>()),
+                                            Arc::new(force_any_val::<Context>()),
                                             proof.clone(),
                                             message.as_slice())
                             .unwrap().result,
@@ -207,7 +253,7 @@ mod tests {
             // possible to append bytes
             prop_assert_eq!(verifier.verify(&tree,
                                             &Env::empty(),
-                                            Rc::new(force_any_val::<Context>()),
+                                            Arc::new(force_any_val::<Context>()),
                                             proof_append_some_byte(&proof),
                                             message.as_slice())
                             .unwrap().result,
@@ -216,7 +262,7 @@ mod tests {
             // wrong message
             prop_assert_eq!(verifier.verify(&tree,
                                             &Env::empty(),
-                                            Rc::new(force_any_val::<Context>()),
+                                            Arc::new(force_any_val::<Context>()),
                                             proof,
                                             vec![1u8; 100].as_slice())
                             .unwrap().result,
@@ -233,14 +279,14 @@ mod tests {
             };
             let res = prover.prove(&tree,
                 &Env::empty(),
-                Rc::new(force_any_val::<Context>()),
+                Arc::new(force_any_val::<Context>()),
                 message.as_slice(),
                 &HintsBag::empty());
             let proof = res.unwrap().proof;
             let verifier = TestVerifier;
             prop_assert_eq!(verifier.verify(&tree,
                                             &Env::empty(),
-                                            Rc::new(force_any_val::<Context>()),
+                                            Arc::new(force_any_val::<Context>()),
                                             proof.clone(),
                                             message.as_slice())
                             .unwrap().result,
@@ -249,7 +295,7 @@ mod tests {
             // possible to append bytes
             prop_assert_eq!(verifier.verify(&tree,
                                             &Env::empty(),
-                                            Rc::new(force_any_val::<Context>()),
+                                            Arc::new(force_any_val::<Context>()),
                                             proof_append_some_byte(&proof),
                                             message.as_slice())
                             .unwrap().result,
@@ -258,7 +304,7 @@ mod tests {
             // wrong message
             prop_assert_eq!(verifier.verify(&tree,
                                             &Env::empty(),
-                                            Rc::new(force_any_val::<Context>()),
+                                            Arc::new(force_any_val::<Context>()),
                                             proof,
                                             vec![1u8; 100].as_slice())
                             .unwrap().result,
@@ -280,14 +326,14 @@ mod tests {
             };
             let res = prover.prove(&tree,
                 &Env::empty(),
-                Rc::new(force_any_val::<Context>()),
+                Arc::new(force_any_val::<Context>()),
                 message.as_slice(),
                 &HintsBag::empty());
             let proof = res.unwrap().proof;
             let verifier = TestVerifier;
             let ver_res = verifier.verify(&tree,
                                           &Env::empty(),
-                                          Rc::new(force_any_val::<Context>()),
+                                          Arc::new(force_any_val::<Context>()),
                                           proof,
                                           message.as_slice());
             prop_assert_eq!(ver_res.unwrap().result, true);
@@ -311,14 +357,14 @@ mod tests {
             let prover = TestProver { secrets: vec![secret1, secret2, secret3] };
             let res = prover.prove(&tree,
                 &Env::empty(),
-                Rc::new(force_any_val::<Context>()),
+                Arc::new(force_any_val::<Context>()),
                 message.as_slice(),
                 &HintsBag::empty());
             let proof = res.unwrap().proof;
             let verifier = TestVerifier;
             let ver_res = verifier.verify(&tree,
                                           &Env::empty(),
-                                          Rc::new(force_any_val::<Context>()),
+                                          Arc::new(force_any_val::<Context>()),
                                           proof,
                                           message.as_slice());
             prop_assert_eq!(ver_res.unwrap().result, true);
@@ -342,14 +388,14 @@ mod tests {
                 };
                 let res = prover.prove(&tree,
                     &Env::empty(),
-                    Rc::new(force_any_val::<Context>()),
+                    Arc::new(force_any_val::<Context>()),
                     message.as_slice(),
                     &HintsBag::empty());
                 let proof = res.unwrap_or_else(|_| panic!("proof failed for secret: {:?}", secret)).proof;
                 let verifier = TestVerifier;
                 let ver_res = verifier.verify(&tree,
                                               &Env::empty(),
-                                              Rc::new(force_any_val::<Context>()),
+                                              Arc::new(force_any_val::<Context>()),
                                               proof,
                                               message.as_slice());
                 prop_assert_eq!(ver_res.unwrap().result, true, "verify failed on secret: {:?}", &secret);
@@ -379,18 +425,73 @@ mod tests {
                 };
                 let res = prover.prove(&tree,
                     &Env::empty(),
-                    Rc::new(force_any_val::<Context>()),
+                    Arc::new(force_any_val::<Context>()),
                     message.as_slice(),
                     &HintsBag::empty());
                 let proof = res.unwrap_or_else(|_| panic!("proof failed for secret: {:?}", secret)).proof;
                 let verifier = TestVerifier;
                 let ver_res = verifier.verify(&tree,
                                               &Env::empty(),
-                                              Rc::new(force_any_val::<Context>()),
+                                              Arc::new(force_any_val::<Context>()),
                                               proof,
                                               message.as_slice());
                 prop_assert_eq!(ver_res.unwrap().result, true, "verify failed on secret: {:?}", &secret);
             }
         }
+
+        #[test]
+        fn test_prover_verifier_deterministic_nonces(secret1 in any::<PrivateInput>(),
+                                                     secret2 in any::<PrivateInput>(),
+                                                     secret3 in any::<PrivateInput>(),
+                                                     message in vec(any::<u8>(), 100..200)) {
+            let pk1 = secret1.public_image();
+            let pk2 = secret2.public_image();
+            let pk3 = secret3.public_image();
+            let expr: Expr = SigmaOr::new(vec![
+                Expr::Const(pk1.into()),
+                SigmaAnd::new(vec![Expr::Const(pk2.into()), Expr::Const(pk3.into())])
+                    .unwrap()
+                    .into(),
+            ]).unwrap().into();
+            let tree = ErgoTree::try_from(expr).unwrap();
+            // second branch is simulated, so simulated challenges and responses are covered as well
+            let prover = NonceModeProver::new(vec![secret1], NonceMode::Deterministic);
+            let prove = |message: &[u8]| prover.prove(&tree,
+                &Env::empty(),
+                Arc::new(force_any_val::<Context>()),
+                message,
+                &HintsBag::empty()).unwrap().proof;
+            let proof = prove(message.as_slice());
+            prop_assert_eq!(&proof, &prove(message.as_slice()));
+            prop_assert_ne!(&proof, &prove(&message[1..]));
+            let verifier = TestVerifier;
+            let ver_res = verifier.verify(&tree,
+                                          &Env::empty(),
+                                          Arc::new(force_any_val::<Context>()),
+                                          proof,
+                                          message.as_slice());
+            prop_assert_eq!(ver_res.unwrap().result, true);
+        }
+    }
+
+    #[test]
+    fn test_cost_limited_verifier() {
+        let tree = ErgoTree::try_from(Expr::Const(true.into())).unwrap();
+        let verify = |verifier: CostLimitedVerifier| {
+            verifier.verify(
+                &tree,
+                &Env::empty(),
+                Arc::new(force_any_val::<Context>()),
+                ProofBytes::Empty,
+                &[],
+            )
+        };
+        let res = verify(CostLimitedVerifier::new(1)).unwrap();
+        assert!(res.result);
+        assert_eq!(res.cost, 1);
+        assert!(matches!(
+            verify(CostLimitedVerifier::new(0)),
+            Err(VerifierError::EvalError(EvalError::CostError(_)))
+        ));
     }
 }
@@ -0,0 +1,184 @@
+//! Storage rent: boxes older than the storage period can be spent by anyone without a proof,
+//! if the box is recreated (with the same script, tokens and registers) in the spending
+//! transaction with the value reduced by at most the storage fee, or if the box value does not
+//! cover the fee at all.
+//! Index of the recreated box is in the context extension variable [`STORAGE_INDEX_VAR_ID`].
+
+use std::convert::TryFrom;
+
+use ergotree_ir::chain::ergo_box::ErgoBox;
+use ergotree_ir::mir::constant::TryExtractInto;
+use ergotree_ir::serialization::SigmaSerializable;
+use ergotree_ir::serialization::SigmaSerializationError;
+
+use crate::eval::context::Context;
+use crate::sigma_protocol::verifier::VerifierError;
+
+/// Storage period (in blocks, ~4 years) after which the box can be spent with the storage fee
+pub const STORAGE_PERIOD: u32 = 1_051_200;
+
+/// Context extension variable id with the index (`Short`) of the output recreating the expired box
+pub const STORAGE_INDEX_VAR_ID: u8 = 127;
+
+/// Check if the box is old enough to be spent with the storage fee at the given height
+pub fn is_expired(b: &ErgoBox, height: u32) -> bool {
+    height.saturating_sub(b.creation_height) >= STORAGE_PERIOD
+}
+
+/// Storage fee of the box (storage fee factor times the serialized box size)
+pub fn storage_fee(b: &ErgoBox, storage_fee_factor: u64) -> Result<u64, SigmaSerializationError> {
+    let box_size = b.sigma_serialize_bytes()?.len() as u64;
+    Ok(storage_fee_factor.saturating_mul(box_size))
+}
+
+/// Check if the context describes the storage rent spending of the self box (expired box and the
+/// recreated box index is set in the context extension)
+pub fn is_storage_rent_spending(ctx: &Context) -> bool {
+    is_expired(ctx.self_box.as_ref(), ctx.pre_header.height)
+        && ctx.extension.values.contains_key(&STORAGE_INDEX_VAR_ID)
+}
+
+/// Check the storage rent spending rules for the self box: the output at the index from the
+/// context extension is created at the current height, keeps the script, tokens and registers
+/// (R4-R9) of the self box and its value is reduced by at most the storage fee.
+/// Any output is accepted if the storage fee is not covered by the self box value.
+pub fn check_storage_rent_output(
+    ctx: &Context,
+    storage_fee_factor: u64,
+) -> Result<bool, VerifierError> {
+    let self_box = ctx.self_box.as_ref();
+    let output_index = ctx
+        .extension
+        .values
+        .get(&STORAGE_INDEX_VAR_ID)
+        .cloned()
+        .ok_or_else(|| {
+            VerifierError::Unexpected("storage rent output index is not set".to_string())
+        })?
+        .try_extract_into::<i16>()
+        .map_err(|e| VerifierError::Unexpected(format!("storage rent output index: {}", e)))?;
+    let output = usize::try_from(output_index)
+        .ok()
+        .and_then(|idx| ctx.outputs.get(idx))
+        .ok_or_else(|| {
+            VerifierError::Unexpected(format!(
+                "storage rent output index {} is out of bounds",
+                output_index
+            ))
+        })?;
+    let fee = storage_fee(self_box, storage_fee_factor)
+        .map_err(|e| VerifierError::Unexpected(format!("box serialization failed: {}", e)))?;
+    let value = *self_box.value.as_u64();
+    if value <= fee {
+        return Ok(true);
+    }
+    let correct_creation_height = output.creation_height == ctx.pre_header.height;
+    let correct_value = *output.value.as_u64() >= value - fee;
+    let correct_registers = output.ergo_tree == self_box.ergo_tree
+        && output.tokens == self_box.tokens
+        && output.additional_registers == self_box.additional_registers;
+    Ok(correct_creation_height && correct_value && correct_registers)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::convert::TryInto;
+    use std::sync::Arc;
+
+    use ergotree_ir::chain::ergo_box::box_value::BoxValue;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisterId;
+    use ergotree_ir::chain::ergo_box::NonMandatoryRegisters;
+    use ergotree_ir::chain::tx_id::TxId;
+    use sigma_test_util::force_any_val;
+
+    use super::*;
+    use crate::sigma_protocol::prover::ContextExtension;
+
+    const HEIGHT: u32 = STORAGE_PERIOD + 100;
+    const FEE_FACTOR: u64 = 1_250_000;
+
+    fn expired_box(value: u64) -> ErgoBox {
+        let b = force_any_val::<ErgoBox>();
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            b.ergo_tree,
+            vec![],
+            NonMandatoryRegisters::new(
+                vec![(NonMandatoryRegisterId::R4, 1i32.into())]
+                    .into_iter()
+                    .collect(),
+            )
+            .unwrap(),
+            100,
+            TxId::zero(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn recreated(b: &ErgoBox, value: u64, height: u32) -> ErgoBox {
+        ErgoBox::new(
+            value.try_into().unwrap(),
+            b.ergo_tree.clone(),
+            b.tokens.clone(),
+            b.additional_registers.clone(),
+            height,
+            force_any_val::<TxId>(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn rent_ctx(self_box: ErgoBox, output: ErgoBox, index: Option<i16>) -> Context {
+        let mut ctx = force_any_val::<Context>();
+        ctx.pre_header.height = HEIGHT;
+        ctx.height = HEIGHT;
+        ctx.self_box = Arc::new(self_box);
+        ctx.outputs = vec![Arc::new(output)];
+        let mut extension = ContextExtension::empty();
+        if let Some(idx) = index {
+            extension.values.insert(STORAGE_INDEX_VAR_ID, idx.into());
+        }
+        ctx.with_extension(extension)
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let b = expired_box(*BoxValue::SAFE_USER_MIN.as_u64());
+        assert!(!is_expired(&b, 0));
+        assert!(!is_expired(&b, STORAGE_PERIOD + 99));
+        assert!(is_expired(&b, STORAGE_PERIOD + 100));
+        let ctx = rent_ctx(b.clone(), b.clone(), Some(0));
+        assert!(is_storage_rent_spending(&ctx));
+        assert!(!is_storage_rent_spending(&rent_ctx(b.clone(), b, None)));
+    }
+
+    #[test]
+    fn test_check_output() {
+        let value = 10_000_000_000u64;
+        let b = expired_box(value);
+        let fee = storage_fee(&b, FEE_FACTOR).unwrap();
+        let check = |output: ErgoBox, idx: i16| {
+            check_storage_rent_output(&rent_ctx(b.clone(), output, Some(idx)), FEE_FACTOR)
+        };
+        assert_eq!(check(recreated(&b, value - fee, HEIGHT), 0), Ok(true));
+        // fee is too high
+        assert_eq!(check(recreated(&b, value - fee - 1, HEIGHT), 0), Ok(false));
+        // wrong creation height
+        assert_eq!(check(recreated(&b, value - fee, HEIGHT - 1), 0), Ok(false));
+        // registers are not preserved
+        let mut other = recreated(&b, value - fee, HEIGHT);
+        other.additional_registers = NonMandatoryRegisters::empty();
+        assert_eq!(check(other, 0), Ok(false));
+        assert!(check(recreated(&b, value - fee, HEIGHT), 1).is_err());
+    }
+
+    #[test]
+    fn test_fee_not_covered() {
+        let b = expired_box(1_000_000);
+        assert!(storage_fee(&b, FEE_FACTOR).unwrap() > 1_000_000);
+        let ctx = rent_ctx(b, force_any_val::<ErgoBox>(), Some(0));
+        assert_eq!(check_storage_rent_output(&ctx, FEE_FACTOR), Ok(true));
+    }
+}
@@ -11,7 +11,7 @@ use ergotree_ir::sigma_protocol::sigma_boolean::ProveDhTuple;
 use num_bigint::BigUint;
 use sigma_test_util::force_any_val;
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[test]
 fn sig_test_vector_provedlog() {
@@ -40,7 +40,7 @@ fn sig_test_vector_provedlog() {
     let ver_res = verifier.verify(
         &expr.try_into().unwrap(),
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -67,7 +67,7 @@ fn sig_test_vector_prove_dht() {
     // let res = prover.prove(
     //     &tree,
     //     &Env::empty(),
-    //     Rc::new(force_any_val::<Context>()),
+    //     Arc::new(force_any_val::<Context>()),
     //     msg.as_slice(),
     //     &HintsBag::empty(),
     // );
@@ -78,7 +78,7 @@ fn sig_test_vector_prove_dht() {
     let ver_res = verifier.verify(
         &expr.try_into().unwrap(),
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -124,7 +124,7 @@ fn sig_test_vector_conj_and() {
     // let res = prover.prove(
     //     &tree,
     //     &Env::empty(),
-    //     Rc::new(force_any_val::<Context>()),
+    //     Arc::new(force_any_val::<Context>()),
     //     msg.as_slice(),
     //     &HintsBag::empty(),
     // );
@@ -135,7 +135,7 @@ fn sig_test_vector_conj_and() {
     let ver_res = verifier.verify(
         &tree,
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -181,7 +181,7 @@ fn sig_test_vector_conj_or() {
     // let res = prover.prove(
     //     &tree,
     //     &Env::empty(),
-    //     Rc::new(force_any_val::<Context>()),
+    //     Arc::new(force_any_val::<Context>()),
     //     msg.as_slice(),
     //     &HintsBag::empty(),
     // );
@@ -192,7 +192,7 @@ fn sig_test_vector_conj_or() {
     let ver_res = verifier.verify(
         &tree,
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -232,7 +232,7 @@ fn sig_test_vector_conj_or_prove_dht() {
     // let res = prover.prove(
     //     &tree,
     //     &Env::empty(),
-    //     Rc::new(force_any_val::<Context>()),
+    //     Arc::new(force_any_val::<Context>()),
     //     msg.as_slice(),
     //     &HintsBag::empty(),
     // );
@@ -243,7 +243,7 @@ fn sig_test_vector_conj_or_prove_dht() {
     let ver_res = verifier.verify(
         &expr.try_into().unwrap(),
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -303,7 +303,7 @@ fn sig_test_vector_conj_and_or() {
     // let res = prover.prove(
     //     &tree,
     //     &Env::empty(),
-    //     Rc::new(force_any_val::<Context>()),
+    //     Arc::new(force_any_val::<Context>()),
     //     msg.as_slice(),
     //     &HintsBag::empty(),
     // );
@@ -314,7 +314,7 @@ fn sig_test_vector_conj_and_or() {
     let ver_res = verifier.verify(
         &tree,
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -374,7 +374,7 @@ fn sig_test_vector_conj_or_and() {
     // let res = prover.prove(
     //     &tree,
     //     &Env::empty(),
-    //     Rc::new(force_any_val::<Context>()),
+    //     Arc::new(force_any_val::<Context>()),
     //     msg.as_slice(),
     //     &HintsBag::empty(),
     // );
@@ -385,7 +385,7 @@ fn sig_test_vector_conj_or_and() {
     let ver_res = verifier.verify(
         &tree,
         &Env::empty(),
-        Rc::new(force_any_val::<Context>()),
+        Arc::new(force_any_val::<Context>()),
         signature.into(),
         msg.as_slice(),
     );
@@ -12,7 +12,9 @@ use crate::mir::constant::{Constant, Literal};
 use crate::mir::deserialize_context::DeserializeContext;
 use crate::mir::expr::Expr;
 use crate::mir::get_var::GetVar;
+use crate::mir::option_get::OptionGet;
 use crate::mir::sigma_and::SigmaAnd;
+use crate::mir::unary_op::OneArgOpTryBuild;
 use crate::mir::value::CollKind;
 use crate::mir::value::NativeColl::CollByte;
 use crate::serialization::SigmaParsingError;
@@ -84,6 +86,12 @@ pub enum Address {
 }
 
 impl Address {
+    /// Context extension variable id holding the serialized script of P2SH address
+    pub const P2SH_SCRIPT_VAR_ID: u8 = 1;
+
+    /// Length of the script hash in P2SH address
+    pub const P2SH_SCRIPT_HASH_LENGTH: usize = 24;
+
     /// Create a P2PK address from serialized PK bytes(EcPoint/GroupElement)
     pub fn p2pk_from_pk_bytes(bytes: &[u8]) -> Result<Address, SigmaParsingError> {
         EcPoint::sigma_parse_bytes(bytes)
@@ -91,6 +99,40 @@ impl Address {
             .map(Address::P2Pk)
     }
 
+    /// Create a P2SH address from the given script.
+    /// The script hash is calculated from the serialized proposition of the tree (with constants
+    /// substituted), which is exactly what is expected in the context extension on spending
+    /// (see [`Address::p2sh_script_bytes`])
+    pub fn p2sh_from_ergo_tree(tree: &ErgoTree) -> Result<Address, ErgoTreeError> {
+        let script_bytes = Address::p2sh_script_bytes(tree)?;
+        Ok(Address::P2SH(Address::p2sh_script_hash(&script_bytes)))
+    }
+
+    /// Serialized script bytes that should be put into the context extension (with id
+    /// [`Address::P2SH_SCRIPT_VAR_ID`]) to spend a box guarded by P2SH address created for this
+    /// script
+    pub fn p2sh_script_bytes(tree: &ErgoTree) -> Result<Vec<u8>, ErgoTreeError> {
+        Ok(tree.proposition()?.sigma_serialize_bytes()?)
+    }
+
+    /// Returns true if this is a P2SH address and its script hash matches the given script
+    pub fn p2sh_script_matches(&self, tree: &ErgoTree) -> Result<bool, ErgoTreeError> {
+        Ok(match self {
+            Address::P2SH(script_hash) => {
+                let script_bytes = Address::p2sh_script_bytes(tree)?;
+                Address::p2sh_script_hash(&script_bytes) == *script_hash
+            }
+            _ => false,
+        })
+    }
+
+    fn p2sh_script_hash(script_bytes: &[u8]) -> [u8; 24] {
+        let mut script_hash = [0u8; 24];
+        script_hash
+            .copy_from_slice(&blake2b256_hash(script_bytes)[..Address::P2SH_SCRIPT_HASH_LENGTH]);
+        script_hash
+    }
+
     /// Re-create the address from ErgoTree that was built from the address
     ///
     /// At some point in the past a user entered an address from which the ErgoTree was built.
@@ -110,8 +152,11 @@ impl Address {
                     if let [Expr::BoolToSigmaProp(BoolToSigmaProp { input }), Expr::DeserializeContext(DeserializeContext { tpe, id })] =
                         items.as_slice()
                     {
-                        if let (Expr::BinOp(BinOp { kind, left, right }), SType::SSigmaProp, 1) =
-                            (*input.clone(), tpe.clone(), id)
+                        if let (
+                            Expr::BinOp(BinOp { kind, left, right }),
+                            SType::SSigmaProp,
+                            Address::P2SH_SCRIPT_VAR_ID,
+                        ) = (*input.clone(), tpe.clone(), *id)
                         {
                             if let (
                                 Relation(RelationOp::Eq),
@@ -201,16 +246,16 @@ impl Address {
             Address::P2S(bytes) => ErgoTree::sigma_parse_bytes(bytes),
             Address::P2SH(script_hash) => {
                 let get_var_expr = Expr::GetVar(GetVar {
-                    var_id: 1,
+                    var_id: Address::P2SH_SCRIPT_VAR_ID,
                     var_tpe: SType::SColl(Box::new(SType::SByte)),
                 });
                 let hash_expr = Expr::CalcBlake2b256(CalcBlake2b256 {
-                    input: Box::new(get_var_expr),
+                    input: Box::new(Expr::OptionGet(OptionGet::try_build(get_var_expr)?)),
                 });
                 let slice_expr = Expr::Slice(Slice {
                     input: Box::new(hash_expr),
                     from: Box::new(0i32.into()),
-                    until: Box::new(24i32.into()),
+                    until: Box::new((Address::P2SH_SCRIPT_HASH_LENGTH as i32).into()),
                 });
                 let hash_equals = Expr::BinOp(BinOp {
                     kind: Relation(RelationOp::Eq),
@@ -219,7 +264,7 @@ impl Address {
                 });
                 let script_is_correct = Expr::DeserializeContext(DeserializeContext {
                     tpe: SType::SSigmaProp,
-                    id: 1,
+                    id: Address::P2SH_SCRIPT_VAR_ID,
                 });
                 let sigma_prop = Expr::BoolToSigmaProp(BoolToSigmaProp {
                     input: Box::from(hash_equals),
@@ -288,6 +333,7 @@ impl From<SigmaSerializationError> for AddressError {
 }
 
 /// Address types
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum AddressTypePrefix {
     /// 0x01 - Pay-to-PublicKey(P2PK) address
     P2Pk = 1,
@@ -514,6 +560,143 @@ impl AddressEncoder {
     }
 }
 
+/// Detailed report on the encoded address, see [`AddressEncoder::inspect_address_str`]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AddressInspection {
+    /// Base58 decoding error, if any (all the checks below are skipped in this case)
+    pub base58_error: Option<String>,
+    /// Network from the prefix byte (None if the prefix byte is missing or invalid)
+    pub network: Option<NetworkPrefix>,
+    /// Address type from the prefix byte (None if the prefix byte is missing or invalid)
+    pub address_type: Option<AddressTypePrefix>,
+    /// Checksum matches the prefix byte and content bytes
+    pub checksum_valid: bool,
+    /// Size of the content bytes (without prefix byte and checksum)
+    pub content_length: usize,
+    /// Content length is valid for the address type (33 bytes for P2PK, 24 bytes for P2SH,
+    /// non-empty for P2S)
+    pub content_length_valid: bool,
+    /// Whether the P2S content is a valid ErgoTree (with a parseable root expression).
+    /// None for other address types
+    pub ergo_tree_valid: Option<bool>,
+    /// Content is a public key (P2PK address) or a P2S script that is recognized as P2PK
+    pub is_p2pk: bool,
+    /// Decoded address (regardless of the checksum validity), if content could be parsed
+    pub address: Option<Address>,
+    /// The same address encoded for the other network (testnet for mainnet and vice versa)
+    pub other_network_address: Option<String>,
+}
+
+impl AddressInspection {
+    /// Returns true if the address passed all the checks
+    pub fn is_valid(&self) -> bool {
+        self.base58_error.is_none()
+            && self.network.is_some()
+            && self.checksum_valid
+            && self.content_length_valid
+            && self.ergo_tree_valid.unwrap_or(true)
+            && self.address.is_some()
+    }
+}
+
+impl AddressEncoder {
+    /// Inspect Base58 encoded address, reporting the result of every check instead of the first
+    /// error (as in [`AddressEncoder::parse_address_from_str`]). Network prefix is not checked
+    /// against this encoder.
+    pub fn inspect_address_str(str: &str) -> AddressInspection {
+        match bs58::decode(str).into_vec() {
+            Ok(bytes) => AddressEncoder::inspect_address_bytes(&bytes),
+            Err(e) => AddressInspection {
+                base58_error: Some(e.to_string()),
+                network: None,
+                address_type: None,
+                checksum_valid: false,
+                content_length: 0,
+                content_length_valid: false,
+                ergo_tree_valid: None,
+                is_p2pk: false,
+                address: None,
+                other_network_address: None,
+            },
+        }
+    }
+
+    /// Inspect address bytes (prefix byte, content bytes and checksum),
+    /// see [`AddressEncoder::inspect_address_str`]
+    pub fn inspect_address_bytes(bytes: &[u8]) -> AddressInspection {
+        let (without_checksum, checksum) = if bytes.len() >= AddressEncoder::CHECKSUM_LENGTH {
+            bytes.split_at(bytes.len() - AddressEncoder::CHECKSUM_LENGTH)
+        } else {
+            (bytes, &[][..])
+        };
+        let checksum_valid = !without_checksum.is_empty()
+            && checksum == AddressEncoder::calc_checksum(without_checksum);
+        let head_byte = without_checksum.first().cloned();
+        let network = head_byte.and_then(|b| NetworkPrefix::try_from(b & 0xF0).ok());
+        let address_type = head_byte.and_then(|b| AddressTypePrefix::try_from(b & 0xF).ok());
+        let content_bytes = without_checksum.get(1..).unwrap_or(&[]);
+        let content_length = content_bytes.len();
+        let content_length_valid = match address_type {
+            Some(AddressTypePrefix::P2Pk) => content_length == EcPoint::GROUP_SIZE,
+            Some(AddressTypePrefix::Pay2Sh) => content_length == Address::P2SH_SCRIPT_HASH_LENGTH,
+            Some(AddressTypePrefix::Pay2S) => content_length > 0,
+            None => false,
+        };
+        let mut ergo_tree_valid = None;
+        let mut is_p2pk = false;
+        let address = match address_type {
+            Some(AddressTypePrefix::P2Pk) => EcPoint::sigma_parse_bytes(content_bytes)
+                .ok()
+                .map(|pk| Address::P2Pk(ProveDlog::new(pk))),
+            Some(AddressTypePrefix::Pay2Sh) => {
+                <[u8; 24]>::try_from(content_bytes).ok().map(Address::P2SH)
+            }
+            Some(AddressTypePrefix::Pay2S) => {
+                let tree_valid = ErgoTree::sigma_parse_bytes(content_bytes)
+                    .map(|tree| {
+                        is_p2pk = matches!(
+                            Address::recreate_from_ergo_tree(&tree),
+                            Ok(Address::P2Pk(_))
+                        );
+                        tree.proposition().is_ok()
+                    })
+                    .unwrap_or(false);
+                ergo_tree_valid = Some(tree_valid);
+                Some(Address::P2S(content_bytes.to_vec()))
+            }
+            None => None,
+        };
+        if let Some(Address::P2Pk(_)) = address {
+            is_p2pk = true;
+        }
+        let other_network_address = match (network, &address) {
+            (Some(network), Some(address)) => {
+                let other_network = match network {
+                    NetworkPrefix::Mainnet => NetworkPrefix::Testnet,
+                    NetworkPrefix::Testnet => NetworkPrefix::Mainnet,
+                };
+                Some(AddressEncoder::encode_address_as_string(
+                    other_network,
+                    address,
+                ))
+            }
+            _ => None,
+        };
+        AddressInspection {
+            base58_error: None,
+            network,
+            address_type,
+            checksum_valid,
+            content_length,
+            content_length_valid,
+            ergo_tree_valid,
+            is_p2pk,
+            address,
+            other_network_address,
+        }
+    }
+}
+
 #[cfg(feature = "arbitrary")]
 #[allow(clippy::unwrap_used)]
 pub(crate) mod arbitrary {
@@ -568,10 +751,73 @@ mod tests {
             prop_assert_eq![recreated, v];
         }
 
+        #[test]
+        fn p2sh_script_matches(tree in any::<ErgoTree>()) {
+            let address = Address::p2sh_from_ergo_tree(&tree).unwrap();
+            prop_assert![address.p2sh_script_matches(&tree).unwrap()];
+            let recreated = Address::recreate_from_ergo_tree(&address.script().unwrap()).unwrap();
+            prop_assert_eq![recreated, address];
+        }
+
+        #[test]
+        fn inspect_valid_address(v in any::<Address>()) {
+            let encoded_addr = AddressEncoder::encode_address_as_string(NetworkPrefix::Mainnet, &v);
+            let inspection = AddressEncoder::inspect_address_str(&encoded_addr);
+            prop_assert![inspection.checksum_valid];
+            prop_assert![inspection.content_length_valid];
+            prop_assert_eq![inspection.network, Some(NetworkPrefix::Mainnet)];
+            prop_assert_eq![inspection.address_type, Some(v.address_type_prefix())];
+            prop_assert_eq![inspection.address, Some(v.clone())];
+            prop_assert_eq![
+                inspection.other_network_address,
+                Some(AddressEncoder::encode_address_as_string(NetworkPrefix::Testnet, &v))
+            ];
+        }
+
+        #[test]
+        fn inspect_doesnt_crash_on_invalid_input(s in "\\w+") {
+            prop_assert![!AddressEncoder::inspect_address_str(&s).is_valid()];
+        }
+
         #[test]
         fn doesnt_crash_on_invalid_input(s in "\\w+") {
             let encoder = AddressEncoder::new(NetworkPrefix::Testnet);
             prop_assert![encoder.parse_address_from_str(&s).is_err()];
         }
     }
+
+    #[test]
+    fn inspect_invalid_checksum() {
+        let mut bytes = bs58::decode("3WvsT2Gm4EpsM9Pg18PdY6XyhNNMqXDsvJTbbf6ihLvAmSb7u5RN")
+            .into_vec()
+            .unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let inspection = AddressEncoder::inspect_address_bytes(&bytes);
+        assert!(!inspection.checksum_valid);
+        assert!(!inspection.is_valid());
+        assert!(inspection.content_length_valid);
+        assert!(inspection.is_p2pk);
+        assert_eq!(inspection.network, Some(NetworkPrefix::Testnet));
+        assert_eq!(inspection.address_type, Some(AddressTypePrefix::P2Pk));
+        let other_network_address = AddressEncoder::new(NetworkPrefix::Mainnet)
+            .parse_address_from_str(&inspection.other_network_address.unwrap())
+            .unwrap();
+        assert_eq!(Some(other_network_address), inspection.address);
+    }
+
+    #[test]
+    fn inspect_p2s_with_p2pk_tree() {
+        let p2pk = AddressEncoder::unchecked_parse_address_from_str(
+            "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA",
+        )
+        .unwrap();
+        let p2s = Address::P2S(p2pk.script().unwrap().sigma_serialize_bytes().unwrap());
+        let inspection = AddressEncoder::inspect_address_str(
+            &AddressEncoder::encode_address_as_string(NetworkPrefix::Mainnet, &p2s),
+        );
+        assert!(inspection.is_valid());
+        assert_eq!(inspection.ergo_tree_valid, Some(true));
+        assert!(inspection.is_p2pk);
+    }
 }
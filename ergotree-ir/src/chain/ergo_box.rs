@@ -1,6 +1,7 @@
 //! Ergo box
 
 mod box_id;
+mod box_registers;
 pub mod box_value;
 mod register;
 
@@ -15,6 +16,7 @@ use crate::serialization::SigmaSerializeResult;
 use crate::util::AsVecI8;
 
 pub use box_id::*;
+pub use box_registers::*;
 pub use register::*;
 
 use indexmap::IndexSet;
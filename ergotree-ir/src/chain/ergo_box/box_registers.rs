@@ -0,0 +1,243 @@
+//! Typed access to the box registers
+
+use crate::mir::constant::Constant;
+use crate::mir::constant::Literal;
+use crate::mir::constant::TryExtractFrom;
+use crate::serialization::SigmaSerializable;
+use crate::types::stype::SType;
+use crate::util::AsVecI8;
+
+use super::ErgoBox;
+use super::ErgoBoxCandidate;
+use super::MandatoryRegisterId;
+use super::RegisterId;
+
+use thiserror::Error;
+
+/// Errors on the typed register access
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum RegisterValueError {
+    /// Register is empty
+    #[error("Register {0} is empty")]
+    EmptyRegister(RegisterId),
+    /// Register value has a type that cannot be extracted as the requested type
+    #[error("Register {register} has type {actual:?}, which cannot be extracted as {expected}")]
+    UnexpectedType {
+        /// Register id
+        register: RegisterId,
+        /// Actual type of the register value
+        actual: SType,
+        /// Requested type
+        expected: &'static str,
+    },
+}
+
+/// Box with registers (R0-R9)
+pub trait BoxRegisters {
+    /// Get register value
+    fn register(&self, id: RegisterId) -> Option<Constant>;
+
+    /// Get register value as `T`, failing if the register is empty or has a different type
+    fn get_register_as<T: TryExtractFrom<Literal>>(
+        &self,
+        id: RegisterId,
+    ) -> Result<T, RegisterValueError> {
+        self.get_register_opt_as(id)?
+            .ok_or(RegisterValueError::EmptyRegister(id))
+    }
+
+    /// Get register value as `T` (`None` if the register is empty), failing if the register
+    /// has a different type
+    fn get_register_opt_as<T: TryExtractFrom<Literal>>(
+        &self,
+        id: RegisterId,
+    ) -> Result<Option<T>, RegisterValueError> {
+        self.register(id)
+            .map(|c| {
+                let actual = c.tpe.clone();
+                T::try_extract_from(c.v).map_err(|_| RegisterValueError::UnexpectedType {
+                    register: id,
+                    actual,
+                    expected: std::any::type_name::<T>(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Map the register values into `T` (see [`FromBoxRegisters`])
+    fn registers_as<T: FromBoxRegisters>(&self) -> Result<T, RegisterValueError>
+    where
+        Self: Sized,
+    {
+        T::from_box_registers(self)
+    }
+}
+
+/// Type stored in (possibly several) box registers
+pub trait FromBoxRegisters: Sized {
+    /// Extract the value from the box registers
+    fn from_box_registers<B: BoxRegisters>(b: &B) -> Result<Self, RegisterValueError>;
+}
+
+impl BoxRegisters for ErgoBox {
+    fn register(&self, id: RegisterId) -> Option<Constant> {
+        self.get_register(id)
+    }
+}
+
+/// R3 (creation info) of the candidate is not available, since it includes the id of the
+/// transaction creating the box
+impl BoxRegisters for ErgoBoxCandidate {
+    fn register(&self, id: RegisterId) -> Option<Constant> {
+        match id {
+            RegisterId::MandatoryRegisterId(id) => match id {
+                MandatoryRegisterId::R0 => Some(self.value.into()),
+                // chance of box script is not serializable are tiny comparing to returning Result
+                #[allow(clippy::unwrap_used)]
+                MandatoryRegisterId::R1 => Some(
+                    self.ergo_tree
+                        .sigma_serialize_bytes()
+                        .unwrap()
+                        .as_vec_i8()
+                        .into(),
+                ),
+                MandatoryRegisterId::R2 => Some(
+                    self.tokens
+                        .iter()
+                        .cloned()
+                        .map(Into::into)
+                        .collect::<Vec<(Vec<i8>, i64)>>()
+                        .into(),
+                ),
+                MandatoryRegisterId::R3 => None,
+            },
+            RegisterId::NonMandatoryRegisterId(id) => self.additional_registers.get(id).cloned(),
+        }
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use super::super::NonMandatoryRegisterId;
+    use super::*;
+    use sigma_test_util::force_any_val;
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+
+    #[derive(PartialEq, Eq, Debug)]
+    struct Order {
+        keys: Vec<Vec<u8>>,
+        price: (i32, i64),
+        note: Option<Vec<u8>>,
+    }
+
+    impl FromBoxRegisters for Order {
+        fn from_box_registers<B: BoxRegisters>(b: &B) -> Result<Self, RegisterValueError> {
+            Ok(Order {
+                keys: b.get_register_as(NonMandatoryRegisterId::R4.into())?,
+                price: b.get_register_as(NonMandatoryRegisterId::R5.into())?,
+                note: b.get_register_opt_as(NonMandatoryRegisterId::R6.into())?,
+            })
+        }
+    }
+
+    fn candidate_with_registers(regs: Vec<(NonMandatoryRegisterId, Constant)>) -> ErgoBoxCandidate {
+        let mut b = force_any_val::<ErgoBoxCandidate>();
+        b.additional_registers = regs
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+            .try_into()
+            .unwrap();
+        b
+    }
+
+    #[test]
+    fn get_register_as_coll_coll_byte_and_tuple() {
+        let keys = vec![vec![1u8, 2], vec![3u8]];
+        let b = candidate_with_registers(vec![
+            (
+                NonMandatoryRegisterId::R4,
+                keys.iter()
+                    .map(|k| k.as_vec_i8())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            (NonMandatoryRegisterId::R5, (1i32, 2i64).into()),
+        ]);
+        assert_eq!(
+            b.get_register_as::<Vec<Vec<u8>>>(NonMandatoryRegisterId::R4.into()),
+            Ok(keys.clone())
+        );
+        assert_eq!(
+            b.get_register_as::<(i32, i64)>(NonMandatoryRegisterId::R5.into()),
+            Ok((1, 2))
+        );
+        assert_eq!(
+            b.registers_as::<Order>(),
+            Ok(Order {
+                keys,
+                price: (1, 2),
+                note: None
+            })
+        );
+    }
+
+    #[test]
+    fn get_register_as_wrong_type() {
+        let b = candidate_with_registers(vec![(NonMandatoryRegisterId::R4, 1i32.into())]);
+        let err = b
+            .get_register_as::<i64>(NonMandatoryRegisterId::R4.into())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RegisterValueError::UnexpectedType {
+                register: NonMandatoryRegisterId::R4.into(),
+                actual: SType::SInt,
+                expected: "i64",
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Register R4 has type SInt, which cannot be extracted as i64"
+        );
+    }
+
+    #[test]
+    fn get_register_as_empty() {
+        let b =
+            candidate_with_registers(vec![(NonMandatoryRegisterId::R4, vec![vec![1i8]].into())]);
+        assert_eq!(
+            b.registers_as::<Order>(),
+            Err(RegisterValueError::EmptyRegister(
+                NonMandatoryRegisterId::R5.into()
+            ))
+        );
+        assert_eq!(
+            b.get_register_opt_as::<i32>(NonMandatoryRegisterId::R5.into()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn get_register_as_mandatory() {
+        let c = force_any_val::<ErgoBoxCandidate>();
+        let b = ErgoBox::from_box_candidate(&c, force_any_val(), 0).unwrap();
+        assert_eq!(
+            c.get_register_as::<i64>(RegisterId::R0),
+            Ok(c.value.as_i64())
+        );
+        assert_eq!(
+            c.get_register_as::<Vec<(Vec<i8>, i64)>>(RegisterId::R2),
+            b.get_register_as::<Vec<(Vec<i8>, i64)>>(RegisterId::R2)
+        );
+        assert_eq!(
+            c.get_register_opt_as::<(i32, Vec<i8>)>(RegisterId::R3),
+            Ok(None)
+        );
+        assert_eq!(
+            b.get_register_as::<(i32, Vec<i8>)>(RegisterId::R3),
+            Ok(b.creation_info())
+        );
+    }
+}
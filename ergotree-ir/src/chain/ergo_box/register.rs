@@ -31,6 +31,27 @@ impl RegisterId {
     pub const R3: RegisterId = RegisterId::MandatoryRegisterId(MandatoryRegisterId::R3);
 }
 
+impl From<NonMandatoryRegisterId> for RegisterId {
+    fn from(id: NonMandatoryRegisterId) -> Self {
+        RegisterId::NonMandatoryRegisterId(id)
+    }
+}
+
+impl From<MandatoryRegisterId> for RegisterId {
+    fn from(id: MandatoryRegisterId) -> Self {
+        RegisterId::MandatoryRegisterId(id)
+    }
+}
+
+impl std::fmt::Display for RegisterId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterId::MandatoryRegisterId(id) => write!(f, "R{}", *id as u8),
+            RegisterId::NonMandatoryRegisterId(id) => write!(f, "R{}", *id as u8),
+        }
+    }
+}
+
 /// Register id out of bounds error (not in 0-9 range)
 #[derive(Error, PartialEq, Eq, Debug, Clone)]
 #[error("register id {0} is out of bounds (0 - 9)")]
@@ -207,6 +228,21 @@ impl NonMandatoryRegisters {
     pub fn get_ordered_values(&self) -> &Vec<Constant> {
         &self.0
     }
+
+    /// Register values with their types and human-readable values (explorer API JSON format)
+    #[cfg(feature = "json")]
+    pub fn to_rendered(
+        &self,
+    ) -> Result<
+        HashMap<NonMandatoryRegisterId, crate::chain::json::constant::RenderedConstant>,
+        crate::serialization::SigmaSerializationError,
+    > {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, c)| Ok((NonMandatoryRegisterId::get_by_zero_index(i), c.try_into()?)))
+            .collect()
+    }
 }
 
 /// Create new from ordered values (first element will be R4, and so on)
@@ -1,9 +1,15 @@
 //! Block header
+use std::io::Write;
+
 use num_bigint::BigInt;
+use sigma_ser::vlq_encode::WriteSigmaVlqExt;
 
+use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializationError;
 use crate::sigma_protocol::dlog_group;
 
 use super::block_id::BlockId;
+use super::digest32::blake2b256_hash;
 use super::digest32::{ADDigest, Digest32};
 use super::preheader::PreHeader;
 use super::votes::Votes;
@@ -62,6 +68,54 @@ pub struct Header {
 }
 
 impl Header {
+    /// Block version on launch (Autolykos v1 PoW)
+    pub const INITIAL_VERSION: u8 = 1;
+
+    /// Serialized header without the PoW solution (the message the PoW solution is found for),
+    /// same as `HeaderSerializer.bytesWithoutPow` in the node
+    pub fn serialize_without_pow(&self) -> Result<Vec<u8>, SigmaSerializationError> {
+        let mut w = Vec::new();
+        w.put_u8(self.version)?;
+        w.write_all(self.parent_id.0 .0.as_ref())?;
+        w.write_all(self.ad_proofs_root.0.as_ref())?;
+        w.write_all(self.transaction_root.0.as_ref())?;
+        w.write_all(self.state_root.0.as_ref())?;
+        w.put_u64(self.timestamp)?;
+        w.write_all(self.extension_root.0.as_ref())?;
+        // compact difficulty fits in 4 bytes (big-endian)
+        w.write_all(&(self.n_bits as u32).to_be_bytes())?;
+        w.put_u32(self.height)?;
+        w.write_all(&self.votes.0)?;
+        if self.version > Header::INITIAL_VERSION {
+            // number of the fields added in the later versions (none yet)
+            w.put_u8(0)?;
+        }
+        Ok(w)
+    }
+
+    /// Serialized PoW solution (Autolykos v1 for the initial version, Autolykos v2 otherwise)
+    fn serialize_pow_solution(&self) -> Result<Vec<u8>, SigmaSerializationError> {
+        let mut w = Vec::new();
+        w.write_all(&self.miner_pk.sigma_serialize_bytes()?)?;
+        if self.version == Header::INITIAL_VERSION {
+            w.write_all(&self.pow_onetime_pk.sigma_serialize_bytes()?)?;
+            w.write_all(&self.nonce)?;
+            let (_, d_bytes) = self.pow_distance.to_bytes_be();
+            w.put_u8(d_bytes.len() as u8)?;
+            w.write_all(&d_bytes)?;
+        } else {
+            w.write_all(&self.nonce)?;
+        }
+        Ok(w)
+    }
+
+    /// Calculate the header id (hash of the serialized header with the PoW solution)
+    pub fn calc_id(&self) -> Result<BlockId, SigmaSerializationError> {
+        let mut bytes = self.serialize_without_pow()?;
+        bytes.extend(self.serialize_pow_solution()?);
+        Ok(BlockId(blake2b256_hash(&bytes)))
+    }
+
     /// Dummy instance intended for tests where actual values are not used
     pub fn dummy() -> Self {
         let empty_digest = Digest32::zero();
@@ -177,7 +231,14 @@ mod arbitrary {
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 mod tests {
+    use std::convert::TryFrom;
+
+    use crate::chain::block_id::BlockId;
+    use crate::chain::digest32::{ADDigest, Digest32};
     use crate::chain::header::Header;
+    use crate::chain::votes::Votes;
+    use crate::serialization::SigmaSerializable;
+    use crate::sigma_protocol::dlog_group::EcPoint;
 
     #[test]
     fn parse_block_header() {
@@ -209,6 +270,59 @@ mod tests {
         assert_eq!(header.height, 471746);
     }
 
+    #[test]
+    fn calc_header_id() {
+        // mainnet header 4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b
+        let header = Header {
+            version: 2,
+            parent_id: BlockId(
+                Digest32::try_from(
+                    "6481752bace5fa5acba5d5ef7124d48826664742d46c974c98a2d60ace229a34".to_string(),
+                )
+                .unwrap(),
+            ),
+            ad_proofs_root: Digest32::try_from(
+                "d882aaf42e0a95eb95fcce5c3705adf758e591532f733efe790ac3c404730c39".to_string(),
+            )
+            .unwrap(),
+            state_root: ADDigest::try_from(
+                "8ad868627ea4f7de6e2a2fe3f98fafe57f914e0f2ef3331c006def36c697f92713".to_string(),
+            )
+            .unwrap(),
+            transaction_root: Digest32::try_from(
+                "63eaa9aff76a1de3d71c81e4b2d92e8d97ae572a8e9ab9e66599ed0912dd2f8b".to_string(),
+            )
+            .unwrap(),
+            timestamp: 1618929697400,
+            n_bits: 117586360,
+            height: 471746,
+            extension_root: Digest32::try_from(
+                "3f91f3c680beb26615fdec251aee3f81aaf5a02740806c167c0f3c929471df44".to_string(),
+            )
+            .unwrap(),
+            miner_pk: EcPoint::sigma_parse_bytes(
+                &base16::decode(
+                    "02b3a06d6eaa8671431ba1db4dd427a77f75a5c2acbd71bfb725d38adc2b55f669",
+                )
+                .unwrap(),
+            )
+            .unwrap()
+            .into(),
+            nonce: base16::decode("5939ecfee6b0d7f4").unwrap(),
+            votes: Votes([4, 0, 0]),
+            ..Header::dummy()
+        };
+        assert_eq!(
+            header.calc_id().unwrap(),
+            BlockId(
+                Digest32::try_from(
+                    "4caa17e62fe66ba7bd69597afdc996ae35b1ff12e0ba90c22ff288a4de10e91b".to_string(),
+                )
+                .unwrap()
+            )
+        );
+    }
+
     #[test]
     fn parse_block_header_explorer_v1() {
         // see https://api.ergoplatform.com/api/v1/blocks/de68a9cd727510d01eae3146f862261661f3bebdfd3c45c19d431b2ae81fb4b6
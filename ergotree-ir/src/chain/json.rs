@@ -2,6 +2,7 @@
 
 use serde::Serializer;
 
+pub mod constant;
 pub(crate) mod ergo_box;
 pub mod ergo_tree;
 
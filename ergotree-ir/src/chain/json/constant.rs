@@ -0,0 +1,70 @@
+//! Constant with its type and human-readable value (explorer API format)
+
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chain::base16_bytes::Base16EncodedBytes;
+use crate::mir::constant::render_stype;
+use crate::mir::constant::Constant;
+use crate::serialization::SigmaSerializable;
+use crate::serialization::SigmaSerializationError;
+
+/// Constant with its type and human-readable value, e.g.
+/// `{"serializedValue": "0500", "sigmaType": "SLong", "renderedValue": "0"}`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct RenderedConstant {
+    /// Serialized constant (type and value)
+    #[serde(rename = "serializedValue")]
+    pub serialized_value: Base16EncodedBytes,
+    /// Type (see [`render_stype`])
+    #[serde(rename = "sigmaType")]
+    pub sigma_type: String,
+    /// Value (see [`Constant::to_rendered_string`])
+    #[serde(rename = "renderedValue")]
+    pub rendered_value: String,
+}
+
+impl TryFrom<&Constant> for RenderedConstant {
+    type Error = SigmaSerializationError;
+
+    fn try_from(c: &Constant) -> Result<Self, Self::Error> {
+        Ok(RenderedConstant {
+            serialized_value: Base16EncodedBytes::new(&c.sigma_serialize_bytes()?),
+            sigma_type: render_stype(&c.tpe),
+            rendered_value: c.to_rendered_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_constant_json() {
+        let c: Constant = 0i64.into();
+        let j = serde_json::to_string(&RenderedConstant::try_from(&c).unwrap()).unwrap();
+        assert_eq!(
+            j,
+            r#"{"serializedValue":"0500","sigmaType":"SLong","renderedValue":"0"}"#
+        );
+    }
+
+    #[test]
+    fn rendered_option_constant_json() {
+        let some: Constant = Some(1i32).into();
+        let j = serde_json::to_string(&RenderedConstant::try_from(&some).unwrap()).unwrap();
+        assert_eq!(
+            j,
+            r#"{"serializedValue":"280102","sigmaType":"Option[SInt]","renderedValue":"Some(1)"}"#
+        );
+        let none: Constant = Option::<i32>::None.into();
+        let j = serde_json::to_string(&RenderedConstant::try_from(&none).unwrap()).unwrap();
+        assert_eq!(
+            j,
+            r#"{"serializedValue":"2800","sigmaType":"Option[SInt]","renderedValue":"None"}"#
+        );
+    }
+}
@@ -5,7 +5,9 @@ use crate::chain::ergo_box::NonMandatoryRegisters;
 use crate::chain::token::Token;
 use crate::chain::tx_id::TxId;
 use crate::ergo_tree::ErgoTree;
+use crate::mir::constant::parse_rendered_stype;
 use crate::mir::constant::Constant;
+use crate::mir::constant::RenderedValueParsingError;
 use crate::serialization::SigmaParsingError;
 use crate::serialization::SigmaSerializable;
 use crate::serialization::SigmaSerializationError;
@@ -74,11 +76,45 @@ impl From<ConstantHolder> for Constant {
 }
 
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(try_from = "RichConstantJson")]
 struct RichConstant {
-    #[serde(rename = "rawValue", alias = "serializedValue")]
     raw_value: ConstantWrapper,
 }
 
+/// Serialized value or, if missing, the rendered value with its type
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+struct RichConstantJson {
+    #[serde(rename = "rawValue", alias = "serializedValue", default)]
+    raw_value: Option<ConstantWrapper>,
+    #[serde(rename = "sigmaType", alias = "valueType", default)]
+    sigma_type: Option<String>,
+    #[serde(rename = "renderedValue", alias = "decodedValue", default)]
+    rendered_value: Option<String>,
+}
+
+impl TryFrom<RichConstantJson> for RichConstant {
+    type Error = ConstantParsingError;
+
+    fn try_from(json: RichConstantJson) -> Result<Self, Self::Error> {
+        let raw_value = match json {
+            RichConstantJson {
+                raw_value: Some(raw_value),
+                ..
+            } => raw_value,
+            RichConstantJson {
+                sigma_type: Some(sigma_type),
+                rendered_value: Some(rendered_value),
+                ..
+            } => {
+                let tpe = parse_rendered_stype(&sigma_type)?;
+                ConstantWrapper(Constant::from_rendered_str(tpe, &rendered_value)?)
+            }
+            _ => return Err(ConstantParsingError::MissingValue),
+        };
+        Ok(RichConstant { raw_value })
+    }
+}
+
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
 #[serde(try_from = "Base16DecodedBytes")]
 struct ConstantWrapper(Constant);
@@ -89,6 +125,10 @@ pub enum ConstantParsingError {
     DecodeError(base16::DecodeError),
     #[error("Deserialization error: {0}")]
     DeserializationError(SigmaParsingError),
+    #[error("Rendered value parsing error: {0}")]
+    RenderedValueParsingError(RenderedValueParsingError),
+    #[error("Neither serialized nor rendered value (with its type) is found")]
+    MissingValue,
 }
 
 impl TryFrom<Base16DecodedBytes> for ConstantWrapper {
@@ -161,12 +201,14 @@ where
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 mod tests {
+    use std::convert::TryFrom;
     use std::convert::TryInto;
 
     use crate::chain::ergo_box::ErgoBox;
     use crate::chain::ergo_box::NonMandatoryRegisterId;
     use crate::chain::ergo_box::NonMandatoryRegisters;
     use crate::chain::token::Token;
+    use crate::mir::constant::Constant;
     use pretty_assertions::assert_eq;
     use proptest::prelude::*;
 
@@ -191,6 +233,18 @@ mod tests {
         assert_eq!(regs.get_ordered_values().len(), 2)
     }
 
+    #[test]
+    fn parse_option_registers() {
+        let json = r#"
+        {"R4":"280102","R5":"2800"}
+        "#;
+        let regs: NonMandatoryRegisters = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            regs.get_ordered_values(),
+            &vec![Some(1i32).into(), Option::<i32>::None.into()]
+        );
+    }
+
     #[test]
     fn parse_registers_explorer_api_v1() {
         let json = r#"
@@ -215,6 +269,46 @@ mod tests {
         assert!(regs.get(NonMandatoryRegisterId::R4).is_some());
     }
 
+    #[test]
+    fn parse_registers_rendered_value_only() {
+        let json = r#"
+            {
+                "R4": {"sigmaType": "(SInt, Coll[SByte])", "renderedValue": "(10,Coll[Byte](01ff))"},
+                "R5": {"valueType": "Coll[Byte]", "decodedValue": "Coll(-89,30)"}
+            }
+        "#;
+        let regs: NonMandatoryRegisters = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            regs.get(NonMandatoryRegisterId::R4).unwrap(),
+            &(10i32, vec![1u8, 255u8]).into()
+        );
+        assert_eq!(
+            regs.get(NonMandatoryRegisterId::R5).unwrap(),
+            &vec![-89i8, 30i8].into()
+        );
+    }
+
+    #[test]
+    fn rendered_registers_roundtrip() {
+        let regs = NonMandatoryRegisters::try_from(vec![
+            Constant::from(1i64),
+            Constant::from(Some(vec![1i32, 2])),
+        ])
+        .unwrap();
+        let rendered = regs.to_rendered().unwrap();
+        assert_eq!(
+            rendered[&NonMandatoryRegisterId::R5].rendered_value,
+            "Some(Coll(1,2))"
+        );
+        assert_eq!(
+            rendered[&NonMandatoryRegisterId::R5].sigma_type,
+            "Option[Coll[SInt]]"
+        );
+        let json = serde_json::to_string(&rendered).unwrap();
+        let parsed: NonMandatoryRegisters = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, regs);
+    }
+
     #[test]
     fn parse_registers_error() {
         let json = r#"
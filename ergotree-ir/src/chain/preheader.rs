@@ -2,6 +2,7 @@
 
 use crate::sigma_protocol::dlog_group;
 
+use super::header::Header;
 use super::{block_id::BlockId, digest32::Digest32, votes::Votes};
 
 /// Block header with the current `spendingTransaction`, that can be predicted
@@ -37,6 +38,22 @@ impl PreHeader {
             votes: Votes([0u8; 3]),
         }
     }
+
+    /// Pre-header of the block following the given one (its id as the parent id, height + 1).
+    /// Version, timestamp, difficulty, miner public key and votes are copied from the given
+    /// header and might be replaced when known. Returns `None` if the given header is at the
+    /// maximal height.
+    pub fn for_next_block(parent: &Header) -> Option<Self> {
+        Some(PreHeader {
+            version: parent.version,
+            parent_id: parent.id.clone(),
+            timestamp: parent.timestamp,
+            n_bits: parent.n_bits,
+            height: parent.height.checked_add(1)?,
+            miner_pk: parent.miner_pk.clone(),
+            votes: parent.votes.clone(),
+        })
+    }
 }
 
 #[cfg(feature = "arbitrary")]
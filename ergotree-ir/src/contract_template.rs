@@ -0,0 +1,349 @@
+//! Contract templates (ErgoTree with named parameters)
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::chain::digest32::blake2b256_hash;
+use crate::chain::digest32::Digest32;
+use crate::ergo_tree::ErgoTree;
+use crate::ergo_tree::ErgoTreeError;
+use crate::mir::constant::Constant;
+use crate::types::stype::SType;
+use thiserror::Error;
+
+/// Named parameter of the contract template (segregated constant of the tree)
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ContractParameter {
+    /// Parameter name
+    pub name: String,
+    /// Index of the constant in the tree constants (as stored in serialized ErgoTree)
+    pub constant_index: usize,
+    /// Value used on instantiation if the parameter is not provided
+    pub default_value: Option<Constant>,
+}
+
+impl ContractParameter {
+    /// Create a parameter without default value
+    pub fn new(name: &str, constant_index: usize) -> Self {
+        ContractParameter {
+            name: name.to_string(),
+            constant_index,
+            default_value: None,
+        }
+    }
+
+    /// Set the value used on instantiation if the parameter is not provided
+    pub fn with_default(self, default_value: Constant) -> Self {
+        ContractParameter {
+            default_value: Some(default_value),
+            ..self
+        }
+    }
+}
+
+/// Errors on contract template creation, instantiation
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum ContractTemplateError {
+    /// Tree does not use constant segregation, so it has no parameters
+    #[error("ErgoTree has no segregated constants")]
+    NoConstantSegregation,
+    /// Tree cannot be parsed
+    #[error("ErgoTree error: {0}")]
+    ErgoTreeError(#[from] ErgoTreeError),
+    /// Parameter constant index is out of bounds
+    #[error("Parameter {name}: constant index {index} is out of bounds (constants: {len})")]
+    IndexOutOfBounds {
+        /// Parameter name
+        name: String,
+        /// Constant index
+        index: usize,
+        /// Number of constants in the tree
+        len: usize,
+    },
+    /// Parameter name or constant index is used by more than one parameter
+    #[error("Parameter {0} is defined more than once (by name or constant index)")]
+    DuplicateParameter(String),
+    /// Parameter value has a type different from the type of the constant in the tree
+    #[error("Parameter {name}: expected type {expected:?}, got {actual:?}")]
+    TypeMismatch {
+        /// Parameter name
+        name: String,
+        /// Type of the constant in the tree
+        expected: Box<SType>,
+        /// Type of the provided value
+        actual: Box<SType>,
+    },
+    /// Parameter without default value is not provided
+    #[error("Parameter {0} is not provided and has no default value")]
+    MissingParameter(String),
+    /// Provided value does not correspond to any parameter
+    #[error("Unknown parameter {0}")]
+    UnknownParameter(String),
+}
+
+/// Contract template, i.e. the tree with the constants, some of which are named parameters.
+/// All the contract instances share the same template bytes (root expression with constant
+/// placeholders), and the same values of the constants which are not parameters.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ContractTemplate {
+    tree: ErgoTree,
+    template_bytes: Vec<u8>,
+    constants: Vec<Constant>,
+    parameters: Vec<ContractParameter>,
+}
+
+impl ContractTemplate {
+    /// Create a template from the contract instance (the tree must use constant segregation).
+    /// Constants which are not parameters keep their values in every instance.
+    pub fn new(
+        tree: ErgoTree,
+        parameters: Vec<ContractParameter>,
+    ) -> Result<Self, ContractTemplateError> {
+        let constants = tree_constants(&tree)?;
+        let template_bytes = tree.template_bytes()?;
+        let mut names = HashSet::new();
+        let mut indices = HashSet::new();
+        for p in &parameters {
+            if !names.insert(p.name.clone()) || !indices.insert(p.constant_index) {
+                return Err(ContractTemplateError::DuplicateParameter(p.name.clone()));
+            }
+            let tpe = constants
+                .get(p.constant_index)
+                .map(|c| c.tpe.clone())
+                .ok_or_else(|| ContractTemplateError::IndexOutOfBounds {
+                    name: p.name.clone(),
+                    index: p.constant_index,
+                    len: constants.len(),
+                })?;
+            if let Some(default_value) = &p.default_value {
+                check_type(&p.name, &tpe, default_value)?;
+            }
+        }
+        Ok(ContractTemplate {
+            tree,
+            template_bytes,
+            constants,
+            parameters,
+        })
+    }
+
+    /// Serialized root expression with constant placeholders
+    pub fn template_bytes(&self) -> &[u8] {
+        &self.template_bytes
+    }
+
+    /// Blake2b256 hash of the template bytes, identifying the contract instances
+    pub fn template_hash(&self) -> Digest32 {
+        blake2b256_hash(&self.template_bytes)
+    }
+
+    /// Template parameters
+    pub fn parameters(&self) -> &[ContractParameter] {
+        &self.parameters
+    }
+
+    /// Type of the parameter with the given name
+    pub fn parameter_type(&self, name: &str) -> Option<SType> {
+        self.parameters
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| self.constants.get(p.constant_index))
+            .map(|c| c.tpe.clone())
+    }
+
+    /// Create the contract instance with the given parameter values (the ones not provided
+    /// take their default values)
+    pub fn instantiate(
+        &self,
+        values: &HashMap<String, Constant>,
+    ) -> Result<ErgoTree, ContractTemplateError> {
+        if let Some(unknown) = values
+            .keys()
+            .find(|name| !self.parameters.iter().any(|p| &p.name == *name))
+        {
+            return Err(ContractTemplateError::UnknownParameter(unknown.clone()));
+        }
+        self.parameters
+            .iter()
+            .try_fold(self.tree.clone(), |tree, p| {
+                let value = values
+                    .get(&p.name)
+                    .or(p.default_value.as_ref())
+                    .ok_or_else(|| ContractTemplateError::MissingParameter(p.name.clone()))?;
+                check_type(&p.name, &self.constants[p.constant_index].tpe, value)?;
+                // index and type are checked above
+                #[allow(clippy::unwrap_used)]
+                Ok(tree.with_constant(p.constant_index, value.clone()).unwrap())
+            })
+    }
+
+    /// Parameter values of the contract instance, or `None` if the tree is not an instance of
+    /// this template (template bytes or non-parameter constants differ)
+    pub fn match_tree(&self, tree: &ErgoTree) -> Option<HashMap<String, Constant>> {
+        if tree.template_bytes().ok()? != self.template_bytes {
+            return None;
+        }
+        let constants = tree_constants(tree).ok()?;
+        if constants.len() != self.constants.len() {
+            return None;
+        }
+        let is_parameter = |i: usize| self.parameters.iter().any(|p| p.constant_index == i);
+        let fixed_constants_match =
+            constants
+                .iter()
+                .zip(self.constants.iter())
+                .enumerate()
+                .all(|(i, (c, template_c))| {
+                    if is_parameter(i) {
+                        c.tpe == template_c.tpe
+                    } else {
+                        c == template_c
+                    }
+                });
+        if !fixed_constants_match {
+            return None;
+        }
+        Some(
+            self.parameters
+                .iter()
+                .map(|p| (p.name.clone(), constants[p.constant_index].clone()))
+                .collect(),
+        )
+    }
+}
+
+fn tree_constants(tree: &ErgoTree) -> Result<Vec<Constant>, ContractTemplateError> {
+    let len = tree.constants_len().map_err(ErgoTreeError::from)?;
+    if len == 0 {
+        return Err(ContractTemplateError::NoConstantSegregation);
+    }
+    let constants = (0..len)
+        .map(|i| tree.get_constant(i))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ErgoTreeError::from)?;
+    // all indices are in bounds
+    Ok(constants.into_iter().flatten().collect())
+}
+
+fn check_type(name: &str, expected: &SType, value: &Constant) -> Result<(), ContractTemplateError> {
+    if &value.tpe == expected {
+        Ok(())
+    } else {
+        Err(ContractTemplateError::TypeMismatch {
+            name: name.to_string(),
+            expected: expected.clone().into(),
+            actual: value.tpe.clone().into(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::serialization::SigmaSerializable;
+
+    // DEX pool contract from ergo_tree::tests::dex_t2tpool_parse
+    const DEX_POOL_TREE: &str = "19a3030f0400040204020404040404060406058080a0f6f4acdbe01b058080a0f6f4acdbe01b050004d00f0400040005000500d81ad601b2a5730000d602e4c6a70405d603db63087201d604db6308a7d605b27203730100d606b27204730200d607b27203730300d608b27204730400d609b27203730500d60ab27204730600d60b9973078c720602d60c999973088c720502720bd60d8c720802d60e998c720702720dd60f91720e7309d6108c720a02d6117e721006d6127e720e06d613998c7209027210d6147e720d06d615730ad6167e721306d6177e720c06d6187e720b06d6199c72127218d61a9c72167218d1edededededed93c27201c2a793e4c672010405720292c17201c1a793b27203730b00b27204730c00938c7205018c720601ed938c7207018c720801938c7209018c720a019593720c730d95720f929c9c721172127e7202069c7ef07213069a9c72147e7215067e9c720e720206929c9c721472167e7202069c7ef0720e069a9c72117e7215067e9c721372020695ed720f917213730e907217a19d721972149d721a7211ed9272199c7217721492721a9c72177211";
+
+    fn dex_pool_template() -> ContractTemplate {
+        let tree = ErgoTree::sigma_parse_bytes(&base16::decode(DEX_POOL_TREE.as_bytes()).unwrap())
+            .unwrap();
+        ContractTemplate::new(
+            tree,
+            vec![
+                ContractParameter::new("a", 7),
+                ContractParameter::new("b", 8).with_default(5i64.into()),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn instantiate_and_match() {
+        let template = dex_pool_template();
+        assert_eq!(template.parameter_type("a"), Some(SType::SLong));
+        let values: HashMap<String, Constant> =
+            vec![("a".to_string(), 1i64.into())].into_iter().collect();
+        let tree = template.instantiate(&values).unwrap();
+        assert_eq!(tree.get_constant(7).unwrap().unwrap(), 1i64.into());
+        assert_eq!(tree.get_constant(8).unwrap().unwrap(), 5i64.into());
+        assert_eq!(tree.template_bytes().unwrap(), template.template_bytes());
+        // roundtrip through serialization, as a scanner would see the tree
+        let parsed_tree =
+            ErgoTree::sigma_parse_bytes(&tree.sigma_serialize_bytes().unwrap()).unwrap();
+        let extracted = template.match_tree(&parsed_tree).unwrap();
+        assert_eq!(extracted.get("a"), Some(&1i64.into()));
+        assert_eq!(extracted.get("b"), Some(&5i64.into()));
+    }
+
+    #[test]
+    fn match_tree_with_different_fixed_constant() {
+        let template = dex_pool_template();
+        let tree = template
+            .tree
+            .clone()
+            .with_constant(0, 42i32.into())
+            .unwrap();
+        assert_eq!(template.match_tree(&tree), None);
+    }
+
+    #[test]
+    fn instantiate_errors() {
+        let template = dex_pool_template();
+        let wrong_type: HashMap<String, Constant> =
+            vec![("a".to_string(), 1i32.into())].into_iter().collect();
+        assert_eq!(
+            template.instantiate(&wrong_type),
+            Err(ContractTemplateError::TypeMismatch {
+                name: "a".to_string(),
+                expected: SType::SLong.into(),
+                actual: SType::SInt.into(),
+            })
+        );
+        let unknown: HashMap<String, Constant> =
+            vec![("c".to_string(), 1i64.into())].into_iter().collect();
+        assert_eq!(
+            template.instantiate(&unknown),
+            Err(ContractTemplateError::UnknownParameter("c".to_string()))
+        );
+        assert_eq!(
+            template.instantiate(&HashMap::new()),
+            Err(ContractTemplateError::MissingParameter("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn new_errors() {
+        let tree = dex_pool_template().tree;
+        assert_eq!(
+            ContractTemplate::new(
+                tree.clone(),
+                vec![
+                    ContractParameter::new("a", 7),
+                    ContractParameter::new("b", 7)
+                ]
+            ),
+            Err(ContractTemplateError::DuplicateParameter("b".to_string()))
+        );
+        assert_eq!(
+            ContractTemplate::new(tree.clone(), vec![ContractParameter::new("a", 100)]),
+            Err(ContractTemplateError::IndexOutOfBounds {
+                name: "a".to_string(),
+                index: 100,
+                len: 15
+            })
+        );
+        assert_eq!(
+            ContractTemplate::new(
+                tree,
+                vec![ContractParameter::new("a", 7).with_default(true.into())]
+            ),
+            Err(ContractTemplateError::TypeMismatch {
+                name: "a".to_string(),
+                expected: SType::SLong.into(),
+                actual: SType::SBoolean.into(),
+            })
+        );
+    }
+}
@@ -1,7 +1,9 @@
 //! ErgoTree
+use crate::has_opcode::has_opcode;
 use crate::mir::constant::Constant;
 use crate::mir::constant::TryExtractFromError;
 use crate::mir::expr::Expr;
+use crate::serialization::op_code::OpCode;
 use crate::serialization::SigmaSerializationError;
 use crate::serialization::SigmaSerializeResult;
 use crate::serialization::{
@@ -22,13 +24,13 @@ use std::convert::TryFrom;
 use std::io;
 use std::io::Read;
 use std::io::Write;
-use std::rc::Rc;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 struct ParsedTree {
     constants: Vec<Constant>,
-    root: Result<Rc<Expr>, ErgoTreeRootParsingError>,
+    root: Result<Arc<Expr>, ErgoTreeRootParsingError>,
 }
 
 impl ParsedTree {
@@ -71,6 +73,7 @@ impl ParsedTree {
     ) -> Result<Vec<u8>, SigmaSerializationError> {
         let mut data = Vec::new();
         let mut w = SigmaByteWriter::new(&mut data, None);
+        w.set_tree_version(header.version());
         header.sigma_serialize(&mut w)?;
         if header.is_constant_segregation() {
             w.put_usize_as_u32_unwrapped(self.constants.len())?;
@@ -177,7 +180,7 @@ impl Default for ErgoTreeHeader {
 }
 
 /// ErgoTree version 0..=7, should fit in 3 bits
-#[derive(PartialEq, Eq, Debug, Clone, Into)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Into)]
 pub struct ErgoTreeVersion(u8);
 
 impl ErgoTreeVersion {
@@ -187,6 +190,10 @@ impl ErgoTreeVersion {
     pub const V0: Self = ErgoTreeVersion(0);
     /// Version 1 (size flag is mandatory)
     pub const V1: Self = ErgoTreeVersion(1);
+    /// Version 3 (`Option` values in constants)
+    pub const V3: Self = ErgoTreeVersion(3);
+    /// Latest supported version
+    pub const MAX_SUPPORTED: Self = ErgoTreeVersion::V3;
 
     /// Returns a value of the version bits from the given header byte.
     pub fn parse_version(header: &ErgoTreeHeader) -> ErgoTreeVersion {
@@ -243,19 +250,20 @@ impl ErgoTree {
         let mut buf = vec![0u8; size as usize];
         r.read_exact(buf.as_mut_slice())?;
         if let Ok((constants, mut tree_bytes)) =
-            ErgoTree::sigma_parse_tree_bytes(buf.as_mut_slice(), header.is_constant_segregation())
+            ErgoTree::sigma_parse_tree_bytes(buf.as_mut_slice(), &header)
         {
             let tree_bytes_copy = tree_bytes.clone();
             let mut tree_reader = SigmaByteReader::new(
                 Cursor::new(&mut tree_bytes[..]),
                 ConstantStore::new(constants.clone()),
             );
+            tree_reader.set_tree_version(header.version());
             match Expr::sigma_parse(&mut tree_reader) {
                 Ok(parsed) => Ok(ErgoTree {
                     header,
                     tree: Ok(ParsedTree {
                         constants,
-                        root: Ok(Rc::new(parsed)),
+                        root: Ok(Arc::new(parsed)),
                     }),
                 }),
                 Err(err) => Ok(ErgoTree {
@@ -291,10 +299,11 @@ impl ErgoTree {
 
     fn sigma_parse_tree_bytes(
         bytes: &mut [u8],
-        is_constant_segregation: bool,
+        header: &ErgoTreeHeader,
     ) -> Result<(Vec<Constant>, Vec<u8>), SigmaParsingError> {
         let mut r = SigmaByteReader::new(Cursor::new(&bytes), ConstantStore::empty());
-        let constants = if is_constant_segregation {
+        r.set_tree_version(header.version());
+        let constants = if header.is_constant_segregation() {
             ErgoTree::sigma_parse_constants(&mut r)?
         } else {
             vec![]
@@ -321,12 +330,33 @@ impl ErgoTree {
         Ok(constants)
     }
 
+    fn sigma_parse_unsized<R: SigmaByteRead>(
+        r: &mut R,
+        header: ErgoTreeHeader,
+    ) -> Result<Self, SigmaParsingError> {
+        let constants = if header.is_constant_segregation() {
+            ErgoTree::sigma_parse_constants(r)?
+        } else {
+            vec![]
+        };
+        r.set_constant_store(ConstantStore::new(constants.clone()));
+        let root = Expr::sigma_parse(r)?;
+        Ok(ErgoTree {
+            header,
+            tree: Ok(ParsedTree {
+                constants,
+                root: Ok(Arc::new(root)),
+            }),
+        })
+    }
+
     /// Creates a tree using provided header and root expression
     pub fn new(header: ErgoTreeHeader, expr: &Expr) -> Result<Self, ErgoTreeError> {
         Ok(if header.is_constant_segregation() {
             let mut data = Vec::new();
             let cs = ConstantStore::empty();
             let mut w = SigmaByteWriter::new(&mut data, Some(cs));
+            w.set_tree_version(header.version());
             expr.sigma_serialize(&mut w)?;
             #[allow(clippy::unwrap_used)]
             // We set constant store earlier
@@ -334,6 +364,7 @@ impl ErgoTree {
             let cursor = Cursor::new(&mut data[..]);
             let new_cs = ConstantStore::new(constants.clone());
             let mut sr = SigmaByteReader::new(cursor, new_cs);
+            sr.set_tree_version(header.version());
             let parsed_expr =
                 Expr::sigma_parse(&mut sr).map_err(|error| ErgoTreeRootParsingError {
                     root_expr_bytes: data,
@@ -343,7 +374,7 @@ impl ErgoTree {
                 header: ErgoTreeHeader(ErgoTreeHeader::CONSTANT_SEGREGATION_FLAG | header.0),
                 tree: Ok(ParsedTree {
                     constants,
-                    root: Ok(Rc::new(parsed_expr)),
+                    root: Ok(Arc::new(parsed_expr)),
                 }),
             }
         } else {
@@ -351,17 +382,22 @@ impl ErgoTree {
                 header,
                 tree: Ok(ParsedTree {
                     constants: Vec::new(),
-                    root: Ok(Rc::new(expr.clone())),
+                    root: Ok(Arc::new(expr.clone())),
                 }),
             }
         })
     }
 
+    /// Tree header
+    pub fn header(&self) -> &ErgoTreeHeader {
+        &self.header
+    }
+
     /// Reasonable limit for the number of constants allowed in the ErgoTree
     pub const MAX_CONSTANTS_COUNT: usize = 4096;
 
     /// get Expr out of ErgoTree
-    pub fn proposition(&self) -> Result<Rc<Expr>, ErgoTreeError> {
+    pub fn proposition(&self) -> Result<Arc<Expr>, ErgoTreeError> {
         let tree = self
             .tree
             .clone()
@@ -374,18 +410,20 @@ impl ErgoTree {
             let mut data = Vec::new();
             let cs = ConstantStore::empty();
             let mut w = SigmaByteWriter::new(&mut data, Some(cs));
+            w.set_tree_version(self.header.version());
             root.sigma_serialize(&mut w)?;
             let cursor = Cursor::new(&mut data[..]);
             let mut sr = SigmaByteReader::new_with_substitute_placeholders(
                 cursor,
                 ConstantStore::new(tree.constants),
             );
+            sr.set_tree_version(self.header.version());
             let parsed_expr =
                 Expr::sigma_parse(&mut sr).map_err(|error| ErgoTreeRootParsingError {
                     root_expr_bytes: data,
                     error,
                 })?;
-            Ok(Rc::new(parsed_expr))
+            Ok(Arc::new(parsed_expr))
         } else {
             Ok(root)
         }
@@ -445,6 +483,17 @@ impl ErgoTree {
     pub fn template_bytes(&self) -> Result<Vec<u8>, ErgoTreeError> {
         self.clone().tree?.template_bytes()
     }
+
+    /// Returns true if the tree has `DeserializeContext` or `DeserializeRegister` nodes, i.e.
+    /// the script executed may differ from the one in the tree
+    pub fn has_deserialize(&self) -> Result<bool, ErgoTreeError> {
+        let tree = self.tree.clone()?;
+        let root = tree.root?;
+        Ok(has_opcode(
+            root.as_ref(),
+            &[OpCode::DESERIALIZE_CONTEXT, OpCode::DESERIALIZE_REGISTER],
+        ))
+    }
 }
 
 /// Constants related errors
@@ -496,20 +545,12 @@ impl SigmaSerializable for ErgoTree {
             let tree_size_bytes = r.get_u32()?;
             ErgoTree::sigma_parse_sized(r, header, tree_size_bytes)
         } else {
-            let constants = if header.is_constant_segregation() {
-                ErgoTree::sigma_parse_constants(r)?
-            } else {
-                vec![]
-            };
-            r.set_constant_store(ConstantStore::new(constants.clone()));
-            let root = Expr::sigma_parse(r)?;
-            Ok(ErgoTree {
-                header,
-                tree: Ok(ParsedTree {
-                    constants,
-                    root: Ok(Rc::new(root)),
-                }),
-            })
+            // values following the tree (e.g. box registers) are parsed with the outer version
+            let outer_tree_version = r.tree_version();
+            r.set_tree_version(header.version());
+            let tree = ErgoTree::sigma_parse_unsized(r, header);
+            r.set_tree_version(outer_tree_version);
+            tree
         }
     }
 
@@ -621,6 +662,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn option_data_before_v3() {
+        let expr: Expr = Constant::from(Some(1i32)).into();
+        let tree = ErgoTree::new(ErgoTreeHeader::v1(false), &expr).unwrap();
+        assert!(tree.sigma_serialize_bytes().is_err());
+        let tree = ErgoTree::new(ErgoTreeHeader::v1(true), &expr).unwrap();
+        assert!(tree.sigma_serialize_bytes().is_err());
+        // no constant segregation, root is an Option constant
+        let bytes = [ErgoTreeHeader::v0(false).into(), 40, 1, 2];
+        let tree = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        assert!(tree.tree.unwrap().root.is_err(), "parsing root should fail");
+    }
+
+    #[test]
+    fn option_data_v3() {
+        let expr: Expr = Constant::from(Some(1i32)).into();
+        // version 3 with size flag set
+        let header = ErgoTreeHeader::from(0x0b);
+        assert_eq!(header.version(), ErgoTreeVersion::V3);
+        let tree = ErgoTree::new(header.clone(), &expr).unwrap();
+        let bytes = tree.sigma_serialize_bytes().unwrap();
+        assert_eq!(bytes, vec![0x0b, 3, 40, 1, 2]);
+        let parsed = ErgoTree::sigma_parse_bytes(&bytes).unwrap();
+        assert_eq!(parsed.proposition().unwrap(), Arc::new(expr.clone()));
+        let tree = ErgoTree::new(header, &expr).unwrap();
+        assert_eq!(
+            ErgoTree::sigma_parse_bytes(&tree.sigma_serialize_bytes().unwrap()).unwrap(),
+            tree
+        );
+    }
+
     #[test]
     fn deserialization_non_parseable_tree_v0() {
         // constants length is set, invalid constant
@@ -772,6 +844,38 @@ mod tests {
         assert_eq!(new_ergo_tree.get_constant(0).unwrap().unwrap(), true.into());
     }
 
+    #[test]
+    fn test_has_deserialize() {
+        let deserialize: Expr = crate::mir::deserialize_context::DeserializeContext {
+            tpe: SType::SBoolean,
+            id: 1,
+        }
+        .into();
+        // nested in a function body to check the whole tree is searched
+        let expr: Expr = crate::mir::bool_to_sigma::BoolToSigmaProp {
+            input: Box::new(
+                crate::mir::coll_exists::Exists::new(
+                    vec![1i32].into(),
+                    crate::mir::func_value::FuncValue::new(
+                        vec![crate::mir::func_value::FuncArg {
+                            idx: 1.into(),
+                            tpe: SType::SInt,
+                        }],
+                        deserialize,
+                    )
+                    .into(),
+                )
+                .unwrap()
+                .into(),
+            ),
+        }
+        .into();
+        let tree = ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap();
+        assert!(tree.has_deserialize().unwrap());
+        let tree = ErgoTree::new(ErgoTreeHeader::v0(true), &Expr::Const(true.into())).unwrap();
+        assert!(!tree.has_deserialize().unwrap());
+    }
+
     #[test]
     fn dex_t2tpool_parse() {
         let base16_str = "19a3030f0400040204020404040404060406058080a0f6f4acdbe01b058080a0f6f4acdbe01b050004d00f0400040005000500d81ad601b2a5730000d602e4c6a70405d603db63087201d604db6308a7d605b27203730100d606b27204730200d607b27203730300d608b27204730400d609b27203730500d60ab27204730600d60b9973078c720602d60c999973088c720502720bd60d8c720802d60e998c720702720dd60f91720e7309d6108c720a02d6117e721006d6127e720e06d613998c7209027210d6147e720d06d615730ad6167e721306d6177e720c06d6187e720b06d6199c72127218d61a9c72167218d1edededededed93c27201c2a793e4c672010405720292c17201c1a793b27203730b00b27204730c00938c7205018c720601ed938c7207018c720801938c7209018c720a019593720c730d95720f929c9c721172127e7202069c7ef07213069a9c72147e7215067e9c720e720206929c9c721472167e7202069c7ef0720e069a9c72117e7215067e9c721372020695ed720f917213730e907217a19d721972149d721a7211ed9272199c7217721492721a9c72177211";
@@ -1,3 +1,6 @@
+use crate::mir::expr::Expr;
+use crate::mir::visitor::visit;
+use crate::mir::visitor::Visitor;
 use crate::serialization::op_code::OpCode;
 
 pub(crate) trait HasStaticOpCode {
@@ -13,3 +16,98 @@ impl<T: HasStaticOpCode> HasOpCode for T {
         T::OP_CODE
     }
 }
+
+/// Op code of the expression root node (constants have `OpCode::CONSTANT_CODE`, since they are
+/// serialized with their type code instead)
+impl HasOpCode for Expr {
+    fn op_code(&self) -> OpCode {
+        match self {
+            Expr::Const(_) => OpCode::CONSTANT_CODE,
+            Expr::ConstPlaceholder(op) => op.op_code(),
+            Expr::Context => OpCode::CONTEXT,
+            Expr::Global => OpCode::GLOBAL,
+            Expr::GlobalVars(op) => op.op_code(),
+            Expr::Append(op) => op.op_code(),
+            Expr::SubstConstants(op) => op.op_code(),
+            Expr::ByteArrayToLong(op) => op.op_code(),
+            Expr::ByteArrayToBigInt(op) => op.op_code(),
+            Expr::LongToByteArray(op) => op.op_code(),
+            Expr::Collection(op) => op.op_code(),
+            Expr::Tuple(op) => op.op_code(),
+            Expr::CalcBlake2b256(op) => op.op_code(),
+            Expr::CalcSha256(op) => op.op_code(),
+            Expr::FuncValue(op) => op.op_code(),
+            Expr::Apply(op) => op.op_code(),
+            Expr::MethodCall(op) => op.op_code(),
+            Expr::ProperyCall(op) => op.op_code(),
+            Expr::BlockValue(op) => op.op_code(),
+            Expr::ValDef(op) => op.op_code(),
+            Expr::ValUse(op) => op.op_code(),
+            Expr::If(op) => op.op_code(),
+            Expr::BinOp(op) => op.op_code(),
+            Expr::And(op) => op.op_code(),
+            Expr::Or(op) => op.op_code(),
+            Expr::Xor(op) => op.op_code(),
+            Expr::Atleast(op) => op.op_code(),
+            Expr::LogicalNot(op) => op.op_code(),
+            Expr::Negation(op) => op.op_code(),
+            Expr::BitInversion(op) => op.op_code(),
+            Expr::OptionGet(op) => op.op_code(),
+            Expr::OptionIsDefined(op) => op.op_code(),
+            Expr::OptionGetOrElse(op) => op.op_code(),
+            Expr::ExtractAmount(op) => op.op_code(),
+            Expr::ExtractRegisterAs(op) => op.op_code(),
+            Expr::ExtractBytes(op) => op.op_code(),
+            Expr::ExtractBytesWithNoRef(op) => op.op_code(),
+            Expr::ExtractScriptBytes(op) => op.op_code(),
+            Expr::ExtractCreationInfo(op) => op.op_code(),
+            Expr::ExtractId(op) => op.op_code(),
+            Expr::ByIndex(op) => op.op_code(),
+            Expr::SizeOf(op) => op.op_code(),
+            Expr::Slice(op) => op.op_code(),
+            Expr::Fold(op) => op.op_code(),
+            Expr::Map(op) => op.op_code(),
+            Expr::Filter(op) => op.op_code(),
+            Expr::Exists(op) => op.op_code(),
+            Expr::ForAll(op) => op.op_code(),
+            Expr::SelectField(op) => op.op_code(),
+            Expr::BoolToSigmaProp(op) => op.op_code(),
+            Expr::Upcast(op) => op.op_code(),
+            Expr::Downcast(op) => op.op_code(),
+            Expr::CreateProveDlog(op) => op.op_code(),
+            Expr::CreateProveDhTuple(op) => op.op_code(),
+            Expr::SigmaPropBytes(op) => op.op_code(),
+            Expr::DecodePoint(op) => op.op_code(),
+            Expr::SigmaAnd(op) => op.op_code(),
+            Expr::SigmaOr(op) => op.op_code(),
+            Expr::GetVar(op) => op.op_code(),
+            Expr::DeserializeRegister(op) => op.op_code(),
+            Expr::DeserializeContext(op) => op.op_code(),
+            Expr::MultiplyGroup(op) => op.op_code(),
+            Expr::Exponentiate(op) => op.op_code(),
+            Expr::XorOf(op) => op.op_code(),
+        }
+    }
+}
+
+/// Returns true if any node of the expression tree has one of the given op codes
+pub(crate) fn has_opcode(expr: &Expr, op_codes: &[OpCode]) -> bool {
+    struct OpCodeFinder<'a> {
+        op_codes: &'a [OpCode],
+        found: bool,
+    }
+
+    impl Visitor for OpCodeFinder<'_> {
+        fn visit_pre(&mut self, expr: &Expr) -> bool {
+            self.found = self.found || self.op_codes.contains(&expr.op_code());
+            !self.found
+        }
+    }
+
+    let mut finder = OpCodeFinder {
+        op_codes,
+        found: false,
+    };
+    visit(expr, &mut finder);
+    finder.found
+}
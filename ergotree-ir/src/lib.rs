@@ -23,6 +23,7 @@ mod has_opcode;
 pub mod base16_str;
 pub mod bigint256;
 pub mod chain;
+pub mod contract_template;
 pub mod ergo_tree;
 pub mod mir;
 pub mod serialization;
@@ -17,11 +17,13 @@ use crate::types::stype::SType;
 use impl_trait_for_tuples::impl_for_tuples;
 use std::convert::TryFrom;
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 mod constant_placeholder;
+mod rendered;
 
 pub use constant_placeholder::*;
+pub use rendered::*;
 
 use super::avl_tree_data::AvlTreeData;
 use super::value::NativeColl;
@@ -61,7 +63,7 @@ pub enum Literal {
     /// AVL tree
     AvlTree(Box<AvlTreeData>),
     /// Ergo box
-    CBox(Rc<ErgoBox>),
+    CBox(Arc<ErgoBox>),
     /// Collection
     Coll(CollKind<Literal>),
     /// Option type
@@ -118,15 +120,15 @@ impl From<EcPoint> for Literal {
     }
 }
 
-impl From<Rc<ErgoBox>> for Literal {
-    fn from(b: Rc<ErgoBox>) -> Self {
+impl From<Arc<ErgoBox>> for Literal {
+    fn from(b: Arc<ErgoBox>) -> Self {
         Literal::CBox(b)
     }
 }
 
 impl From<ErgoBox> for Literal {
     fn from(b: ErgoBox) -> Self {
-        Literal::CBox(Rc::new(b))
+        Literal::CBox(Arc::new(b))
     }
 }
 
@@ -140,7 +142,7 @@ impl From<Vec<u8>> for Literal {
 
 impl From<Vec<i8>> for Literal {
     fn from(v: Vec<i8>) -> Literal {
-        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(v)))
+        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(v.into())))
     }
 }
 
@@ -181,14 +183,14 @@ impl TryFrom<Value> for Constant {
                     ),
                     CollKind::WrappedColl { elem_tpe, items } => {
                         let mut new_items = Vec::with_capacity(items.len());
-                        for v in items {
-                            let c = Constant::try_from(v)?;
+                        for v in items.iter() {
+                            let c = Constant::try_from(v.clone())?;
                             new_items.push(c.v);
                         }
                         (
                             Literal::Coll(CollKind::WrappedColl {
                                 elem_tpe: elem_tpe.clone(),
-                                items: new_items,
+                                items: new_items.into(),
                             }),
                             SType::SColl(Box::new(elem_tpe)),
                         )
@@ -296,8 +298,8 @@ impl From<EcPoint> for Constant {
     }
 }
 
-impl From<Rc<ErgoBox>> for Constant {
-    fn from(b: Rc<ErgoBox>) -> Self {
+impl From<Arc<ErgoBox>> for Constant {
+    fn from(b: Arc<ErgoBox>) -> Self {
         Constant {
             tpe: SType::SBox,
             v: b.into(),
@@ -510,7 +512,7 @@ impl TryExtractFrom<Literal> for SigmaProp {
     }
 }
 
-impl TryExtractFrom<Literal> for Rc<ErgoBox> {
+impl TryExtractFrom<Literal> for Arc<ErgoBox> {
     fn try_extract_from(c: Literal) -> Result<Self, TryExtractFromError> {
         match c {
             Literal::CBox(b) => Ok(b),
@@ -541,7 +543,7 @@ impl<T: TryExtractFrom<Literal> + StoreWrapped> TryExtractFrom<Literal> for Vec<
                 CollKind::WrappedColl {
                     elem_tpe: _,
                     items: v,
-                } => v.into_iter().map(T::try_extract_from).collect(),
+                } => v.iter().cloned().map(T::try_extract_from).collect(),
                 _ => Err(TryExtractFromError(format!(
                     "expected {:?}, found {:?}",
                     std::any::type_name::<Self>(),
@@ -561,7 +563,7 @@ impl TryExtractFrom<Literal> for Vec<i8> {
     fn try_extract_from(v: Literal) -> Result<Self, TryExtractFromError> {
         match v {
             Literal::Coll(v) => match v {
-                CollKind::NativeColl(NativeColl::CollByte(bs)) => Ok(bs),
+                CollKind::NativeColl(NativeColl::CollByte(bs)) => Ok(bs.to_vec()),
                 _ => Err(TryExtractFromError(format!(
                     "expected {:?}, found {:?}",
                     std::any::type_name::<Self>(),
@@ -728,7 +730,7 @@ pub(crate) mod arbitrary {
                 for _ in 0..length {
                     values.push(byte);
                 }
-                CollKind::NativeColl(NativeColl::CollByte(values))
+                CollKind::NativeColl(NativeColl::CollByte(values.into()))
             } else {
                 let mut values: Vec<Literal> = Vec::with_capacity(length);
                 for _ in 0..length {
@@ -736,7 +738,7 @@ pub(crate) mod arbitrary {
                 }
                 CollKind::WrappedColl {
                     elem_tpe: c.tpe,
-                    items: values,
+                    items: values.into(),
                 }
             }),
         }
@@ -755,7 +757,18 @@ pub(crate) mod arbitrary {
             SType::SSigmaProp => any::<SigmaProp>().prop_map_into().boxed(),
             SType::SBox => any::<ErgoBox>().prop_map_into().boxed(),
             // SType::SAvlTree => {}
-            // SType::SOption(tpe) =>
+            SType::SOption(elem_tpe) => {
+                let tpe = SType::SOption(elem_tpe.clone());
+                prop_oneof![
+                    const_with_type(*elem_tpe).prop_map(|c| Some(c.v)),
+                    Just(None)
+                ]
+                .prop_map(move |opt| Constant {
+                    tpe: tpe.clone(),
+                    v: Literal::Opt(Box::new(opt)),
+                })
+                .boxed()
+            }
             SType::SColl(elem_tpe) => match *elem_tpe {
                 SType::SBoolean => vec(any::<bool>(), 0..400).prop_map_into().boxed(),
                 SType::SByte => vec(any::<u8>(), 0..400).prop_map_into().boxed(),
@@ -802,18 +815,16 @@ pub(crate) mod arbitrary {
                                 elem.clone().prop_map(|c| coll_from_constant(c, 1)),
                                 elem.clone().prop_map(|c| coll_from_constant(c, 2)),
                                 elem.clone().prop_map(|c| coll_from_constant(c, 10)),
-                                // no Option[_] since it cannot be serialized (for now)
-                                // // Some(v)
-                                // elem.clone().prop_map(|c| Constant {
-                                //     tpe: SType::SOption(Box::new(c.tpe)),
-                                //     v: Value::Opt(Box::new(Some(c.v)))
-                                // }),
-                                // // None
-                                // elem.prop_map(|c| Constant {
-                                //     tpe: SType::SOption(Box::new(c.tpe)),
-                                //     v: Value::Opt(Box::new(None))
-                                // })
-
+                                // Some(v)
+                                elem.clone().prop_map(|c| Constant {
+                                    tpe: SType::SOption(Box::new(c.tpe)),
+                                    v: Literal::Opt(Box::new(Some(c.v)))
+                                }),
+                                // None
+                                elem.clone().prop_map(|c| Constant {
+                                    tpe: SType::SOption(Box::new(c.tpe)),
+                                    v: Literal::Opt(Box::new(None))
+                                }),
                                 // Tuple
                                 vec(elem, 2..=4).prop_map(|constants| Constant {
                                     tpe: SType::STuple(
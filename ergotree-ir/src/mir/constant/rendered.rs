@@ -0,0 +1,664 @@
+//! Human-readable ("rendered") constant values and types, as shown by the explorer
+//! (`renderedValue`, `sigmaType`) and parsing them back
+
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use num_traits::Num;
+use thiserror::Error;
+
+use crate::bigint256::BigInt256;
+use crate::chain::ergo_box::ErgoBox;
+use crate::mir::avl_tree_data::AvlTreeData;
+use crate::mir::value::CollKind;
+use crate::mir::value::NativeColl;
+use crate::serialization::SigmaSerializable;
+use crate::sigma_protocol::dlog_group::EcPoint;
+use crate::sigma_protocol::sigma_boolean::cand::Cand;
+use crate::sigma_protocol::sigma_boolean::cor::Cor;
+use crate::sigma_protocol::sigma_boolean::cthreshold::Cthreshold;
+use crate::sigma_protocol::sigma_boolean::ProveDhTuple;
+use crate::sigma_protocol::sigma_boolean::ProveDlog;
+use crate::sigma_protocol::sigma_boolean::SigmaBoolean;
+use crate::sigma_protocol::sigma_boolean::SigmaConjecture;
+use crate::sigma_protocol::sigma_boolean::SigmaConjectureItems;
+use crate::sigma_protocol::sigma_boolean::SigmaProofOfKnowledgeTree;
+use crate::sigma_protocol::sigma_boolean::SigmaProp;
+use crate::types::stuple::STuple;
+use crate::types::stype::SType;
+
+use super::Constant;
+use super::Literal;
+
+/// Errors on parsing rendered value or type
+#[derive(Error, PartialEq, Eq, Debug, Clone)]
+pub enum RenderedValueParsingError {
+    /// Unexpected input
+    #[error("expected {expected} at position {pos}")]
+    Unexpected {
+        /// What was expected
+        expected: String,
+        /// Position (in bytes) in the input
+        pos: usize,
+    },
+    /// Invalid value (number out of range, malformed hex, etc.)
+    #[error("invalid value: {0}")]
+    InvalidValue(String),
+    /// Values of the type cannot be rendered
+    #[error("values of type {0:?} are not supported")]
+    UnsupportedType(SType),
+    /// Values or types are nested deeper than [`MAX_NESTING_DEPTH`]
+    #[error("nesting depth exceeds {MAX_NESTING_DEPTH} at position {pos}")]
+    TooDeep {
+        /// Position (in bytes) in the input
+        pos: usize,
+    },
+}
+
+/// Maximum nesting depth of the parsed values and types (collections, options, tuples and sigma
+/// conjectures), same as `SigmaSerializer.MaxTreeDepth` in sigmastate
+pub const MAX_NESTING_DEPTH: usize = 110;
+
+impl Constant {
+    /// Human-readable value, e.g. `Coll(1,2)`, `(10,Coll[Byte](0a0b))`, `Some(ProveDlog(02..))`.
+    /// Can be parsed back with [`Constant::from_rendered_str`].
+    pub fn to_rendered_string(&self) -> String {
+        render_literal(&self.v)
+    }
+
+    /// Parse a value of the given type rendered with [`Constant::to_rendered_string`].
+    /// Also accepts collections of bytes rendered as a list of numbers (`Coll(-1,2)`) or
+    /// as a bare hex string (explorer API)
+    pub fn from_rendered_str(tpe: SType, s: &str) -> Result<Constant, RenderedValueParsingError> {
+        let mut p = Parser::new(s);
+        let v = p.literal(&tpe)?;
+        p.end()?;
+        Ok(Constant { tpe, v })
+    }
+}
+
+/// Render the type as `sigmaType` in the explorer API, e.g. `SLong`, `Coll[SByte]`,
+/// `(SInt, Option[SLong])`
+pub fn render_stype(tpe: &SType) -> String {
+    match tpe {
+        SType::SColl(elem_tpe) => format!("Coll[{}]", render_stype(elem_tpe)),
+        SType::SOption(elem_tpe) => format!("Option[{}]", render_stype(elem_tpe)),
+        SType::STuple(STuple { items }) => format!(
+            "({})",
+            items
+                .iter()
+                .map(render_stype)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        SType::STypeVar(tv) => format!("{:?}", tv),
+        SType::SFunc(f) => format!("{:?}", f),
+        _ => format!("{:?}", tpe),
+    }
+}
+
+/// Parse the type rendered with [`render_stype`], `S` prefix of the type names is optional
+/// (`Coll[Byte]` and `Coll[SByte]` are the same type)
+pub fn parse_rendered_stype(s: &str) -> Result<SType, RenderedValueParsingError> {
+    let mut p = Parser::new(s);
+    let tpe = p.stype()?;
+    p.end()?;
+    Ok(tpe)
+}
+
+fn render_literal(v: &Literal) -> String {
+    match v {
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Byte(b) => b.to_string(),
+        Literal::Short(s) => s.to_string(),
+        Literal::Int(i) => i.to_string(),
+        Literal::Long(l) => l.to_string(),
+        Literal::BigInt(bi) => bi.to_string(),
+        Literal::GroupElement(ge) => render_ec_point(ge),
+        Literal::SigmaProp(sp) => render_sigma_boolean(sp.value()),
+        Literal::AvlTree(t) => format!("AvlTree({})", render_bytes(t.as_ref())),
+        Literal::CBox(b) => format!("ErgoBox({})", render_bytes(b.as_ref())),
+        Literal::Coll(CollKind::NativeColl(NativeColl::CollByte(bytes))) => format!(
+            "Coll[Byte]({})",
+            base16::encode_lower(&bytes.iter().map(|b| *b as u8).collect::<Vec<_>>())
+        ),
+        Literal::Coll(CollKind::WrappedColl { items, .. }) => {
+            format!("Coll({})", render_items(items.iter()))
+        }
+        Literal::Opt(opt) => match opt.as_ref() {
+            Some(v) => format!("Some({})", render_literal(v)),
+            None => "None".to_string(),
+        },
+        Literal::Tup(items) => format!("({})", render_items(items.iter())),
+    }
+}
+
+fn render_items<'a>(items: impl Iterator<Item = &'a Literal>) -> String {
+    items.map(render_literal).collect::<Vec<_>>().join(",")
+}
+
+fn render_bytes<T: SigmaSerializable>(v: &T) -> String {
+    // serialization of the values that were successfully built cannot fail
+    #[allow(clippy::unwrap_used)]
+    base16::encode_lower(&v.sigma_serialize_bytes().unwrap())
+}
+
+fn render_ec_point(p: &EcPoint) -> String {
+    render_bytes(p)
+}
+
+fn render_sigma_boolean(sb: &SigmaBoolean) -> String {
+    let render_conj = |items: &SigmaConjectureItems<SigmaBoolean>| {
+        items
+            .as_vec()
+            .iter()
+            .map(render_sigma_boolean)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    match sb {
+        SigmaBoolean::TrivialProp(b) => format!("TrivialProp({})", b),
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDlog(pd)) => {
+            format!("ProveDlog({})", render_ec_point(&pd.h))
+        }
+        SigmaBoolean::ProofOfKnowledge(SigmaProofOfKnowledgeTree::ProveDhTuple(dht)) => format!(
+            "ProveDHTuple({},{},{},{})",
+            render_ec_point(&dht.g),
+            render_ec_point(&dht.h),
+            render_ec_point(&dht.u),
+            render_ec_point(&dht.v)
+        ),
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cand(c)) => {
+            format!("CAND({})", render_conj(&c.items))
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cor(c)) => {
+            format!("COR({})", render_conj(&c.items))
+        }
+        SigmaBoolean::SigmaConjecture(SigmaConjecture::Cthreshold(c)) => {
+            format!("CTHRESHOLD({},{})", c.n, render_conj(&c.items))
+        }
+    }
+}
+
+/// Recursive descent parser of the rendered values, guided by the expected type
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        Parser {
+            s,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    /// Parse a nested value or type, failing when nested deeper than [`MAX_NESTING_DEPTH`]
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, RenderedValueParsingError>,
+    ) -> Result<T, RenderedValueParsingError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(RenderedValueParsingError::TooDeep { pos: self.pos });
+        }
+        self.depth += 1;
+        let res = parse(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T, RenderedValueParsingError> {
+        Err(RenderedValueParsingError::Unexpected {
+            expected: expected.to_string(),
+            pos: self.pos,
+        })
+    }
+
+    /// Consume the token if the input (after whitespaces) starts with it
+    fn try_consume(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), RenderedValueParsingError> {
+        if self.try_consume(token) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("'{}'", token))
+        }
+    }
+
+    fn end(&mut self) -> Result<(), RenderedValueParsingError> {
+        self.skip_ws();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            self.unexpected("end of input")
+        }
+    }
+
+    /// Alphanumeric word (identifier, number or hex string), optionally prefixed with `-`
+    fn word(&mut self, expected: &str) -> Result<&'a str, RenderedValueParsingError> {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|(i, c)| !(c.is_ascii_alphanumeric() || (*i == 0 && *c == '-')))
+            .map(|(i, _)| i)
+            .unwrap_or_else(|| rest.len());
+        if len == 0 {
+            return self.unexpected(expected);
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Comma-separated items until the closing parenthesis (already consumed the opening one)
+    fn items<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, RenderedValueParsingError>,
+    ) -> Result<Vec<T>, RenderedValueParsingError> {
+        let mut res = Vec::new();
+        if self.try_consume(")") {
+            return Ok(res);
+        }
+        loop {
+            res.push(item(self)?);
+            if self.try_consume(")") {
+                return Ok(res);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, RenderedValueParsingError> {
+        let w = self.word("number")?;
+        w.parse::<T>()
+            .map_err(|_| RenderedValueParsingError::InvalidValue(format!("number {}", w)))
+    }
+
+    fn hex(&mut self) -> Result<Vec<u8>, RenderedValueParsingError> {
+        self.skip_ws();
+        if self.rest().starts_with(')') {
+            // empty byte array
+            return Ok(Vec::new());
+        }
+        let w = self.word("hex string")?;
+        base16::decode(w).map_err(|e| RenderedValueParsingError::InvalidValue(e.to_string()))
+    }
+
+    fn sigma_parsed<T: SigmaSerializable>(&mut self) -> Result<T, RenderedValueParsingError> {
+        let bytes = self.hex()?;
+        T::sigma_parse_bytes(&bytes)
+            .map_err(|e| RenderedValueParsingError::InvalidValue(e.to_string()))
+    }
+
+    /// `<name>(<hex>)` where hex is a serialized value
+    fn wrapped_sigma_parsed<T: SigmaSerializable>(
+        &mut self,
+        name: &str,
+    ) -> Result<T, RenderedValueParsingError> {
+        self.expect(name)?;
+        self.expect("(")?;
+        let v = self.sigma_parsed()?;
+        self.expect(")")?;
+        Ok(v)
+    }
+
+    fn literal(&mut self, tpe: &SType) -> Result<Literal, RenderedValueParsingError> {
+        self.nested(|p| p.literal_unchecked(tpe))
+    }
+
+    fn literal_unchecked(&mut self, tpe: &SType) -> Result<Literal, RenderedValueParsingError> {
+        Ok(match tpe {
+            SType::SBoolean => match self.word("boolean")? {
+                "true" => Literal::Boolean(true),
+                "false" => Literal::Boolean(false),
+                w => {
+                    return Err(RenderedValueParsingError::InvalidValue(format!(
+                        "boolean {}",
+                        w
+                    )))
+                }
+            },
+            SType::SByte => Literal::Byte(self.number()?),
+            SType::SShort => Literal::Short(self.number()?),
+            SType::SInt => Literal::Int(self.number()?),
+            SType::SLong => Literal::Long(self.number()?),
+            SType::SBigInt => {
+                let w = self.word("number")?;
+                Literal::BigInt(BigInt256::from_str_radix(w, 10).map_err(|_| {
+                    RenderedValueParsingError::InvalidValue(format!("big integer {}", w))
+                })?)
+            }
+            SType::SGroupElement => Literal::GroupElement(Box::new(self.sigma_parsed()?)),
+            SType::SSigmaProp => {
+                // optional wrapper
+                let sb = if self.try_consume("SigmaProp(") {
+                    let sb = self.sigma_boolean()?;
+                    self.expect(")")?;
+                    sb
+                } else {
+                    self.sigma_boolean()?
+                };
+                Literal::SigmaProp(Box::new(SigmaProp::new(sb)))
+            }
+            SType::SAvlTree => Literal::AvlTree(Box::new(
+                self.wrapped_sigma_parsed::<AvlTreeData>("AvlTree")?,
+            )),
+            SType::SBox => {
+                Literal::CBox(Arc::new(self.wrapped_sigma_parsed::<ErgoBox>("ErgoBox")?))
+            }
+            SType::SColl(elem_tpe) => self.coll(elem_tpe)?,
+            SType::SOption(elem_tpe) => {
+                if self.try_consume("None") {
+                    Literal::Opt(Box::new(None))
+                } else {
+                    self.expect("Some")?;
+                    self.expect("(")?;
+                    let v = self.literal(elem_tpe)?;
+                    self.expect(")")?;
+                    Literal::Opt(Box::new(Some(v)))
+                }
+            }
+            SType::STuple(STuple { items: types }) => {
+                self.expect("(")?;
+                let mut types_iter = types.iter();
+                let items = self.items(|p| match types_iter.next() {
+                    Some(t) => p.literal(t),
+                    None => p.unexpected("')'"),
+                })?;
+                if items.len() != types.len() {
+                    return Err(RenderedValueParsingError::InvalidValue(format!(
+                        "expected tuple of {} items, got {}",
+                        types.len(),
+                        items.len()
+                    )));
+                }
+                #[allow(clippy::unwrap_used)]
+                Literal::Tup(items.try_into().unwrap())
+            }
+            _ => return Err(RenderedValueParsingError::UnsupportedType(tpe.clone())),
+        })
+    }
+
+    fn coll(&mut self, elem_tpe: &SType) -> Result<Literal, RenderedValueParsingError> {
+        if !self.try_consume("Coll") {
+            if *elem_tpe == SType::SByte {
+                // bare hex string (explorer API)
+                return Ok(self.hex()?.into());
+            }
+            return self.unexpected("'Coll'");
+        }
+        if self.try_consume("[") {
+            // the element type in brackets means bytes rendered as a hex string
+            let tpe = self.stype()?;
+            self.expect("]")?;
+            if tpe != *elem_tpe {
+                return Err(RenderedValueParsingError::InvalidValue(format!(
+                    "expected collection of {:?}, got {:?}",
+                    elem_tpe, tpe
+                )));
+            }
+            if tpe == SType::SByte {
+                self.expect("(")?;
+                let bytes = self.hex()?;
+                self.expect(")")?;
+                return Ok(bytes.into());
+            }
+        }
+        self.expect("(")?;
+        if *elem_tpe == SType::SByte {
+            let bytes = self.items(|p| p.number::<i8>())?;
+            return Ok(bytes.into());
+        }
+        let items = self.items(|p| p.literal(elem_tpe))?;
+        Ok(Literal::Coll(CollKind::WrappedColl {
+            elem_tpe: elem_tpe.clone(),
+            items: items.into(),
+        }))
+    }
+
+    fn ec_point_arg(&mut self) -> Result<EcPoint, RenderedValueParsingError> {
+        self.sigma_parsed()
+    }
+
+    fn sigma_boolean(&mut self) -> Result<SigmaBoolean, RenderedValueParsingError> {
+        self.nested(Parser::sigma_boolean_unchecked)
+    }
+
+    fn sigma_boolean_unchecked(&mut self) -> Result<SigmaBoolean, RenderedValueParsingError> {
+        let conj_items = |items: Vec<SigmaBoolean>| {
+            SigmaConjectureItems::try_from(items)
+                .map_err(|e| RenderedValueParsingError::InvalidValue(e.to_string()))
+        };
+        let name = self.word("sigma proposition")?;
+        self.expect("(")?;
+        let sb = match name {
+            "TrivialProp" => match self.word("boolean")? {
+                "true" => SigmaBoolean::TrivialProp(true),
+                "false" => SigmaBoolean::TrivialProp(false),
+                w => {
+                    return Err(RenderedValueParsingError::InvalidValue(format!(
+                        "boolean {}",
+                        w
+                    )))
+                }
+            },
+            "ProveDlog" => ProveDlog::new(self.ec_point_arg()?).into(),
+            "ProveDHTuple" => {
+                let g = self.ec_point_arg()?;
+                self.expect(",")?;
+                let h = self.ec_point_arg()?;
+                self.expect(",")?;
+                let u = self.ec_point_arg()?;
+                self.expect(",")?;
+                let v = self.ec_point_arg()?;
+                ProveDhTuple::new(g, h, u, v).into()
+            }
+            "CAND" => {
+                let items = conj_items(self.items(|p| p.sigma_boolean())?)?;
+                return Ok(SigmaConjecture::Cand(Cand { items }).into());
+            }
+            "COR" => {
+                let items = conj_items(self.items(|p| p.sigma_boolean())?)?;
+                return Ok(SigmaConjecture::Cor(Cor { items }).into());
+            }
+            "CTHRESHOLD" => {
+                let n = self.number()?;
+                self.expect(",")?;
+                let items = conj_items(self.items(|p| p.sigma_boolean())?)?;
+                return Ok(SigmaConjecture::Cthreshold(Cthreshold { n, items }).into());
+            }
+            _ => return self.unexpected("sigma proposition"),
+        };
+        self.expect(")")?;
+        Ok(sb)
+    }
+
+    fn stype(&mut self) -> Result<SType, RenderedValueParsingError> {
+        self.nested(Parser::stype_unchecked)
+    }
+
+    fn stype_unchecked(&mut self) -> Result<SType, RenderedValueParsingError> {
+        if self.try_consume("(") {
+            let items = self.items(|p| p.stype())?;
+            return STuple::try_from(items)
+                .map(SType::STuple)
+                .map_err(|e| RenderedValueParsingError::InvalidValue(format!("{:?}", e)));
+        }
+        let name = self.word("type")?;
+        let name = name.strip_prefix('S').unwrap_or(name);
+        Ok(match name {
+            "Any" => SType::SAny,
+            "Boolean" => SType::SBoolean,
+            "Byte" => SType::SByte,
+            "Short" => SType::SShort,
+            "Int" => SType::SInt,
+            "Long" => SType::SLong,
+            "BigInt" => SType::SBigInt,
+            "GroupElement" => SType::SGroupElement,
+            "SigmaProp" => SType::SSigmaProp,
+            "Box" => SType::SBox,
+            "AvlTree" => SType::SAvlTree,
+            "Context" => SType::SContext,
+            "Header" => SType::SHeader,
+            "PreHeader" => SType::SPreHeader,
+            "Global" => SType::SGlobal,
+            "Coll" | "Option" => {
+                self.expect("[")?;
+                let elem_tpe = Box::new(self.stype()?);
+                self.expect("]")?;
+                if name == "Coll" {
+                    SType::SColl(elem_tpe)
+                } else {
+                    SType::SOption(elem_tpe)
+                }
+            }
+            _ => return self.unexpected("type"),
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "arbitrary")]
+#[allow(clippy::unwrap_used)]
+#[allow(clippy::panic)]
+mod tests {
+    use super::*;
+    use crate::mir::constant::arbitrary::ArbConstantParams;
+    use proptest::prelude::*;
+
+    proptest! {
+
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn rendered_roundtrip(c in any_with::<Constant>(ArbConstantParams::AnyWithDepth(2))) {
+            let s = c.to_rendered_string();
+            prop_assert_eq![Constant::from_rendered_str(c.tpe.clone(), &s).unwrap(), c.clone()];
+            prop_assert_eq![parse_rendered_stype(&render_stype(&c.tpe)).unwrap(), c.tpe];
+        }
+    }
+
+    #[test]
+    fn render_values() {
+        let c: Constant = vec![1i32, 2].into();
+        assert_eq!(c.to_rendered_string(), "Coll(1,2)");
+        let c: Constant = (10i32, vec![1u8, 255u8]).into();
+        assert_eq!(c.to_rendered_string(), "(10,Coll[Byte](01ff))");
+        assert_eq!(render_stype(&c.tpe), "(SInt, Coll[SByte])");
+        let c: Constant = Some(1i64).into();
+        assert_eq!(c.to_rendered_string(), "Some(1)");
+    }
+
+    #[test]
+    fn parse_values() {
+        let tpe = parse_rendered_stype("(Int, Coll[Byte])").unwrap();
+        let c = Constant::from_rendered_str(tpe.clone(), "(10, Coll[Byte](01ff))").unwrap();
+        assert_eq!(c, (10i32, vec![1u8, 255u8]).into());
+        // bytes as numbers and as a bare hex string
+        assert_eq!(
+            Constant::from_rendered_str(tpe.clone(), "(10,Coll(1,-1))").unwrap(),
+            c
+        );
+        assert_eq!(Constant::from_rendered_str(tpe, "(10,01ff)").unwrap(), c);
+        assert_eq!(
+            Constant::from_rendered_str(SType::SLong, "0").unwrap(),
+            0i64.into()
+        );
+        assert!(Constant::from_rendered_str(SType::SByte, "128").is_err());
+        assert!(Constant::from_rendered_str(SType::SInt, "1 2").is_err());
+    }
+
+    #[test]
+    fn parse_prove_dlog() {
+        let pk = ProveDlog::new(crate::sigma_protocol::dlog_group::generator());
+        let c: Constant = SigmaProp::from(pk).into();
+        let s = c.to_rendered_string();
+        assert_eq!(
+            s,
+            "ProveDlog(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)"
+        );
+        assert_eq!(
+            Constant::from_rendered_str(SType::SSigmaProp, &s).unwrap(),
+            c
+        );
+    }
+
+    #[test]
+    fn parse_deep_nesting() {
+        let nested_stype =
+            |depth: usize| format!("{}Int{}", "Coll[".repeat(depth), "]".repeat(depth));
+        assert!(parse_rendered_stype(&nested_stype(MAX_NESTING_DEPTH - 1)).is_ok());
+        assert!(matches!(
+            parse_rendered_stype(&nested_stype(MAX_NESTING_DEPTH)),
+            Err(RenderedValueParsingError::TooDeep { .. })
+        ));
+        assert!(matches!(
+            parse_rendered_stype(&nested_stype(100_000)),
+            Err(RenderedValueParsingError::TooDeep { .. })
+        ));
+        assert!(matches!(
+            parse_rendered_stype(&format!(
+                "{}Int{}",
+                "(Int, ".repeat(100_000),
+                ")".repeat(100_000)
+            )),
+            Err(RenderedValueParsingError::TooDeep { .. })
+        ));
+
+        let nested_sigma = |depth: usize| {
+            format!(
+                "{}TrivialProp(true){}",
+                "CAND(TrivialProp(true),".repeat(depth),
+                ")".repeat(depth)
+            )
+        };
+        assert!(Constant::from_rendered_str(SType::SSigmaProp, &nested_sigma(50)).is_ok());
+        assert!(matches!(
+            Constant::from_rendered_str(SType::SSigmaProp, &nested_sigma(100_000)),
+            Err(RenderedValueParsingError::TooDeep { .. })
+        ));
+        assert!(matches!(
+            Constant::from_rendered_str(
+                SType::SSigmaProp,
+                &format!(
+                    "{}TrivialProp(true){}",
+                    "COR(".repeat(100_000),
+                    ")".repeat(100_000)
+                )
+            ),
+            Err(RenderedValueParsingError::TooDeep { .. })
+        ));
+
+        let deep_tpe = (0..MAX_NESTING_DEPTH).fold(SType::SInt, |t, _| SType::SOption(t.into()));
+        let deep_value = format!(
+            "{}1{}",
+            "Some(".repeat(MAX_NESTING_DEPTH),
+            ")".repeat(MAX_NESTING_DEPTH)
+        );
+        assert!(matches!(
+            Constant::from_rendered_str(deep_tpe, &deep_value),
+            Err(RenderedValueParsingError::TooDeep { .. })
+        ));
+    }
+}
@@ -72,6 +72,11 @@ impl FuncValue {
         &self.body
     }
 
+    /// Mutable function body (its type should be preserved on rewrite)
+    pub(crate) fn body_mut(&mut self) -> &mut Expr {
+        &mut self.body
+    }
+
     /// Type
     pub fn tpe(&self) -> SType {
         self.tpe.clone()
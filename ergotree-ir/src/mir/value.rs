@@ -1,7 +1,7 @@
 //! Ergo data type
 
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use impl_trait_for_tuples::impl_for_tuples;
 
@@ -27,11 +27,15 @@ use super::func_value::FuncArg;
 extern crate derive_more;
 use derive_more::From;
 
+mod coll_items;
+
+pub use coll_items::CollItems;
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 /// Collection for primitive values (i.e byte array)
 pub enum NativeColl {
-    /// Collection of bytes
-    CollByte(Vec<i8>),
+    /// Collection of bytes (shared, cloning does not copy the bytes)
+    CollByte(CollItems<i8>),
 }
 
 impl NativeColl {
@@ -43,7 +47,7 @@ impl NativeColl {
     }
 }
 
-/// Collection elements
+/// Collection elements (shared, cloning does not copy the elements)
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum CollKind<T> {
     /// Collection elements stored as a vector of Rust values
@@ -53,7 +57,7 @@ pub enum CollKind<T> {
         /// Collection element type
         elem_tpe: SType,
         /// Collection elements
-        items: Vec<T>,
+        items: CollItems<T>,
     },
 }
 
@@ -72,8 +76,11 @@ where
                 .into_iter()
                 .map(|v| v.try_extract_into::<i8>())
                 .collect::<Result<Vec<_>, _>>()
-                .map(|bytes| CollKind::NativeColl(NativeColl::CollByte(bytes))),
-            _ => Ok(CollKind::WrappedColl { elem_tpe, items }),
+                .map(|bytes| CollKind::NativeColl(NativeColl::CollByte(bytes.into()))),
+            _ => Ok(CollKind::WrappedColl {
+                elem_tpe,
+                items: items.into(),
+            }),
         }
     }
 
@@ -87,14 +94,14 @@ where
                 .into_iter()
                 .map(|v| v.try_extract_into::<Vec<i8>>())
                 .collect::<Result<Vec<_>, _>>()
-                .map(|bytes| CollKind::NativeColl(NativeColl::CollByte(bytes.concat()))),
+                .map(|bytes| CollKind::NativeColl(NativeColl::CollByte(bytes.concat().into()))),
             _ => items
                 .into_iter()
                 .map(|v| v.try_extract_into::<Vec<T>>())
                 .collect::<Result<Vec<_>, _>>()
                 .map(|v| CollKind::WrappedColl {
                     elem_tpe,
-                    items: v.concat(),
+                    items: v.concat().into(),
                 }),
         }
     }
@@ -102,25 +109,98 @@ where
     /// Collection element type
     pub fn elem_tpe(&self) -> &SType {
         match self {
-            cp @ CollKind::NativeColl(_) => cp.elem_tpe(),
+            CollKind::NativeColl(ncoll) => ncoll.elem_tpe(),
             CollKind::WrappedColl { elem_tpe, .. } => elem_tpe,
         }
     }
 
-    /// Return items, as vector of Values
-    pub fn as_vec(&self) -> Vec<T> {
+    /// Number of elements
+    pub fn len(&self) -> usize {
         match self {
-            CollKind::NativeColl(NativeColl::CollByte(coll_byte)) => coll_byte
-                .clone()
-                .into_iter()
-                .map(|byte| byte.into())
-                .collect(),
-            CollKind::WrappedColl {
-                elem_tpe: _,
-                items: v,
-            } => v.clone(),
+            CollKind::NativeColl(NativeColl::CollByte(coll_byte)) => coll_byte.len(),
+            CollKind::WrappedColl { items, .. } => items.len(),
+        }
+    }
+
+    /// Returns true if the collection has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Element at the given index
+    pub fn get(&self, index: usize) -> Option<T> {
+        match self {
+            CollKind::NativeColl(NativeColl::CollByte(coll_byte)) => {
+                coll_byte.get(index).map(|byte| (*byte).into())
+            }
+            CollKind::WrappedColl { items, .. } => items.get(index).cloned(),
         }
     }
+
+    /// View of the elements in the given range (`from` and `until` are clamped to the collection
+    /// bounds), keeping the storage kind
+    pub fn slice(&self, from: usize, until: usize) -> CollKind<T> {
+        match self {
+            CollKind::NativeColl(NativeColl::CollByte(coll_byte)) => {
+                CollKind::NativeColl(NativeColl::CollByte(coll_byte.slice(from, until)))
+            }
+            CollKind::WrappedColl { elem_tpe, items } => CollKind::WrappedColl {
+                elem_tpe: elem_tpe.clone(),
+                items: items.slice(from, until),
+            },
+        }
+    }
+
+    /// View of the elements of this collection followed by the elements of the other one (the
+    /// elements are copied only if the storage kinds differ). Returns `None` if the element
+    /// types differ.
+    pub fn append(&self, other: &CollKind<T>) -> Option<CollKind<T>> {
+        if self.elem_tpe() != other.elem_tpe() {
+            return None;
+        }
+        if other.is_empty() {
+            return Some(self.clone());
+        }
+        if self.is_empty() {
+            return Some(other.clone());
+        }
+        Some(match (self, other) {
+            (
+                CollKind::NativeColl(NativeColl::CollByte(left)),
+                CollKind::NativeColl(NativeColl::CollByte(right)),
+            ) => CollKind::NativeColl(NativeColl::CollByte(left.append(right))),
+            (
+                CollKind::WrappedColl { elem_tpe, items },
+                CollKind::WrappedColl {
+                    items: other_items, ..
+                },
+            ) => CollKind::WrappedColl {
+                elem_tpe: elem_tpe.clone(),
+                items: items.append(other_items),
+            },
+            (left, right) => CollKind::WrappedColl {
+                elem_tpe: left.elem_tpe().clone(),
+                items: left.iter().chain(right.iter()).collect(),
+            },
+        })
+    }
+
+    /// Iterator over the elements (byte collections elements are converted on the fly)
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        let (bytes, items) = match self {
+            CollKind::NativeColl(NativeColl::CollByte(coll_byte)) => (Some(coll_byte), None),
+            CollKind::WrappedColl { items, .. } => (None, Some(items)),
+        };
+        bytes
+            .into_iter()
+            .flat_map(|bytes| bytes.iter().map(|byte| (*byte).into()))
+            .chain(items.into_iter().flat_map(|items| items.iter().cloned()))
+    }
+
+    /// Return items, as vector of Values
+    pub fn as_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
 }
 
 /// Lambda
@@ -152,7 +232,7 @@ pub enum Value {
     /// Sigma property
     SigmaProp(Box<SigmaProp>),
     /// Ergo box
-    CBox(Rc<ErgoBox>),
+    CBox(Arc<ErgoBox>),
     /// AVL tree
     AvlTree(Box<AvlTreeData>),
     /// Collection of values of the same type
@@ -194,13 +274,15 @@ impl From<EcPoint> for Value {
 
 impl From<Vec<i8>> for Value {
     fn from(v: Vec<i8>) -> Self {
-        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(v)))
+        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(v.into())))
     }
 }
 
 impl From<Vec<u8>> for Value {
     fn from(v: Vec<u8>) -> Self {
-        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(v.as_vec_i8())))
+        Value::Coll(CollKind::NativeColl(NativeColl::CollByte(
+            v.as_vec_i8().into(),
+        )))
     }
 }
 
@@ -227,7 +309,7 @@ impl From<Literal> for Value {
                     CollKind::NativeColl(n) => CollKind::NativeColl(n),
                     CollKind::WrappedColl { elem_tpe, items } => CollKind::WrappedColl {
                         elem_tpe,
-                        items: items.into_iter().map(Value::from).collect(),
+                        items: items.iter().cloned().map(Value::from).collect(),
                     },
                 };
                 Value::Coll(converted_coll)
@@ -248,7 +330,7 @@ impl StoreWrapped for i32 {}
 impl StoreWrapped for i64 {}
 impl StoreWrapped for BigInt256 {}
 impl StoreWrapped for Header {}
-impl StoreWrapped for Rc<ErgoBox> {}
+impl StoreWrapped for Arc<ErgoBox> {}
 impl StoreWrapped for EcPoint {}
 impl StoreWrapped for SigmaProp {}
 impl<T: StoreWrapped> StoreWrapped for Option<T> {}
@@ -277,8 +359,8 @@ impl Into<Value> for Tuple {
     }
 }
 
-impl From<Vec<Rc<ErgoBox>>> for Value {
-    fn from(v: Vec<Rc<ErgoBox>>) -> Self {
+impl From<Vec<Arc<ErgoBox>>> for Value {
+    fn from(v: Vec<Arc<ErgoBox>>) -> Self {
         Value::Coll(CollKind::WrappedColl {
             elem_tpe: SType::SBox,
             items: v.into_iter().map(|i| i.into()).collect(),
@@ -358,7 +440,7 @@ impl TryExtractFrom<Value> for SigmaProp {
     }
 }
 
-impl TryExtractFrom<Value> for Rc<ErgoBox> {
+impl TryExtractFrom<Value> for Arc<ErgoBox> {
     fn try_extract_from(c: Value) -> Result<Self, TryExtractFromError> {
         match c {
             Value::CBox(b) => Ok(b),
@@ -401,7 +483,7 @@ impl<T: TryExtractFrom<Value> + StoreWrapped> TryExtractFrom<Value> for Vec<T> {
                 CollKind::WrappedColl {
                     elem_tpe: _,
                     items: v,
-                } => v.into_iter().map(T::try_extract_from).collect(),
+                } => v.iter().cloned().map(T::try_extract_from).collect(),
                 _ => Err(TryExtractFromError(format!(
                     "expected {:?}, found {:?}",
                     std::any::type_name::<Self>(),
@@ -426,7 +508,8 @@ impl<T: TryExtractFrom<Value> + StoreWrapped, const N: usize> TryExtractFrom<Val
                     items: v,
                 } => {
                     let v = v
-                        .into_iter()
+                        .iter()
+                        .cloned()
                         .map(T::try_extract_from)
                         .collect::<Result<Vec<_>, _>>()?;
                     let len = v.len();
@@ -451,7 +534,7 @@ impl TryExtractFrom<Value> for Vec<i8> {
     fn try_extract_from(v: Value) -> Result<Self, TryExtractFromError> {
         match v {
             Value::Coll(v) => match v {
-                CollKind::NativeColl(NativeColl::CollByte(bs)) => Ok(bs),
+                CollKind::NativeColl(NativeColl::CollByte(bs)) => Ok(bs.to_vec()),
                 _ => Err(TryExtractFromError(format!(
                     "expected {:?}, found {:?}",
                     std::any::type_name::<Self>(),
@@ -512,7 +595,7 @@ impl<T: TryExtractFrom<Value> + StoreWrapped> TryExtractFrom<Vec<Value>> for Vec
     }
 }
 
-// impl TryExtractFrom<Value> for Rc<Context> {
+// impl TryExtractFrom<Value> for Arc<Context> {
 //     fn try_extract_from(v: Value) -> Result<Self, TryExtractFromError> {
 //         match v {
 //             Value::Context(ctx) => Ok(ctx),
@@ -0,0 +1,212 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+/// Number of chunks after which appending copies the elements into a single allocation
+/// (bounds the cost of the element access by index)
+const MAX_CHUNKS: usize = 16;
+
+/// Collection elements shared between the collection values. Cloning, slicing and appending
+/// create views of the existing storage instead of copying the elements. The elements of the
+/// appended collections are copied into a single allocation when there are too many chunks to
+/// view, or on the first access as a contiguous slice (via `Deref`).
+#[derive(Clone)]
+pub struct CollItems<T> {
+    chunks: Arc<[Chunk<T>]>,
+    len: usize,
+    contiguous: Arc<OnceLock<Arc<[T]>>>,
+}
+
+/// View of the elements in the given range of the storage
+#[derive(Clone)]
+struct Chunk<T> {
+    storage: Arc<[T]>,
+    range: Range<usize>,
+}
+
+impl<T> Chunk<T> {
+    fn as_slice(&self) -> &[T] {
+        &self.storage[self.range.clone()]
+    }
+}
+
+impl<T> CollItems<T> {
+    fn from_chunks(chunks: Vec<Chunk<T>>) -> Self {
+        let chunks: Vec<Chunk<T>> = chunks.into_iter().filter(|c| !c.range.is_empty()).collect();
+        CollItems {
+            len: chunks.iter().map(|c| c.range.len()).sum(),
+            chunks: chunks.into(),
+            contiguous: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Element at the given index
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut index = index;
+        for chunk in self.chunks.iter() {
+            if index < chunk.range.len() {
+                return chunk.as_slice().get(index);
+            }
+            index -= chunk.range.len();
+        }
+        None
+    }
+
+    /// Iterator over the elements
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.chunks.iter().flat_map(|c| c.as_slice().iter())
+    }
+
+    /// View of the elements in the given range (`from` and `until` are clamped to the bounds)
+    pub fn slice(&self, from: usize, until: usize) -> Self {
+        let until = until.min(self.len);
+        let from = from.min(until);
+        let mut offset = 0;
+        let mut chunks = Vec::new();
+        for chunk in self.chunks.iter() {
+            let chunk_len = chunk.range.len();
+            let start = from.max(offset).min(offset + chunk_len);
+            let end = until.max(offset).min(offset + chunk_len);
+            if start < end {
+                chunks.push(Chunk {
+                    storage: chunk.storage.clone(),
+                    range: chunk.range.start + start - offset..chunk.range.start + end - offset,
+                });
+            }
+            offset += chunk_len;
+        }
+        CollItems::from_chunks(chunks)
+    }
+}
+
+impl<T: Clone> CollItems<T> {
+    /// View of the elements of this collection followed by the elements of the other one
+    pub fn append(&self, other: &CollItems<T>) -> Self {
+        if self.chunks.len() + other.chunks.len() > MAX_CHUNKS {
+            return self.iter().chain(other.iter()).cloned().collect();
+        }
+        CollItems::from_chunks(
+            self.chunks
+                .iter()
+                .chain(other.chunks.iter())
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl<T: Clone> Deref for CollItems<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self.chunks.as_ref() {
+            [] => &[],
+            [chunk] => chunk.as_slice(),
+            _ => self
+                .contiguous
+                .get_or_init(|| self.iter().cloned().collect()),
+        }
+    }
+}
+
+impl<T> From<Arc<[T]>> for CollItems<T> {
+    fn from(storage: Arc<[T]>) -> Self {
+        let range = 0..storage.len();
+        CollItems::from_chunks(vec![Chunk { storage, range }])
+    }
+}
+
+impl<T> From<Vec<T>> for CollItems<T> {
+    fn from(v: Vec<T>) -> Self {
+        Arc::<[T]>::from(v).into()
+    }
+}
+
+impl<T: Clone> From<&[T]> for CollItems<T> {
+    fn from(v: &[T]) -> Self {
+        Arc::<[T]>::from(v).into()
+    }
+}
+
+impl<T> FromIterator<T> for CollItems<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<T>>().into()
+    }
+}
+
+impl<T: PartialEq> PartialEq for CollItems<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for CollItems<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for CollItems<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slice_is_a_view() {
+        let items: CollItems<i32> = (0..10).collect();
+        let slice = items.slice(2, 5);
+        assert_eq!(slice.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert!(Arc::ptr_eq(
+            &slice.chunks[0].storage,
+            &items.chunks[0].storage
+        ));
+        assert_eq!(items.slice(8, 20).len(), 2);
+        assert!(items.slice(5, 2).is_empty());
+    }
+
+    #[test]
+    fn append_is_a_view() {
+        let items: CollItems<i32> = (0..10).collect();
+        let appended = items.slice(5, 10).append(&items.slice(0, 5));
+        assert_eq!(appended.len(), 10);
+        assert_eq!(appended.get(0), Some(&5));
+        assert_eq!(appended.get(9), Some(&4));
+        assert_eq!(appended.get(10), None);
+        assert!(appended
+            .chunks
+            .iter()
+            .all(|c| Arc::ptr_eq(&c.storage, &items.chunks[0].storage)));
+        assert_eq!(&appended[..], &[5, 6, 7, 8, 9, 0, 1, 2, 3, 4]);
+        assert_eq!(
+            appended.slice(3, 7),
+            vec![8, 9, 0, 1].into_iter().collect::<CollItems<i32>>()
+        );
+    }
+
+    #[test]
+    fn append_many_chunks() {
+        let items = (0..100).fold(CollItems::from(vec![]), |acc, i| {
+            acc.append(&CollItems::from(vec![i]))
+        });
+        assert!(items.chunks.len() <= MAX_CHUNKS);
+        assert_eq!(
+            items.iter().copied().collect::<Vec<_>>(),
+            (0..100).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,479 @@
+//! Expression tree traversal: visiting ([`Visitor`]) and rewriting ([`Folder`])
+
+use super::collection::Collection;
+use super::expr::Expr;
+
+/// Immediate child nodes of the expression (in the serialization order), including
+/// `MethodCall` arguments and `FuncValue` bodies
+pub fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Const(_)
+        | Expr::ConstPlaceholder(_)
+        | Expr::Context
+        | Expr::Global
+        | Expr::GlobalVars(_)
+        | Expr::ValUse(_)
+        | Expr::GetVar(_)
+        | Expr::DeserializeContext(_) => vec![],
+        Expr::Append(op) => vec![&op.input, &op.col_2],
+        Expr::SubstConstants(op) => vec![&op.script_bytes, &op.positions, &op.new_values],
+        Expr::ByteArrayToLong(op) => vec![&op.input],
+        Expr::ByteArrayToBigInt(op) => vec![&op.input],
+        Expr::LongToByteArray(op) => vec![&op.input],
+        Expr::Collection(Collection::BoolConstants(_)) => vec![],
+        Expr::Collection(Collection::Exprs { items, .. }) => items.iter().collect(),
+        Expr::Tuple(op) => op.items.iter().collect(),
+        Expr::CalcBlake2b256(op) => vec![&op.input],
+        Expr::CalcSha256(op) => vec![&op.input],
+        Expr::FuncValue(op) => vec![op.body()],
+        Expr::Apply(op) => std::iter::once(op.func.as_ref())
+            .chain(op.args.iter())
+            .collect(),
+        Expr::MethodCall(op) => std::iter::once(op.obj.as_ref())
+            .chain(op.args.iter())
+            .collect(),
+        Expr::ProperyCall(op) => vec![&op.obj],
+        Expr::BlockValue(op) => op
+            .items
+            .iter()
+            .chain(std::iter::once(op.result.as_ref()))
+            .collect(),
+        Expr::ValDef(op) => vec![&op.rhs],
+        Expr::If(op) => vec![&op.condition, &op.true_branch, &op.false_branch],
+        Expr::BinOp(op) => vec![&op.left, &op.right],
+        Expr::And(op) => vec![&op.input],
+        Expr::Or(op) => vec![&op.input],
+        Expr::Xor(op) => vec![&op.left, &op.right],
+        Expr::Atleast(op) => vec![&op.n_required, &op.expressions],
+        Expr::LogicalNot(op) => vec![&op.input],
+        Expr::Negation(op) => vec![&op.input],
+        Expr::BitInversion(op) => vec![&op.input],
+        Expr::OptionGet(op) => vec![&op.input],
+        Expr::OptionIsDefined(op) => vec![&op.input],
+        Expr::OptionGetOrElse(op) => vec![&op.input, &op.default],
+        Expr::ExtractAmount(op) => vec![&op.input],
+        Expr::ExtractRegisterAs(op) => vec![&op.input],
+        Expr::ExtractBytes(op) => vec![&op.input],
+        Expr::ExtractBytesWithNoRef(op) => vec![&op.input],
+        Expr::ExtractScriptBytes(op) => vec![&op.input],
+        Expr::ExtractCreationInfo(op) => vec![&op.input],
+        Expr::ExtractId(op) => vec![&op.input],
+        Expr::ByIndex(op) => std::iter::once(op.input.as_ref())
+            .chain(std::iter::once(op.index.as_ref()))
+            .chain(op.default.as_deref())
+            .collect(),
+        Expr::SizeOf(op) => vec![&op.input],
+        Expr::Slice(op) => vec![&op.input, &op.from, &op.until],
+        Expr::Fold(op) => vec![&op.input, &op.zero, &op.fold_op],
+        Expr::Map(op) => vec![&op.input, &op.mapper],
+        Expr::Filter(op) => vec![&op.input, &op.condition],
+        Expr::Exists(op) => vec![&op.input, &op.condition],
+        Expr::ForAll(op) => vec![&op.input, &op.condition],
+        Expr::SelectField(op) => vec![&op.input],
+        Expr::BoolToSigmaProp(op) => vec![&op.input],
+        Expr::Upcast(op) => vec![&op.input],
+        Expr::Downcast(op) => vec![&op.input],
+        Expr::CreateProveDlog(op) => vec![&op.input],
+        Expr::CreateProveDhTuple(op) => vec![&op.g, &op.h, &op.u, &op.v],
+        Expr::SigmaPropBytes(op) => vec![&op.input],
+        Expr::DecodePoint(op) => vec![&op.input],
+        Expr::SigmaAnd(op) => op.items.iter().collect(),
+        Expr::SigmaOr(op) => op.items.iter().collect(),
+        Expr::DeserializeRegister(op) => op.default.as_deref().into_iter().collect(),
+        Expr::MultiplyGroup(op) => vec![&op.left, &op.right],
+        Expr::Exponentiate(op) => vec![&op.left, &op.right],
+        Expr::XorOf(op) => vec![&op.input],
+    }
+}
+
+/// Mutable references to the immediate child nodes of the expression (same order as in
+/// [`children`])
+pub fn children_mut(expr: &mut Expr) -> Vec<&mut Expr> {
+    match expr {
+        Expr::Const(_)
+        | Expr::ConstPlaceholder(_)
+        | Expr::Context
+        | Expr::Global
+        | Expr::GlobalVars(_)
+        | Expr::ValUse(_)
+        | Expr::GetVar(_)
+        | Expr::DeserializeContext(_) => vec![],
+        Expr::Append(op) => vec![&mut op.input, &mut op.col_2],
+        Expr::SubstConstants(op) => {
+            vec![&mut op.script_bytes, &mut op.positions, &mut op.new_values]
+        }
+        Expr::ByteArrayToLong(op) => vec![&mut op.input],
+        Expr::ByteArrayToBigInt(op) => vec![&mut op.input],
+        Expr::LongToByteArray(op) => vec![&mut op.input],
+        Expr::Collection(Collection::BoolConstants(_)) => vec![],
+        Expr::Collection(Collection::Exprs { items, .. }) => items.iter_mut().collect(),
+        Expr::Tuple(op) => op.items.iter_mut().collect(),
+        Expr::CalcBlake2b256(op) => vec![&mut op.input],
+        Expr::CalcSha256(op) => vec![&mut op.input],
+        Expr::FuncValue(op) => vec![op.body_mut()],
+        Expr::Apply(op) => std::iter::once(op.func.as_mut())
+            .chain(op.args.iter_mut())
+            .collect(),
+        Expr::MethodCall(op) => std::iter::once(op.obj.as_mut())
+            .chain(op.args.iter_mut())
+            .collect(),
+        Expr::ProperyCall(op) => vec![&mut op.obj],
+        Expr::BlockValue(op) => op
+            .items
+            .iter_mut()
+            .chain(std::iter::once(op.result.as_mut()))
+            .collect(),
+        Expr::ValDef(op) => vec![&mut op.rhs],
+        Expr::If(op) => vec![&mut op.condition, &mut op.true_branch, &mut op.false_branch],
+        Expr::BinOp(op) => vec![&mut op.left, &mut op.right],
+        Expr::And(op) => vec![&mut op.input],
+        Expr::Or(op) => vec![&mut op.input],
+        Expr::Xor(op) => vec![&mut op.left, &mut op.right],
+        Expr::Atleast(op) => vec![&mut op.n_required, &mut op.expressions],
+        Expr::LogicalNot(op) => vec![&mut op.input],
+        Expr::Negation(op) => vec![&mut op.input],
+        Expr::BitInversion(op) => vec![&mut op.input],
+        Expr::OptionGet(op) => vec![&mut op.input],
+        Expr::OptionIsDefined(op) => vec![&mut op.input],
+        Expr::OptionGetOrElse(op) => vec![&mut op.input, &mut op.default],
+        Expr::ExtractAmount(op) => vec![&mut op.input],
+        Expr::ExtractRegisterAs(op) => vec![&mut op.input],
+        Expr::ExtractBytes(op) => vec![&mut op.input],
+        Expr::ExtractBytesWithNoRef(op) => vec![&mut op.input],
+        Expr::ExtractScriptBytes(op) => vec![&mut op.input],
+        Expr::ExtractCreationInfo(op) => vec![&mut op.input],
+        Expr::ExtractId(op) => vec![&mut op.input],
+        Expr::ByIndex(op) => std::iter::once(op.input.as_mut())
+            .chain(std::iter::once(op.index.as_mut()))
+            .chain(op.default.as_deref_mut())
+            .collect(),
+        Expr::SizeOf(op) => vec![&mut op.input],
+        Expr::Slice(op) => vec![&mut op.input, &mut op.from, &mut op.until],
+        Expr::Fold(op) => vec![&mut op.input, &mut op.zero, &mut op.fold_op],
+        Expr::Map(op) => vec![&mut op.input, &mut op.mapper],
+        Expr::Filter(op) => vec![&mut op.input, &mut op.condition],
+        Expr::Exists(op) => vec![&mut op.input, &mut op.condition],
+        Expr::ForAll(op) => vec![&mut op.input, &mut op.condition],
+        Expr::SelectField(op) => vec![&mut op.input],
+        Expr::BoolToSigmaProp(op) => vec![&mut op.input],
+        Expr::Upcast(op) => vec![&mut op.input],
+        Expr::Downcast(op) => vec![&mut op.input],
+        Expr::CreateProveDlog(op) => vec![&mut op.input],
+        Expr::CreateProveDhTuple(op) => vec![&mut op.g, &mut op.h, &mut op.u, &mut op.v],
+        Expr::SigmaPropBytes(op) => vec![&mut op.input],
+        Expr::DecodePoint(op) => vec![&mut op.input],
+        Expr::SigmaAnd(op) => op.items.iter_mut().collect(),
+        Expr::SigmaOr(op) => op.items.iter_mut().collect(),
+        Expr::DeserializeRegister(op) => op.default.as_deref_mut().into_iter().collect(),
+        Expr::MultiplyGroup(op) => vec![&mut op.left, &mut op.right],
+        Expr::Exponentiate(op) => vec![&mut op.left, &mut op.right],
+        Expr::XorOf(op) => vec![&mut op.input],
+    }
+}
+
+/// Immutable expression tree visitor, see [`visit`]
+pub trait Visitor {
+    /// Called on the node before its children are visited (top-down order).
+    /// Returning `false` skips the node children.
+    fn visit_pre(&mut self, _expr: &Expr) -> bool {
+        true
+    }
+
+    /// Called on the node after its children are visited (bottom-up order)
+    fn visit_post(&mut self, _expr: &Expr) {}
+}
+
+/// Visit every node of the expression tree
+pub fn visit<V: Visitor + ?Sized>(expr: &Expr, visitor: &mut V) {
+    if visitor.visit_pre(expr) {
+        for child in children(expr) {
+            visit(child, visitor);
+        }
+    }
+    visitor.visit_post(expr);
+}
+
+/// Expression tree rewriter, see [`fold`].
+/// Rewrites should preserve the type of the rewritten node, since the parent nodes may keep the
+/// types of their children (e.g. `FuncValue` type).
+pub trait Folder {
+    /// Rewrite error
+    type Error;
+
+    /// Called on the node before its children are folded (top-down order), the children of the
+    /// returned node are folded next
+    fn fold_pre(&mut self, expr: Expr) -> Result<Expr, Self::Error> {
+        Ok(expr)
+    }
+
+    /// Called on the node after its children are folded (bottom-up order)
+    fn fold_post(&mut self, expr: Expr) -> Result<Expr, Self::Error> {
+        Ok(expr)
+    }
+}
+
+/// Rewrite every node of the expression tree
+pub fn fold<F: Folder + ?Sized>(expr: Expr, folder: &mut F) -> Result<Expr, F::Error> {
+    let mut expr = folder.fold_pre(expr)?;
+    for child in children_mut(&mut expr) {
+        // placeholder until the folded child is put back
+        let c = std::mem::replace(child, Expr::Context);
+        *child = fold(c, folder)?;
+    }
+    folder.fold_post(expr)
+}
+
+/// Rewrite the tree starting from the root, `f` returns a replacement for the node (if any)
+/// whose children are rewritten next
+pub fn rewrite_top_down<E, F: FnMut(&Expr) -> Result<Option<Expr>, E>>(
+    expr: Expr,
+    f: F,
+) -> Result<Expr, E> {
+    fold(expr, &mut FnFolder { f, top_down: true })
+}
+
+/// Rewrite the tree starting from the leaves, `f` returns a replacement for the node (if any),
+/// which is called on the node with its children already rewritten
+pub fn rewrite_bottom_up<E, F: FnMut(&Expr) -> Result<Option<Expr>, E>>(
+    expr: Expr,
+    f: F,
+) -> Result<Expr, E> {
+    fold(expr, &mut FnFolder { f, top_down: false })
+}
+
+struct FnFolder<F> {
+    f: F,
+    top_down: bool,
+}
+
+impl<E, F: FnMut(&Expr) -> Result<Option<Expr>, E>> Folder for FnFolder<F> {
+    type Error = E;
+
+    fn fold_pre(&mut self, expr: Expr) -> Result<Expr, E> {
+        if self.top_down {
+            Ok((self.f)(&expr)?.unwrap_or(expr))
+        } else {
+            Ok(expr)
+        }
+    }
+
+    fn fold_post(&mut self, expr: Expr) -> Result<Expr, E> {
+        if self.top_down {
+            Ok(expr)
+        } else {
+            Ok((self.f)(&expr)?.unwrap_or(expr))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::mir::bin_op::ArithOp;
+    use crate::mir::bin_op::BinOp;
+    use crate::mir::coll_fold::Fold;
+    use crate::mir::constant::TryExtractInto;
+    use crate::mir::func_value::FuncArg;
+    use crate::mir::func_value::FuncValue;
+    use crate::mir::global_vars::GlobalVars;
+    use crate::mir::method_call::MethodCall;
+    use crate::mir::val_use::ValUse;
+    use crate::types::scoll;
+    use crate::types::stype::SType;
+    use crate::types::stype_param::STypeVar;
+    use std::convert::Infallible;
+    use std::convert::TryInto;
+
+    struct NodeCounter {
+        pre: Vec<String>,
+        post: Vec<String>,
+    }
+
+    impl Visitor for NodeCounter {
+        fn visit_pre(&mut self, expr: &Expr) -> bool {
+            self.pre.push(node_name(expr));
+            true
+        }
+
+        fn visit_post(&mut self, expr: &Expr) {
+            self.post.push(node_name(expr));
+        }
+    }
+
+    fn node_name(expr: &Expr) -> String {
+        match expr {
+            Expr::Const(c) if c.tpe == SType::SInt => format!("{:?}", c.v),
+            Expr::Const(_) => "Const".to_string(),
+            _ => format!("{:?}", expr).split('(').next().unwrap().to_string(),
+        }
+    }
+
+    fn plus(left: Expr, right: Expr) -> Expr {
+        BinOp {
+            kind: ArithOp::Plus.into(),
+            left: left.into(),
+            right: right.into(),
+        }
+        .into()
+    }
+
+    // HEIGHT + (Coll(1L, 2L).indexOf(3L, 0) + Coll(5).fold(1, { (t: (Int, Int)) => 1 + 2 }))
+    fn expr() -> Expr {
+        let index_of: Expr = MethodCall::new(
+            vec![1i64, 2i64].into(),
+            scoll::INDEX_OF_METHOD
+                .clone()
+                .with_concrete_types(&[(STypeVar::t(), SType::SLong)].iter().cloned().collect()),
+            vec![3i64.into(), 0i32.into()],
+        )
+        .unwrap()
+        .into();
+        let fold_op: Expr = FuncValue::new(
+            vec![FuncArg {
+                idx: 1.into(),
+                tpe: SType::STuple(vec![SType::SInt, SType::SInt].try_into().unwrap()),
+            }],
+            plus(1i32.into(), 2i32.into()),
+        )
+        .into();
+        let fold: Expr = Fold::new(vec![5i32].into(), 1i32.into(), fold_op)
+            .unwrap()
+            .into();
+        plus(GlobalVars::Height.into(), plus(index_of, fold))
+    }
+
+    #[test]
+    fn visit_order() {
+        let mut counter = NodeCounter {
+            pre: vec![],
+            post: vec![],
+        };
+        visit(&expr(), &mut counter);
+        assert_eq!(
+            counter.pre,
+            vec![
+                "BinOp",
+                "GlobalVars",
+                "BinOp",
+                "MethodCall",
+                "Const",
+                "Const",
+                "Int(0)",
+                "Fold",
+                "Const",
+                "Int(1)",
+                "FuncValue",
+                "BinOp",
+                "Int(1)",
+                "Int(2)"
+            ]
+        );
+        assert_eq!(
+            counter.post,
+            vec![
+                "GlobalVars",
+                "Const",
+                "Const",
+                "Int(0)",
+                "MethodCall",
+                "Const",
+                "Int(1)",
+                "Int(1)",
+                "Int(2)",
+                "BinOp",
+                "FuncValue",
+                "Fold",
+                "BinOp",
+                "BinOp"
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrite_constants() {
+        // every Int constant in the tree (including the function body) is incremented
+        let res = rewrite_bottom_up(expr(), |e| {
+            Ok::<_, Infallible>(match e {
+                Expr::Const(c) if c.tpe == SType::SInt => {
+                    let v = c.v.clone().try_extract_into::<i32>().unwrap();
+                    Some((v + 1).into())
+                }
+                _ => None,
+            })
+        })
+        .unwrap();
+        let mut counter = NodeCounter {
+            pre: vec![],
+            post: vec![],
+        };
+        visit(&res, &mut counter);
+        assert_eq!(
+            counter
+                .pre
+                .into_iter()
+                .filter(|n| n.starts_with("Int"))
+                .collect::<Vec<_>>(),
+            vec!["Int(1)", "Int(2)", "Int(2)", "Int(3)"]
+        );
+    }
+
+    #[test]
+    fn rewrite_top_down_replaces_subtree() {
+        // the replacement node is rewritten as well
+        let mut calls = 0;
+        let res = rewrite_top_down(expr(), |e| {
+            calls += 1;
+            Ok::<_, Infallible>(match e {
+                Expr::BinOp(op) if matches!(*op.right, Expr::Fold(_)) => {
+                    Some(plus(1i32.into(), 1i32.into()))
+                }
+                Expr::Const(_) => Some(Expr::ValUse(ValUse {
+                    val_id: 1.into(),
+                    tpe: SType::SInt,
+                })),
+                _ => None,
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            res,
+            plus(
+                GlobalVars::Height.into(),
+                plus(
+                    ValUse {
+                        val_id: 1.into(),
+                        tpe: SType::SInt
+                    }
+                    .into(),
+                    ValUse {
+                        val_id: 1.into(),
+                        tpe: SType::SInt
+                    }
+                    .into()
+                )
+            )
+        );
+        assert_eq!(calls, 5);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    proptest::proptest! {
+        #[test]
+        fn identity_fold(e in proptest::prelude::any::<Expr>()) {
+            let mut e_mut = e.clone();
+            proptest::prop_assert_eq!(children(&e).len(), children_mut(&mut e_mut).len());
+            let res = rewrite_bottom_up(e.clone(), |_| Ok::<_, Infallible>(None)).unwrap();
+            proptest::prop_assert_eq!(res, e);
+        }
+    }
+
+    #[test]
+    fn fold_error() {
+        let res = rewrite_bottom_up(expr(), |e| match e {
+            Expr::FuncValue(_) => Err("func"),
+            _ => Ok(None),
+        });
+        assert_eq!(res, Err("func"));
+    }
+}
@@ -22,7 +22,7 @@ impl Constant {
 impl SigmaSerializable for Constant {
     fn sigma_serialize<W: SigmaByteWrite>(&self, w: &mut W) -> SigmaSerializeResult {
         self.tpe.sigma_serialize(w)?;
-        DataSerializer::sigma_serialize(&self.v, w)
+        DataSerializer::sigma_serialize(&self.v, &self.tpe, w)
     }
 
     fn sigma_parse<R: SigmaByteRead>(r: &mut R) -> Result<Self, SigmaParsingError> {
@@ -35,6 +35,7 @@ impl SigmaSerializable for Constant {
 #[cfg(test)]
 #[cfg(feature = "arbitrary")]
 #[allow(clippy::panic)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
     use crate::mir::constant::arbitrary::ArbConstantParams;
@@ -52,5 +53,33 @@ mod tests {
         fn ser_roundtrip_sbox(v in any_with::<Constant>(ArbConstantParams::Exact(SType::SBox))) {
             prop_assert_eq![sigma_serialize_roundtrip(&v), v];
         }
+
+        #[test]
+        fn ser_roundtrip_soption(v in any_with::<Constant>(ArbConstantParams::Exact(
+            SType::SOption(Box::new(SType::SColl(Box::new(SType::SLong))))
+        ))) {
+            prop_assert_eq![sigma_serialize_roundtrip(&v), v];
+        }
+    }
+
+    #[test]
+    fn option_bytes() {
+        let some: Constant = Some(1i32).into();
+        assert_eq!(some.sigma_serialize_bytes().unwrap(), vec![40, 1, 2]);
+        let none: Constant = Option::<i32>::None.into();
+        assert_eq!(none.sigma_serialize_bytes().unwrap(), vec![40, 0]);
+        assert_eq!(Constant::sigma_parse_bytes(&[40, 1, 2]).unwrap(), some);
+        assert_eq!(Constant::sigma_parse_bytes(&[40, 0]).unwrap(), none);
+    }
+
+    #[test]
+    fn sany_data_not_supported() {
+        // no SAny data in the reference DataSerializer
+        let c = Constant {
+            tpe: SType::SAny,
+            v: 1i64.into(),
+        };
+        assert!(c.sigma_serialize_bytes().is_err());
+        assert!(Constant::sigma_parse_bytes(&[97, 5, 2]).is_err());
     }
 }
@@ -1,4 +1,5 @@
 use crate::chain::ergo_box::ErgoBox;
+use crate::ergo_tree::ErgoTreeVersion;
 use crate::mir::avl_tree_data::AvlTreeData;
 use crate::mir::constant::Literal;
 use crate::mir::constant::TryExtractFromError;
@@ -19,14 +20,22 @@ use crate::util::AsVecU8;
 
 use super::sigma_byte_writer::SigmaByteWrite;
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// Used to serialize and parse `Literal` and `Value`.
 pub struct DataSerializer {}
 
 impl DataSerializer {
-    pub fn sigma_serialize<W: SigmaByteWrite>(c: &Literal, w: &mut W) -> SigmaSerializeResult {
+    /// Serialize the value of the given type (the type is needed for the `Option` elements)
+    pub fn sigma_serialize<W: SigmaByteWrite>(
+        c: &Literal,
+        tpe: &SType,
+        w: &mut W,
+    ) -> SigmaSerializeResult {
         // for reference see http://github.com/ScorexFoundation/sigmastate-interpreter/blob/25251c1313b0131835f92099f02cef8a5d932b5e/sigmastate/src/main/scala/sigmastate/serialization/DataSerializer.scala#L26-L26
+        if *tpe == SType::SAny {
+            return Err(SigmaSerializationError::NotSupported("SAny data"));
+        }
         Ok(match c {
             Literal::Boolean(v) => w.put_u8(if *v { 1 } else { 0 })?,
             Literal::Byte(v) => w.put_i8(*v)?,
@@ -45,7 +54,7 @@ impl DataSerializer {
             Literal::Coll(ct) => match ct {
                 CollKind::NativeColl(NativeColl::CollByte(b)) => {
                     w.put_usize_as_u16_unwrapped(b.len())?;
-                    w.write_all(b.clone().as_vec_u8().as_slice())?
+                    w.write_all(b.as_vec_u8().as_slice())?
                 }
                 CollKind::WrappedColl {
                     elem_tpe: SType::SBoolean,
@@ -53,29 +62,55 @@ impl DataSerializer {
                 } => {
                     w.put_usize_as_u16_unwrapped(v.len())?;
                     let maybe_bools: Result<Vec<bool>, TryExtractFromError> = v
-                        .clone()
-                        .into_iter()
+                        .iter()
+                        .cloned()
                         .map(|i| i.try_extract_into::<bool>())
                         .collect();
                     w.put_bits(maybe_bools?.as_slice())?
                 }
-                CollKind::WrappedColl {
-                    elem_tpe: _,
-                    items: v,
-                } => {
+                CollKind::WrappedColl { elem_tpe, items: v } => {
                     w.put_usize_as_u16_unwrapped(v.len())?;
                     v.iter()
-                        .try_for_each(|e| DataSerializer::sigma_serialize(e, w))?
+                        .try_for_each(|e| DataSerializer::sigma_serialize(e, elem_tpe, w))?
+                }
+            },
+            Literal::Tup(items) => match tpe {
+                SType::STuple(stuple::STuple { items: types }) if types.len() == items.len() => {
+                    items
+                        .iter()
+                        .zip(types.iter())
+                        .try_for_each(|(i, t)| DataSerializer::sigma_serialize(i, t, w))?
+                }
+                _ => {
+                    return Err(TryExtractFromError(format!(
+                        "expected tuple type with {0} items, got {1:?}",
+                        items.len(),
+                        tpe
+                    ))
+                    .into())
                 }
             },
-            Literal::Tup(items) => items
-                .iter()
-                .try_for_each(|i| DataSerializer::sigma_serialize(i, w))?,
-            // unsupported, see
-            // https://github.com/ScorexFoundation/sigmastate-interpreter/issues/659
-            Literal::Opt(_) => {
-                return Err(SigmaSerializationError::NotSupported("Option"));
+            // same layout as `putOption` in the reference implementation:
+            // 0 for None, 1 followed by the value for Some
+            Literal::Opt(_) if w.tree_version() < ErgoTreeVersion::V3 => {
+                return Err(SigmaSerializationError::NotSupported(
+                    "Option data in ErgoTree before version 3",
+                ))
             }
+            Literal::Opt(opt) => match (opt.as_ref(), tpe) {
+                (None, _) => w.put_u8(0)?,
+                (Some(v), SType::SOption(elem_tpe)) => {
+                    w.put_u8(1)?;
+                    DataSerializer::sigma_serialize(v, elem_tpe, w)?
+                }
+                (Some(_), _) => {
+                    return Err(TryExtractFromError(format!(
+                        "expected Option type, got {0:?}",
+                        tpe
+                    ))
+                    .into())
+                }
+            },
         })
     }
 
@@ -134,7 +169,7 @@ impl DataSerializer {
                 }
                 Literal::Coll(CollKind::WrappedColl {
                     elem_tpe: *elem_type.clone(),
-                    items: elems,
+                    items: elems.into(),
                 })
             }
             STuple(stuple::STuple { items: types }) => {
@@ -147,11 +182,23 @@ impl DataSerializer {
                 // is correct
                 Literal::Tup(items.try_into()?)
             }
-            SBox => Literal::CBox(Rc::new(ErgoBox::sigma_parse(r)?)),
+            SBox => Literal::CBox(Arc::new(ErgoBox::sigma_parse(r)?)),
             SAvlTree => Literal::AvlTree(Box::new(AvlTreeData::sigma_parse(r)?)),
             STypeVar(_) => return Err(SigmaParsingError::NotSupported("TypeVar data")),
             SAny => return Err(SigmaParsingError::NotSupported("SAny data")),
-            SOption(_) => return Err(SigmaParsingError::NotSupported("SOption data")),
+            SOption(_) if r.tree_version() < ErgoTreeVersion::V3 => {
+                return Err(SigmaParsingError::NotSupported(
+                    "Option data in ErgoTree before version 3",
+                ))
+            }
+            SOption(elem_type) => {
+                let v = if r.get_u8()? != 0 {
+                    Some(DataSerializer::sigma_parse(elem_type, r)?)
+                } else {
+                    None
+                };
+                Literal::Opt(Box::new(v))
+            }
             SFunc(_) => return Err(SigmaParsingError::NotSupported("SFunc data")),
             SContext => return Err(SigmaParsingError::NotSupported("SContext data")),
             SHeader => return Err(SigmaParsingError::NotSupported("SHeader data")),
@@ -1,6 +1,7 @@
 //! Sigma byte stream writer
 use super::constant_store::ConstantStore;
 use super::val_def_type_store::ValDefTypeStore;
+use crate::ergo_tree::ErgoTreeVersion;
 use sigma_ser::vlq_encode::ReadSigmaVlqExt;
 use std::io::Cursor;
 use std::io::Read;
@@ -11,6 +12,7 @@ pub struct SigmaByteReader<R> {
     constant_store: ConstantStore,
     substitute_placeholders: bool,
     val_def_type_store: ValDefTypeStore,
+    tree_version: ErgoTreeVersion,
 }
 
 impl<R: Read> SigmaByteReader<R> {
@@ -21,6 +23,7 @@ impl<R: Read> SigmaByteReader<R> {
             constant_store,
             substitute_placeholders: false,
             val_def_type_store: ValDefTypeStore::new(),
+            tree_version: ErgoTreeVersion::MAX_SUPPORTED,
         }
     }
 
@@ -35,6 +38,7 @@ impl<R: Read> SigmaByteReader<R> {
             constant_store,
             substitute_placeholders: true,
             val_def_type_store: ValDefTypeStore::new(),
+            tree_version: ErgoTreeVersion::MAX_SUPPORTED,
         }
     }
 }
@@ -46,6 +50,7 @@ pub fn from_bytes<T: AsRef<[u8]>>(bytes: T) -> SigmaByteReader<Cursor<T>> {
         constant_store: ConstantStore::empty(),
         substitute_placeholders: false,
         val_def_type_store: ValDefTypeStore::new(),
+        tree_version: ErgoTreeVersion::MAX_SUPPORTED,
     }
 }
 
@@ -62,6 +67,13 @@ pub trait SigmaByteRead: ReadSigmaVlqExt {
 
     /// ValDef types store (resolves tpe on ValUse parsing)
     fn val_def_type_store(&mut self) -> &mut ValDefTypeStore;
+
+    /// Version of the ErgoTree being parsed ([`ErgoTreeVersion::MAX_SUPPORTED`] outside of
+    /// ErgoTree, e.g. for registers)
+    fn tree_version(&self) -> ErgoTreeVersion;
+
+    /// Set the version of the ErgoTree being parsed
+    fn set_tree_version(&mut self, tree_version: ErgoTreeVersion);
 }
 
 impl<R: Read> Read for SigmaByteReader<R> {
@@ -86,4 +98,12 @@ impl<R: ReadSigmaVlqExt> SigmaByteRead for SigmaByteReader<R> {
     fn val_def_type_store(&mut self) -> &mut ValDefTypeStore {
         &mut self.val_def_type_store
     }
+
+    fn tree_version(&self) -> ErgoTreeVersion {
+        self.tree_version
+    }
+
+    fn set_tree_version(&mut self, tree_version: ErgoTreeVersion) {
+        self.tree_version = tree_version;
+    }
 }
@@ -1,5 +1,6 @@
 //! Sigma byte stream writer
 use super::constant_store::ConstantStore;
+use crate::ergo_tree::ErgoTreeVersion;
 use sigma_ser::vlq_encode::WriteSigmaVlqExt;
 use std::io::Write;
 
@@ -8,6 +9,7 @@ pub struct SigmaByteWriter<'a, W> {
     inner: &'a mut W,
     /// Constant store where constants (swapped for placeholders) are stored
     pub constant_store: Option<ConstantStore>,
+    tree_version: ErgoTreeVersion,
 }
 
 impl<'a, W: Write> SigmaByteWriter<'a, W> {
@@ -16,6 +18,7 @@ impl<'a, W: Write> SigmaByteWriter<'a, W> {
         SigmaByteWriter {
             inner: w,
             constant_store,
+            tree_version: ErgoTreeVersion::MAX_SUPPORTED,
         }
     }
 }
@@ -24,6 +27,13 @@ impl<'a, W: Write> SigmaByteWriter<'a, W> {
 pub trait SigmaByteWrite: WriteSigmaVlqExt {
     /// Constant store (if any) attached to the writer to collect segregated constants
     fn constant_store_mut_ref(&mut self) -> Option<&mut ConstantStore>;
+
+    /// Version of the ErgoTree being serialized ([`ErgoTreeVersion::MAX_SUPPORTED`] outside of
+    /// ErgoTree, e.g. for registers)
+    fn tree_version(&self) -> ErgoTreeVersion;
+
+    /// Set the version of the ErgoTree being serialized
+    fn set_tree_version(&mut self, tree_version: ErgoTreeVersion);
 }
 
 impl<'a, W: Write> Write for SigmaByteWriter<'a, W> {
@@ -40,4 +50,12 @@ impl<'a, W: Write> SigmaByteWrite for SigmaByteWriter<'a, W> {
     fn constant_store_mut_ref(&mut self) -> Option<&mut ConstantStore> {
         self.constant_store.as_mut()
     }
+
+    fn tree_version(&self) -> ErgoTreeVersion {
+        self.tree_version
+    }
+
+    fn set_tree_version(&mut self, tree_version: ErgoTreeVersion) {
+        self.tree_version = tree_version;
+    }
 }
@@ -42,6 +42,12 @@ impl AsVecU8 for Vec<i8> {
     }
 }
 
+impl AsVecU8 for [i8] {
+    fn as_vec_u8(&self) -> Vec<u8> {
+        self.iter().map(|b| *b as u8).collect()
+    }
+}
+
 /// Convert Vec<u8> to Vec<i8>
 pub trait AsVecI8 {
     /// Returns Vec<i8>